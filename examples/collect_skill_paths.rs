@@ -0,0 +1,86 @@
+//! Crawls a directory of HTML files and collects the path of every skill
+//! written, through a crawl observer.
+//!
+//! ```bash
+//! cargo run --example collect_skill_paths
+//! ```
+
+use agent_skills_generator::observer::{CrawlObserver, ObserverFuture};
+use agent_skills_generator::processor::ProcessedPage;
+use agent_skills_generator::{Config, CrawlBuilder};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const PAGES: &[(&str, &str)] = &[
+    (
+        "index.html",
+        "<html><head><title>Widgets Guide</title></head><body><main>\
+         <h1>Widgets Guide</h1>\
+         <p>Widgets are the building blocks of every screen. Start by installing \
+         the toolkit, then compose widgets into layouts.</p>\
+         </main></body></html>",
+    ),
+    (
+        "guide/install.html",
+        "<html><head><title>Installation</title></head><body><main>\
+         <h1>Installation</h1>\
+         <p>Download the toolkit for your platform and add its bin directory to \
+         your PATH. Run <code>widgets doctor</code> to check the setup.</p>\
+         </main></body></html>",
+    ),
+    (
+        "guide/layouts.html",
+        "<html><head><title>Layouts</title></head><body><main>\
+         <h1>Layouts</h1>\
+         <p>Rows and columns place widgets next to or below each other. Wrap a \
+         widget in padding to give it room.</p>\
+         </main></body></html>",
+    ),
+];
+
+/// Collects the path of every skill written.
+#[derive(Default)]
+struct SkillPaths {
+    paths: Mutex<Vec<PathBuf>>,
+}
+
+impl CrawlObserver for SkillPaths {
+    fn skill_written<'a>(&'a self, _page: &'a ProcessedPage, path: &'a Path) -> ObserverFuture<'a> {
+        Box::pin(async move {
+            self.paths.lock().unwrap().push(path.to_path_buf());
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let root = std::env::temp_dir().join("agent-skills-collect-example");
+    let site = root.join("site");
+    let output = root.join("skills");
+    let _ = std::fs::remove_dir_all(&root);
+    for (file, html) in PAGES {
+        let path = site.join(file);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, html)?;
+    }
+
+    let skill_paths = Arc::new(SkillPaths::default());
+    let config = Config {
+        min_content_chars: 0,
+        ..Default::default()
+    };
+    let report = CrawlBuilder::new(site.to_string_lossy())
+        .config(config)
+        .output(&output)
+        .observer(Arc::clone(&skill_paths) as Arc<dyn CrawlObserver>)
+        .run()
+        .await?;
+
+    println!("{}", report.stats.summary());
+    let mut paths = skill_paths.paths.lock().unwrap().clone();
+    paths.sort();
+    for path in paths {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
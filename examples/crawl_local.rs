@@ -0,0 +1,101 @@
+//! Crawls a small site served on localhost and prints the crawl report.
+//!
+//! ```bash
+//! cargo run --example crawl_local
+//! ```
+
+use agent_skills_generator::{Config, CrawlBuilder};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const PAGES: &[(&str, &str)] = &[
+    (
+        "/",
+        "<html><head><title>Widgets Guide</title></head><body><main>\
+         <h1>Widgets Guide</h1>\
+         <p>Widgets are the building blocks of every screen. Start by installing \
+         the toolkit, then compose widgets into layouts.</p>\
+         <a href=\"/install/\">Installation</a> <a href=\"/layouts/\">Layouts</a>\
+         </main></body></html>",
+    ),
+    (
+        "/install/",
+        "<html><head><title>Installation</title></head><body><main>\
+         <h1>Installation</h1>\
+         <p>Download the toolkit for your platform and add its bin directory to \
+         your PATH. Run <code>widgets doctor</code> to check the setup.</p>\
+         </main></body></html>",
+    ),
+    (
+        "/layouts/",
+        "<html><head><title>Layouts</title></head><body><main>\
+         <h1>Layouts</h1>\
+         <p>Rows and columns place widgets next to or below each other. Wrap a \
+         widget in padding to give it room.</p>\
+         </main></body></html>",
+    ),
+];
+
+/// Serves `PAGES` until the process exits, returning the server's URL.
+async fn serve() -> anyhow::Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/", listener.local_addr()?);
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match socket.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+
+            let request = String::from_utf8_lossy(&request);
+            let path = request.split_whitespace().nth(1).unwrap_or("/");
+            let response = match PAGES.iter().find(|(page, _)| *page == path) {
+                Some((_, body)) => format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                ),
+                None => "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                    .to_string(),
+            };
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    Ok(url)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let url = serve().await?;
+    let output_dir = std::env::temp_dir().join("agent-skills-example");
+
+    // A local server has neither robots.txt nor a need for politeness delays
+    let config = Config {
+        delay_ms: 0,
+        respect_robots_txt: false,
+        ..Default::default()
+    };
+    let report = CrawlBuilder::new(&url)
+        .config(config)
+        .output(&output_dir)
+        .max_pages(10)
+        .run()
+        .await?;
+
+    println!("Crawled {}: {}", url, report.stats.summary());
+    for skill in &report.skills {
+        println!("  {} <- {}", skill.name, skill.url);
+    }
+    for seed in report.seeds.iter().filter(|seed| seed.result.is_err()) {
+        println!("  {} failed", seed.url);
+    }
+    println!("Skills written to {}", output_dir.display());
+
+    Ok(())
+}
@@ -0,0 +1,364 @@
+//! Admonition conversion for the agent-skills-generator.
+//!
+//! Docusaurus, MkDocs, Sphinx and GitHub render notes and warnings as
+//! `<div class="admonition warning">`-style blocks, which htmd turns into
+//! stray paragraphs. They are rewritten here as GitHub-flavored callouts:
+//!
+//! ```text
+//! > [!WARNING]
+//! > **Title, unless it only names the type**
+//! >
+//! > Content
+//! ```
+//!
+//! Before conversion each admonition becomes a `<blockquote>` opening with a
+//! marker paragraph, and [`callouts`] swaps the markers for `[!TYPE]` once
+//! the markdown exists, since htmd would escape the brackets. With
+//! `callout_style: labeled` the marker becomes a bold label on the first
+//! line instead, `> **Warning:** Content`, or `> **Warning: Title**` for a
+//! titled admonition.
+
+use crate::config::{CalloutKind, CalloutStyle};
+use regex::{Captures, Regex};
+use scraper::{ElementRef, Html, Selector};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+/// Marker paragraph standing in for `[!TYPE]` during conversion.
+const CALLOUT_MARKER: &str = "AGENTSKILLSCALLOUT";
+
+/// Classes marking an element as an admonition, whatever its type.
+const CONTAINER_CLASSES: [&str; 5] = [
+    "admonition",
+    "alert",
+    "callout",
+    "markdown-alert",
+    "theme-admonition",
+];
+
+/// Prefixes of type classes: `alert--warning` (Docusaurus), `alert-warning`
+/// (Bootstrap), `markdown-alert-warning` (GitHub) and so on.
+const TYPE_CLASS_PREFIXES: [&str; 5] = [
+    "theme-admonition-",
+    "markdown-alert-",
+    "alert--",
+    "alert-",
+    "callout-",
+];
+
+/// Elements a bare type class (`<div class="note">`) makes an admonition.
+const BLOCK_ELEMENTS: [&str; 5] = ["div", "aside", "section", "details", "blockquote"];
+
+/// Type classes that make a block an admonition on their own; the other
+/// built-in ones (`info`, `error`) are too common outside admonitions.
+const BARE_TYPE_CLASSES: [&str; 6] = ["note", "tip", "important", "warning", "caution", "danger"];
+
+/// Built-in type classes.
+const TYPE_CLASSES: [(&str, CalloutKind); 19] = [
+    ("note", CalloutKind::Note),
+    ("info", CalloutKind::Note),
+    ("information", CalloutKind::Note),
+    ("seealso", CalloutKind::Note),
+    ("abstract", CalloutKind::Note),
+    ("example", CalloutKind::Note),
+    ("tip", CalloutKind::Tip),
+    ("hint", CalloutKind::Tip),
+    ("success", CalloutKind::Tip),
+    ("important", CalloutKind::Important),
+    ("warning", CalloutKind::Warning),
+    ("warn", CalloutKind::Warning),
+    ("attention", CalloutKind::Warning),
+    ("caution", CalloutKind::Caution),
+    ("danger", CalloutKind::Caution),
+    ("error", CalloutKind::Caution),
+    ("failure", CalloutKind::Caution),
+    ("bug", CalloutKind::Caution),
+    ("critical", CalloutKind::Caution),
+];
+
+/// A marker paragraph in the converted markdown, with the blank quoted line
+/// htmd puts after it.
+static MARKER_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?m)^(\s*>\s?){}([A-Z]+)\s*\n(?:\s*>\s*\n)?",
+        CALLOUT_MARKER
+    ))
+    .expect("Failed to compile callout marker regex")
+});
+
+/// A marker paragraph and the quoted line after it, which a label joins.
+static LABELED_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?m)^(\s*>\s?){}([A-Z]+)\s*\n(?:\s*>\s*\n)?\s*>\s?(.*)$",
+        CALLOUT_MARKER
+    ))
+    .expect("Failed to compile labeled callout regex")
+});
+
+/// Rewrites the admonitions in `html` as marked `<blockquote>`s for
+/// [`callouts`] to finish. `classes` adds site-specific type classes, which
+/// take precedence over the built-in ones.
+pub fn mark(html: &str, classes: &BTreeMap<String, CalloutKind>) -> String {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("[class]").expect("valid selector");
+
+    let mut source = None;
+    for element in document.select(&selector) {
+        let Some(kind) = admonition_kind(element, classes) else {
+            continue;
+        };
+        let nested = element
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|e| admonition_kind(e, classes).is_some());
+        if nested {
+            continue;
+        }
+
+        let source = source.get_or_insert_with(|| document.html());
+        let element_html = element.html();
+        if !source.contains(&element_html) {
+            continue;
+        }
+        *source = source.replacen(&element_html, &blockquote(element, kind), 1);
+    }
+
+    source.unwrap_or_else(|| html.to_string())
+}
+
+/// Replaces the markers left by [`mark`] with `[!TYPE]`, or a bold label
+/// in the `labeled` style.
+pub fn callouts(markdown: &str, style: CalloutStyle) -> String {
+    if !markdown.contains(CALLOUT_MARKER) {
+        return markdown.to_string();
+    }
+    match style {
+        CalloutStyle::Github => MARKER_LINE
+            .replace_all(markdown, |caps: &Captures| {
+                format!("{}[!{}]\n", &caps[1], &caps[2])
+            })
+            .into_owned(),
+        CalloutStyle::Labeled => LABELED_LINE
+            .replace_all(markdown, |caps: &Captures| {
+                let label = label(&caps[2]);
+                let line = caps[3].trim_end();
+                // A title takes the label in, so it isn't bold twice
+                match line
+                    .strip_prefix("**")
+                    .and_then(|title| title.strip_suffix("**"))
+                    .filter(|title| !title.contains("**"))
+                {
+                    Some(title) => format!("{}**{}: {}**", &caps[1], label, title),
+                    None => format!("{}**{}:** {}", &caps[1], label, line),
+                }
+            })
+            .into_owned(),
+    }
+}
+
+/// `WARNING` as `Warning`.
+fn label(kind: &str) -> String {
+    let mut chars = kind.chars();
+    chars
+        .next()
+        .map(|first| first.to_string() + &chars.as_str().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+/// The callout type of `element`, if it is an admonition.
+fn admonition_kind(
+    element: ElementRef,
+    classes: &BTreeMap<String, CalloutKind>,
+) -> Option<CalloutKind> {
+    let value = element.value();
+    if let Some(kind) = value.classes().find_map(|class| classes.get(class)) {
+        return Some(*kind);
+    }
+
+    let is_container = value
+        .classes()
+        .any(|class| CONTAINER_CLASSES.contains(&class));
+    let is_block = BLOCK_ELEMENTS.contains(&value.name());
+    let kind = value.classes().find_map(|class| {
+        TYPE_CLASS_PREFIXES
+            .iter()
+            .find_map(|prefix| class.strip_prefix(prefix))
+            .and_then(type_class)
+            .or_else(|| {
+                let bare = is_block && BARE_TYPE_CLASSES.contains(&class);
+                type_class(class).filter(|_| is_container || bare)
+            })
+    });
+
+    match kind {
+        Some(kind) => Some(kind),
+        // An admonition without a known type is still a callout
+        None if is_container && value.name() != "span" => Some(CalloutKind::Note),
+        None => None,
+    }
+}
+
+fn type_class(class: &str) -> Option<CalloutKind> {
+    let class = class.to_ascii_lowercase();
+    TYPE_CLASSES
+        .iter()
+        .find(|(name, _)| *name == class)
+        .map(|(_, kind)| *kind)
+}
+
+/// The marked `<blockquote>` standing in for an admonition: the marker, the
+/// title in bold unless it only names the type, then the content.
+fn blockquote(element: ElementRef, kind: CalloutKind) -> String {
+    let title = element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| is_title(*child));
+
+    let mut html = format!(
+        "<blockquote><p>{}{}</p>",
+        CALLOUT_MARKER,
+        kind.name().to_ascii_uppercase()
+    );
+    if let Some(title) = title {
+        let text = title
+            .text()
+            .flat_map(str::split_whitespace)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let redundant = text.is_empty()
+            || text.eq_ignore_ascii_case(kind.name())
+            || type_class(&text).is_some();
+        if !redundant {
+            html.push_str(&format!("<p><strong>{}</strong></p>", escape(&text)));
+        }
+    }
+
+    for child in element.children() {
+        if let Some(child) = ElementRef::wrap(child) {
+            if Some(child) == title {
+                continue;
+            }
+            html.push_str(&child.html());
+        } else if let Some(text) = child.value().as_text() {
+            html.push_str(&escape(text));
+        }
+    }
+    html.push_str("</blockquote>");
+    html
+}
+
+/// Whether `element` is an admonition's title: a `<summary>`, or a class
+/// naming a title or heading (`admonition-title`, `admonitionHeading_x1`).
+fn is_title(element: ElementRef) -> bool {
+    let value = element.value();
+    value.name() == "summary"
+        || value.classes().any(|class| {
+            let class = class.to_ascii_lowercase();
+            class.contains("title") || class.contains("heading")
+        })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(html: &str, classes: &BTreeMap<String, CalloutKind>) -> String {
+        callouts(
+            &htmd::convert(&mark(html, classes)).unwrap(),
+            CalloutStyle::Github,
+        )
+    }
+
+    #[test]
+    fn test_mkdocs_admonition() {
+        let markdown = convert(
+            r#"<p>Before.</p><div class="admonition warning"><p class="admonition-title">Data loss</p><p>Back up <code>config.yaml</code> first.</p></div>"#,
+            &BTreeMap::new(),
+        );
+
+        assert_eq!(
+            markdown,
+            "Before.\n\n> [!WARNING]\n> **Data loss**\n> \n> Back up `config.yaml` first."
+        );
+    }
+
+    #[test]
+    fn test_docusaurus_admonition() {
+        let markdown = convert(
+            r#"<div class="theme-admonition theme-admonition-tip admonition_xJq3 alert alert--success"><div class="admonitionHeading_Gvgb"><span class="admonitionIcon_Rf37"></span>tip</div><div class="admonitionContent_BuS1"><p>Use <code>--watch</code> while editing.</p></div></div>"#,
+            &BTreeMap::new(),
+        );
+
+        assert_eq!(markdown, "> [!TIP]\n> Use `--watch` while editing.");
+    }
+
+    #[test]
+    fn test_github_alert_and_sphinx_note() {
+        let markdown = convert(
+            r#"<div class="markdown-alert markdown-alert-important"><p class="markdown-alert-title">Important</p><p>Tokens expire.</p></div>
+<div class="admonition note"><p class="admonition-title">Note</p><p>Only on Linux.</p></div>"#,
+            &BTreeMap::new(),
+        );
+
+        assert!(
+            markdown.contains("> [!IMPORTANT]\n> Tokens expire."),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("> [!NOTE]\n> Only on Linux."),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn test_custom_classes() {
+        let classes = BTreeMap::from([("msg-box--danger".to_string(), CalloutKind::Caution)]);
+        let html = r#"<div class="msg-box msg-box--danger"><p>Irreversible.</p></div>"#;
+
+        assert_eq!(convert(html, &classes), "> [!CAUTION]\n> Irreversible.");
+        assert_eq!(convert(html, &BTreeMap::new()), "Irreversible.");
+    }
+
+    #[test]
+    fn test_labeled_style() {
+        let labeled = |html: &str| {
+            callouts(
+                &htmd::convert(&mark(html, &BTreeMap::new())).unwrap(),
+                CalloutStyle::Labeled,
+            )
+        };
+
+        // MkDocs Material, collapsible and titled
+        assert_eq!(
+            labeled(
+                r#"<details class="warning"><summary>Data loss</summary><p>Back up first.</p></details>"#
+            ),
+            "> **Warning: Data loss**\n> \n> Back up first."
+        );
+        // Docusaurus, titled after the type
+        assert_eq!(
+            labeled(
+                r#"<div class="theme-admonition theme-admonition-note alert alert--secondary"><div class="admonitionHeading_Gvgb">note</div><div class="admonitionContent_BuS1"><p>Requires <code>v2</code>.</p></div></div>"#
+            ),
+            "> **Note:** Requires `v2`."
+        );
+    }
+
+    #[test]
+    fn test_inline_classes_are_not_admonitions() {
+        let markdown = convert(
+            r#"<p>Status: <span class="warning">deprecated</span></p>"#,
+            &BTreeMap::new(),
+        );
+
+        assert_eq!(markdown, "Status: deprecated");
+    }
+}
@@ -0,0 +1,137 @@
+//! Zip archives of generated skills (`crawl --archive`).
+//!
+//! Skills are packed as they are laid out in the output directory,
+//! `skill-name/SKILL.md` and everything next to it, so the archive unpacks
+//! into a skills directory as is. Other files there, such as the crawl
+//! cache, are left out. Entries are deflated and dated 1980-01-01, the
+//! earliest date a zip can hold, so the same skills always make the same
+//! archive.
+
+use crate::utils::skill_files;
+use anyhow::{Context, Result};
+use std::io::{Seek, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipWriter};
+
+/// Packs the skills in `output_dir` into a zip archive at `archive`,
+/// replacing it. Files are streamed into the archive one at a time, and
+/// Zip64 is used for those too large for a plain zip. Returns how many
+/// skills were packed.
+pub fn write_skills(output_dir: &Path, archive: &Path) -> Result<usize> {
+    let skills = skill_files(output_dir)?;
+
+    if let Some(parent) = archive.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs_err::create_dir_all(parent)?;
+    }
+    let file = std::io::BufWriter::new(fs_err::File::create(archive)?);
+    let mut zip = ZipWriter::new(file);
+    for skill in &skills {
+        add_path(&mut zip, output_dir, &skill.path)?;
+    }
+    zip.finish()
+        .with_context(|| format!("Failed to write {}", archive.display()))?
+        .flush()?;
+    Ok(skills.len())
+}
+
+/// Adds the file at `path`, or every file under it, named relative to
+/// `root`.
+fn add_path<W: Write + Seek>(zip: &mut ZipWriter<W>, root: &Path, path: &Path) -> Result<()> {
+    if path.is_dir() {
+        let mut entries = fs_err::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            add_path(zip, root, &entry)?;
+        }
+        return Ok(());
+    }
+
+    let name = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut file = fs_err::File::open(path)?;
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(DateTime::default())
+        .unix_permissions(0o644)
+        .large_file(file.metadata()?.len() >= u32::MAX as u64);
+    zip.start_file(name.as_str(), options)
+        .with_context(|| format!("Failed to add {} to the archive", name))?;
+    std::io::copy(&mut file, zip)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::io::Read;
+
+    /// Reads every entry of `archive`.
+    fn read_zip(archive: &[u8]) -> BTreeMap<String, String> {
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        (0..zip.len())
+            .map(|i| {
+                let mut file = zip.by_index(i).unwrap();
+                assert_eq!(file.compression(), CompressionMethod::Deflated);
+                let mut contents = String::new();
+                file.read_to_string(&mut contents).unwrap();
+                (file.name().unwrap().to_string(), contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_write_skills() {
+        let root = std::env::temp_dir().join(format!("archive-test-{}", std::process::id()));
+        let output_dir = root.join("skills");
+        std::fs::create_dir_all(output_dir.join("docs-install")).unwrap();
+        std::fs::create_dir_all(output_dir.join("api-routes/references")).unwrap();
+        std::fs::write(output_dir.join("docs-install/SKILL.md"), "# Install\n").unwrap();
+        std::fs::write(output_dir.join("api-routes/SKILL.md"), "# Routes\n").unwrap();
+        std::fs::write(
+            output_dir.join("api-routes/references/params.md"),
+            "Parameters\n".repeat(100),
+        )
+        .unwrap();
+        std::fs::write(output_dir.join(".crawl-cache.json"), "{}").unwrap();
+        std::fs::create_dir_all(output_dir.join("notes")).unwrap();
+
+        let archive = root.join("out/skills.zip");
+        assert_eq!(write_skills(&output_dir, &archive).unwrap(), 2);
+
+        let bytes = std::fs::read(&archive).unwrap();
+        let files = read_zip(&bytes);
+        let names: Vec<&str> = files.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            [
+                "api-routes/SKILL.md",
+                "api-routes/references/params.md",
+                "docs-install/SKILL.md"
+            ]
+        );
+        assert_eq!(files["docs-install/SKILL.md"], "# Install\n");
+        assert_eq!(
+            files["api-routes/references/params.md"],
+            "Parameters\n".repeat(100)
+        );
+        // Deflated
+        assert!(bytes.len() < 1100);
+
+        // The same skills make the same archive
+        write_skills(&output_dir, &archive).unwrap();
+        assert_eq!(std::fs::read(&archive).unwrap(), bytes);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
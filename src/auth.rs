@@ -0,0 +1,99 @@
+//! Authorization headers for the agent-skills-generator.
+//!
+//! Credentials come from either `basic_auth` (sent to every host) or `auth`
+//! (a bearer token or basic auth read from the environment, optionally
+//! limited to some domains). Both resolve to an [`Authorization`], which
+//! hands out the header only for the hosts it is meant for.
+
+use crate::config::Config;
+use anyhow::Result;
+use reqwest::header::HeaderValue;
+
+/// A resolved `Authorization` header and the hosts it is sent to.
+#[derive(Debug, Clone)]
+pub struct Authorization {
+    /// Sensitive header value, redacted in debug output.
+    value: HeaderValue,
+    /// Lowercase hosts without a leading dot; empty means every host.
+    domains: Vec<String>,
+}
+
+impl Authorization {
+    /// Resolves the configured credentials, reading environment variables.
+    /// Returns `None` when no credentials are configured.
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        match (&config.basic_auth, &config.auth) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!("basic_auth and auth can't be combined; keep one of them")
+            }
+            (Some(basic_auth), None) => Ok(Some(Self {
+                value: basic_auth.header_value()?,
+                domains: Vec::new(),
+            })),
+            (None, Some(auth)) => Ok(Some(Self {
+                value: auth.header_value()?,
+                domains: auth
+                    .domains
+                    .iter()
+                    .map(|domain| domain.trim().trim_start_matches('.').to_lowercase())
+                    .collect(),
+            })),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Returns the header value if `url`'s host should receive it.
+    pub fn header_for(&self, url: &str) -> Option<HeaderValue> {
+        if self.domains.is_empty() {
+            return Some(self.value.clone());
+        }
+
+        let url = url::Url::parse(url).ok()?;
+        let host = url.host_str()?.to_lowercase();
+        self.domains
+            .iter()
+            .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+            .then(|| self.value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuthConfig, BasicAuth};
+
+    #[test]
+    fn test_header_is_scoped_to_domains() {
+        let auth = Authorization {
+            value: HeaderValue::from_static("Bearer t0ken"),
+            domains: vec!["corp.example".to_string()],
+        };
+
+        assert!(auth.header_for("https://corp.example/").is_some());
+        assert!(auth.header_for("https://docs.corp.example/guide").is_some());
+        assert!(auth.header_for("https://notcorp.example/").is_none());
+        assert!(auth.header_for("https://docs.flutter.dev/").is_none());
+    }
+
+    #[test]
+    fn test_basic_auth_and_auth_are_exclusive() {
+        let config = Config {
+            basic_auth: Some(BasicAuth {
+                username: "wiki-bot".to_string(),
+                password: "hunter2".to_string(),
+            }),
+            auth: Some(AuthConfig::bearer("DOCS_TOKEN")),
+            ..Default::default()
+        };
+
+        let err = Authorization::from_config(&config).unwrap_err();
+        assert!(err.to_string().contains("can't be combined"), "{}", err);
+
+        let basic_only = Config {
+            auth: None,
+            ..config
+        };
+        let auth = Authorization::from_config(&basic_only).unwrap().unwrap();
+        assert!(auth.header_for("https://anywhere.example/").is_some());
+    }
+}
@@ -0,0 +1,332 @@
+//! Library entry point for running a crawl.
+//!
+//! [`CrawlBuilder`] does what `agent-skills-generator crawl` does for one
+//! or more start URLs: seeds share their scope, skill names, visited pages,
+//! rate limits and token budget, and `post_crawl_command`/`webhook_url` run
+//! once at the end if every seed was crawled. Instead of log lines it returns a [`CrawlReport`].
+
+use crate::config::Config;
+use crate::crawler::{self, Crawler, StatsSnapshot, VisitedUrls};
+use crate::naming::SkillNames;
+use crate::observer::{CrawlObserver, LogObserver};
+use crate::rate_limit::RateLimiter;
+use crate::tokens::TokenBudget;
+use crate::{hooks, http, robots, scope};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+/// Configures and runs a crawl.
+///
+/// ```no_run
+/// # async fn crawl() -> anyhow::Result<()> {
+/// use agent_skills_generator::CrawlBuilder;
+///
+/// let report = CrawlBuilder::new("https://docs.example.com/guide/")
+///     .url("https://docs.example.com/api/")
+///     .output("skills")
+///     .run()
+///     .await?;
+/// println!("{}", report.stats.summary());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CrawlBuilder {
+    urls: Vec<String>,
+    config: Config,
+    output: Option<PathBuf>,
+    max_pages: Option<u32>,
+    observers: Vec<Arc<dyn CrawlObserver>>,
+}
+
+impl std::fmt::Debug for CrawlBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrawlBuilder")
+            .field("urls", &self.urls)
+            .field("config", &self.config)
+            .field("output", &self.output)
+            .field("max_pages", &self.max_pages)
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
+
+/// Outcome of [`CrawlBuilder::run`].
+#[derive(Debug, Default)]
+pub struct CrawlReport {
+    /// Counters summed over every seed.
+    pub stats: StatsSnapshot,
+    /// Each seed crawled, in order: the start URLs and any seeds derived
+    /// from `entry_selector`.
+    pub seeds: Vec<SeedReport>,
+    /// Skills written (or rewritten) by the crawl, in the order their
+    /// pages were processed. Unchanged skills aren't listed.
+    pub skills: Vec<ReportedSkill>,
+    /// Directories the skills were written to, one per target.
+    pub output_dirs: Vec<PathBuf>,
+}
+
+impl CrawlReport {
+    /// Whether every seed was crawled. Failed pages don't count; see
+    /// [`StatsSnapshot::failed`].
+    pub fn is_success(&self) -> bool {
+        self.seeds.iter().all(|seed| seed.result.is_ok())
+    }
+}
+
+/// Outcome of crawling one seed.
+#[derive(Debug)]
+pub struct SeedReport {
+    /// The seed URL.
+    pub url: String,
+    /// The seed's counters, or why its crawl couldn't run.
+    pub result: Result<StatsSnapshot>,
+}
+
+/// A skill written by the crawl.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportedSkill {
+    /// Name of the skill's directory (or file, with `flat`).
+    pub name: String,
+    /// Page the skill was generated from.
+    pub url: String,
+}
+
+impl CrawlBuilder {
+    /// Starts a crawl of `url` with the default [`Config`].
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            urls: vec![url.into()],
+            config: Config::default(),
+            output: None,
+            max_pages: None,
+            observers: Vec::new(),
+        }
+    }
+
+    /// Adds another start URL, crawled in the same session.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.urls.push(url.into());
+        self
+    }
+
+    /// Crawls with `config` instead of the default one.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Writes skills to `dir` instead of the directory of the config's
+    /// target. Fails to run with several targets, like `--output`.
+    pub fn output(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output = Some(dir.into());
+        self
+    }
+
+    /// Stops after fetching `max_pages` pages per seed, overriding the
+    /// config's `max_pages`.
+    pub fn max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Tells `observer` about the pages fetched, skills written and pages
+    /// failed, alongside the logging observer every crawl has.
+    pub fn observer(mut self, observer: Arc<dyn CrawlObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Runs the crawl. Errors only for an invalid setup (config, output
+    /// directory); a seed that fails to crawl is recorded in
+    /// [`CrawlReport::seeds`] and the others still run.
+    pub async fn run(self) -> Result<CrawlReport> {
+        let Self {
+            urls,
+            mut config,
+            output,
+            max_pages,
+            mut observers,
+        } = self;
+        observers.insert(0, Arc::new(LogObserver));
+        if let Some(max_pages) = max_pages {
+            config.max_pages = max_pages;
+        }
+        config.validate()?;
+
+        let output_dirs = match output {
+            Some(_) if config.targets().len() > 1 => anyhow::bail!(
+                "An output directory can't be used with several targets; each target is written to its own directory"
+            ),
+            Some(output) => vec![output],
+            None => config.resolve_output_paths(),
+        };
+
+        let rate_limiter = Arc::new(RateLimiter::from_config(&config));
+        let robots_client =
+            http::build_http_client(&config)?.with_rate_limiter(Arc::clone(&rate_limiter));
+        let mut crawl_delays = HashMap::new();
+        let skill_names =
+            Arc::new(SkillNames::new(&config.targets()).with_group_by(config.group_by));
+        let token_budget = Arc::new(TokenBudget::new(config.max_total_tokens));
+        let visited = Arc::new(VisitedUrls::new());
+        let skills = Arc::new(Mutex::new(Vec::new()));
+
+        let mut report = CrawlReport {
+            output_dirs: output_dirs.clone(),
+            ..Default::default()
+        };
+        for scope in scope::scope_all(&config, &urls) {
+            let scope::CrawlScope {
+                base_url,
+                config: crawl_config,
+            } = scope;
+
+            let mut seeds = vec![base_url.clone()];
+            if let Some(ref selector) = crawl_config.entry_selector {
+                match crawler::resolve_entry_seeds(&crawl_config, &base_url, selector).await {
+                    Ok(derived) => seeds.extend(derived),
+                    Err(e) => {
+                        error!("Failed to derive entry seeds from {}: {:?}", base_url, e);
+                    }
+                }
+            }
+
+            for seed in seeds {
+                let mut seed_config = crawl_config.clone();
+                if seed_config.respect_robots_txt {
+                    robots::apply_crawl_delay(
+                        &mut seed_config,
+                        &seed,
+                        &robots_client,
+                        &mut crawl_delays,
+                    )
+                    .await;
+                }
+
+                let skills = Arc::clone(&skills);
+                let crawl = async {
+                    let mut crawler = Crawler::new(seed_config, output_dirs.clone())?
+                        .with_names(Arc::clone(&skill_names))
+                        .with_visited(Arc::clone(&visited))
+                        .with_token_budget(Arc::clone(&token_budget))
+                        .with_rate_limiter(Arc::clone(&rate_limiter))
+                        .with_page_handler(move |page| {
+                            let skill = ReportedSkill {
+                                name: page.metadata.skill_name.clone(),
+                                url: page.metadata.url.clone(),
+                            };
+                            skills.lock().unwrap_or_else(|e| e.into_inner()).push(skill);
+                            Ok(())
+                        });
+                    for observer in &observers {
+                        crawler = crawler.with_observer(Arc::clone(observer));
+                    }
+                    crawler.crawl(&seed).await
+                };
+                let result = crawl.await.map(|stats| stats.snapshot());
+                if let Ok(stats) = result {
+                    report.stats += stats;
+                }
+                report.seeds.push(SeedReport { url: seed, result });
+            }
+        }
+
+        info!("Crawl complete: {}", report.stats.summary());
+        if report.is_success() {
+            hooks::run(&config, &report.stats, &output_dirs).await;
+        }
+
+        report.skills = std::mem::take(&mut *skills.lock().unwrap_or_else(|e| e.into_inner()));
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SkillsTarget;
+    use crate::http::{ok_response, test_server};
+
+    fn respond(request: &str) -> String {
+        let path = request.split_whitespace().nth(1).unwrap_or("/");
+        match path {
+            "/" => ok_response(
+                r#"<html><head><title>Guide</title></head><body><main>
+<h1>Guide</h1>
+<p>Start with the installation page, then read about configuration.</p>
+<a href="/install/">Install</a>
+</main></body></html>"#,
+            ),
+            "/install/" => ok_response(
+                r#"<html><head><title>Install</title></head><body><main>
+<h1>Install</h1>
+<p>Download the binary for your platform and put it on your PATH.</p>
+</main></body></html>"#,
+            ),
+            _ => "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                .to_string(),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_crawl_builder() {
+        let port = test_server(respond).await;
+        let output_dir =
+            std::env::temp_dir().join(format!("agent-skills-builder-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        let config = Config {
+            delay_ms: 0,
+            respect_robots_txt: false,
+            min_content_chars: 0,
+            ..Default::default()
+        };
+        let report = CrawlBuilder::new(format!("http://127.0.0.1:{}/", port))
+            .config(config)
+            .output(&output_dir)
+            .max_pages(10)
+            .run()
+            .await
+            .unwrap();
+
+        assert!(report.is_success());
+        assert_eq!(report.seeds.len(), 1);
+        assert_eq!(report.stats.processed, 2);
+        assert_eq!(report.output_dirs, std::slice::from_ref(&output_dir));
+        let mut urls: Vec<&str> = report
+            .skills
+            .iter()
+            .map(|skill| skill.url.as_str())
+            .collect();
+        urls.sort();
+        assert_eq!(
+            urls,
+            [
+                format!("http://127.0.0.1:{}/", port),
+                format!("http://127.0.0.1:{}/install/", port)
+            ]
+        );
+        for skill in &report.skills {
+            assert!(output_dir.join(&skill.name).join("SKILL.md").is_file());
+        }
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_output_needs_a_single_target() {
+        let mut config = Config::default();
+        config.set_targets(&[SkillsTarget::ClaudeCode, SkillsTarget::OpenAICodex]);
+        let err = CrawlBuilder::new("https://docs.example.com/")
+            .config(config)
+            .output("skills")
+            .run()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("several targets"), "{}", err);
+    }
+}
@@ -0,0 +1,251 @@
+//! Crawl cache for incremental crawls in the agent-skills-generator.
+//!
+//! With `incremental: true`, every written skill is recorded in
+//! `.crawl-cache.json` in the output directory together with the page's
+//! `ETag`, `Last-Modified` and a hash of its HTML. The next crawl revalidates
+//! known pages with conditional requests and skips regenerating pages that
+//! answer `304 Not Modified` or whose content hash hasn't changed.
+
+use crate::config::SkillFormat;
+use anyhow::{Context, Result};
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Name of the cache file in the output directory.
+pub const CACHE_FILE: &str = ".crawl-cache.json";
+
+/// Bumped when the file format changes; older caches are discarded.
+const CACHE_VERSION: u32 = 1;
+
+/// What is known about a page from the last crawl that wrote it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// [`content_hash`] of the page's HTML.
+    pub content_hash: String,
+    /// Directory the skill was written to.
+    pub skill_name: String,
+}
+
+impl CacheEntry {
+    /// Builds the entry of a page written to `skill_name`, taking the
+    /// validators from its response headers.
+    pub fn new(headers: Option<&HeaderMap>, content_hash: String, skill_name: &str) -> Self {
+        let validator =
+            |name: header::HeaderName| headers?.get(name)?.to_str().ok().map(str::to_string);
+
+        Self {
+            etag: validator(header::ETAG),
+            last_modified: validator(header::LAST_MODIFIED),
+            content_hash,
+            skill_name: skill_name.to_string(),
+        }
+    }
+
+    /// Conditional request headers revalidating this entry. Empty if the
+    /// server sent neither an `ETag` nor a `Last-Modified` header.
+    pub fn conditions(&self) -> HeaderMap {
+        let mut conditions = HeaderMap::new();
+        let validators = [
+            (header::IF_NONE_MATCH, &self.etag),
+            (header::IF_MODIFIED_SINCE, &self.last_modified),
+        ];
+        for (name, value) in validators {
+            if let Some(value) = value.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                conditions.insert(name, value);
+            }
+        }
+        conditions
+    }
+}
+
+/// On-disk layout of the cache file.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    pages: BTreeMap<String, CacheEntry>,
+}
+
+/// URL -> [`CacheEntry`] map backed by the cache file of an output directory.
+#[derive(Debug)]
+pub struct CrawlCache {
+    output_dir: PathBuf,
+    pages: Mutex<BTreeMap<String, CacheEntry>>,
+}
+
+impl CrawlCache {
+    /// Loads the cache of `output_dir`. A missing, unreadable or outdated
+    /// cache file gives an empty cache, so the crawl regenerates everything.
+    pub fn load(output_dir: &Path) -> Self {
+        let path = output_dir.join(CACHE_FILE);
+        let pages = match fs_err::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<CacheFile>(&content) {
+                Ok(file) if file.version == CACHE_VERSION => file.pages,
+                Ok(_) => BTreeMap::new(),
+                Err(e) => {
+                    warn!("Ignoring invalid crawl cache {}: {}", path.display(), e);
+                    BTreeMap::new()
+                }
+            },
+            Err(_) => BTreeMap::new(),
+        };
+
+        Self {
+            output_dir: output_dir.to_path_buf(),
+            pages: Mutex::new(pages),
+        }
+    }
+
+    /// Returns the entry for `url`, if its skill is still on disk (in either
+    /// output layout and any format).
+    pub fn get(&self, url: &str) -> Option<CacheEntry> {
+        let pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        pages
+            .get(url)
+            .filter(|entry| {
+                let skill_dir = self.output_dir.join(&entry.skill_name);
+                SkillFormat::ALL.iter().any(|format| {
+                    skill_dir.join(format.file_name()).is_file()
+                        || skill_dir.with_extension(format.extension()).is_file()
+                })
+            })
+            .cloned()
+    }
+
+    /// Records the entry for `url`, replacing any previous one.
+    pub fn record(&self, url: &str, entry: CacheEntry) {
+        let mut pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        pages.insert(url.to_string(), entry);
+    }
+
+    /// Every cached URL, sorted.
+    pub fn urls(&self) -> Vec<String> {
+        let pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        pages.keys().cloned().collect()
+    }
+
+    /// Writes the cache file, replacing it atomically.
+    pub fn save(&self) -> Result<()> {
+        let file = CacheFile {
+            version: CACHE_VERSION,
+            pages: self.pages.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        };
+        let json =
+            serde_json::to_string_pretty(&file).context("Failed to serialize crawl cache")?;
+
+        let path = self.output_dir.join(CACHE_FILE);
+        let temp = path.with_extension("json.tmp");
+        fs_err::write(&temp, json)?;
+        fs_err::rename(&temp, &path)
+            .with_context(|| format!("Failed to write crawl cache: {}", path.display()))
+    }
+}
+
+/// Hash of a page's HTML, used to detect changes when the server sends no
+/// validators, and to name downloaded images. 64-bit FNV-1a: stable across
+/// Rust releases and platforms.
+pub fn content_hash(content: impl AsRef<[u8]>) -> String {
+    let hash = content
+        .as_ref()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-skills-cache-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(skill_name: &str) -> CacheEntry {
+        CacheEntry {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            content_hash: content_hash("<html>v1</html>"),
+            skill_name: skill_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = test_dir("round-trip");
+        std::fs::create_dir_all(dir.join("docs-intro")).unwrap();
+        std::fs::write(dir.join("docs-intro").join("SKILL.md"), "---\n").unwrap();
+
+        let cache = CrawlCache::load(&dir);
+        assert!(cache.urls().is_empty());
+        cache.record("https://docs.example.com/intro", entry("docs-intro"));
+        cache.save().unwrap();
+
+        let reloaded = CrawlCache::load(&dir);
+        assert_eq!(
+            reloaded.get("https://docs.example.com/intro"),
+            Some(entry("docs-intro"))
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_conditions_from_validators() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, HeaderValue::from_static("\"abc\""));
+        let entry = CacheEntry::new(Some(&headers), content_hash("<html></html>"), "docs");
+
+        let conditions = entry.conditions();
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[header::IF_NONE_MATCH], "\"abc\"");
+
+        assert!(
+            CacheEntry::new(None, content_hash(""), "docs")
+                .conditions()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_entries_without_skill_on_disk_are_ignored() {
+        let dir = test_dir("missing-skill");
+
+        let cache = CrawlCache::load(&dir);
+        cache.record("https://docs.example.com/gone", entry("docs-gone"));
+        assert_eq!(cache.get("https://docs.example.com/gone"), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_cache_file_is_ignored() {
+        let dir = test_dir("invalid");
+        std::fs::write(dir.join(CACHE_FILE), "not json").unwrap();
+
+        assert!(CrawlCache::load(&dir).urls().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_ne!(content_hash("<p>a</p>"), content_hash("<p>b</p>"));
+    }
+}
@@ -0,0 +1,259 @@
+//! Code block normalization for the agent-skills-generator.
+//!
+//! Syntax highlighters (Prism, Shiki, highlight.js, Pygments) wrap every
+//! token of a code block in its own `<span>`, sometimes every line in its own
+//! `<div>`, and put the language wherever they like: a `language-xxx` class
+//! on `<pre>` or `<code>`, a `data-language` attribute, or a class on a
+//! wrapper `<div>`. htmd only looks for `language-xxx` on `<pre>`/`<code>`,
+//! so each block is rewritten as plain `<pre><code class="language-xxx">`
+//! around its flattened text before conversion, giving a ```` ```xxx ````
+//! fence with the code intact.
+
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// How many ancestors of a `<pre>` are searched for the language, to reach
+/// wrappers like `<div class="highlight-source-rust"><div><pre>`.
+const WRAPPER_DEPTH: usize = 3;
+
+/// Language attributes, most specific first.
+const LANGUAGE_ATTRIBUTES: [&str; 2] = ["data-language", "data-lang"];
+
+/// Class prefixes that carry the language: `language-rust` (Prism, hljs),
+/// `lang-rust` (prettify), `highlight-source-rust` (GitHub) and
+/// `highlight-rust` (Sphinx).
+const LANGUAGE_CLASS_PREFIXES: [&str; 4] =
+    ["language-", "lang-", "highlight-source-", "highlight-"];
+
+/// Languages that highlighters use for "no language".
+const NO_LANGUAGE: [&str; 5] = ["none", "text", "plain", "plaintext", "default"];
+
+/// Classes of elements that only hold line numbers.
+const LINE_NUMBER_CLASSES: [&str; 4] = ["linenos", "lineno", "line-number", "line-numbers"];
+
+/// Rewrites every `<pre>` in `html` as `<pre><code class="language-xxx">`
+/// around its plain text, `xxx` being the language found on the block or
+/// its wrappers.
+pub fn normalize(html: &str) -> String {
+    if !html.contains("<pre") {
+        return html.to_string();
+    }
+
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("pre").expect("valid selector");
+
+    let mut source = document.html();
+    for pre in document.select(&selector) {
+        let mut ancestors = pre.ancestors().filter_map(ElementRef::wrap);
+        if ancestors.any(|e| e.value().name() == "pre") {
+            continue;
+        }
+
+        let pre_html = pre.html();
+        if !source.contains(&pre_html) {
+            continue;
+        }
+
+        // Pygments tables put the line numbers in a <pre> of their own
+        let replacement = if is_line_numbers(pre) {
+            String::new()
+        } else {
+            let class = language(pre)
+                .map(|language| format!(" class=\"language-{}\"", language))
+                .unwrap_or_default();
+            format!(
+                "<pre><code{}>{}</code></pre>",
+                class,
+                escape(&code_text(pre))
+            )
+        };
+        source = source.replacen(&pre_html, &replacement, 1);
+    }
+
+    source
+}
+
+/// The language of a code block: from its `<code>`, the `<pre>` itself, or
+/// one of its wrappers, in that order.
+pub fn language(pre: ElementRef) -> Option<String> {
+    let code = pre
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|e| e.value().name() == "code");
+    let wrappers = pre
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .take(WRAPPER_DEPTH);
+
+    code.into_iter()
+        .chain([pre])
+        .chain(wrappers)
+        .find_map(element_language)
+}
+
+fn element_language(element: ElementRef) -> Option<String> {
+    let value = element.value();
+    let from_attribute = LANGUAGE_ATTRIBUTES
+        .iter()
+        .filter_map(|name| value.attr(name))
+        .find_map(clean_language);
+    from_attribute.or_else(|| {
+        value.classes().find_map(|class| {
+            LANGUAGE_CLASS_PREFIXES
+                .iter()
+                .find_map(|prefix| class.strip_prefix(prefix))
+                .and_then(clean_language)
+        })
+    })
+}
+
+/// Lowercases a language name, rejecting empty, "no language" and
+/// malformed ones.
+fn clean_language(language: &str) -> Option<String> {
+    let language = language.trim().to_ascii_lowercase();
+    let valid = !language.is_empty()
+        && !NO_LANGUAGE.contains(&language.as_str())
+        && language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '#' | '-' | '_' | '.'));
+    valid.then_some(language)
+}
+
+fn is_line_numbers(element: ElementRef) -> bool {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .take(WRAPPER_DEPTH)
+        .chain([element])
+        .any(|e| {
+            e.value()
+                .classes()
+                .any(|c| LINE_NUMBER_CLASSES.contains(&c))
+        })
+}
+
+/// The text of a code block, with `<br>`s and per-line `<div>`s turned
+/// into line breaks and line-number gutters left out.
+pub fn code_text(pre: ElementRef) -> String {
+    let mut text = String::new();
+    push_text(pre, &mut text);
+    text.trim_end_matches('\n').to_string()
+}
+
+fn push_text(element: ElementRef, text: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(t) => text.push_str(t),
+            Node::Element(e) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                if e.name() == "br" {
+                    text.push('\n');
+                    continue;
+                }
+                if e.name() == "button" || e.classes().any(|c| LINE_NUMBER_CLASSES.contains(&c)) {
+                    continue;
+                }
+
+                let is_line = e.name() == "div";
+                if is_line && !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                push_text(child, text);
+                if is_line && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_pre(html: &str) -> (Option<String>, String) {
+        let document = Html::parse_fragment(html);
+        let selector = Selector::parse("pre").unwrap();
+        let pre = document.select(&selector).next().unwrap();
+        (language(pre), code_text(pre))
+    }
+
+    #[test]
+    fn test_prism_block() {
+        let html = r#"<pre class="language-rust" tabindex="0"><code class="language-rust"><span class="token keyword">fn</span> <span class="token function-definition function">main</span><span class="token punctuation">(</span><span class="token punctuation">)</span> <span class="token punctuation">{</span>
+    <span class="token macro property">println!</span><span class="token punctuation">(</span><span class="token string">"a &lt; b"</span><span class="token punctuation">)</span><span class="token punctuation">;</span>
+<span class="token punctuation">}</span>
+</code></pre>"#;
+
+        let (language, text) = first_pre(html);
+        assert_eq!(language.as_deref(), Some("rust"));
+        assert_eq!(text, "fn main() {\n    println!(\"a < b\");\n}");
+        assert!(normalize(html).contains(
+            "<pre><code class=\"language-rust\">fn main() {\n    println!(\"a &lt; b\");\n}</code></pre>"
+        ));
+    }
+
+    #[test]
+    fn test_shiki_block() {
+        let html = r#"<pre class="astro-code github-dark" style="background-color:#24292e;color:#e1e4e8; overflow-x: auto;" tabindex="0" data-language="ts"><code><span class="line"><span style="color:#F97583">const</span><span style="color:#79B8FF"> answer</span><span style="color:#F97583"> =</span><span style="color:#79B8FF"> 42</span><span style="color:#E1E4E8">;</span></span>
+<span class="line"><span style="color:#E1E4E8">console.</span><span style="color:#B392F0">log</span><span style="color:#E1E4E8">(answer);</span></span></code></pre>"#;
+
+        let (language, text) = first_pre(html);
+        assert_eq!(language.as_deref(), Some("ts"));
+        assert_eq!(text, "const answer = 42;\nconsole.log(answer);");
+    }
+
+    #[test]
+    fn test_wrapper_languages() {
+        // GitHub
+        let (language, text) = first_pre(
+            r#"<div class="highlight highlight-source-python notranslate position-relative overflow-auto"><pre><span class="pl-k">import</span> <span class="pl-s1">os</span></pre></div>"#,
+        );
+        assert_eq!(language.as_deref(), Some("python"));
+        assert_eq!(text, "import os");
+
+        // Docusaurus, one span per line ending in <br>
+        let (language, text) = first_pre(
+            r#"<div class="language-bash theme-code-block"><div class="codeBlockContent"><pre tabindex="0" class="prism-code language-bash codeBlock thin-scrollbar"><code class="codeBlockLines"><span class="token-line"><span class="token function">npm</span><span class="token plain"> </span><span class="token function">install</span><br></span><span class="token-line"><span class="token function">npm</span><span class="token plain"> start</span><br></span></code></pre><button type="button" aria-label="Copy code to clipboard">Copy</button></div></div>"#,
+        );
+        assert_eq!(language.as_deref(), Some("bash"));
+        assert_eq!(text, "npm install\nnpm start");
+
+        // Prism's "no language"
+        let (language, _) = first_pre(r#"<pre class="language-none"><code>plain</code></pre>"#);
+        assert_eq!(language, None);
+    }
+
+    #[test]
+    fn test_div_per_line_block() {
+        let (language, text) = first_pre(
+            r#"<pre data-language="go"><code><div class="ec-line"><div class="code"><span>x</span><span> := </span><span>1</span></div></div><div class="ec-line"><div class="code"><span>y</span><span> := x</span></div></div></code></pre>"#,
+        );
+        assert_eq!(language.as_deref(), Some("go"));
+        assert_eq!(text, "x := 1\ny := x");
+    }
+
+    #[test]
+    fn test_line_numbers_are_dropped() {
+        let html = r#"<table class="highlighttable"><tr><td class="linenos"><div class="linenodiv"><pre><span class="normal">1</span>
+<span class="normal">2</span></pre></div></td><td class="code"><div class="highlight-python"><pre><span></span><span class="n">a</span> <span class="o">=</span> <span class="mi">1</span>
+<span class="n">b</span> <span class="o">=</span> <span class="mi">2</span>
+</pre></div></td></tr></table>"#;
+
+        let normalized = normalize(html);
+        assert!(!normalized.contains(">1\n2<"), "{}", normalized);
+        assert!(
+            normalized.contains("<pre><code class=\"language-python\">a = 1\nb = 2</code></pre>"),
+            "{}",
+            normalized
+        );
+    }
+}
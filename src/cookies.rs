@@ -0,0 +1,265 @@
+//! Cookie support for the agent-skills-generator.
+//!
+//! Docs behind SSO can be crawled with a session exported from a browser,
+//! either as a raw `Cookie` header bound to one domain or as a Netscape
+//! `cookies.txt` file. Cookies are only ever sent to hosts they are scoped
+//! to, so a multi-URL crawl never leaks a corporate session to another site,
+//! and their values never appear in logs.
+
+use crate::config::CookieConfig;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// A single cookie and where it may be sent.
+#[derive(Clone, PartialEq, Eq)]
+struct Cookie {
+    /// Domain without a leading dot.
+    domain: String,
+    include_subdomains: bool,
+    path: String,
+    secure: bool,
+    /// Unix timestamp; `0` for session cookies.
+    expires: i64,
+    name: String,
+    value: String,
+}
+
+/// Cookies loaded from the `cookies` config option.
+#[derive(Clone, Default)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl std::fmt::Debug for CookieJar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.cookies
+                    .iter()
+                    .map(|c| format!("{}=<redacted> ({})", c.name, c.domain)),
+            )
+            .finish()
+    }
+}
+
+impl CookieJar {
+    /// Loads the cookies described by `config`, reading the cookie file if
+    /// one is given.
+    pub fn load(config: &CookieConfig) -> Result<Self> {
+        let mut jar = Self::default();
+
+        if let Some(ref header) = config.header {
+            let domain = config
+                .domain
+                .as_deref()
+                .context("cookies.header needs cookies.domain so it isn't sent to every site")?;
+            jar.add_header(header, domain);
+        }
+
+        if let Some(ref file) = config.file {
+            jar.add_netscape_file(file)?;
+        }
+
+        Ok(jar)
+    }
+
+    /// Adds every `name=value` pair of a raw `Cookie` header, scoped to
+    /// `domain` and its subdomains.
+    fn add_header(&mut self, header: &str, domain: &str) {
+        let domain = normalize_domain(domain);
+        for pair in header.split(';') {
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+            self.cookies.push(Cookie {
+                domain: domain.clone(),
+                include_subdomains: true,
+                path: "/".to_string(),
+                secure: false,
+                expires: 0,
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+            });
+        }
+    }
+
+    /// Adds the cookies of a Netscape `cookies.txt` file.
+    fn add_netscape_file(&mut self, path: &Path) -> Result<()> {
+        let content = fs_err::read_to_string(path)
+            .with_context(|| format!("Failed to read cookie file: {}", path.display()))?;
+        self.cookies.extend(
+            parse_netscape(&content)
+                .with_context(|| format!("Invalid cookie file: {}", path.display()))?,
+        );
+        Ok(())
+    }
+
+    /// Number of cookies loaded.
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    /// Whether no cookies were loaded.
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    /// Builds the `Cookie` header for `url` from the unexpired cookies
+    /// scoped to it, or `None` if there are none.
+    pub fn header_for(&self, url: &str) -> Option<String> {
+        let url = url::Url::parse(url).ok()?;
+        let host = url.host_str()?.to_lowercase();
+        let now = chrono::Utc::now().timestamp();
+
+        let pairs: Vec<String> = self
+            .cookies
+            .iter()
+            .filter(|c| c.expires == 0 || c.expires > now)
+            .filter(|c| !c.secure || url.scheme() == "https")
+            .filter(|c| url.path().starts_with(&c.path))
+            .filter(|c| {
+                host == c.domain
+                    || (c.include_subdomains && host.ends_with(&format!(".{}", c.domain)))
+            })
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+
+        (!pairs.is_empty()).then(|| pairs.join("; "))
+    }
+
+    /// Like [`Self::header_for`], as a header value marked sensitive so it
+    /// is redacted from debug output.
+    pub fn header_value_for(&self, url: &str) -> Result<Option<reqwest::header::HeaderValue>> {
+        let Some(header) = self.header_for(url) else {
+            return Ok(None);
+        };
+
+        let mut value = reqwest::header::HeaderValue::from_str(&header)
+            .context("Cookie values contain invalid characters")?;
+        value.set_sensitive(true);
+        Ok(Some(value))
+    }
+}
+
+/// Parses a Netscape `cookies.txt` body.
+///
+/// Each line holds seven tab-separated fields: domain, include-subdomains,
+/// path, secure, expiry, name and value. `#HttpOnly_` prefixed lines are
+/// cookies; other `#` lines are comments.
+fn parse_netscape(content: &str) -> Result<Vec<Cookie>> {
+    let mut cookies = Vec::new();
+
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        let line = match line.strip_prefix("#HttpOnly_") {
+            Some(rest) => rest,
+            None if line.starts_with('#') || line.trim().is_empty() => continue,
+            None => line,
+        };
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [
+            domain,
+            include_subdomains,
+            path,
+            secure,
+            expires,
+            name,
+            value,
+        ] = fields[..]
+        else {
+            anyhow::bail!(
+                "line {}: expected 7 tab-separated fields, found {}",
+                number + 1,
+                fields.len()
+            );
+        };
+
+        cookies.push(Cookie {
+            domain: normalize_domain(domain),
+            include_subdomains: include_subdomains.eq_ignore_ascii_case("TRUE"),
+            path: path.to_string(),
+            secure: secure.eq_ignore_ascii_case("TRUE"),
+            expires: expires
+                .trim()
+                .parse()
+                .with_context(|| format!("line {}: invalid expiry '{}'", number + 1, expires))?,
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    Ok(cookies)
+}
+
+fn normalize_domain(domain: &str) -> String {
+    domain.trim().trim_start_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOKIES_TXT: &str = "# Netscape HTTP Cookie File\n\
+        \n\
+        .corp.example\tTRUE\t/\tTRUE\t0\tsso\tabc123\n\
+        #HttpOnly_docs.corp.example\tFALSE\t/api\tFALSE\t0\tapi_session\txyz\n\
+        docs.corp.example\tFALSE\t/\tFALSE\t1\told\texpired\n";
+
+    fn jar(content: &str) -> CookieJar {
+        CookieJar {
+            cookies: parse_netscape(content).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_netscape_cookies_are_scoped() {
+        let jar = jar(COOKIES_TXT);
+        assert_eq!(jar.len(), 3);
+
+        assert_eq!(
+            jar.header_for("https://docs.corp.example/api/v1")
+                .as_deref(),
+            Some("sso=abc123; api_session=xyz")
+        );
+        // Path, secure flag and domain all limit where cookies go
+        assert_eq!(
+            jar.header_for("https://docs.corp.example/guide").as_deref(),
+            Some("sso=abc123")
+        );
+        assert_eq!(jar.header_for("http://wiki.corp.example/"), None);
+        assert_eq!(jar.header_for("https://docs.flutter.dev/"), None);
+        assert_eq!(jar.header_for("https://notcorp.example/"), None);
+    }
+
+    #[test]
+    fn test_raw_header_needs_and_respects_domain() {
+        let config = CookieConfig {
+            header: Some("session=s3cret; theme=dark".to_string()),
+            domain: None,
+            file: None,
+        };
+        assert!(CookieJar::load(&config).is_err());
+
+        let jar = CookieJar::load(&CookieConfig {
+            domain: Some("docs.corp.example".to_string()),
+            ..config
+        })
+        .unwrap();
+
+        assert_eq!(
+            jar.header_for("https://docs.corp.example/page").as_deref(),
+            Some("session=s3cret; theme=dark")
+        );
+        assert_eq!(jar.header_for("https://example.com/"), None);
+        assert!(!format!("{:?}", jar).contains("s3cret"));
+    }
+
+    #[test]
+    fn test_malformed_cookie_file() {
+        let Err(err) = parse_netscape("docs.corp.example\tFALSE\t/\n") else {
+            panic!("expected an error");
+        };
+        assert!(err.to_string().contains("line 1"));
+    }
+}
@@ -7,18 +7,53 @@
 //! - Respect for robots.txt and polite crawling delays
 //! - URL filtering based on configuration rules using globset
 
-use crate::config::Config;
-use crate::processor::Processor;
+use crate::auth::Authorization;
+use crate::cache::{CacheEntry, CrawlCache, content_hash};
+use crate::config::{
+    Config, QueryParams, SkillWriter, SkillsTarget, UrlFilter, is_html_content_type,
+    is_included_content_type,
+};
+use crate::cookies::CookieJar;
+use crate::diff::CrawlDiff;
+use crate::events::{CrawlEvent, CrawlReporter};
+use crate::frontmatter;
+use crate::graph::LinkGraph;
+use crate::http::{
+    ProxySettings, Revalidation, build_http_client, fetch_if_modified, redact_proxy,
+};
+use crate::naming::{NameMapping, SkillNames};
+use crate::observer::CrawlObserver;
+use crate::processor::{
+    OtherLanguage, PageBody, PageContext, PageMetadata, PageTooSmall, ProcessedPage, Processor,
+    SkippedPage, WrittenSkill,
+};
+use crate::rate_limit::RateLimiter;
+use crate::sink::{CollectSink, FsSink, JSONL_FILE, JsonlSink, OutputSink};
+use crate::tokens::{self, TokenBudget};
 use anyhow::{Context, Result};
+use globset::GlobMatcher;
+use scraper::{Html, Selector};
+use serde::Serialize;
 use spider::page::Page;
 use spider::website::Website;
+use std::collections::{BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
-use tokio::sync::Semaphore;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{Semaphore, broadcast};
+use tokio::task::{JoinError, JoinSet};
 use tracing::{debug, error, info, warn};
 
+/// Extensions of images, fonts, media, scripts, styles, documents and
+/// archives, kept out with a spider blacklist pattern when its own asset
+/// filter is off.
+const BINARY_ASSET_EXTENSIONS: &[&str] = &[
+    "png", "jpe?g", "gif", "svg", "webp", "avif", "ico", "bmp", "tiff?", "css", "js", "mjs", "map",
+    "woff2?", "ttf", "otf", "eot", "mp3", "mp4", "m4a", "webm", "mov", "avi", "ogg", "wav", "flac",
+    "pdf", "zip", "gz", "tgz", "tar", "bz2", "xz", "rar", "7z", "jar", "exe", "dmg", "wasm",
+];
+
 /// Statistics for a crawl session.
 #[derive(Debug, Default)]
 pub struct CrawlStats {
@@ -30,6 +65,20 @@ pub struct CrawlStats {
     pub pages_skipped: AtomicUsize,
     /// Pages that failed to process.
     pub pages_failed: AtomicUsize,
+    /// Pages lost because the subscription buffer overflowed.
+    pub pages_dropped: AtomicUsize,
+    /// Pages answered with 401 or 403.
+    pub pages_unauthorized: AtomicUsize,
+    /// Pages unchanged since the last incremental crawl.
+    pub pages_unchanged: AtomicUsize,
+    /// Pages with less content than `min_content_chars`.
+    pub pages_too_small: AtomicUsize,
+    /// Copies of a canonical page the crawl covers, with `respect_canonical`.
+    pub pages_deduplicated: AtomicUsize,
+    /// Pages in a language that isn't one of `languages`.
+    pub pages_skipped_language: AtomicUsize,
+    /// Estimated tokens of the skills generated, or kept unchanged.
+    pub tokens: AtomicUsize,
 }
 
 impl CrawlStats {
@@ -38,47 +87,302 @@ impl CrawlStats {
         Self::default()
     }
 
+    /// Returns a point-in-time copy of the counters.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            visited: self.pages_visited.load(Ordering::Relaxed),
+            processed: self.pages_processed.load(Ordering::Relaxed),
+            skipped: self.pages_skipped.load(Ordering::Relaxed),
+            failed: self.pages_failed.load(Ordering::Relaxed),
+            dropped: self.pages_dropped.load(Ordering::Relaxed),
+            unauthorized: self.pages_unauthorized.load(Ordering::Relaxed),
+            unchanged: self.pages_unchanged.load(Ordering::Relaxed),
+            too_small: self.pages_too_small.load(Ordering::Relaxed),
+            deduplicated: self.pages_deduplicated.load(Ordering::Relaxed),
+            skipped_language: self.pages_skipped_language.load(Ordering::Relaxed),
+            tokens: self.tokens.load(Ordering::Relaxed),
+        }
+    }
+
     /// Returns a summary of the crawl.
+    pub fn summary(&self) -> String {
+        format!("Crawl complete: {}", self.snapshot().summary())
+    }
+}
+
+/// Plain copy of [`CrawlStats`], used in machine-readable output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct StatsSnapshot {
+    pub visited: usize,
+    pub processed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub dropped: usize,
+    pub unauthorized: usize,
+    pub unchanged: usize,
+    pub too_small: usize,
+    pub deduplicated: usize,
+    pub skipped_language: usize,
+    pub tokens: usize,
+}
+
+impl StatsSnapshot {
+    /// The counters as one line, like `10 visited, 8 processed, ...`.
     pub fn summary(&self) -> String {
         format!(
-            "Crawl complete: {} visited, {} processed, {} skipped, {} failed",
-            self.pages_visited.load(Ordering::Relaxed),
-            self.pages_processed.load(Ordering::Relaxed),
-            self.pages_skipped.load(Ordering::Relaxed),
-            self.pages_failed.load(Ordering::Relaxed),
+            "{} visited, {} processed, {} unchanged, {} skipped, {} too small, {} deduplicated, {} in other languages, {} failed, {} dropped, {} unauthorized; ~{} tokens",
+            self.visited,
+            self.processed,
+            self.unchanged,
+            self.skipped,
+            self.too_small,
+            self.deduplicated,
+            self.skipped_language,
+            self.failed,
+            self.dropped,
+            self.unauthorized,
+            tokens::format_count(self.tokens),
         )
     }
 }
 
+impl std::ops::AddAssign for StatsSnapshot {
+    fn add_assign(&mut self, other: Self) {
+        self.visited += other.visited;
+        self.processed += other.processed;
+        self.skipped += other.skipped;
+        self.failed += other.failed;
+        self.dropped += other.dropped;
+        self.unauthorized += other.unauthorized;
+        self.unchanged += other.unchanged;
+        self.too_small += other.too_small;
+        self.deduplicated += other.deduplicated;
+        self.skipped_language += other.skipped_language;
+        self.tokens += other.tokens;
+    }
+}
+
+/// Called with every page a crawl processes, see [`Crawler::with_page_handler`].
+pub type PageHandler = dyn Fn(&ProcessedPage) -> Result<()> + Send + Sync;
+
+/// Pages already crawled, shared by the crawlers of a run so a page
+/// reachable from several seeds is only fetched and processed once.
+#[derive(Debug, Default)]
+pub struct VisitedUrls {
+    urls: Mutex<HashSet<String>>,
+}
+
+impl VisitedUrls {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks `url` as crawled. Returns whether it wasn't before.
+    pub fn insert(&self, url: &str) -> bool {
+        self.urls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(url.to_string())
+    }
+
+    /// Whether `url` has been crawled.
+    pub fn contains(&self, url: &str) -> bool {
+        self.urls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(url)
+    }
+
+    /// Every URL crawled so far, in no particular order.
+    pub fn urls(&self) -> Vec<String> {
+        let urls = self.urls.lock().unwrap_or_else(|e| e.into_inner());
+        urls.iter().cloned().collect()
+    }
+}
+
 /// Web crawler that processes pages and generates skill files.
 pub struct Crawler {
     /// Configuration for the crawler.
     config: Config,
-    /// Content processor - stored for potential future use in custom processing.
-    #[allow(dead_code)]
-    processor: Processor,
-    /// Output directory for generated skills.
-    output_dir: PathBuf,
+    /// Content processor, shared with the page processing tasks.
+    processor: Arc<Processor>,
+    /// Output directory of each target; the first one holds the crawl cache.
+    outputs: Arc<[SkillOutput]>,
     /// Crawl statistics.
     stats: Arc<CrawlStats>,
+    /// Receives per-page events for `--json`/`--json-stream` output.
+    reporter: Option<Arc<CrawlReporter>>,
+    /// Hands out collision-free skill names, possibly shared with other crawlers.
+    names: Arc<SkillNames>,
+    /// Records what the crawl changes in the first output, for `--diff`.
+    diff: Option<Arc<CrawlDiff>>,
+    /// Records the crawl's link graph for `--graph`.
+    graph: Option<Arc<LinkGraph>>,
+    /// Pages crawled by other crawlers of the run, which this one skips.
+    visited: Option<Arc<VisitedUrls>>,
+    /// Called with every page processed, after it is written.
+    page_handler: Option<Arc<PageHandler>>,
+    /// Hear about the pages fetched, the skills written and the failures.
+    observers: Vec<Arc<dyn CrawlObserver>>,
+    /// Caps the tokens of the skills emitted, possibly shared with other crawlers.
+    budget: Arc<TokenBudget>,
+    /// Paces the requests made outside spider, possibly shared with other crawlers.
+    rate_limiter: Arc<RateLimiter>,
+}
+
+/// Where one target's copy of every skill is written.
+#[derive(Clone)]
+struct SkillOutput {
+    target: SkillsTarget,
+    /// Output directory of the target, also holding the crawl cache.
+    dir: PathBuf,
+    sink: Arc<dyn OutputSink>,
 }
 
 impl Crawler {
-    /// Creates a new crawler with the given configuration.
-    pub fn new(config: Config, output_dir: PathBuf) -> Result<Self> {
-        let processor = Processor::new(&config)?;
+    /// Creates a new crawler with the given configuration, writing skills to
+    /// `output_dirs`: one directory per target in [`Config::targets`], in
+    /// the same order.
+    pub fn new(config: Config, output_dirs: Vec<PathBuf>) -> Result<Self> {
+        let processor = Arc::new(Processor::new(&config)?);
 
         // Validate that URL filter can be built from config
         config.build_url_filter()?;
 
+        let targets = config.targets();
+        if output_dirs.len() != targets.len() {
+            anyhow::bail!(
+                "Expected one output directory per target ({}), got {}",
+                targets.len(),
+                output_dirs.len()
+            );
+        }
+        let outputs = targets
+            .iter()
+            .zip(output_dirs)
+            .map(|(&target, dir)| SkillOutput {
+                target,
+                sink: match config.writer {
+                    SkillWriter::Directory => {
+                        Arc::new(FsSink::new(Arc::clone(&processor), dir.clone()))
+                    }
+                    SkillWriter::Jsonl => Arc::new(JsonlSink::new(dir.join(JSONL_FILE))),
+                },
+                dir,
+            })
+            .collect();
+
         Ok(Self {
+            names: Arc::new(SkillNames::new(&targets).with_group_by(config.group_by)),
+            budget: Arc::new(TokenBudget::new(config.max_total_tokens)),
+            rate_limiter: Arc::new(RateLimiter::from_config(&config)),
             config,
             processor,
-            output_dir,
+            outputs,
             stats: Arc::new(CrawlStats::new()),
+            reporter: None,
+            diff: None,
+            graph: None,
+            visited: None,
+            page_handler: None,
+            observers: Vec::new(),
         })
     }
 
+    /// Allocates skill names from `names`, so crawlers sharing it never
+    /// write two pages to the same directory.
+    pub fn with_names(mut self, names: Arc<SkillNames>) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Sends crawl events to `reporter`, which may be shared between crawlers.
+    pub fn with_reporter(mut self, reporter: Arc<CrawlReporter>) -> Self {
+        self.reporter = Some(reporter);
+        self
+    }
+
+    /// Records every skill the crawl generates in `diff`, which may be
+    /// shared between crawlers. A dry-run diff writes nothing to disk.
+    pub fn with_diff(mut self, diff: Arc<CrawlDiff>) -> Self {
+        self.diff = Some(diff);
+        self
+    }
+
+    /// Records the links between the crawl's pages in `graph`, which may be
+    /// shared between crawlers.
+    pub fn with_link_graph(mut self, graph: Arc<LinkGraph>) -> Self {
+        self.graph = Some(graph);
+        self
+    }
+
+    /// Skips the pages in `visited` and adds the ones this crawl visits, so
+    /// crawlers sharing it never fetch or process a page twice. The seed
+    /// itself is still fetched to find the pages linked from it.
+    pub fn with_visited(mut self, visited: Arc<VisitedUrls>) -> Self {
+        self.visited = Some(visited);
+        self
+    }
+
+    /// Calls `handler` with every page processed, once its skill is
+    /// written, e.g. to store pages elsewhere or count them. It runs in
+    /// the page's processing task, within `concurrency`, so slow work is
+    /// better spawned off. Errors it returns are logged and the crawl goes
+    /// on.
+    pub fn with_page_handler(
+        mut self,
+        handler: impl Fn(&ProcessedPage) -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.page_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Adds `observer` to the ones told about each page fetched, skill
+    /// written and page failed; see [`CrawlObserver`]. Its hooks are awaited
+    /// in the page's processing task, so a slow observer slows the crawl
+    /// down instead of missing events.
+    pub fn with_observer(mut self, observer: Arc<dyn CrawlObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Spends the tokens of every skill the crawl generates from `budget`,
+    /// which may be shared between crawlers so `max_total_tokens` caps the
+    /// whole run.
+    pub fn with_token_budget(mut self, budget: Arc<TokenBudget>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Paces revalidation requests with `rate_limiter`, which may be shared
+    /// between crawlers so every host is paced across the whole run.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Writes skills to `sink` instead of the output directories, which
+    /// still hold the crawl cache. With several targets, `sink` receives
+    /// each target's copy of a skill under that target's name.
+    pub fn with_sink(mut self, sink: Box<dyn OutputSink>) -> Self {
+        let sink: Arc<dyn OutputSink> = Arc::from(sink);
+        self.outputs = self
+            .outputs
+            .iter()
+            .map(|output| SkillOutput {
+                sink: Arc::clone(&sink),
+                ..output.clone()
+            })
+            .collect();
+        self
+    }
+
+    /// Whether skills are only diffed, not written.
+    fn dry_run(&self) -> bool {
+        self.diff.as_ref().is_some_and(|diff| diff.dry_run())
+    }
+
     /// Returns the current crawl statistics.
     pub fn stats(&self) -> &Arc<CrawlStats> {
         &self.stats
@@ -92,100 +396,294 @@ impl Crawler {
     /// # Returns
     /// The crawl statistics on success.
     pub async fn crawl(&self, url: &str) -> Result<Arc<CrawlStats>> {
+        self.crawl_to(url, Arc::clone(&self.outputs), true).await
+    }
+
+    /// Crawls a website like [`Crawler::crawl`], but returns the processed
+    /// pages, sorted by URL, instead of writing skills. With several
+    /// targets, pages are named for the first one. Nothing is written to
+    /// disk, so `incremental` is ignored and every page is processed.
+    pub async fn crawl_collect(&self, url: &str) -> Result<Vec<ProcessedPage>> {
+        let sink = Arc::new(CollectSink::new());
+        let output = SkillOutput {
+            sink: Arc::clone(&sink) as Arc<dyn OutputSink>,
+            ..self.outputs[0].clone()
+        };
+        self.crawl_to(url, Arc::from([output]), false).await?;
+
+        let mut pages = sink.take();
+        pages.sort_by(|a, b| a.metadata.url.cmp(&b.metadata.url));
+        Ok(pages)
+    }
+
+    /// Crawls a website, writing skills to `outputs`. Without `persist`,
+    /// output directories aren't created and the crawl cache isn't used.
+    async fn crawl_to(
+        &self,
+        url: &str,
+        outputs: Arc<[SkillOutput]>,
+        persist: bool,
+    ) -> Result<Arc<CrawlStats>> {
         info!("Starting crawl of: {}", url);
+        // Ensure the output directories exist
+        for output in outputs.iter().filter(|_| persist && !self.dry_run()) {
+            fs_err::tokio::create_dir_all(&output.dir)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to create output directory: {}",
+                        output.dir.display()
+                    )
+                })?;
+        }
 
-        // Ensure output directory exists
-        fs_err::tokio::create_dir_all(&self.output_dir)
-            .await
-            .with_context(|| {
-                format!(
-                    "Failed to create output directory: {}",
-                    self.output_dir.display()
-                )
-            })?;
+        // Pages the server reports unchanged are kept and not crawled again
+        let cache = (self.config.incremental && persist)
+            .then(|| Arc::new(CrawlCache::load(&outputs[0].dir)));
+        let pipeline = PagePipeline {
+            outputs,
+            ..self.pipeline(url, cache.clone())?
+        };
+        // Local files are read from disk rather than fetched
+        match crate::local::local_path(url) {
+            Some(path) => self.read_local(&path, pipeline).await?,
+            None => self.crawl_website(url, pipeline, cache.as_ref()).await?,
+        }
+
+        // Losing the cache only means the next crawl regenerates everything
+        if let Some(cache) = cache
+            && !self.dry_run()
+            && let Err(e) = cache.save()
+        {
+            warn!("{:#}", e);
+        }
+
+        // Callers crawling several seeds summarize them together
+        debug!("{}", self.stats.summary());
+
+        Ok(Arc::clone(&self.stats))
+    }
+
+    /// Crawls the site at `url` with spider, handing its pages to
+    /// `pipeline`.
+    async fn crawl_website(
+        &self,
+        url: &str,
+        pipeline: PagePipeline,
+        cache: Option<&Arc<CrawlCache>>,
+    ) -> Result<()> {
+        let unchanged = match cache {
+            Some(cache) => self.revalidate(url, cache).await?,
+            None => Vec::new(),
+        };
 
         // Initialize the website with configuration
         let mut website = Website::new(url);
 
-        // Configure the website
-        self.configure_website(&mut website);
-
-        // Subscribe to page events with a buffer
-        let mut rx = website
-            .subscribe(self.config.concurrency * 2)
-            .context("Failed to subscribe to page events")?;
+        // Configure the website, leaving out pages that needn't be fetched
+        let mut skip: Vec<String> = unchanged.iter().map(|page| page.url.clone()).collect();
+        if let Some(ref visited) = self.visited {
+            skip.extend(visited.urls().into_iter().filter(|visited| visited != url));
+        }
+        self.configure_website(&mut website, &skip);
 
-        // Semaphore for concurrency control
-        let semaphore = Arc::new(Semaphore::new(self.config.concurrency));
+        // Spider takes a single proxy list, so NO_PROXY is applied per seed
+        if let Some(proxy) = ProxySettings::from_config(&self.config).proxy_for(url) {
+            info!("Crawling {} through proxy {}", url, redact_proxy(proxy));
+            website.with_proxies(Some(vec![proxy.to_string()]));
+        }
 
-        // Clone references for the spawned task
-        let stats = Arc::clone(&self.stats);
-        let config = self.config.clone();
-        let output_dir = self.output_dir.clone();
-        let processor = Processor::new(&config)?;
+        let mut headers = spider::reqwest::header::HeaderMap::new();
+        // Spider sends the same headers on every request, so credentials
+        // and cookies are picked for the seed; the crawl stays on its host
+        if let Some(authorization) = Authorization::from_config(&self.config)?
+            .and_then(|authorization| authorization.header_for(url))
+        {
+            headers.insert(spider::reqwest::header::AUTHORIZATION, authorization);
+        }
+        if let Some(ref cookies) = self.config.cookies
+            && let Some(cookie) = CookieJar::load(cookies)?.header_value_for(url)?
+        {
+            headers.insert(spider::reqwest::header::COOKIE, cookie);
+        }
+        if !headers.is_empty() {
+            website.with_headers(Some(headers));
+        }
 
-        // Build URL filter for the spawned task
-        let url_filter = config.build_url_filter()?;
+        // Subscribe to page events with a buffer
+        let rx = website
+            .subscribe(self.config.subscription_buffer.max(1))
+            .context("Failed to subscribe to page events")?;
 
         debug!(
             "URL filter built with {} rules (has_allow_rules: {})",
-            config.rules.len(),
-            config.has_allow_rules()
+            self.config.rules.len(),
+            self.config.has_allow_rules()
         );
 
         // Spawn a task to process pages as they come in
-        let process_handle = tokio::spawn(async move {
-            while let Ok(page) = rx.recv().await {
-                let url = page.get_url().to_string();
+        let process_handle = tokio::spawn(pipeline.run(unchanged, rx));
 
-                stats.pages_visited.fetch_add(1, Ordering::Relaxed);
+        // Start the crawl
+        website.crawl().await;
 
-                // Check if URL should be crawled based on rules using UrlFilter
-                if !url_filter.should_crawl(&url) {
-                    debug!("Skipping URL due to rules: {}", url);
-                    stats.pages_skipped.fetch_add(1, Ordering::Relaxed);
-                    continue;
-                }
+        // Unsubscribe to close the channel and signal completion
+        website.unsubscribe();
 
-                // Acquire semaphore permit for concurrency control
-                let permit = semaphore.clone().acquire_owned().await;
-                if permit.is_err() {
-                    warn!("Failed to acquire semaphore permit");
-                    continue;
-                }
-                let _permit = permit.unwrap();
+        // Wait for the pipeline to drain; it only returns once every
+        // in-flight page has been written (or has failed).
+        if let Err(e) = process_handle.await {
+            error!("Page processing task failed: {}", e);
+        }
 
-                // Process the page
-                match Self::process_page(&processor, &page, &output_dir).await {
-                    Ok(skill_dir) => {
-                        info!("Processed: {} -> {}", url, skill_dir.display());
-                        stats.pages_processed.fetch_add(1, Ordering::Relaxed);
-                    }
-                    Err(e) => {
-                        error!("Failed to process {}: {:?}", url, e);
-                        stats.pages_failed.fetch_add(1, Ordering::Relaxed);
-                    }
+        Ok(())
+    }
+
+    /// Processes the HTML files at `path`, a file or a directory, through
+    /// `pipeline` as if they had been crawled. `max_pages` still applies.
+    async fn read_local(&self, path: &Path, pipeline: PagePipeline) -> Result<()> {
+        let mut files = crate::local::html_files(path)?;
+        if self.config.max_pages > 0 {
+            files.truncate(self.config.max_pages as usize);
+        }
+        info!("Reading {} HTML files from {}", files.len(), path.display());
+
+        // Every page is queued up front, so none can be dropped
+        let (tx, rx) = broadcast::channel(files.len().max(1));
+        for file in &files {
+            match crate::local::read_page(file) {
+                Ok(page) => {
+                    let _ = tx.send(page);
+                }
+                Err(e) => {
+                    warn!("Failed to read {}: {:#}", file.display(), e);
+                    self.stats.pages_failed.fetch_add(1, Ordering::Relaxed);
                 }
             }
-        });
+        }
+        drop(tx);
 
-        // Start the crawl
-        website.crawl().await;
+        pipeline.run(Vec::new(), rx).await;
+        Ok(())
+    }
 
-        // Unsubscribe to close the channel and signal completion
-        website.unsubscribe();
+    /// Revalidates the cached pages in `seed`'s scope with conditional
+    /// requests and returns those that haven't changed: answered with
+    /// `304 Not Modified`, or sent again with the same content hash.
+    ///
+    /// The seed itself is always crawled, since spider needs it to find
+    /// new pages. Pages that fail to revalidate are left to spider.
+    async fn revalidate(&self, seed: &str, cache: &CrawlCache) -> Result<Vec<UnchangedPage>> {
+        let client = Arc::new(
+            build_http_client(&self.config)?.with_rate_limiter(Arc::clone(&self.rate_limiter)),
+        );
+        let url_filter = self.config.build_url_filter()?;
+        let seed_host = url_host(seed);
+        // The seed's delay may be raised by its robots.txt Crawl-delay
+        self.rate_limiter
+            .raise_host_delay(&seed_host, Duration::from_millis(self.config.delay_ms));
 
-        // Wait for processing to complete
-        // The receiver will complete when the channel is closed
-        let _ = process_handle.await;
+        let candidates: Vec<(String, CacheEntry)> = cache
+            .urls()
+            .into_iter()
+            .filter(|url| url != seed && url_filter.should_crawl(url))
+            .filter(|url| in_scope(url, &seed_host, self.config.subdomains))
+            .filter(|url| {
+                !self
+                    .visited
+                    .as_ref()
+                    .is_some_and(|visited| visited.contains(url))
+            })
+            .filter_map(|url| cache.get(&url).map(|entry| (url, entry)))
+            .collect();
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+        info!("Revalidating {} cached pages", candidates.len());
 
-        info!("{}", self.stats.summary());
+        let semaphore = Arc::new(Semaphore::new(self.config.concurrency.max(1)));
+        let mut tasks = JoinSet::new();
 
-        Ok(Arc::clone(&self.stats))
+        for (url, entry) in candidates {
+            let client = Arc::clone(&client);
+            let semaphore = Arc::clone(&semaphore);
+
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.ok()?;
+                let result = fetch_if_modified(&client, &url, entry.conditions()).await;
+
+                let unchanged = match result {
+                    Ok(Revalidation::NotModified) => true,
+                    Ok(Revalidation::Modified(body)) => content_hash(&body) == entry.content_hash,
+                    Err(e) => {
+                        debug!("Revalidation failed, leaving {} to the crawl: {:#}", url, e);
+                        false
+                    }
+                };
+                unchanged.then_some(UnchangedPage {
+                    url,
+                    skill_name: entry.skill_name,
+                })
+            });
+        }
+
+        let mut unchanged = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok(page) => unchanged.extend(page),
+                Err(e) => error!("Revalidation task panicked: {}", e),
+            }
+        }
+        unchanged.sort_by(|a, b| a.url.cmp(&b.url));
+
+        Ok(unchanged)
+    }
+
+    /// Builds the page pipeline that shares this crawler's processor and stats.
+    fn pipeline(&self, seed: &str, cache: Option<Arc<CrawlCache>>) -> Result<PagePipeline> {
+        let url_filter = Arc::new(self.config.build_url_filter()?);
+        let query_params = Arc::new(self.config.query_params());
+        let seed_host: Arc<str> = Arc::from(url_host(seed));
+        let scope = Arc::new(SeedScope {
+            url_filter: Arc::clone(&url_filter),
+            query_params: Arc::clone(&query_params),
+            seed_host: Arc::clone(&seed_host),
+            subdomains: self.config.subdomains,
+        });
+        Ok(PagePipeline {
+            processor: Arc::clone(&self.processor),
+            url_filter,
+            outputs: Arc::clone(&self.outputs),
+            stats: Arc::clone(&self.stats),
+            concurrency: self.config.concurrency,
+            names: Arc::clone(&self.names),
+            response_headers: Arc::from(self.config.response_headers.as_slice()),
+            auth_failures: Arc::default(),
+            cache,
+            include_content_types: Arc::from(self.config.include_content_types.as_slice()),
+            process_pdfs: self.config.process_pdfs,
+            query_params,
+            seed_host,
+            subdomains: self.config.subdomains,
+            diff: self.diff.clone(),
+            budget: Arc::clone(&self.budget),
+            scope,
+            respect_canonical: self.config.respect_canonical,
+            graph: self.graph.clone(),
+            visited: self.visited.clone(),
+            page_handler: self.page_handler.clone(),
+            observers: Arc::from(self.observers.as_slice()),
+            events: SeedEvents {
+                seed: Arc::from(seed),
+                reporter: self.reporter.clone(),
+            },
+        })
     }
 
-    /// Configures the spider Website with our settings.
-    fn configure_website(&self, website: &mut Website) {
+    /// Configures the spider Website with our settings. `skip` pages, either
+    /// unchanged or crawled from another seed, are blacklisted so spider
+    /// doesn't fetch them again.
+    fn configure_website(&self, website: &mut Website, skip: &[String]) {
         // Set user agent
         if let Some(ref user_agent) = self.config.user_agent {
             website.with_user_agent(Some(user_agent.as_str()));
@@ -196,15 +694,26 @@ impl Crawler {
         }
 
         // Configure politeness settings
-        website.configuration.delay = self.config.delay_ms;
+        // Seeds are crawled one at a time, so spider's delay also keeps
+        // max_requests_per_sec
+        website.configuration.delay = self.config.delay_ms.max(
+            self.config
+                .request_interval()
+                .map_or(0, |interval| interval.as_millis() as u64),
+        );
         website.configuration.respect_robots_txt = self.config.respect_robots_txt;
         website.configuration.subdomains = self.config.subdomains;
         website.configuration.depth = self.config.max_depth;
+        // Depth alone doesn't bound a wide site; the budget caps the fetches
+        if self.config.max_pages > 0 {
+            website.with_limit(self.config.max_pages);
+        }
 
         // Set request timeout
         website.configuration.request_timeout = Some(Box::new(Duration::from_secs(
             self.config.request_timeout_secs,
         )));
+        website.configuration.retry = self.config.max_retries;
 
         // Configure whitelist from allow rules - these are regex patterns
         // Spider will ONLY crawl URLs matching these patterns
@@ -225,116 +734,2943 @@ impl Crawler {
 
         // Configure blacklist from ignore rules - these are checked even when whitelist exists
         // This allows user-defined ignore patterns to exclude specific paths
-        let blacklist = self.config.get_blacklist_patterns();
+        let mut blacklist = self.config.get_blacklist_patterns();
         if !blacklist.is_empty() {
             info!("Configuring blacklist with {} patterns", blacklist.len());
             for pattern in &blacklist {
                 info!("Blacklist regex: {}", pattern);
             }
+        }
+        // Spider's asset filter also drops .md/.txt/.pdf links, so it's off
+        // when such pages are included; binary assets are kept out here instead
+        if self.crawls_non_html() {
+            blacklist.push(binary_asset_pattern(self.config.process_pdfs));
+        }
+        if !skip.is_empty() {
+            info!("Skipping {} unchanged or already crawled pages", skip.len());
+            blacklist.extend(skip.iter().map(|url| format!("^{}$", regex::escape(url))));
+        }
+        let has_blacklist = !blacklist.is_empty();
+        if has_blacklist {
             let blacklist_vec: Vec<spider::compact_str::CompactString> =
                 blacklist.into_iter().map(|s| s.into()).collect();
             website.with_blacklist_url(Some(blacklist_vec));
         }
 
         // Compile the allowlist/blocklist if any patterns were configured
-        if !self.config.get_whitelist_regex_patterns().is_empty()
-            || !self.config.get_blacklist_patterns().is_empty()
-        {
+        if !self.config.get_whitelist_regex_patterns().is_empty() || has_blacklist {
             website.configuration.configure_allowlist();
         }
 
-        // Only crawl HTML pages
-        website.configuration.only_html = true;
+        // Only crawl HTML pages, unless other content types are included
+        if !self.crawls_non_html() {
+            website.configuration.only_html = true;
+        } else {
+            website.configuration.only_html = false;
+            website.configuration.full_resources = true;
+        }
 
         debug!("Website configured: {:?}", website.configuration);
     }
 
-    /// Processes a single page.
-    async fn process_page(
-        processor: &Processor,
-        page: &Page,
-        output_dir: &Path,
-    ) -> Result<PathBuf> {
-        let url = page.get_url();
-        let html = page.get_html();
+    /// Whether pages other than HTML are crawled, through
+    /// `include_content_types` or `process_pdfs`.
+    fn crawls_non_html(&self) -> bool {
+        !self.config.include_content_types.is_empty() || self.config.process_pdfs
+    }
+}
 
-        if html.is_empty() {
-            anyhow::bail!("Empty HTML content for: {}", url);
-        }
+/// Receives crawled pages and processes them concurrently.
+///
+/// Each page that passes the URL rules is handed to its own task, which
+/// waits for a semaphore permit so at most `concurrency` pages are being
+/// converted or written at any time. The receive loop itself never waits on
+/// processing, which keeps the subscription drained; pages it still misses
+/// are counted as dropped. HTML-to-markdown conversion runs on the blocking
+/// thread pool to keep it off the async workers.
+struct PagePipeline {
+    processor: Arc<Processor>,
+    url_filter: Arc<UrlFilter>,
+    /// Every page is written once per target, under that target's name.
+    outputs: Arc<[SkillOutput]>,
+    stats: Arc<CrawlStats>,
+    concurrency: usize,
+    names: Arc<SkillNames>,
+    /// Response headers recorded in each page's [`PageContext`].
+    response_headers: Arc<[String]>,
+    /// Hosts already warned about for rejecting credentials.
+    auth_failures: Arc<Mutex<HashSet<String>>>,
+    /// Crawl cache of an incremental crawl.
+    cache: Option<Arc<CrawlCache>>,
+    /// Non-HTML content types that are processed too.
+    include_content_types: Arc<[String]>,
+    /// PDFs are converted too.
+    process_pdfs: bool,
+    /// Tracking parameters dropped from page URLs.
+    query_params: Arc<QueryParams>,
+    /// Host of the seed; pages redirected off it are skipped.
+    seed_host: Arc<str>,
+    subdomains: bool,
+    /// Records generated skills for `--diff`, writing nothing on a dry run.
+    diff: Option<Arc<CrawlDiff>>,
+    /// Pages over `max_total_tokens` are skipped.
+    budget: Arc<TokenBudget>,
+    /// Pages the crawl covers.
+    scope: Arc<SeedScope>,
+    /// Copies of another page in `scope` are skipped.
+    respect_canonical: bool,
+    /// Records the links between pages in `scope`, for `--graph`.
+    graph: Option<Arc<LinkGraph>>,
+    /// Pages crawled from other seeds are skipped.
+    visited: Option<Arc<VisitedUrls>>,
+    page_handler: Option<Arc<PageHandler>>,
+    observers: Arc<[Arc<dyn CrawlObserver>]>,
+    events: SeedEvents,
+}
 
-        // Process the page
-        let processed = processor
-            .process(url, &html)
-            .with_context(|| format!("Failed to process page: {}", url))?;
+/// The pages a seed's crawl covers: on its host and admitted by its rules.
+struct SeedScope {
+    url_filter: Arc<UrlFilter>,
+    query_params: Arc<QueryParams>,
+    seed_host: Arc<str>,
+    subdomains: bool,
+}
 
-        // Write to disk
-        let skill_dir = processor
-            .write_to_disk(&processed, output_dir)
-            .await
-            .with_context(|| format!("Failed to write skill for: {}", url))?;
+impl SeedScope {
+    /// `url` as the crawl knows it, if the crawl has visited or will visit
+    /// it.
+    fn covered(&self, url: &str) -> Option<String> {
+        let url = self.query_params.canonicalize(url);
+        (in_scope(&url, &self.seed_host, self.subdomains) && self.url_filter.should_crawl(&url))
+            .then_some(url)
+    }
 
-        Ok(skill_dir)
+    /// The canonical page `metadata` is a copy of, if it isn't the page at
+    /// `url` and the crawl covers it.
+    fn duplicate_of(&self, url: &str, metadata: &PageMetadata) -> Option<String> {
+        self.covered(metadata.canonical_url.as_deref()?)
+            .filter(|canonical| canonical != url)
     }
 }
 
-/// Cleans up the output directory by removing all generated skills.
-pub async fn clean_output_dir(output_dir: &PathBuf) -> Result<usize> {
-    use fs_err::tokio as fs;
+/// What [`process_page`] made of a page.
+struct PageOutcome {
+    /// The skills written, in output order.
+    written: Vec<WrittenSkill>,
+    mapping: NameMapping,
+    /// Estimated tokens of the skill.
+    tokens: usize,
+    /// Pages the page links to.
+    links: BTreeSet<String>,
+}
 
-    if !output_dir.exists() {
-        info!("Output directory does not exist: {}", output_dir.display());
-        return Ok(0);
+/// A page whose canonical URL is another page of the crawl.
+#[derive(Debug, thiserror::Error)]
+#[error("Skipping {url}: a copy of {canonical_url}")]
+struct DuplicatePage {
+    url: String,
+    canonical_url: String,
+}
+
+/// A cached page that hasn't changed since its skill was written.
+#[derive(Debug, Clone)]
+struct UnchangedPage {
+    url: String,
+    skill_name: String,
+}
+
+/// Forwards events for one seed to the reporter, if there is one.
+#[derive(Clone)]
+struct SeedEvents {
+    seed: Arc<str>,
+    reporter: Option<Arc<CrawlReporter>>,
+}
+
+impl SeedEvents {
+    fn emit(&self, event: impl FnOnce() -> CrawlEvent) {
+        if let Some(ref reporter) = self.reporter {
+            reporter.emit(&self.seed, event());
+        }
     }
+}
 
-    let mut count = 0;
-    let mut entries = fs::read_dir(output_dir).await?;
+impl PagePipeline {
+    /// Runs until the subscription closes and every spawned task has finished.
+    /// `unchanged` pages were revalidated before the crawl and are only
+    /// reported.
+    async fn run(self, unchanged: Vec<UnchangedPage>, mut rx: broadcast::Receiver<Page>) {
+        self.events.emit(|| CrawlEvent::SeedStarted);
+
+        for page in unchanged {
+            self.stats.pages_visited.fetch_add(1, Ordering::Relaxed);
+            if let Some(ref visited) = self.visited {
+                visited.insert(&page.url);
+            }
+            self.record_unchanged(page);
+        }
 
-    while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
+        let semaphore = Arc::new(Semaphore::new(self.concurrency.max(1)));
+        let mut tasks = JoinSet::new();
+        // Canonical URLs of the pages seen so far
+        let mut seen = HashSet::new();
 
-        if path.is_dir() {
-            // Check if it looks like a skill directory (has SKILL.md)
-            let skill_md = path.join("SKILL.md");
-            if skill_md.exists() {
-                fs::remove_dir_all(&path).await.with_context(|| {
-                    format!("Failed to remove skill directory: {}", path.display())
-                })?;
-                count += 1;
-                debug!("Removed: {}", path.display());
+        loop {
+            let page = match rx.recv().await {
+                Ok(page) => page,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(
+                        "Processing fell behind the crawl; {} pages were dropped (consider raising subscription_buffer)",
+                        missed
+                    );
+                    self.stats
+                        .pages_dropped
+                        .fetch_add(missed as usize, Ordering::Relaxed);
+                    self.events.emit(|| CrawlEvent::PagesDropped {
+                        count: missed as usize,
+                    });
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            // Pages are known by where they ended up after redirects
+            let url = self.query_params.canonicalize(page.get_url_final());
+            let requested_url = self.query_params.canonicalize(page.get_url());
+            let context = page_context(&page, &url, &self.response_headers)
+                .with_requested_url(&requested_url);
+
+            self.stats.pages_visited.fetch_add(1, Ordering::Relaxed);
+
+            if context.requested_url.is_some() && !in_scope(&url, &self.seed_host, self.subdomains)
+            {
+                debug!(
+                    "Skipping {}: redirected out of scope to {}",
+                    requested_url, url
+                );
+                self.stats.pages_skipped.fetch_add(1, Ordering::Relaxed);
+                self.events.emit(|| CrawlEvent::PageSkipped {
+                    url,
+                    headers: context.headers,
+                });
+                continue;
+            }
+
+            // The same page behind different tracking parameters
+            if !seen.insert(url.clone()) {
+                debug!("Skipping duplicate of {}: {}", url, page.get_url());
+                self.stats.pages_skipped.fetch_add(1, Ordering::Relaxed);
+                self.events.emit(|| CrawlEvent::PageSkipped {
+                    url,
+                    headers: context.headers,
+                });
+                continue;
+            }
+
+            // Crawled from another seed, which wrote its skill already
+            if let Some(ref visited) = self.visited
+                && !visited.insert(&url)
+            {
+                debug!("Skipping {}: already crawled from another seed", url);
+                self.stats.pages_skipped.fetch_add(1, Ordering::Relaxed);
+                self.events.emit(|| CrawlEvent::PageSkipped {
+                    url,
+                    headers: context.headers,
+                });
+                continue;
+            }
+
+            // Error pages of a failing login aren't content; count them once
+            if let status @ (401 | 403) = page.status_code.as_u16() {
+                self.record_auth_failure(&url, status);
+                self.events.emit(|| CrawlEvent::PageUnauthorized {
+                    url,
+                    status,
+                    headers: context.headers,
+                });
+                continue;
+            }
+
+            // Check the URL and response content type against the rules
+            if !self
+                .url_filter
+                .should_process(&url, page_content_type(&page))
+            {
+                debug!("Skipping URL due to rules: {}", url);
+                self.stats.pages_skipped.fetch_add(1, Ordering::Relaxed);
+                self.events.emit(|| CrawlEvent::PageSkipped {
+                    url,
+                    headers: context.headers,
+                });
+                continue;
+            }
+
+            // With spider's HTML-only filter off, other types end up here
+            if (!self.include_content_types.is_empty() || self.process_pdfs)
+                && !self.processor.handles_pdf(&context)
+                && !is_text_page(&page, &self.include_content_types)
+            {
+                debug!("Skipping non-text content: {}", url);
+                self.stats.pages_skipped.fetch_add(1, Ordering::Relaxed);
+                self.events.emit(|| CrawlEvent::PageSkipped {
+                    url,
+                    headers: context.headers,
+                });
+                continue;
+            }
+
+            // Pages identical to the cached copy keep their skill
+            let cached = match self.cache {
+                Some(ref cache) => {
+                    let hash = content_hash(page.get_html());
+                    if let Some(entry) = cache.get(&url)
+                        && entry.content_hash == hash
+                    {
+                        self.record_unchanged(UnchangedPage {
+                            url,
+                            skill_name: entry.skill_name,
+                        });
+                        continue;
+                    }
+                    Some((Arc::clone(cache), page.headers.clone(), hash))
+                }
+                None => None,
+            };
+
+            // Wait for a free slot before taking on the page, so in-flight
+            // work stays bounded; meanwhile the subscription buffer fills
+            // up, and what overflows it is counted as dropped
+            let Ok(permit) = Arc::clone(&semaphore).acquire_owned().await else {
+                warn!("Failed to acquire semaphore permit for {}", url);
+                self.stats.pages_failed.fetch_add(1, Ordering::Relaxed);
+                self.events.emit(|| CrawlEvent::PageFailed {
+                    url,
+                    error: "Failed to acquire semaphore permit".to_string(),
+                    headers: context.headers,
+                });
+                continue;
+            };
+
+            let processor = Arc::clone(&self.processor);
+            let outputs = Arc::clone(&self.outputs);
+            let stats = Arc::clone(&self.stats);
+            let events = self.events.clone();
+            let names = Arc::clone(&self.names);
+            let diff = self.diff.clone();
+            let budget = Arc::clone(&self.budget);
+            let scope = Arc::clone(&self.scope);
+            let respect_canonical = self.respect_canonical;
+            let graph = self.graph.clone();
+            let page_handler = self.page_handler.clone();
+            let observers = Arc::clone(&self.observers);
+
+            tasks.spawn(async move {
+                let _permit = permit;
+                for observer in observers.iter() {
+                    observer.page_fetched(&page).await;
+                }
+                let result = process_page(
+                    processor,
+                    page,
+                    &context,
+                    &outputs,
+                    &names,
+                    diff.as_deref(),
+                    &budget,
+                    respect_canonical.then_some(&*scope),
+                    page_handler.as_deref(),
+                    &observers,
+                )
+                .await;
+                match result {
+                    Ok(PageOutcome {
+                        written,
+                        mapping,
+                        tokens,
+                        links,
+                    }) => {
+                        stats.tokens.fetch_add(tokens, Ordering::Relaxed);
+                        let changed = written.iter().any(|skill| skill.changed);
+                        let skill_name = written[0].skill_name.clone();
+                        let mut skill_dirs: Vec<PathBuf> =
+                            written.into_iter().map(|skill| skill.skill_dir).collect();
+                        let skill_dir = skill_dirs.swap_remove(0);
+                        if mapping.is_renamed() {
+                            info!(
+                                "Skill name {} is taken or too long; using {}",
+                                mapping.canonical,
+                                skill_dir.display()
+                            );
+                            events.emit(|| CrawlEvent::SkillRenamed(mapping));
+                        }
+                        if let Some(graph) = graph {
+                            let links = links.iter().filter_map(|link| scope.covered(link));
+                            graph.record(&url, &skill_name, links);
+                        }
+                        if let Some((cache, headers, hash)) = cached {
+                            cache
+                                .record(&url, CacheEntry::new(headers.as_ref(), hash, &skill_name));
+                        }
+
+                        // Regenerated exactly as it was, so nothing was written
+                        if !changed {
+                            debug!("Unchanged: {} -> {}", url, skill_dir.display());
+                            stats.pages_unchanged.fetch_add(1, Ordering::Relaxed);
+                            events.emit(|| CrawlEvent::PageUnchanged { url, skill_dir });
+                            return;
+                        }
+
+                        for copy in &skill_dirs {
+                            debug!("Also written to {}", copy.display());
+                        }
+                        stats.pages_processed.fetch_add(1, Ordering::Relaxed);
+                        events.emit(|| CrawlEvent::PageProcessed {
+                            url,
+                            skill_dir,
+                            headers: context.headers,
+                        });
+                    }
+                    Err(e) if e.downcast_ref::<PageTooSmall>().is_some() => {
+                        debug!("{:#}", e);
+                        stats.pages_too_small.fetch_add(1, Ordering::Relaxed);
+                        let chars = e.downcast_ref::<PageTooSmall>().map_or(0, |e| e.chars);
+                        events.emit(|| CrawlEvent::PageTooSmall { url, chars });
+                    }
+                    Err(e) if e.downcast_ref::<DuplicatePage>().is_some() => {
+                        debug!("{:#}", e);
+                        stats.pages_deduplicated.fetch_add(1, Ordering::Relaxed);
+                        let canonical_url = e
+                            .downcast::<DuplicatePage>()
+                            .map_or_else(|_| String::new(), |e| e.canonical_url);
+                        events.emit(|| CrawlEvent::PageDeduplicated { url, canonical_url });
+                    }
+                    Err(e) if e.downcast_ref::<OtherLanguage>().is_some() => {
+                        debug!("{:#}", e);
+                        stats.pages_skipped_language.fetch_add(1, Ordering::Relaxed);
+                        events.emit(|| CrawlEvent::PageSkipped {
+                            url,
+                            headers: context.headers,
+                        });
+                    }
+                    Err(e) if e.downcast_ref::<SkippedPage>().is_some() => {
+                        debug!("{:#}", e);
+                        stats.pages_skipped.fetch_add(1, Ordering::Relaxed);
+                        events.emit(|| CrawlEvent::PageSkipped {
+                            url,
+                            headers: context.headers,
+                        });
+                    }
+                    Err(e) => {
+                        for observer in observers.iter() {
+                            observer.page_failed(&url, &e).await;
+                        }
+                        stats.pages_failed.fetch_add(1, Ordering::Relaxed);
+                        events.emit(|| CrawlEvent::PageFailed {
+                            url,
+                            error: format!("{:#}", e),
+                            headers: context.headers,
+                        });
+                    }
+                }
+            });
+
+            // Reap finished tasks so the set doesn't grow for the whole crawl
+            while let Some(result) = tasks.try_join_next() {
+                Self::log_join_error(result);
+            }
+        }
+
+        while let Some(result) = tasks.join_next().await {
+            Self::log_join_error(result);
+        }
+
+        for output in self.outputs.iter() {
+            if let Err(e) = output.sink.finish().await {
+                error!(
+                    "Failed to finish writing to {}: {:?}",
+                    output.dir.display(),
+                    e
+                );
+            }
+        }
+
+        self.events.emit(|| CrawlEvent::SeedFinished {
+            stats: self.stats.snapshot(),
+        });
+    }
+
+    /// Counts a page that hasn't changed and keeps its skill name reserved,
+    /// so no new page is written over it.
+    fn record_unchanged(&self, page: UnchangedPage) {
+        debug!("Unchanged: {}", page.url);
+        self.names.assign(&page.skill_name, &page.url);
+        self.stats.pages_unchanged.fetch_add(1, Ordering::Relaxed);
+
+        let dir = self.processor.group_dir(&self.outputs[0].dir, &page.url);
+        let skill_dir = self.processor.skill_path(&dir, &page.skill_name);
+        let skill_md_path = self.processor.skill_md_path(&dir, &page.skill_name);
+        // The skill stays in the set, so its tokens count towards the budget
+        let tokens = fs_err::read_to_string(&skill_md_path)
+            .ok()
+            .and_then(|content| frontmatter::estimated_tokens(&content))
+            .unwrap_or_default();
+        self.stats.tokens.fetch_add(tokens, Ordering::Relaxed);
+        self.budget.add(tokens);
+        // Seen by this crawl, so `clean --older-than` keeps it
+        for output in self.outputs.iter() {
+            let dir = self.processor.group_dir(&output.dir, &page.url);
+            let path = self.processor.skill_md_path(&dir, &page.skill_name);
+            if let Err(e) = crate::utils::touch(&path) {
+                debug!("{:#}", e);
             }
         }
+        if let Some(ref diff) = self.diff {
+            diff.keep(skill_md_path);
+        }
+        self.events.emit(|| CrawlEvent::PageUnchanged {
+            url: page.url,
+            skill_dir,
+        });
+    }
+
+    /// Counts a 401/403 page, warning only the first time per host so a
+    /// broken login doesn't produce an error for every page.
+    fn record_auth_failure(&self, url: &str, status: u16) {
+        self.stats
+            .pages_unauthorized
+            .fetch_add(1, Ordering::Relaxed);
+
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| url.to_string());
+        let first = self
+            .auth_failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(host.clone());
+
+        if first {
+            warn!(
+                "Authentication appears to be failing for host {} ({} on {}); check basic_auth/auth/cookies",
+                host, status, url
+            );
+        } else {
+            debug!("Unauthorized ({}): {}", status, url);
+        }
     }
 
-    info!("Cleaned {} skill directories", count);
-    Ok(count)
+    fn log_join_error(result: Result<(), JoinError>) {
+        if let Err(e) = result {
+            error!("Page processing task panicked: {}", e);
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Lowercase host of `url`, or an empty string if it has none.
+fn url_host(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+        .unwrap_or_default()
+}
 
-    #[test]
-    fn test_crawl_stats() {
-        let stats = CrawlStats::new();
+/// Whether `url` is on the seed's host, or one of its subdomains when
+/// `subdomains` is set.
+fn in_scope(url: &str, seed_host: &str, subdomains: bool) -> bool {
+    let host = url_host(url);
+    host == seed_host || (subdomains && host.ends_with(&format!(".{}", seed_host)))
+}
 
-        stats.pages_visited.fetch_add(10, Ordering::Relaxed);
-        stats.pages_processed.fetch_add(8, Ordering::Relaxed);
-        stats.pages_skipped.fetch_add(1, Ordering::Relaxed);
-        stats.pages_failed.fetch_add(1, Ordering::Relaxed);
+/// Returns the `Content-Type` header of a fetched page, if any.
+fn page_content_type(page: &Page) -> Option<&str> {
+    page.headers
+        .as_ref()?
+        .get(spider::reqwest::header::CONTENT_TYPE)?
+        .to_str()
+        .ok()
+}
 
-        let summary = stats.summary();
-        assert!(summary.contains("10 visited"));
-        assert!(summary.contains("8 processed"));
-        assert!(summary.contains("1 skipped"));
-        assert!(summary.contains("1 failed"));
+/// Whether a page is HTML or an included text content type, and not a
+/// binary body served under a text type.
+fn is_text_page(page: &Page, include_content_types: &[String]) -> bool {
+    let content_type = page_content_type(page);
+    let is_html = content_type.is_none_or(is_html_content_type);
+    is_included_content_type(include_content_types, content_type)
+        && (is_html || !page.get_html_bytes_u8().contains(&0))
+}
+
+/// Spider blacklist pattern matching [`BINARY_ASSET_EXTENSIONS`], leaving
+/// out PDFs when they are converted.
+fn binary_asset_pattern(process_pdfs: bool) -> String {
+    let extensions: Vec<&str> = BINARY_ASSET_EXTENSIONS
+        .iter()
+        .copied()
+        .filter(|extension| !(process_pdfs && *extension == "pdf"))
+        .collect();
+    format!(r"(?i)\.(?:{})(?:[?#].*)?$", extensions.join("|"))
+}
+
+/// Builds the [`PageContext`] of a page fetched from `url`, keeping the
+/// headers named in `allowlist`.
+fn page_context(page: &Page, url: &str, allowlist: &[String]) -> PageContext {
+    let context = PageContext::new(url);
+    match page.headers {
+        Some(ref headers) => context.with_headers(headers, allowlist),
+        None => context,
     }
+}
 
-    #[tokio::test]
-    async fn test_crawler_creation() {
-        let config = Config::default();
-        let output_dir = PathBuf::from("/tmp/test-skills");
+/// Processes a single page and writes it to every output, naming its skill
+/// through `names`. Returns the written skills and the page's links. The first
+/// output's skill is recorded in `diff`, and nothing is written if it is a
+/// dry run. The skill's estimated tokens are spent from `budget`, skipping
+/// the page if they don't fit. Pages that are copies of another page in
+/// `canonical_scope` fail with [`DuplicatePage`]. Once written, the page is
+/// handed to `handler`, whose errors are only logged, and `observers` are
+/// told about its skill unless it was regenerated unchanged.
+#[allow(clippy::too_many_arguments)]
+async fn process_page(
+    processor: Arc<Processor>,
+    page: Page,
+    context: &PageContext,
+    outputs: &[SkillOutput],
+    names: &SkillNames,
+    diff: Option<&CrawlDiff>,
+    budget: &TokenBudget,
+    canonical_scope: Option<&SeedScope>,
+    handler: Option<&PageHandler>,
+    observers: &[Arc<dyn CrawlObserver>],
+) -> Result<PageOutcome> {
+    let url = context.url.clone();
+    // PDFs are read from the raw bytes, everything else as decoded text
+    let body = if processor.handles_pdf(context) {
+        PageBody::Pdf(page.get_html_bytes_u8().to_vec())
+    } else {
+        PageBody::Html(page.get_html())
+    };
 
-        let crawler = Crawler::new(config, output_dir);
-        assert!(crawler.is_ok());
+    if body.is_empty() {
+        anyhow::bail!("Empty HTML content for: {}", url);
+    }
+
+    // Conversion is CPU-bound, so keep it off the async worker threads
+    let blocking_processor = Arc::clone(&processor);
+    let blocking_context = context.clone();
+    let mut processed = tokio::task::spawn_blocking(move || {
+        blocking_processor.process_body(&blocking_context, &body)
+    })
+    .await
+    .with_context(|| format!("Processing task failed for: {}", url))?
+    .with_context(|| format!("Failed to process page: {}", url))?;
+
+    // The canonical page gets the skill; this copy is left out
+    if let Some(canonical_url) =
+        canonical_scope.and_then(|scope| scope.duplicate_of(&url, &processed.metadata))
+    {
+        return Err(DuplicatePage { url, canonical_url }.into());
+    }
+    processor.download_images(&mut processed).await?;
+
+    // Checked before naming, so a skipped page doesn't take a name
+    let tokens = processed.estimated_tokens();
+    if !budget.try_spend(tokens) {
+        return Err(SkippedPage {
+            url,
+            reason: "max_total_tokens reached".to_string(),
+        }
+        .into());
+    }
+
+    let mapping = names.assign_with_fallback(
+        &processed.metadata.skill_name,
+        processed.metadata.fallback_skill_name.as_deref(),
+        &url,
+    );
+
+    // Write to disk, under the name each target gave the page
+    let mut written = Vec::with_capacity(outputs.len());
+    for (i, output) in outputs.iter().enumerate() {
+        if let Some(name) = mapping.name_for(output.target)
+            && name != processed.metadata.skill_name
+        {
+            processor.rename(&mut processed, name)?;
+        }
+
+        // Only the first output is diffed, before it is overwritten
+        let skill_name = &processed.metadata.skill_name;
+        let changed = match diff {
+            Some(diff) if i == 0 => {
+                let dir = processor.group_dir(&output.dir, &url);
+                let path = processor.skill_md_path(&dir, skill_name);
+                diff.record(&path, &processed.skill_md).await?
+            }
+            _ => false,
+        };
+        if diff.is_some_and(CrawlDiff::dry_run) {
+            written.push(WrittenSkill {
+                skill_dir: processor
+                    .skill_path(&processor.group_dir(&output.dir, &url), skill_name),
+                skill_name: skill_name.clone(),
+                changed,
+            });
+            continue;
+        }
+
+        let skill = output
+            .sink
+            .write_skill(&processed)
+            .await
+            .with_context(|| format!("Failed to write skill for: {}", url))?;
+        written.push(skill);
+    }
+
+    if let Some(handler) = handler
+        && let Err(e) = handler(&processed)
+    {
+        warn!("Page handler failed for {}: {:#}", url, e);
+    }
+    let dry_run = diff.is_some_and(CrawlDiff::dry_run);
+    if !dry_run && written.iter().any(|skill| skill.changed) {
+        for observer in observers {
+            observer
+                .skill_written(&processed, &written[0].skill_dir)
+                .await;
+        }
+    }
+
+    Ok(PageOutcome {
+        written,
+        mapping,
+        tokens,
+        links: processed.links,
+    })
+}
+
+/// Fetches the seed page and derives additional seeds via `entry_selector`.
+pub async fn resolve_entry_seeds(
+    config: &Config,
+    base_url: &str,
+    selector: &str,
+) -> Result<Vec<String>> {
+    let client = crate::http::build_http_client(config)?;
+    let html = crate::http::fetch_text(&client, base_url).await?;
+    let filter = config.build_url_filter()?;
+
+    derive_entry_seeds(&html, base_url, selector, &filter, config.max_entry_seeds)
+}
+
+/// Derives additional crawl seeds from an entry ("chooser") page.
+///
+/// Every element matching `selector` that carries an `href` (or contains an
+/// anchor that does) is resolved against `base_url`. Fragment-only, duplicate,
+/// self-referencing and rule-rejected links are dropped, and the result is
+/// capped at `max_seeds` in document order.
+pub fn derive_entry_seeds(
+    html: &str,
+    base_url: &str,
+    selector: &str,
+    filter: &UrlFilter,
+    max_seeds: usize,
+) -> Result<Vec<String>> {
+    let selector = Selector::parse(selector)
+        .map_err(|e| anyhow::anyhow!("Invalid entry_selector '{}': {:?}", selector, e))?;
+    let anchor = Selector::parse("a[href]").expect("static selector");
+    let base =
+        url::Url::parse(base_url).with_context(|| format!("Invalid seed URL: {}", base_url))?;
+
+    let document = Html::parse_document(html);
+    let mut seeds: Vec<String> = Vec::new();
+
+    for element in document.select(&selector) {
+        let hrefs: Vec<&str> = match element.value().attr("href") {
+            Some(href) => vec![href],
+            None => element
+                .select(&anchor)
+                .filter_map(|a| a.value().attr("href"))
+                .collect(),
+        };
+
+        for href in hrefs {
+            let href = href.trim();
+            if href.is_empty() || href.starts_with('#') {
+                continue;
+            }
+
+            let Ok(mut resolved) = base.join(href) else {
+                debug!("Skipping unresolvable entry link: {}", href);
+                continue;
+            };
+            resolved.set_fragment(None);
+            let resolved = resolved.to_string();
+
+            if resolved == base.as_str() || seeds.contains(&resolved) {
+                continue;
+            }
+
+            if !filter.should_crawl(&resolved) {
+                debug!("Entry link rejected by rules: {}", resolved);
+                continue;
+            }
+
+            seeds.push(resolved);
+        }
+    }
+
+    if seeds.len() > max_seeds {
+        warn!(
+            "entry_selector matched {} links; keeping the first {} (max_entry_seeds)",
+            seeds.len(),
+            max_seeds
+        );
+        seeds.truncate(max_seeds);
+    }
+
+    Ok(seeds)
+}
+
+/// Which skills `clean` removes. The default removes all of them.
+#[derive(Debug, Clone, Default)]
+pub struct CleanFilter {
+    /// Only skills whose name matches this glob.
+    pub pattern: Option<GlobMatcher>,
+    /// Only skills no crawl has seen for longer than this: crawls bump the
+    /// mtime of SKILL.md even when its content is unchanged. A later
+    /// `processed_at` in the frontmatter counts too, for copied files.
+    pub older_than: Option<Duration>,
+}
+
+impl CleanFilter {
+    /// Whether every skill is removed.
+    pub fn is_all(&self) -> bool {
+        self.pattern.is_none() && self.older_than.is_none()
+    }
+
+    fn selects(
+        &self,
+        skill: &crate::utils::SkillFile,
+        content: &str,
+        now: SystemTime,
+    ) -> Result<bool> {
+        if self
+            .pattern
+            .as_ref()
+            .is_some_and(|pattern| !pattern.is_match(&skill.name))
+        {
+            return Ok(false);
+        }
+        let Some(older_than) = self.older_than else {
+            return Ok(true);
+        };
+        let modified = fs_err::metadata(&skill.skill_md)?.modified()?;
+        let last_seen = match crate::frontmatter::processed_at(content) {
+            Some(processed_at) => modified.max(processed_at.into()),
+            None => modified,
+        };
+        Ok(now.duration_since(last_seen).unwrap_or_default() > older_than)
+    }
+}
+
+/// Lists the generated skills in `output_dir` that `filter` selects, which
+/// [`clean_output_dir`] removes.
+pub fn selected_skills(
+    output_dir: &Path,
+    filter: &CleanFilter,
+) -> Result<Vec<crate::utils::SkillFile>> {
+    if !output_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let now = SystemTime::now();
+    let mut selected = Vec::new();
+    for skill in crate::utils::skill_files(output_dir)? {
+        let content = fs_err::read_to_string(&skill.skill_md)?;
+        // Only flat files this tool wrote, not e.g. a README next to them
+        if !skill.path.is_dir() && crate::frontmatter::source_url(&content).is_none() {
+            continue;
+        }
+        if filter.selects(&skill, &content, now)? {
+            selected.push(skill);
+        }
+    }
+    Ok(selected)
+}
+
+/// Cleans up the output directory by removing the generated skills that
+/// `filter` selects: skill directories, and flat `<name>.md` and
+/// `<name>.json` files (with their `<name>.html`, and the shared `assets/`
+/// directory when all are removed) that record the page they were
+/// generated from. `group_by` directories left empty are removed too.
+pub async fn clean_output_dir(output_dir: &Path, filter: &CleanFilter) -> Result<usize> {
+    use fs_err::tokio as fs;
+
+    if !output_dir.exists() {
+        info!("Output directory does not exist: {}", output_dir.display());
+        return Ok(0);
+    }
+
+    let mut count = 0;
+    let mut flat = false;
+    // Directories the removed skills were in, the output directory included
+    let mut dirs = BTreeSet::from([output_dir.to_path_buf()]);
+    for skill in selected_skills(output_dir, filter)? {
+        if let Some(ref group) = skill.group {
+            dirs.extend(
+                Path::new(group)
+                    .ancestors()
+                    .filter(|group| !group.as_os_str().is_empty())
+                    .map(|group| output_dir.join(group)),
+            );
+        }
+        if skill.path.is_dir() {
+            fs::remove_dir_all(&skill.path).await.with_context(|| {
+                format!("Failed to remove skill directory: {}", skill.path.display())
+            })?;
+        } else {
+            fs::remove_file(&skill.path).await.with_context(|| {
+                format!("Failed to remove skill file: {}", skill.path.display())
+            })?;
+            let html = skill.path.with_extension("html");
+            if html.is_file() {
+                fs::remove_file(&html).await?;
+            }
+            flat = true;
+        }
+        count += 1;
+        debug!("Removed: {}", skill.path.display());
+    }
+
+    // Images of the remaining flat skills are in there too
+    for dir in dirs.iter().filter(|_| flat && filter.is_all()) {
+        let assets_dir = dir.join(crate::images::ASSETS_DIR);
+        if assets_dir.is_dir() {
+            fs::remove_dir_all(&assets_dir).await?;
+        }
+    }
+
+    // Nested groups sort after their parent, so they go first
+    for dir in dirs.iter().rev().filter(|dir| *dir != output_dir) {
+        if fs_err::read_dir(dir)?.next().is_none() {
+            fs::remove_dir(dir).await?;
+        }
+    }
+
+    // The cache would otherwise mark the removed skills' pages unchanged
+    let cache_file = output_dir.join(crate::cache::CACHE_FILE);
+    if (count > 0 || filter.is_all()) && cache_file.exists() {
+        fs::remove_file(&cache_file).await?;
+    }
+
+    info!("Cleaned {} skills", count);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Action, Rule, SkillNameSource};
+    use crate::events::{OutputFormat, SharedBuffer};
+    use crate::processor::ProcessedPage;
+    use crate::sink::{MemorySink, WriteFuture};
+
+    #[test]
+    fn test_crawl_stats() {
+        let stats = CrawlStats::new();
+
+        stats.pages_visited.fetch_add(10, Ordering::Relaxed);
+        stats.pages_processed.fetch_add(8, Ordering::Relaxed);
+        stats.pages_skipped.fetch_add(1, Ordering::Relaxed);
+        stats.pages_failed.fetch_add(1, Ordering::Relaxed);
+        stats.pages_dropped.fetch_add(2, Ordering::Relaxed);
+
+        let summary = stats.summary();
+        assert!(summary.contains("10 visited"));
+        assert!(summary.contains("8 processed"));
+        assert!(summary.contains("1 skipped"));
+        assert!(summary.contains("1 failed"));
+        assert!(summary.contains("2 dropped"));
+    }
+
+    const CHOOSER_PAGE: &str = r#"
+<html>
+<body>
+    <h1>Choose a product</h1>
+    <ul class="products">
+        <li><a class="product" href="/docs/alpha/">Alpha</a></li>
+        <li><a class="product" href="/docs/beta/">Beta</a></li>
+        <li><a class="product" href="gamma/">Gamma</a></li>
+        <li><a class="product" href="https://portal.example.com/docs/delta/#intro">Delta</a></li>
+        <li><a class="product" href="/docs/epsilon/">Epsilon</a></li>
+    </ul>
+    <a href="/pricing">Pricing</a>
+</body>
+</html>
+"#;
+
+    #[test]
+    fn test_derive_entry_seeds() {
+        let filter = UrlFilter::new(&[]).unwrap();
+        let seeds = derive_entry_seeds(
+            CHOOSER_PAGE,
+            "https://portal.example.com/docs/",
+            "a.product",
+            &filter,
+            20,
+        )
+        .unwrap();
+
+        assert_eq!(
+            seeds,
+            vec![
+                "https://portal.example.com/docs/alpha/",
+                "https://portal.example.com/docs/beta/",
+                "https://portal.example.com/docs/gamma/",
+                "https://portal.example.com/docs/delta/",
+                "https://portal.example.com/docs/epsilon/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_derive_entry_seeds_respects_rules_and_cap() {
+        let config = Config::from_yaml(
+            r#"
+rules:
+  - url: "*/docs/beta/*"
+    action: ignore
+"#,
+        )
+        .unwrap();
+        let filter = config.build_url_filter().unwrap();
+
+        let seeds = derive_entry_seeds(
+            CHOOSER_PAGE,
+            "https://portal.example.com/docs/",
+            ".products li",
+            &filter,
+            3,
+        )
+        .unwrap();
+
+        assert_eq!(
+            seeds,
+            vec![
+                "https://portal.example.com/docs/alpha/",
+                "https://portal.example.com/docs/gamma/",
+                "https://portal.example.com/docs/delta/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_derive_entry_seeds_invalid_selector() {
+        let filter = UrlFilter::new(&[]).unwrap();
+        let result = derive_entry_seeds(CHOOSER_PAGE, "https://example.com/", "a[", &filter, 5);
+        assert!(result.is_err());
+    }
+
+    fn synthetic_page(url: &str, html: &str) -> Page {
+        typed_page(url, html, "text/html; charset=utf-8")
+    }
+
+    fn typed_page(url: &str, body: &str, content_type: &str) -> Page {
+        status_page(url, body, content_type, spider::reqwest::StatusCode::OK)
+    }
+
+    fn status_page(
+        url: &str,
+        body: &str,
+        content_type: &str,
+        status_code: spider::reqwest::StatusCode,
+    ) -> Page {
+        let mut headers = spider::reqwest::header::HeaderMap::new();
+        headers.insert(
+            spider::reqwest::header::CONTENT_TYPE,
+            content_type.parse().unwrap(),
+        );
+
+        spider::page::build(
+            url,
+            spider::utils::PageResponse {
+                content: Some(Box::new(body.as_bytes().to_vec())),
+                headers: Some(headers),
+                status_code,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn test_output_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-skills-crawler-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Feeds `pages` through a pipeline and waits for it to drain.
+    async fn run_pipeline(config: &Config, output_dir: &Path, pages: Vec<Page>) -> Arc<CrawlStats> {
+        let crawler = Crawler::new(config.clone(), vec![output_dir.to_path_buf()]).unwrap();
+        feed(&crawler, pages).await
+    }
+
+    /// Runs `pages` through `crawler`'s pipeline as if spider had fetched
+    /// them.
+    async fn feed(crawler: &Crawler, pages: Vec<Page>) -> Arc<CrawlStats> {
+        let (tx, rx) = broadcast::channel(pages.len().max(1));
+        for page in pages {
+            tx.send(page).unwrap();
+        }
+        drop(tx);
+
+        crawler
+            .pipeline("https://docs.example.com/", None)
+            .unwrap()
+            .run(Vec::new(), rx)
+            .await;
+        Arc::clone(crawler.stats())
+    }
+
+    fn doc_pages(count: usize) -> Vec<Page> {
+        (0..count)
+            .map(|i| {
+                synthetic_page(
+                    &format!("https://docs.example.com/guide/topic-{}", i),
+                    &format!(
+                        "<html><head><title>Topic {i}</title></head>\
+                         <body><main><h1>Topic {i}</h1><p>Body of topic {i}.</p></main></body></html>"
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_processes_every_page() {
+        let config = Config::from_yaml(
+            r#"
+concurrency: 4
+rules:
+  - url: "*/private/*"
+    action: ignore
+"#,
+        )
+        .unwrap();
+        let output_dir = test_output_dir("every-page");
+
+        let mut pages = doc_pages(8);
+        pages.push(synthetic_page(
+            "https://docs.example.com/private/secret",
+            "<html><body><p>Hidden</p></body></html>",
+        ));
+        pages.push(synthetic_page("https://docs.example.com/guide/empty", ""));
+
+        let stats = run_pipeline(&config, &output_dir, pages).await;
+
+        assert_eq!(stats.pages_visited.load(Ordering::Relaxed), 10);
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 8);
+        assert_eq!(stats.pages_skipped.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.pages_failed.load(Ordering::Relaxed), 1);
+
+        // Every processed page must be on disk by the time the pipeline returns
+        let written = std::fs::read_dir(&output_dir)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().join("SKILL.md").exists())
+            .count();
+        assert_eq!(written, 8);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_calls_page_handler() {
+        let output_dir = test_output_dir("page-handler");
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handled = Arc::clone(&calls);
+        let crawler = Crawler::new(Config::default(), vec![output_dir.clone()])
+            .unwrap()
+            .with_page_handler(move |page| {
+                handled.fetch_add(1, Ordering::Relaxed);
+                // A failing handler doesn't fail the page
+                match page.metadata.title.as_str() {
+                    "Topic 0" => anyhow::bail!("database unavailable"),
+                    _ => Ok(()),
+                }
+            });
+
+        let mut pages = doc_pages(5);
+        pages.push(synthetic_page("https://docs.example.com/guide/empty", ""));
+        let stats = feed(&crawler, pages).await.snapshot();
+
+        assert_eq!(stats.processed, 5);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(calls.load(Ordering::Relaxed), stats.processed);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        fetched: Mutex<Vec<String>>,
+        written: Mutex<Vec<PathBuf>>,
+        failed: Mutex<Vec<String>>,
+    }
+
+    impl CrawlObserver for RecordingObserver {
+        fn page_fetched<'a>(&'a self, page: &'a Page) -> crate::observer::ObserverFuture<'a> {
+            Box::pin(async move {
+                self.fetched
+                    .lock()
+                    .unwrap()
+                    .push(page.get_url().to_string());
+            })
+        }
+
+        fn skill_written<'a>(
+            &'a self,
+            _page: &'a ProcessedPage,
+            path: &'a Path,
+        ) -> crate::observer::ObserverFuture<'a> {
+            Box::pin(async move {
+                // Slow enough that events would pile up if they weren't awaited
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                self.written.lock().unwrap().push(path.to_path_buf());
+            })
+        }
+
+        fn page_failed<'a>(
+            &'a self,
+            url: &'a str,
+            _error: &'a anyhow::Error,
+        ) -> crate::observer::ObserverFuture<'a> {
+            Box::pin(async move {
+                self.failed.lock().unwrap().push(url.to_string());
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_notifies_observers() {
+        let output_dir = test_output_dir("observer");
+        let observer = Arc::new(RecordingObserver::default());
+        let config = Config {
+            concurrency: 2,
+            ..Default::default()
+        };
+        let crawler = Crawler::new(config, vec![output_dir.clone()])
+            .unwrap()
+            .with_observer(Arc::clone(&observer) as Arc<dyn CrawlObserver>);
+
+        let mut pages = doc_pages(20);
+        pages.push(synthetic_page("https://docs.example.com/guide/empty", ""));
+        let stats = feed(&crawler, pages).await.snapshot();
+        assert_eq!(stats.processed, 20);
+        assert_eq!(stats.failed, 1);
+
+        assert_eq!(observer.fetched.lock().unwrap().len(), 21);
+        let written = observer.written.lock().unwrap().clone();
+        assert_eq!(written.len(), 20);
+        assert!(written.iter().all(|dir| dir.join("SKILL.md").is_file()));
+        assert_eq!(
+            *observer.failed.lock().unwrap(),
+            ["https://docs.example.com/guide/empty"]
+        );
+
+        // Regenerated unchanged, so nothing is written
+        let stats = feed(&crawler, doc_pages(20)).await.snapshot();
+        assert_eq!(stats.unchanged, 20);
+        assert_eq!(observer.written.lock().unwrap().len(), 20);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_with_jsonl_writer() {
+        let output_dir = test_output_dir("jsonl-writer");
+        let config = Config {
+            writer: SkillWriter::Jsonl,
+            ..Default::default()
+        };
+        let stats = run_pipeline(&config, &output_dir, doc_pages(5)).await;
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 5);
+
+        // Every skill is a line of the one file, and nothing else is written
+        let jsonl = std::fs::read_to_string(output_dir.join(JSONL_FILE)).unwrap();
+        assert_eq!(jsonl.lines().count(), 5);
+        let entries: Vec<String> = std::fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        assert_eq!(entries, [JSONL_FILE]);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_disambiguates_colliding_names() {
+        let output_dir = test_output_dir("collisions");
+        let html =
+            "<html><head><title>API</title></head><body><main><p>API.</p></main></body></html>";
+
+        // Each pair of URLs sanitizes to one name
+        let pages = vec![
+            synthetic_page("https://docs.example.com/docs/api", html),
+            synthetic_page("https://docs.example.com/docs/api.html", html),
+            synthetic_page("https://docs.example.com/docs/get-started", html),
+            synthetic_page("https://docs.example.com/docs/get_started", html),
+        ];
+        let stats = run_pipeline(&Config::default(), &output_dir, pages).await;
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 4);
+
+        let mut dirs: Vec<String> = std::fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        dirs.sort();
+
+        assert_eq!(dirs.len(), 4, "{:?}", dirs);
+        assert_eq!(dirs[0], "docs-api");
+        assert!(dirs[1].starts_with("docs-api-"), "{:?}", dirs);
+        assert_eq!(dirs[2], "docs-get-started");
+        assert!(dirs[3].starts_with("docs-get-started-"), "{:?}", dirs);
+
+        // The frontmatter name follows the directory
+        let skill_md = std::fs::read_to_string(output_dir.join(&dirs[1]).join("SKILL.md")).unwrap();
+        assert!(skill_md.contains(&format!("name: {}", dirs[1])));
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_counts_dropped_pages() {
+        let output_dir = test_output_dir("dropped");
+        let crawler = Crawler::new(Config::default(), vec![output_dir.clone()]).unwrap();
+
+        // Overflow a small buffer before the pipeline gets to read it
+        let (tx, rx) = broadcast::channel(2);
+        for page in doc_pages(5) {
+            tx.send(page).unwrap();
+        }
+        drop(tx);
+
+        crawler
+            .pipeline("https://docs.example.com/", None)
+            .unwrap()
+            .run(Vec::new(), rx)
+            .await;
+        let stats = crawler.stats();
+
+        assert_eq!(stats.pages_dropped.load(Ordering::Relaxed), 3);
+        assert_eq!(stats.pages_visited.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 2);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    /// Holds up processing of the first page until released.
+    #[derive(Default)]
+    struct GateObserver {
+        held: std::sync::atomic::AtomicBool,
+        started: tokio::sync::Notify,
+        release: tokio::sync::Notify,
+    }
+
+    impl CrawlObserver for GateObserver {
+        fn page_fetched<'a>(&'a self, _page: &'a Page) -> crate::observer::ObserverFuture<'a> {
+            Box::pin(async move {
+                if !self.held.swap(true, Ordering::SeqCst) {
+                    self.started.notify_one();
+                    self.release.notified().await;
+                }
+            })
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_pipeline_bounds_in_flight_pages() {
+        let output_dir = test_output_dir("backlog");
+        let config = Config {
+            concurrency: 1,
+            ..Default::default()
+        };
+        let gate = Arc::new(GateObserver::default());
+        let crawler = Crawler::new(config, vec![output_dir.clone()])
+            .unwrap()
+            .with_observer(Arc::clone(&gate) as Arc<dyn CrawlObserver>);
+
+        let (tx, rx) = broadcast::channel(2);
+        let pipeline = tokio::spawn(
+            crawler
+                .pipeline("https://docs.example.com/", None)
+                .unwrap()
+                .run(Vec::new(), rx),
+        );
+        let mut pages = doc_pages(6).into_iter();
+
+        // The first page takes the only slot and is held there
+        tx.send(pages.next().unwrap()).unwrap();
+        gate.started.notified().await;
+        // The second is taken off the buffer and waits for the slot
+        tx.send(pages.next().unwrap()).unwrap();
+        while !tx.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        // Nothing reads the buffer meanwhile, so two of these overflow it
+        for page in pages {
+            tx.send(page).unwrap();
+        }
+        gate.release.notify_one();
+        drop(tx);
+        pipeline.await.unwrap();
+
+        let stats = crawler.stats();
+        assert_eq!(stats.pages_dropped.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 4);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    /// Runs one pipeline per seed concurrently, all sharing `reporter`.
+    async fn run_concurrent_seeds(reporter: Arc<CrawlReporter>, seeds: &[&str]) -> Vec<PathBuf> {
+        let mut output_dirs = Vec::new();
+        let mut handles = Vec::new();
+
+        for (i, seed) in seeds.iter().enumerate() {
+            let output_dir = test_output_dir(&format!("{:?}-seed-{}", reporter.format(), i));
+            let crawler = Crawler::new(Config::default(), vec![output_dir.clone()])
+                .unwrap()
+                .with_reporter(Arc::clone(&reporter));
+
+            let mut pages = doc_pages(2);
+            pages.push(synthetic_page(&format!("{}empty", seed), ""));
+            let (tx, rx) = broadcast::channel(pages.len());
+            for page in pages {
+                tx.send(page).unwrap();
+            }
+            drop(tx);
+
+            handles.push(tokio::spawn(
+                crawler.pipeline(seed, None).unwrap().run(Vec::new(), rx),
+            ));
+            output_dirs.push(output_dir);
+        }
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        output_dirs
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_json_stream_from_concurrent_seeds() {
+        let seeds = [
+            "https://a.example.com/",
+            "https://b.example.com/",
+            "https://c.example.com/",
+        ];
+        let buffer = SharedBuffer::default();
+        let reporter = Arc::new(CrawlReporter::new(
+            OutputFormat::JsonStream,
+            Box::new(buffer.clone()),
+        ));
+
+        let output_dirs = run_concurrent_seeds(Arc::clone(&reporter), &seeds).await;
+        reporter.finish().unwrap();
+
+        let events: Vec<serde_json::Value> = buffer
+            .contents()
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("every line must be valid JSON"))
+            .collect();
+
+        for seed in seeds {
+            let seed_events: Vec<&str> = events
+                .iter()
+                .filter(|e| e["seed"] == seed)
+                .map(|e| e["event"].as_str().unwrap())
+                .collect();
+            assert_eq!(seed_events.first(), Some(&"seed_started"));
+            assert_eq!(seed_events.last(), Some(&"seed_finished"));
+            assert_eq!(
+                seed_events
+                    .iter()
+                    .filter(|e| **e == "page_processed")
+                    .count(),
+                2
+            );
+            assert_eq!(
+                seed_events.iter().filter(|e| **e == "page_failed").count(),
+                1
+            );
+        }
+        assert!(events.iter().all(|e| e["seed"].is_string()));
+        assert_eq!(events.len(), seeds.len() * 5);
+
+        for dir in output_dirs {
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_json_report_from_concurrent_seeds() {
+        let seeds = ["https://a.example.com/", "https://b.example.com/"];
+        let buffer = SharedBuffer::default();
+        let reporter = Arc::new(CrawlReporter::new(
+            OutputFormat::Json,
+            Box::new(buffer.clone()),
+        ));
+
+        let output_dirs = run_concurrent_seeds(Arc::clone(&reporter), &seeds).await;
+        assert!(buffer.contents().is_empty());
+        reporter.finish().unwrap();
+
+        let report: serde_json::Value = serde_json::from_str(&buffer.contents()).unwrap();
+        let reported = report["seeds"].as_array().unwrap();
+        assert_eq!(reported.len(), 2);
+        for entry in reported {
+            assert!(seeds.contains(&entry["seed"].as_str().unwrap()));
+            assert_eq!(entry["stats"]["processed"], 2);
+            assert_eq!(entry["stats"]["failed"], 1);
+            assert_eq!(entry["pages"].as_array().unwrap().len(), 3);
+        }
+
+        for dir in output_dirs {
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_skips_by_content_type() {
+        let config = Config::from_yaml(
+            r#"
+rules:
+  - url: "*"
+    action: allow
+    content_type: "text/html"
+"#,
+        )
+        .unwrap();
+        let output_dir = test_output_dir("content-type");
+
+        let mut pages = doc_pages(1);
+        pages.push(typed_page(
+            "https://docs.example.com/guide/manual.pdf",
+            "%PDF-1.7",
+            "application/pdf",
+        ));
+
+        let stats = run_pipeline(&config, &output_dir, pages).await;
+
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.pages_skipped.load(Ordering::Relaxed), 1);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_processes_included_text_pages() {
+        let config = Config {
+            include_content_types: vec!["text/markdown".to_string(), "text/plain".to_string()],
+            ..Default::default()
+        };
+        let output_dir = test_output_dir("text-pages");
+
+        let mut pages = doc_pages(1);
+        pages.push(typed_page(
+            "https://docs.example.com/guide/setup.md",
+            "# Setup\n\nRun `setup`.\n",
+            "text/markdown",
+        ));
+        pages.push(typed_page(
+            "https://docs.example.com/logo.png",
+            "\u{89}PNG",
+            "image/png",
+        ));
+        pages.push(typed_page(
+            "https://docs.example.com/data.txt",
+            "\0\0\0",
+            "text/plain",
+        ));
+
+        let stats = run_pipeline(&config, &output_dir, pages).await;
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.pages_skipped.load(Ordering::Relaxed), 2);
+
+        let tree = read_tree(&output_dir);
+        let setup = &tree["guide-setup"];
+        assert!(setup.ends_with("# Setup\n\nRun `setup`.\n"), "{}", setup);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_skips_thin_pages() {
+        let config = Config {
+            min_content_chars: 40,
+            ..Default::default()
+        };
+        let output_dir = test_output_dir("thin-pages");
+
+        let pages = vec![
+            synthetic_page(
+                "https://docs.example.com/moved",
+                "<html><body><main><p>Moved here</p></main></body></html>",
+            ),
+            synthetic_page(
+                "https://docs.example.com/guide/install",
+                "<html><head><title>Install</title></head><body><main><h1>Install</h1>\
+                 <p>Download the installer for your platform and run it. It sets up the \
+                 command line tools and adds them to your PATH.</p></main></body></html>",
+            ),
+        ];
+
+        let buffer = SharedBuffer::default();
+        let reporter = Arc::new(CrawlReporter::new(
+            OutputFormat::Json,
+            Box::new(buffer.clone()),
+        ));
+        let crawler = Crawler::new(config, vec![output_dir.clone()])
+            .unwrap()
+            .with_reporter(Arc::clone(&reporter));
+        let (tx, rx) = broadcast::channel(pages.len());
+        for page in pages {
+            tx.send(page).unwrap();
+        }
+        drop(tx);
+        crawler
+            .pipeline("https://docs.example.com/", None)
+            .unwrap()
+            .run(Vec::new(), rx)
+            .await;
+        reporter.finish().unwrap();
+
+        let stats = crawler.stats().snapshot();
+        assert_eq!(stats.processed, 1);
+        assert_eq!(stats.too_small, 1);
+        assert_eq!(stats.skipped, 0);
+        assert_eq!(stats.failed, 0);
+        assert_eq!(
+            read_tree(&output_dir).keys().collect::<Vec<_>>(),
+            ["guide-install"]
+        );
+
+        // The report lists thin pages to spot false positives
+        let report: serde_json::Value = serde_json::from_str(&buffer.contents()).unwrap();
+        let thin: Vec<&serde_json::Value> = report["seeds"][0]["pages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|page| page["status"] == "too_small")
+            .collect();
+        assert_eq!(thin.len(), 1, "{}", report);
+        assert_eq!(thin[0]["url"], "https://docs.example.com/moved");
+        assert_eq!(thin[0]["content_chars"], 10);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_stops_at_token_budget() {
+        let output_dir = test_output_dir("token-budget");
+
+        // Every page has the same size, recorded in its frontmatter
+        let stats = run_pipeline(&Config::default(), &output_dir, doc_pages(1)).await;
+        let skill_md = std::fs::read_to_string(output_dir.join("guide-topic-0/SKILL.md")).unwrap();
+        let page_tokens = frontmatter::estimated_tokens(&skill_md).unwrap();
+        assert!(page_tokens > 0);
+        assert_eq!(stats.tokens.load(Ordering::Relaxed), page_tokens);
+        std::fs::remove_dir_all(&output_dir).unwrap();
+
+        let config = Config {
+            max_total_tokens: page_tokens * 3 + page_tokens / 2,
+            ..Default::default()
+        };
+        let stats = run_pipeline(&config, &output_dir, doc_pages(8)).await;
+        let snapshot = stats.snapshot();
+
+        assert_eq!(snapshot.processed, 3);
+        assert_eq!(snapshot.skipped, 5);
+        assert_eq!(snapshot.tokens, page_tokens * 3);
+        assert_eq!(read_tree(&output_dir).len(), 3);
+        assert!(
+            stats
+                .summary()
+                .ends_with(&format!("; ~{} tokens", page_tokens * 3))
+        );
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_writes_to_sink() {
+        let output_dir = test_output_dir("memory-sink");
+        let sink = Arc::new(MemorySink::default());
+        let crawler = Crawler::new(Config::default(), vec![output_dir.clone()])
+            .unwrap()
+            .with_sink(Box::new(SharedSink(Arc::clone(&sink))));
+
+        let stats = feed(&crawler, doc_pages(3)).await.snapshot();
+        assert_eq!(stats.processed, 3);
+
+        let skills = sink.skills();
+        let names: Vec<&str> = skills.keys().map(String::as_str).collect();
+        assert_eq!(names, ["guide-topic-0", "guide-topic-1", "guide-topic-2"]);
+        assert!(skills["guide-topic-1"].contains("Body of topic 1."));
+        // No skill reached the disk
+        assert!(!output_dir.join("guide-topic-0").exists());
+
+        // Crawling the same pages again changes nothing
+        let stats = feed(&crawler, doc_pages(3)).await.snapshot();
+        assert_eq!(stats.unchanged, 3);
+
+        let _ = std::fs::remove_dir_all(&output_dir);
+    }
+
+    /// Lets a test keep a handle on the sink it gives the crawler.
+    struct SharedSink(Arc<MemorySink>);
+
+    impl OutputSink for SharedSink {
+        fn write_skill<'a>(&'a self, processed: &'a ProcessedPage) -> WriteFuture<'a> {
+            self.0.write_skill(processed)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_names_skills_after_titles() {
+        let config = Config {
+            skill_name_source: SkillNameSource::TitleFallbackUrl,
+            ..Default::default()
+        };
+        let output_dir = test_output_dir("title-names");
+        let page = |path: &str, head: &str| {
+            synthetic_page(
+                &format!("https://docs.example.com{}", path),
+                &format!(
+                    "<html><head>{head}</head>\
+                     <body><main><p>Body of the page at {path}.</p></main></body></html>"
+                ),
+            )
+        };
+        let pages = vec![
+            page("/p/48219-xyz", "<title>Overview | Acme Docs</title>"),
+            page("/p/50113-abc", "<title>Overview | Acme Docs</title>"),
+            page("/p/77", "<title>🚀 Install Guide | Acme Docs</title>"),
+            page("/p/untitled", ""),
+        ];
+
+        run_pipeline(&config, &output_dir, pages).await;
+        let tree = read_tree(&output_dir);
+        let names: Vec<&str> = tree.keys().map(String::as_str).collect();
+
+        // Whichever Overview page came second is named after its URL
+        assert_eq!(names.len(), 4, "{:?}", names);
+        assert!(names.contains(&"overview"), "{:?}", names);
+        assert!(names.contains(&"install-guide"), "{:?}", names);
+        assert!(names.contains(&"p-untitled"), "{:?}", names);
+        let renamed = if names.contains(&"p-48219-xyz") {
+            "p-48219-xyz"
+        } else {
+            "p-50113-abc"
+        };
+        assert!(names.contains(&renamed), "{:?}", names);
+        assert!(tree[renamed].contains(&format!("name: {}\n", renamed)));
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_diff() {
+        use crate::diff::{ChangeKind, CrawlDiff, SkillChange};
+
+        let config = Config::default();
+        let output_dir = test_output_dir("diff");
+
+        // topic-0 changes, topic-1 is gone from the site and topic-3 is new
+        run_pipeline(&config, &output_dir, doc_pages(2)).await;
+        let before = read_tree(&output_dir);
+
+        let pages = vec![
+            synthetic_page(
+                "https://docs.example.com/guide/topic-0",
+                "<html><head><title>Topic 0</title></head>\
+                 <body><main><h1>Topic 0</h1><p>Body of topic 0.</p><p>More.</p></main></body></html>",
+            ),
+            synthetic_page(
+                "https://docs.example.com/guide/topic-3",
+                "<html><head><title>Topic 3</title></head>\
+                 <body><main><h1>Topic 3</h1><p>Body of topic 3.</p></main></body></html>",
+            ),
+        ];
+        let diff = Arc::new(CrawlDiff::new(&output_dir, true));
+        let crawler = Crawler::new(config, vec![output_dir.clone()])
+            .unwrap()
+            .with_diff(Arc::clone(&diff));
+        let (tx, rx) = broadcast::channel(pages.len());
+        for page in pages {
+            tx.send(page).unwrap();
+        }
+        drop(tx);
+        crawler
+            .pipeline("https://docs.example.com/", None)
+            .unwrap()
+            .run(Vec::new(), rx)
+            .await;
+
+        let changes = diff.changes().unwrap();
+        let kinds: Vec<(&str, ChangeKind)> = changes
+            .iter()
+            .map(|change| (change.name.as_str(), change.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("guide-topic-0", ChangeKind::Modified),
+                ("guide-topic-1", ChangeKind::Removed),
+                ("guide-topic-3", ChangeKind::Added),
+            ]
+        );
+        assert_eq!(
+            changes[0],
+            SkillChange {
+                name: "guide-topic-0".to_string(),
+                kind: ChangeKind::Modified,
+                // The paragraph, and the new estimated_tokens and content_hash
+                added: 4,
+                removed: 2,
+            }
+        );
+        assert_eq!(changes[1].added, 0);
+        assert!(changes[1].removed > 0);
+        assert_eq!(changes[2].removed, 0);
+
+        // A dry run leaves the skills as they were
+        assert_eq!(read_tree(&output_dir), before);
+        assert_eq!(crawler.stats().snapshot().processed, 2);
+
+        let mut out = Vec::new();
+        crate::diff::write_changes(&changes, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("modified  guide-topic-0  +4 -2\n"), "{}", out);
+        assert!(
+            out.ends_with("3 skills changed: 1 added, 1 modified, 1 removed\n"),
+            "{}",
+            out
+        );
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_dedups_tracking_params() {
+        let config = Config::default();
+        let output_dir = test_output_dir("tracking-params");
+
+        let html = "<html><head><title>Install</title></head>\
+                    <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>";
+        let pages = vec![
+            synthetic_page("https://docs.example.com/install?utm_source=blog", html),
+            synthetic_page("https://docs.example.com/install?ref=nav&fbclid=x", html),
+            synthetic_page("https://docs.example.com/install?version=2&gclid=y", html),
+            // Fragments and doubled slashes don't make another page either
+            synthetic_page("https://Docs.Example.com//install#step-2", html),
+        ];
+
+        let stats = run_pipeline(&config, &output_dir, pages).await;
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.pages_skipped.load(Ordering::Relaxed), 2);
+
+        let tree = read_tree(&output_dir);
+        assert_eq!(tree.len(), 2, "{:?}", tree.keys());
+        let urls: Vec<&str> = tree
+            .values()
+            .filter_map(|skill_md| {
+                skill_md
+                    .lines()
+                    .find(|line| line.trim().starts_with("url:"))
+            })
+            .map(str::trim)
+            .collect();
+        assert!(
+            urls.contains(&"url: https://docs.example.com/install"),
+            "{:?}",
+            urls
+        );
+        assert!(
+            urls.contains(&"url: https://docs.example.com/install?version=2"),
+            "{:?}",
+            urls
+        );
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_records_link_graph() {
+        let config = Config::from_yaml(
+            r#"
+rules:
+  - url: "*/changelog*"
+    action: ignore
+"#,
+        )
+        .unwrap();
+        let output_dir = test_output_dir("link-graph");
+        let dot_path = output_dir.join("site.dot");
+
+        let page = |title: &str, links: &[&str]| {
+            let links: String = links
+                .iter()
+                .map(|href| format!("<li><a href=\"{href}\">{href}</a></li>"))
+                .collect();
+            format!(
+                "<html><head><title>{title}</title></head>\
+                 <body><main><h1>{title}</h1><p>About {title}.</p><ul>{links}</ul></main></body></html>"
+            )
+        };
+        let pages = vec![
+            synthetic_page(
+                "https://docs.example.com/",
+                &page(
+                    "Docs",
+                    &[
+                        "/install",
+                        "/api?utm_source=nav",
+                        "/changelog",
+                        "https://github.com/example/docs",
+                        "#top",
+                    ],
+                ),
+            ),
+            synthetic_page(
+                "https://docs.example.com/install",
+                &page("Install", &["/", "/api", "/install#linux"]),
+            ),
+            synthetic_page("https://docs.example.com/api", &page("API", &["/install"])),
+        ];
+
+        let graph = Arc::new(LinkGraph::new());
+        let crawler = Crawler::new(config, vec![output_dir.clone()])
+            .unwrap()
+            .with_link_graph(Arc::clone(&graph));
+        feed(&crawler, pages).await;
+        graph.write(&dot_path).unwrap();
+
+        // The changelog is ignored, GitHub out of scope and anchors and
+        // tracking parameters don't make another page
+        let dot = std::fs::read_to_string(&dot_path).unwrap();
+        assert_eq!(dot.matches(" [label=").count(), 3, "{}", dot);
+        assert_eq!(dot.matches(" -> ").count(), 5, "{}", dot);
+        assert!(
+            dot.contains(r#""https://docs.example.com/install" [label="install""#),
+            "{}",
+            dot
+        );
+        assert!(
+            dot.contains(r#""https://docs.example.com/" -> "https://docs.example.com/api";"#),
+            "{}",
+            dot
+        );
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_skips_other_languages() {
+        let config = Config {
+            languages: vec!["en".to_string()],
+            ..Default::default()
+        };
+        let output_dir = test_output_dir("languages");
+
+        let page = |lang: &str, title: &str| {
+            format!(
+                "<html{lang}><head><title>{title}</title></head>\
+                 <body><main><h1>{title}</h1><p>{title}.</p></main></body></html>"
+            )
+        };
+        let pages = vec![
+            synthetic_page(
+                "https://docs.example.com/en/install",
+                &page(" lang=\"en-US\"", "Install"),
+            ),
+            synthetic_page(
+                "https://docs.example.com/ja/install",
+                &page(" lang=\"ja\"", "Japanese install"),
+            ),
+            synthetic_page(
+                "https://docs.example.com/de/install",
+                &page(" lang=\"de\"", "German install"),
+            ),
+            // Pages that don't say are kept
+            synthetic_page("https://docs.example.com/faq", &page("", "FAQ")),
+        ];
+
+        let stats = run_pipeline(&config, &output_dir, pages).await.snapshot();
+        assert_eq!(stats.processed, 2);
+        assert_eq!(stats.skipped_language, 2);
+
+        let tree = read_tree(&output_dir);
+        assert_eq!(tree.len(), 2, "{:?}", tree.keys());
+        assert!(
+            tree.values()
+                .any(|skill_md| skill_md.contains("language: en-us")),
+            "{:?}",
+            tree
+        );
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_respects_canonical() {
+        let config = Config {
+            respect_canonical: true,
+            ..Default::default()
+        };
+        let output_dir = test_output_dir("canonical");
+
+        let page = |canonical: &str| {
+            format!(
+                "<html><head><title>Install</title><link rel=\"canonical\" href=\"{canonical}\"></head>\
+                 <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>"
+            )
+        };
+        let pages = vec![
+            synthetic_page("https://docs.example.com/install", &page("/install")),
+            synthetic_page(
+                "https://docs.example.com/install/print",
+                &page("https://docs.example.com/install#top"),
+            ),
+            // Canonical pages the crawl doesn't cover keep the copy
+            synthetic_page(
+                "https://docs.example.com/mirror/setup",
+                &page("https://www.example.com/setup"),
+            ),
+        ];
+
+        let buffer = SharedBuffer::default();
+        let reporter = Arc::new(CrawlReporter::new(
+            OutputFormat::Json,
+            Box::new(buffer.clone()),
+        ));
+        let crawler = Crawler::new(config, vec![output_dir.clone()])
+            .unwrap()
+            .with_reporter(Arc::clone(&reporter));
+        feed(&crawler, pages).await;
+        reporter.finish().unwrap();
+
+        let stats = crawler.stats().snapshot();
+        assert_eq!(stats.processed, 2);
+        assert_eq!(stats.deduplicated, 1);
+
+        let tree = read_tree(&output_dir);
+        assert_eq!(tree.len(), 2, "{:?}", tree.keys());
+        assert!(
+            tree.values()
+                .any(|skill_md| skill_md.contains("canonical_url: https://www.example.com/setup")),
+            "{:?}",
+            tree
+        );
+
+        let report: serde_json::Value = serde_json::from_str(&buffer.contents()).unwrap();
+        let deduplicated: Vec<&serde_json::Value> = report["seeds"][0]["pages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|page| page["status"] == "deduplicated")
+            .collect();
+        assert_eq!(deduplicated.len(), 1, "{}", report);
+        assert_eq!(
+            deduplicated[0]["url"],
+            "https://docs.example.com/install/print"
+        );
+        assert_eq!(
+            deduplicated[0]["canonical_url"],
+            "https://docs.example.com/install"
+        );
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_skips_oversized_pdfs() {
+        let config = Config {
+            process_pdfs: true,
+            max_pdf_bytes: 16,
+            ..Default::default()
+        };
+        let output_dir = test_output_dir("pdf-pages");
+
+        let mut pages = doc_pages(1);
+        pages.push(typed_page(
+            "https://docs.example.com/manual.pdf",
+            "%PDF-1.4 far more than sixteen bytes",
+            "application/pdf",
+        ));
+        pages.push(typed_page(
+            "https://docs.example.com/logo.png",
+            "\u{89}PNG",
+            "image/png",
+        ));
+
+        let stats = run_pipeline(&config, &output_dir, pages).await;
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.pages_skipped.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.pages_failed.load(Ordering::Relaxed), 0);
+
+        let pattern = regex::Regex::new(&binary_asset_pattern(true)).unwrap();
+        assert!(!pattern.is_match("https://docs.example.com/manual.pdf"));
+        assert!(pattern.is_match("https://docs.example.com/logo.png?v=2"));
+        let pattern = regex::Regex::new(&binary_asset_pattern(false)).unwrap();
+        assert!(pattern.is_match("https://docs.example.com/manual.pdf"));
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    /// Skill directory name -> SKILL.md content of every skill in `dir`,
+    /// without the fields that differ between runs.
+    fn read_tree(dir: &Path) -> std::collections::BTreeMap<String, String> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                let skill_md = std::fs::read_to_string(path.join("SKILL.md")).unwrap();
+                (name, frontmatter::without_volatile_fields(&skill_md))
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pipeline_output_independent_of_concurrency() {
+        let sequential_dir = test_output_dir("sequential");
+        let concurrent_dir = test_output_dir("concurrent");
+
+        let sequential_config = Config {
+            concurrency: 1,
+            ..Default::default()
+        };
+        let concurrent_config = Config {
+            concurrency: 4,
+            ..Default::default()
+        };
+        run_pipeline(&sequential_config, &sequential_dir, doc_pages(8)).await;
+        run_pipeline(&concurrent_config, &concurrent_dir, doc_pages(8)).await;
+
+        let sequential = read_tree(&sequential_dir);
+        assert_eq!(sequential.len(), 8);
+        assert_eq!(sequential, read_tree(&concurrent_dir));
+
+        std::fs::remove_dir_all(&sequential_dir).unwrap();
+        std::fs::remove_dir_all(&concurrent_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_writes_a_copy_per_target() {
+        let cursor_dir = test_output_dir("target-cursor");
+        let claude_dir = test_output_dir("target-claude");
+        let config = Config {
+            targets: vec![SkillsTarget::Cursor, SkillsTarget::ClaudeCode],
+            ..Default::default()
+        };
+
+        // One directory per target is required
+        assert!(Crawler::new(config.clone(), vec![cursor_dir.clone()]).is_err());
+
+        let crawler = Crawler::new(config, vec![cursor_dir.clone(), claude_dir.clone()]).unwrap();
+        let pages = doc_pages(3);
+        let (tx, rx) = broadcast::channel(pages.len());
+        for page in pages {
+            tx.send(page).unwrap();
+        }
+        drop(tx);
+        crawler
+            .pipeline("https://docs.example.com/", None)
+            .unwrap()
+            .run(Vec::new(), rx)
+            .await;
+
+        assert_eq!(crawler.stats().snapshot().processed, 3);
+        let cursor = read_tree(&cursor_dir);
+        assert_eq!(cursor.len(), 3);
+        assert_eq!(cursor, read_tree(&claude_dir));
+
+        std::fs::remove_dir_all(&cursor_dir).unwrap();
+        std::fs::remove_dir_all(&claude_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_crawl_reports_allowlisted_headers() {
+        let port = crate::http::test_server(|_| {
+            let body = "<html><head><title>Guide</title></head>\
+                        <body><main><h1>Guide</h1><p>Text.</p></main></body></html>";
+            format!(
+                "HTTP/1.1 200 OK\r\n\
+                 content-type: text/html; charset=utf-8\r\n\
+                 content-language: de\r\n\
+                 last-modified: Tue, 01 Sep 2026 10:00:00 GMT\r\n\
+                 x-robots-tag: noindex\r\n\
+                 x-internal-trace: abc123\r\n\
+                 content-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        })
+        .await;
+
+        let output_dir = test_output_dir("headers");
+        let config = Config {
+            delay_ms: 0,
+            respect_robots_txt: false,
+            ..Default::default()
+        };
+        let buffer = SharedBuffer::default();
+        let reporter = Arc::new(CrawlReporter::new(
+            OutputFormat::Json,
+            Box::new(buffer.clone()),
+        ));
+        let crawler = Crawler::new(config, vec![output_dir.clone()])
+            .unwrap()
+            .with_reporter(Arc::clone(&reporter));
+
+        crawler
+            .crawl(&format!("http://127.0.0.1:{}/guide", port))
+            .await
+            .unwrap();
+        reporter.finish().unwrap();
+
+        let report: serde_json::Value = serde_json::from_str(&buffer.contents()).unwrap();
+        let page = &report["seeds"][0]["pages"][0];
+        assert_eq!(page["status"], "processed", "{}", report);
+
+        let headers = page["headers"].as_object().unwrap();
+        assert_eq!(headers["content-type"], "text/html; charset=utf-8");
+        assert_eq!(headers["content-language"], "de");
+        assert_eq!(headers["last-modified"], "Tue, 01 Sep 2026 10:00:00 GMT");
+        assert_eq!(headers["x-robots-tag"], "noindex");
+        // Not on the default allowlist
+        assert!(!headers.contains_key("x-internal-trace"));
+        assert!(!headers.contains_key("content-length"));
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_crawl_names_redirected_pages_after_destination() {
+        let port = crate::http::test_server(|request| {
+            let redirect = |location: String| {
+                format!(
+                    "HTTP/1.1 302 Found\r\nlocation: {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    location
+                )
+            };
+            if request.starts_with("GET /old-guide ") {
+                return redirect("/guide/intro".to_string());
+            }
+            if request.starts_with("GET /moved-away ") {
+                // Same server under another host name: out of the seed's scope
+                let port = request
+                    .lines()
+                    .find_map(|line| line.to_lowercase().strip_prefix("host: 127.0.0.1:").map(str::to_string))
+                    .unwrap_or_default();
+                return redirect(format!("http://localhost:{}/elsewhere", port.trim()));
+            }
+            let body = "<html><head><title>Intro</title></head><body><main><h1>Intro</h1>\
+                        <p>Start here.</p><a href=\"/moved-away\">Moved</a></main></body></html>";
+            crate::http::ok_response(body)
+        })
+        .await;
+
+        let output_dir = test_output_dir("redirects");
+        let config = Config {
+            delay_ms: 0,
+            respect_robots_txt: false,
+            ..Default::default()
+        };
+        let crawler = Crawler::new(config, vec![output_dir.clone()]).unwrap();
+        let stats = crawler
+            .crawl(&format!("http://127.0.0.1:{}/old-guide", port))
+            .await
+            .unwrap()
+            .snapshot();
+
+        let tree = read_tree(&output_dir);
+        assert_eq!(
+            tree.keys().collect::<Vec<_>>(),
+            ["guide-intro"],
+            "{:?}",
+            stats
+        );
+        let skill_md = &tree["guide-intro"];
+        assert!(
+            skill_md.contains(&format!("url: http://127.0.0.1:{}/guide/intro", port)),
+            "{}",
+            skill_md
+        );
+        assert!(
+            skill_md.contains(&format!(
+                "requested_url: http://127.0.0.1:{}/old-guide",
+                port
+            )),
+            "{}",
+            skill_md
+        );
+        assert_eq!(stats.skipped, 1, "{:?}", stats);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_crawl_collect() {
+        let port = crate::http::test_server(|request| {
+            let body = if request.starts_with("GET /guide/install ") {
+                "<html lang=\"en\"><head><title>Install</title>\
+                 <meta name=\"description\" content=\"Installing the widget toolkit\"></head>\
+                 <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>"
+            } else {
+                "<html lang=\"en\"><head><title>Guide</title></head><body><main><h1>Guide</h1>\
+                 <p>Start here.</p><a href=\"/guide/install\">Install</a></main></body></html>"
+            };
+            crate::http::ok_response(body)
+        })
+        .await;
+
+        let output_dir = test_output_dir("collect");
+        let config = Config {
+            delay_ms: 0,
+            respect_robots_txt: false,
+            incremental: true,
+            ..Default::default()
+        };
+        let crawler = Crawler::new(config, vec![output_dir.clone()]).unwrap();
+        let seed = format!("http://127.0.0.1:{}/guide", port);
+        let pages = crawler.crawl_collect(&seed).await.unwrap();
+
+        let urls: Vec<&str> = pages
+            .iter()
+            .map(|page| page.metadata.url.as_str())
+            .collect();
+        assert_eq!(urls, [seed.clone(), format!("{}/install", seed)]);
+        let install = &pages[1].metadata;
+        assert_eq!(install.title, "Install");
+        assert_eq!(install.skill_name, "guide-install");
+        assert_eq!(install.description, "Installing the widget toolkit");
+        assert!(pages[0].links.contains(&install.url));
+        assert_eq!(crawler.stats().snapshot().processed, 2);
+        // Neither skills nor the crawl cache were written
+        assert!(!output_dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_crawl_local_directory() {
+        let docs_dir = test_output_dir("local-docs");
+        let pages = [
+            ("index.html", "Widgets", "Widgets are the building blocks."),
+            ("guide/install.html", "Install", "Run the installer."),
+            ("guide/notes.md", "Notes", "Not HTML, so not a skill."),
+            ("drafts/wip.html", "Draft", "Ignored by the rules."),
+        ];
+        for (file, title, body) in pages {
+            let path = docs_dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(
+                path,
+                format!(
+                    "<html><head><title>{title}</title></head>\
+                     <body><main><h1>{title}</h1><p>{body}</p></main></body></html>"
+                ),
+            )
+            .unwrap();
+        }
+
+        let output_dir = test_output_dir("local-skills");
+        // Rules for the site don't keep its files out, but ignore rules hold
+        let config = Config {
+            min_content_chars: 0,
+            rules: vec![
+                Rule::new("https://docs.example.com/**", Action::Allow),
+                Rule::new("*/drafts/*", Action::Ignore),
+            ],
+            ..Default::default()
+        };
+        let scope = crate::scope::scope(&config, docs_dir.to_str().unwrap());
+        assert!(scope.base_url.starts_with("file:///"));
+        let crawler = Crawler::new(scope.config, vec![output_dir.clone()]).unwrap();
+        let stats = crawler.crawl(&scope.base_url).await.unwrap().snapshot();
+
+        assert_eq!(stats.processed, 2);
+        let skills: Vec<String> = crate::utils::skill_files(&output_dir)
+            .unwrap()
+            .into_iter()
+            .map(|skill| skill.name)
+            .collect();
+        // Named after their path in the directory
+        assert_eq!(skills, ["guide-install", "index"]);
+        let skill_md = std::fs::read_to_string(output_dir.join("guide-install/SKILL.md")).unwrap();
+        assert!(skill_md.contains("# Install"), "{}", skill_md);
+        let url = crate::frontmatter::source_url(&skill_md).unwrap();
+        assert_eq!(
+            url,
+            crate::local::file_url(&docs_dir.join("guide/install.html")).unwrap()
+        );
+
+        std::fs::remove_dir_all(&docs_dir).unwrap();
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    /// Requests for `/guide/install` received by the shared-session test server.
+    static INSTALL_FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_crawlers_share_visited_pages() {
+        let port = crate::http::test_server(|request| {
+            let body = if request.starts_with("GET /guide/install ") {
+                INSTALL_FETCHES.fetch_add(1, Ordering::SeqCst);
+                "<html><head><title>Install</title></head>\
+                 <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>"
+            } else if request.starts_with("GET /guide ") {
+                "<html><head><title>Guide</title></head><body><main><h1>Guide</h1>\
+                 <p>Read on.</p><a href=\"/guide/install\">Install</a></main></body></html>"
+            } else {
+                "<html><head><title>Docs</title></head><body><main><h1>Docs</h1>\
+                 <p>Start here.</p><a href=\"/guide\">Guide</a></main></body></html>"
+            };
+            crate::http::ok_response(body)
+        })
+        .await;
+
+        let output_dir = test_output_dir("shared-visited");
+        let config = Config {
+            delay_ms: 0,
+            respect_robots_txt: false,
+            ..Default::default()
+        };
+        let visited = Arc::new(VisitedUrls::new());
+        let names = Arc::new(SkillNames::new(&config.targets()));
+        let crawl = |seed: String| {
+            let crawler = Crawler::new(config.clone(), vec![output_dir.clone()])
+                .unwrap()
+                .with_names(Arc::clone(&names))
+                .with_visited(Arc::clone(&visited));
+            async move { crawler.crawl(&seed).await.unwrap().snapshot() }
+        };
+
+        let first = crawl(format!("http://127.0.0.1:{}/", port)).await;
+        assert_eq!(first.processed, 3, "{:?}", first);
+
+        // Its seed was crawled already, and the page it links to isn't fetched
+        let second = crawl(format!("http://127.0.0.1:{}/guide", port)).await;
+        assert_eq!(second.processed, 0, "{:?}", second);
+        assert_eq!(second.skipped, 1, "{:?}", second);
+        assert_eq!(INSTALL_FETCHES.load(Ordering::SeqCst), 1);
+        assert_eq!(read_tree(&output_dir).len(), 3);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    /// Full (non-304) responses sent for `/guide` by the incremental test server.
+    static GUIDE_FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+    #[tokio::test]
+    async fn test_incremental_crawl_skips_unchanged_pages() {
+        let port = crate::http::test_server(|request| {
+            let (body, etag) = if request.starts_with("GET /guide ") {
+                if request
+                    .to_lowercase()
+                    .contains("if-none-match: \"guide-v1\"")
+                {
+                    return "HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n".to_string();
+                }
+                GUIDE_FETCHES.fetch_add(1, Ordering::SeqCst);
+                (
+                    "<html><head><title>Guide</title></head>\
+                     <body><main><h1>Guide</h1><p>Text.</p></main></body></html>",
+                    "\"guide-v1\"",
+                )
+            } else {
+                // No validators: only the content hash tells it's unchanged
+                (
+                    "<html><head><title>Home</title></head>\
+                     <body><main><h1>Home</h1><a href=\"/guide\">Guide</a></main></body></html>",
+                    "",
+                )
+            };
+            let etag = if etag.is_empty() {
+                String::new()
+            } else {
+                format!("etag: {}\r\n", etag)
+            };
+            format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\n{}\
+                 content-length: {}\r\nconnection: close\r\n\r\n{}",
+                etag,
+                body.len(),
+                body
+            )
+        })
+        .await;
+
+        let output_dir = test_output_dir("incremental");
+        let config = Config {
+            delay_ms: 0,
+            respect_robots_txt: false,
+            incremental: true,
+            ..Default::default()
+        };
+        let seed = format!("http://127.0.0.1:{}/", port);
+
+        let first = Crawler::new(config.clone(), vec![output_dir.clone()]).unwrap();
+        let stats = first.crawl(&seed).await.unwrap().snapshot();
+        assert_eq!((stats.processed, stats.unchanged), (2, 0));
+        assert!(output_dir.join(crate::cache::CACHE_FILE).is_file());
+
+        let second = Crawler::new(config, vec![output_dir.clone()]).unwrap();
+        let stats = second.crawl(&seed).await.unwrap().snapshot();
+        assert_eq!((stats.processed, stats.unchanged), (0, 2));
+        // The guide was revalidated with a 304 and not fetched again
+        assert_eq!(GUIDE_FETCHES.load(Ordering::SeqCst), 1);
+
+        // Cleaning drops the cache along with the skills
+        clean_output_dir(&output_dir, &CleanFilter::default())
+            .await
+            .unwrap();
+        assert!(!output_dir.join(crate::cache::CACHE_FILE).exists());
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_writes_and_cleans_both_layouts() {
+        for flat in [false, true] {
+            let config = Config {
+                flat,
+                ..Default::default()
+            };
+            let output_dir = test_output_dir(&format!("layout-{}", flat));
+            let stats = run_pipeline(&config, &output_dir, doc_pages(2)).await;
+            assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 2);
+
+            let skill_md = |name: &str| {
+                if flat {
+                    output_dir.join(format!("{}.md", name))
+                } else {
+                    output_dir.join(name).join("SKILL.md")
+                }
+            };
+            let content = std::fs::read_to_string(skill_md("guide-topic-0")).unwrap();
+            assert!(content.contains("name: guide-topic-0"), "{}", content);
+            assert!(skill_md("guide-topic-1").is_file());
+
+            // Hand-written files survive a clean
+            std::fs::write(output_dir.join("README.md"), "# Notes\n").unwrap();
+            std::fs::create_dir_all(output_dir.join("mine")).unwrap();
+            assert_eq!(
+                clean_output_dir(&output_dir, &CleanFilter::default())
+                    .await
+                    .unwrap(),
+                2
+            );
+            let mut left: Vec<String> = std::fs::read_dir(&output_dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            left.sort();
+            assert_eq!(left, ["README.md", "mine"]);
+
+            std::fs::remove_dir_all(&output_dir).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_groups_skills_by_domain_and_section() {
+        let output_dir = test_output_dir("group-by");
+        let config = Config {
+            group_by: crate::config::GroupBy::DomainAndSection,
+            ..Default::default()
+        };
+        let page = |url: &str| {
+            synthetic_page(
+                url,
+                "<html><head><title>Install</title></head>\
+                 <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>",
+            )
+        };
+        let stats = run_pipeline(
+            &config,
+            &output_dir,
+            vec![
+                page("https://docs.example.com/guide/install"),
+                page("https://api.example.com/guide/install"),
+                page("https://docs.example.com/reference/widgets"),
+            ],
+        )
+        .await;
+        assert_eq!(stats.pages_processed.load(Ordering::Relaxed), 3);
+
+        // The same name in two groups doesn't collide
+        for skill in [
+            "docs-example-com/guide/guide-install",
+            "api-example-com/guide/guide-install",
+            "docs-example-com/reference/reference-widgets",
+        ] {
+            assert!(
+                output_dir.join(skill).join("SKILL.md").is_file(),
+                "{}",
+                skill
+            );
+        }
+        let groups: Vec<Option<String>> = crate::utils::skill_files(&output_dir)
+            .unwrap()
+            .into_iter()
+            .map(|skill| skill.group)
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                Some("api-example-com/guide".to_string()),
+                Some("docs-example-com/guide".to_string()),
+                Some("docs-example-com/reference".to_string()),
+            ]
+        );
+
+        // Emptied groups go with their skills
+        assert_eq!(
+            clean_output_dir(&output_dir, &CleanFilter::default())
+                .await
+                .unwrap(),
+            3
+        );
+        assert_eq!(std::fs::read_dir(&output_dir).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_clean_older_than() {
+        let output_dir = test_output_dir("older-than");
+        for name in ["fresh", "stale", "stale-too"] {
+            std::fs::create_dir_all(output_dir.join(name)).unwrap();
+            std::fs::write(output_dir.join(name).join("SKILL.md"), "---\n---\n").unwrap();
+        }
+        let ten_days_ago = SystemTime::now() - Duration::from_secs(10 * 86400);
+        for name in ["stale", "stale-too"] {
+            std::fs::File::options()
+                .write(true)
+                .open(output_dir.join(name).join("SKILL.md"))
+                .unwrap()
+                .set_modified(ten_days_ago)
+                .unwrap();
+        }
+        let skills = |output_dir: &Path| -> Vec<String> {
+            crate::utils::skill_files(output_dir)
+                .unwrap()
+                .into_iter()
+                .map(|skill| skill.name)
+                .collect()
+        };
+
+        // Both filters must hold
+        let filter = CleanFilter {
+            pattern: crate::update::skill_pattern(Some("*-too")).unwrap(),
+            older_than: Some(Duration::from_secs(7 * 86400)),
+        };
+        assert_eq!(clean_output_dir(&output_dir, &filter).await.unwrap(), 1);
+        assert_eq!(skills(&output_dir), ["fresh", "stale"]);
+
+        let filter = CleanFilter {
+            older_than: Some(Duration::from_secs(7 * 86400)),
+            ..Default::default()
+        };
+        assert_eq!(clean_output_dir(&output_dir, &filter).await.unwrap(), 1);
+        assert_eq!(skills(&output_dir), ["fresh"]);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_clean_older_than_goes_by_last_seen() {
+        let output_dir = test_output_dir("older-than-last-seen");
+        let skill_md = |processed_at: &str| {
+            format!(
+                "---\nname: guide\nmetadata:\n  url: https://docs.example.com/guide/\n  processed_at: {}\n---\n",
+                processed_at
+            )
+        };
+        let backdate = |path: &str, days: u64| {
+            std::fs::File::options()
+                .write(true)
+                .open(output_dir.join(path))
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(days * 86400))
+                .unwrap();
+        };
+        // Unchanged since 2020 and not seen by a crawl since
+        for name in ["old", "seen"] {
+            std::fs::create_dir_all(output_dir.join(name)).unwrap();
+            std::fs::write(
+                output_dir.join(name).join("SKILL.md"),
+                skill_md("2020-01-01T00:00:00Z"),
+            )
+            .unwrap();
+        }
+        backdate("old/SKILL.md", 30);
+        // Changed today, but the file was copied with an old mtime
+        std::fs::create_dir_all(output_dir.join("new")).unwrap();
+        let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        std::fs::write(output_dir.join("new/SKILL.md"), skill_md(&now)).unwrap();
+        backdate("new/SKILL.md", 30);
+        // Not generated, so never selected
+        std::fs::write(output_dir.join("README.md"), "# Skills\n").unwrap();
+
+        let filter = CleanFilter {
+            older_than: Some(Duration::from_secs(7 * 86400)),
+            ..Default::default()
+        };
+        let selected: Vec<String> = selected_skills(&output_dir, &filter)
+            .unwrap()
+            .into_iter()
+            .map(|skill| skill.name)
+            .collect();
+        assert_eq!(selected, ["old"]);
+        // Listing them removes nothing
+        assert!(output_dir.join("old/SKILL.md").exists());
+
+        assert_eq!(clean_output_dir(&output_dir, &filter).await.unwrap(), 1);
+        assert!(!output_dir.join("old").exists());
+        assert!(output_dir.join("new/SKILL.md").exists());
+        // Unchanged since 2020, but a crawl touched it today
+        assert!(output_dir.join("seen/SKILL.md").exists());
+        assert!(output_dir.join("README.md").exists());
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_clean_older_than_keeps_recrawled_skills() {
+        let port = crate::http::test_server(|_| {
+            crate::http::ok_response(
+                "<html><head><title>Guide</title></head>\
+                 <body><main><h1>Guide</h1><p>Text.</p></main></body></html>",
+            )
+        })
+        .await;
+        let seed = format!("http://127.0.0.1:{}/guide", port);
+        let filter = CleanFilter {
+            older_than: Some(Duration::from_secs(7 * 86400)),
+            ..Default::default()
+        };
+
+        let processed_at = regex::Regex::new("processed_at: .*").unwrap();
+
+        // Rewritten unchanged, or skipped as unchanged by the crawl cache
+        for incremental in [false, true] {
+            let output_dir = test_output_dir(&format!("recrawl-older-than-{}", incremental));
+            let config = Config {
+                delay_ms: 0,
+                respect_robots_txt: false,
+                incremental,
+                ..Default::default()
+            };
+            Crawler::new(config.clone(), vec![output_dir.clone()])
+                .unwrap()
+                .crawl(&seed)
+                .await
+                .unwrap();
+            // As if it had been crawled ten days ago
+            let skill_md = output_dir.join("guide/SKILL.md");
+            let written = processed_at
+                .replace(
+                    &std::fs::read_to_string(&skill_md).unwrap(),
+                    "processed_at: 2020-01-01T00:00:00Z",
+                )
+                .into_owned();
+            std::fs::write(&skill_md, &written).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&skill_md)
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(10 * 86400))
+                .unwrap();
+            assert_eq!(selected_skills(&output_dir, &filter).unwrap().len(), 1);
+
+            let stats = Crawler::new(config, vec![output_dir.clone()])
+                .unwrap()
+                .crawl(&seed)
+                .await
+                .unwrap()
+                .snapshot();
+            assert_eq!(stats.processed + stats.unchanged, 1);
+            assert_eq!(std::fs::read_to_string(&skill_md).unwrap(), written);
+
+            assert_eq!(clean_output_dir(&output_dir, &filter).await.unwrap(), 0);
+            assert!(skill_md.exists());
+
+            std::fs::remove_dir_all(&output_dir).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_writes_and_cleans_other_formats() {
+        use crate::config::SkillFormat;
+
+        let cases = [
+            (SkillFormat::Json, false, "guide-topic-0/skill.json"),
+            (SkillFormat::Json, true, "guide-topic-0.json"),
+            (SkillFormat::Plain, false, "guide-topic-0/skill.txt"),
+        ];
+        for (output_format, flat, file) in cases {
+            let config = Config {
+                output_format,
+                flat,
+                ..Default::default()
+            };
+            let output_dir = test_output_dir(&format!("format-{}-{}", output_format.name(), flat));
+            run_pipeline(&config, &output_dir, doc_pages(2)).await;
+
+            let content = std::fs::read_to_string(output_dir.join(file)).unwrap();
+            assert!(!content.starts_with("---"), "{}", content);
+            assert!(content.contains("Body of topic 0."), "{}", content);
+
+            // Written as it was, so a second crawl leaves it alone
+            let stats = run_pipeline(&config, &output_dir, doc_pages(2)).await;
+            assert_eq!(stats.pages_unchanged.load(Ordering::Relaxed), 2, "{}", file);
+
+            assert_eq!(crate::utils::skill_files(&output_dir).unwrap().len(), 2);
+            assert_eq!(
+                clean_output_dir(&output_dir, &CleanFilter::default())
+                    .await
+                    .unwrap(),
+                2,
+                "{}",
+                file
+            );
+            assert_eq!(std::fs::read_dir(&output_dir).unwrap().count(), 0);
+
+            std::fs::remove_dir_all(&output_dir).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_counts_unauthorized_pages_once_per_host() {
+        let output_dir = test_output_dir("unauthorized");
+        let crawler = Crawler::new(Config::default(), vec![output_dir.clone()]).unwrap();
+
+        let mut pages: Vec<Page> = ["a", "b", "c"]
+            .iter()
+            .map(|page| {
+                status_page(
+                    &format!("https://docs.example.com/{}", page),
+                    "<html><body><h1>Sign in</h1></body></html>",
+                    "text/html",
+                    spider::reqwest::StatusCode::UNAUTHORIZED,
+                )
+            })
+            .collect();
+        pages.push(status_page(
+            "https://other.example.com/",
+            "<html><body><h1>Forbidden</h1></body></html>",
+            "text/html",
+            spider::reqwest::StatusCode::FORBIDDEN,
+        ));
+
+        let (tx, rx) = broadcast::channel(pages.len());
+        for page in pages {
+            tx.send(page).unwrap();
+        }
+        drop(tx);
+
+        let pipeline = crawler.pipeline("https://docs.example.com/", None).unwrap();
+        let auth_failures = Arc::clone(&pipeline.auth_failures);
+        pipeline.run(Vec::new(), rx).await;
+
+        let stats = crawler.stats().snapshot();
+        assert_eq!(stats.unauthorized, 4);
+        assert_eq!(stats.failed, 0);
+        assert_eq!(stats.processed, 0);
+        // One warning per host
+        assert_eq!(auth_failures.lock().unwrap().len(), 2);
+        assert!(!output_dir.exists() || std::fs::read_dir(&output_dir).unwrap().count() == 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_crawl_with_rejected_credentials() {
+        let port = crate::http::test_server(|request| {
+            // base64("wiki-bot:hunter2")
+            if request.contains("Basic d2lraS1ib3Q6aHVudGVyMg==\r\n") {
+                let body = "<html><head><title>Wiki</title></head>\
+                            <body><main><h1>Wiki</h1><p>Text.</p></main></body></html>";
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 401 Unauthorized\r\ncontent-type: text/html\r\n\
+                 content-length: 0\r\nconnection: close\r\n\r\n"
+                    .to_string()
+            }
+        })
+        .await;
+        let seed = format!("http://127.0.0.1:{}/", port);
+
+        let crawl = |password: &str| {
+            let config = Config {
+                delay_ms: 0,
+                respect_robots_txt: false,
+                basic_auth: Some(crate::config::BasicAuth {
+                    username: "wiki-bot".to_string(),
+                    password: password.to_string(),
+                }),
+                ..Default::default()
+            };
+            let output_dir = test_output_dir(&format!("auth-{}", password));
+            let seed = seed.clone();
+            async move {
+                let crawler = Crawler::new(config, vec![output_dir.clone()]).unwrap();
+                let stats = crawler.crawl(&seed).await.unwrap().snapshot();
+                let _ = std::fs::remove_dir_all(&output_dir);
+                stats
+            }
+        };
+
+        let rejected = crawl("wrong").await;
+        assert_eq!(rejected.unauthorized, 1);
+        assert_eq!(rejected.failed, 0);
+
+        let accepted = crawl("hunter2").await;
+        assert_eq!(accepted.unauthorized, 0);
+        assert_eq!(accepted.processed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_crawler_creation() {
+        let config = Config::default();
+        let output_dir = PathBuf::from("/tmp/test-skills");
+
+        let crawler = Crawler::new(config, vec![output_dir]);
+        assert!(crawler.is_ok());
+    }
+
+    #[test]
+    fn test_configure_website_page_budget() {
+        let budget = |max_pages: u32| {
+            let config = Config {
+                max_pages,
+                ..Default::default()
+            };
+            let crawler = Crawler::new(config, vec![test_output_dir("budget")]).unwrap();
+            let mut website = Website::new("https://docs.example.com/");
+            crawler.configure_website(&mut website, &[]);
+            website.configuration.budget
+        };
+
+        let limited = budget(50).unwrap();
+        assert_eq!(
+            limited.get(&spider::CaseInsensitiveString::from("*")),
+            Some(&50)
+        );
+        assert!(budget(0).is_none());
     }
 }
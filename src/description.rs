@@ -0,0 +1,236 @@
+//! Descriptions for pages without one.
+//!
+//! A page without a `<meta name="description">` is described by its own
+//! content: the first `description_sentences` sentences of the cleaned
+//! markdown after its first heading. What survives cleanup without being
+//! part of the page's introduction is skipped: consent notices, "you are
+//! reading the docs for an old version" banners, and anything that isn't a
+//! plain paragraph (headings, lists, tables, code, callouts).
+
+use crate::outline::{is_fence, is_heading};
+use crate::utils::truncate_description;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Paragraphs with fewer words are labels or fragments, not prose.
+const MIN_WORDS: usize = 6;
+
+/// Words whose trailing `.` doesn't end a sentence.
+const ABBREVIATIONS: [&str; 4] = ["e.g", "i.e", "vs", "cf"];
+
+/// Images, dropped from descriptions.
+static IMAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!\[[^\]]*\]\([^)]*\)").expect("Failed to compile image regex"));
+
+/// Inline links, whose text is kept.
+static LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").expect("Failed to compile link regex"));
+
+/// Sentence-ending punctuation and the whitespace after it.
+static SENTENCE_END: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[.!?]\s+").expect("Failed to compile sentence end regex"));
+
+/// Banners documentation sites show above or around the content.
+static NOISE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)you('re| are) (not )?(reading|viewing|looking at) (the )?(most recent|latest|docs|documentation)|(docs|documentation) for (an? )?(old|older|previous|outdated|unreleased|development|preview) (version|release)|this (version|release) is (no longer|not) (maintained|supported)|for the (latest|current) (stable )?(version|release|docs|documentation)|last (updated|modified)|edit this page|skip to (main )?content",
+    )
+    .expect("Failed to compile description noise regex")
+});
+
+/// Describes a page by the first `sentences` sentences of its prose,
+/// starting after the first heading, in at most `max_chars` characters.
+/// Paragraphs `is_noise` accepts are skipped. `None` if the page has no
+/// prose to speak of.
+pub fn from_markdown(
+    markdown: &str,
+    sentences: usize,
+    max_chars: usize,
+    is_noise: impl Fn(&str) -> bool,
+) -> Option<String> {
+    let paragraphs: Vec<(bool, String)> = paragraphs(markdown)
+        .into_iter()
+        .filter(|(_, text)| is_substantive(text) && !is_noise(text))
+        .collect();
+    // Whatever precedes the first heading is usually a banner, unless the
+    // page has no prose after it
+    let after_heading = paragraphs.iter().any(|(after, _)| *after);
+    let prose = paragraphs
+        .iter()
+        .filter(|(after, _)| *after || !after_heading)
+        .map(|(_, text)| text.as_str());
+
+    let mut description = String::new();
+    for sentence in prose.flat_map(split_sentences).take(sentences) {
+        let len = description.chars().count() + 1 + sentence.chars().count();
+        if len > max_chars {
+            if description.is_empty() {
+                description = truncate_description(sentence, max_chars);
+            }
+            break;
+        }
+        if !description.is_empty() {
+            description.push(' ');
+        }
+        description.push_str(sentence);
+    }
+
+    (!description.is_empty()).then_some(description)
+}
+
+/// The plain paragraphs of `markdown` as single lines of text, each with
+/// whether it comes after the first heading.
+fn paragraphs(markdown: &str) -> Vec<(bool, String)> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut prose = true;
+    let mut after_heading = false;
+    let mut fenced = false;
+
+    let mut flush = |current: &mut Vec<&str>, prose: bool, after_heading: bool| {
+        if prose && !current.is_empty() {
+            paragraphs.push((after_heading, plain_text(&current.join(" "))));
+        }
+        current.clear();
+    };
+
+    for line in markdown.lines() {
+        let line = line.trim();
+        if is_fence(line) {
+            fenced = !fenced;
+            flush(&mut current, prose, after_heading);
+            continue;
+        }
+        if fenced {
+            continue;
+        }
+        if line.is_empty() || is_heading(line) {
+            flush(&mut current, prose, after_heading);
+            after_heading |= is_heading(line);
+            continue;
+        }
+        if current.is_empty() {
+            prose = !is_block_markup(line);
+        }
+        current.push(line);
+    }
+    flush(&mut current, prose, after_heading);
+    paragraphs
+}
+
+/// Whether a trimmed line opens something other than a paragraph: a list,
+/// table, blockquote or callout, image or raw HTML.
+fn is_block_markup(line: &str) -> bool {
+    let ordered = line.split_once(". ").is_some_and(|(number, _)| {
+        !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+    });
+    ordered
+        || ["- ", "* ", "+ ", ">", "|", "![", "<"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+}
+
+/// Text without images, link targets or emphasis, on one line.
+fn plain_text(markdown: &str) -> String {
+    let text = IMAGE.replace_all(markdown, "");
+    let text = LINK.replace_all(&text, "$1");
+    let text = text.replace("**", "").replace("__", "");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Whether a paragraph reads like part of the page's introduction.
+fn is_substantive(text: &str) -> bool {
+    text.split_whitespace().count() >= MIN_WORDS && !NOISE.is_match(text)
+}
+
+/// Splits a paragraph after each `.`, `!` or `?` that is followed by a
+/// capital or a digit, unless it ends an abbreviation like `e.g.`.
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for end in SENTENCE_END.find_iter(text) {
+        let next = text[end.end()..].chars().next();
+        let last_word = text[..end.start()].rsplit(' ').next().unwrap_or_default();
+        if next.is_some_and(|c| c.is_uppercase() || c.is_ascii_digit())
+            && !ABBREVIATIONS.contains(&last_word.to_lowercase().as_str())
+        {
+            sentences.push(text[start..end.start() + 1].trim());
+            start = end.end();
+        }
+    }
+    sentences.push(text[start..].trim());
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn describe(markdown: &str) -> Option<String> {
+        from_markdown(markdown, 2, 1024, |_| false)
+    }
+
+    #[test]
+    fn test_first_sentences_after_heading() {
+        let markdown = "You are reading the documentation for an older version of Acme.\n\n\
+                        # Routing\n\n\
+                        Routes map URLs to [handlers](/docs/handlers). Each route has a **method** \
+                        and a path. Paths may contain parameters, e.g. `/users/:id`.\n\n\
+                        More prose that is never reached.";
+
+        assert_eq!(
+            describe(markdown).unwrap(),
+            "Routes map URLs to handlers. Each route has a method and a path."
+        );
+        assert_eq!(
+            from_markdown(markdown, 3, 1024, |_| false).unwrap(),
+            "Routes map URLs to handlers. Each route has a method and a path. \
+             Paths may contain parameters, e.g. `/users/:id`."
+        );
+    }
+
+    #[test]
+    fn test_skips_markup_and_noise() {
+        let markdown = "# Deploy\n\n\
+                        > [!NOTE]\n> This guide assumes you have an account already.\n\n\
+                        - Step one of the deployment checklist\n- Step two\n\n\
+                        ```sh\nacme deploy --prod --region eu-west-1\n```\n\n\
+                        Short label\n\n\
+                        We use cookies to improve your experience on this site.\n\n\
+                        Deploying ships your app to production in one command.";
+
+        let consent = |text: &str| text.to_lowercase().contains("we use cookies");
+        assert_eq!(
+            from_markdown(markdown, 2, 1024, consent).unwrap(),
+            "Deploying ships your app to production in one command."
+        );
+    }
+
+    #[test]
+    fn test_prose_before_the_only_heading() {
+        let markdown = "Acme is a framework for building command line tools quickly.\n\n\
+                        ## Reference\n\n| Flag | Meaning |\n|---|---|\n| -v | verbose |";
+
+        assert_eq!(
+            describe(markdown).unwrap(),
+            "Acme is a framework for building command line tools quickly."
+        );
+        assert_eq!(describe("# Title\n\n- only\n- a list"), None);
+    }
+
+    #[test]
+    fn test_truncates_at_sentence_boundary() {
+        let markdown = "# Overview\n\nThe first sentence is short. The second one is a good deal \
+                        longer than the first.";
+
+        // The second sentence doesn't fit, so it is dropped whole
+        assert_eq!(
+            from_markdown(markdown, 2, 40, |_| false).unwrap(),
+            "The first sentence is short."
+        );
+        // A single sentence too long for the limit is cut at a word
+        let description = from_markdown(markdown, 2, 22, |_| false).unwrap();
+        assert_eq!(description, "The first sentence...");
+    }
+}
@@ -0,0 +1,593 @@
+//! The `diff` command of the agent-skills-generator.
+//!
+//! Previews what regenerating skills would change: each page is fetched and
+//! processed like the `update` command does, and the result is compared with
+//! the SKILL.md on disk. Nothing is written. Frontmatter fields that change
+//! on every run (see [`frontmatter::VOLATILE_FIELDS`]) are left out of the
+//! comparison, so only real content changes show up.
+//!
+//! `crawl --diff` uses [`CrawlDiff`] to report which skills a crawl added,
+//! modified or no longer generates.
+
+use crate::config::SkillFormat;
+use crate::frontmatter;
+use crate::http::HttpClient;
+use crate::processor::Processor;
+use crate::update::{self, Fetched, SkillSource};
+use anyhow::{Context, Result};
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, error};
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Options of a diff run.
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Diff the skill generated from this URL only.
+    pub url: Option<String>,
+    /// Only existing skills whose name matches this glob are diffed.
+    pub pattern: Option<String>,
+    /// One summary line per skill instead of the full diff.
+    pub stat: bool,
+    /// Color the output with ANSI escapes.
+    pub color: bool,
+}
+
+/// How many skills of a diff run differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    pub changed: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+}
+
+/// The existing and the regenerated content of one skill.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkillDiff {
+    /// SKILL.md the content belongs to.
+    pub path: PathBuf,
+    /// Content on disk, `None` for a new skill.
+    pub old: Option<String>,
+    /// Regenerated content, `None` if the page is gone.
+    pub new: Option<String>,
+}
+
+impl SkillDiff {
+    /// Whether the two versions differ, ignoring volatile fields.
+    pub fn has_changes(&self) -> bool {
+        self.normalized_old() != self.normalized_new()
+    }
+
+    /// Number of added and removed lines.
+    pub fn line_counts(&self) -> (usize, usize) {
+        let (old, new) = (self.normalized_old(), self.normalized_new());
+        TextDiff::from_lines(&old, &new).iter_all_changes().fold(
+            (0, 0),
+            |(added, removed), change| match change.tag() {
+                ChangeTag::Insert => (added + 1, removed),
+                ChangeTag::Delete => (added, removed + 1),
+                ChangeTag::Equal => (added, removed),
+            },
+        )
+    }
+
+    /// Returns a unified diff of the two versions, empty if they don't
+    /// differ.
+    pub fn unified(&self, color: bool) -> String {
+        let (old, new) = (self.normalized_old(), self.normalized_new());
+        let path = self.path.display().to_string();
+        let old_header = if self.old.is_some() {
+            &path
+        } else {
+            "/dev/null"
+        };
+        let new_header = if self.new.is_some() {
+            &path
+        } else {
+            "/dev/null"
+        };
+
+        let diff = TextDiff::from_lines(&old, &new)
+            .unified_diff()
+            .header(old_header, new_header)
+            .to_string();
+        if !color {
+            return diff;
+        }
+
+        diff.lines()
+            .map(|line| {
+                let style = if line.starts_with("---") || line.starts_with("+++") {
+                    BOLD
+                } else if line.starts_with("@@") {
+                    CYAN
+                } else if line.starts_with('+') {
+                    GREEN
+                } else if line.starts_with('-') {
+                    RED
+                } else {
+                    return format!("{}\n", line);
+                };
+                format!("{}{}{}\n", style, line, RESET)
+            })
+            .collect()
+    }
+
+    /// Returns a one-line summary, e.g. `docs/api/SKILL.md | +3 -1`.
+    pub fn stat(&self, color: bool) -> String {
+        let (added, removed) = self.line_counts();
+        if color {
+            format!(
+                "{} | {}+{}{} {}-{}{}",
+                self.path.display(),
+                GREEN,
+                added,
+                RESET,
+                RED,
+                removed,
+                RESET
+            )
+        } else {
+            format!("{} | +{} -{}", self.path.display(), added, removed)
+        }
+    }
+
+    fn normalized_old(&self) -> String {
+        frontmatter::without_volatile_fields(self.old.as_deref().unwrap_or_default())
+    }
+
+    fn normalized_new(&self) -> String {
+        frontmatter::without_volatile_fields(self.new.as_deref().unwrap_or_default())
+    }
+}
+
+/// How a crawl changed a skill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    /// The skill is on disk but the crawl didn't generate it.
+    Removed,
+}
+
+impl ChangeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Removed => "removed",
+        }
+    }
+}
+
+/// One skill a crawl changed, with its line-count delta.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkillChange {
+    pub name: String,
+    pub kind: ChangeKind,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Collects what a crawl changes in the skills of one output directory, for
+/// `crawl --diff`. Shared by every crawler of a run, so skills generated
+/// from any seed don't count as removed.
+#[derive(Debug)]
+pub struct CrawlDiff {
+    output_dir: PathBuf,
+    dry_run: bool,
+    state: Mutex<CrawlDiffState>,
+}
+
+#[derive(Debug, Default)]
+struct CrawlDiffState {
+    /// Every SKILL.md the crawl generated or kept.
+    seen: HashSet<PathBuf>,
+    /// The generated skills, against what was on disk before.
+    diffs: Vec<SkillDiff>,
+}
+
+impl CrawlDiff {
+    /// Diffs against the skills in `output_dir`. With `dry_run` the crawl
+    /// writes nothing and only reports.
+    pub fn new(output_dir: impl Into<PathBuf>, dry_run: bool) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            dry_run,
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// Whether the crawl must leave the disk untouched.
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Records `content` generated for the SKILL.md at `path`, before it is
+    /// written. Returns whether it differs from what is on disk.
+    pub async fn record(&self, path: &Path, content: &str) -> Result<bool> {
+        let old = if path.is_file() {
+            Some(
+                fs_err::tokio::read_to_string(path)
+                    .await
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+            )
+        } else {
+            None
+        };
+        let diff = SkillDiff {
+            path: path.to_path_buf(),
+            old,
+            new: Some(content.to_string()),
+        };
+        let changed = diff.has_changes();
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.seen.insert(diff.path.clone());
+        state.diffs.push(diff);
+        Ok(changed)
+    }
+
+    /// Records a skill the crawl left as it was without regenerating it.
+    pub fn keep(&self, path: PathBuf) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.seen.insert(path);
+    }
+
+    /// The skills the crawl added, modified or didn't generate, sorted by
+    /// name.
+    pub fn changes(&self) -> Result<Vec<SkillChange>> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut changes = Vec::new();
+        for diff in state.diffs.iter().filter(|diff| diff.has_changes()) {
+            let (added, removed) = diff.line_counts();
+            changes.push(SkillChange {
+                name: skill_name(&diff.path),
+                kind: match diff.old {
+                    Some(_) => ChangeKind::Modified,
+                    None => ChangeKind::Added,
+                },
+                added,
+                removed,
+            });
+        }
+
+        for skill in crate::utils::skill_files(&self.output_dir)? {
+            if state.seen.contains(&skill.skill_md) {
+                continue;
+            }
+            let old = fs_err::read_to_string(&skill.skill_md)
+                .with_context(|| format!("Failed to read {}", skill.skill_md.display()))?;
+            let diff = SkillDiff {
+                path: skill.skill_md,
+                old: Some(old),
+                new: None,
+            };
+            let (added, removed) = diff.line_counts();
+            changes.push(SkillChange {
+                name: skill.name,
+                kind: ChangeKind::Removed,
+                added,
+                removed,
+            });
+        }
+
+        changes.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(changes)
+    }
+}
+
+/// Name of the skill whose markdown is at `path`: the directory of a
+/// SKILL.md (or skill.json, skill.txt), or the file stem of a flat skill.
+fn skill_name(path: &Path) -> String {
+    let in_dir = SkillFormat::ALL.iter().any(|format| {
+        path.file_name()
+            .is_some_and(|name| name == format.file_name())
+    });
+    let named = if in_dir {
+        path.parent().and_then(Path::file_name)
+    } else {
+        path.file_stem()
+    };
+    named
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Writes one line per changed skill, e.g. `modified  api-client  +3 -1`,
+/// then the totals.
+pub fn write_changes(changes: &[SkillChange], out: &mut impl Write) -> Result<()> {
+    let width = changes
+        .iter()
+        .map(|change| change.name.len())
+        .max()
+        .unwrap_or_default();
+    for change in changes {
+        writeln!(
+            out,
+            "{:<8}  {:<width$}  +{} -{}",
+            change.kind.name(),
+            change.name,
+            change.added,
+            change.removed,
+        )?;
+    }
+
+    let count = |kind| changes.iter().filter(|change| change.kind == kind).count();
+    writeln!(
+        out,
+        "{} skills changed: {} added, {} modified, {} removed",
+        changes.len(),
+        count(ChangeKind::Added),
+        count(ChangeKind::Modified),
+        count(ChangeKind::Removed),
+    )?;
+    Ok(())
+}
+
+/// Diffs the skills in `output_dir` against freshly generated content and
+/// writes the result to `out`.
+pub async fn diff_skills(
+    client: &HttpClient,
+    processor: &Processor,
+    response_headers: &[String],
+    output_dir: &Path,
+    options: &DiffOptions,
+    out: &mut impl Write,
+) -> Result<DiffStats> {
+    let pattern = update::skill_pattern(options.pattern.as_deref())?;
+    let existing = update::find_skills(output_dir, pattern.as_ref())?;
+
+    // A URL is diffed against the skill generated from it, if there is one
+    let sources: Vec<(String, Option<SkillSource>)> = match options.url {
+        Some(ref url) => {
+            let skill = existing.into_iter().find(|skill| &skill.url == url);
+            vec![(url.clone(), skill)]
+        }
+        None => existing
+            .into_iter()
+            .map(|skill| (skill.url.clone(), Some(skill)))
+            .collect(),
+    };
+    debug!(
+        "Diffing {} skills in {}",
+        sources.len(),
+        output_dir.display()
+    );
+
+    let mut stats = DiffStats::default();
+    for (url, skill) in &sources {
+        let diff = match diff_skill(
+            client,
+            processor,
+            response_headers,
+            output_dir,
+            url,
+            skill.as_ref(),
+        )
+        .await
+        {
+            Ok(diff) => diff,
+            Err(e) => {
+                error!("Failed to diff {}: {:#}", url, e);
+                stats.failed += 1;
+                continue;
+            }
+        };
+
+        if !diff.has_changes() {
+            debug!("Unchanged: {}", diff.path.display());
+            stats.unchanged += 1;
+            continue;
+        }
+
+        stats.changed += 1;
+        if options.stat {
+            writeln!(out, "{}", diff.stat(options.color))?;
+        } else {
+            write!(out, "{}", diff.unified(options.color))?;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Regenerates one skill from `url` and pairs it with what is on disk.
+async fn diff_skill(
+    client: &HttpClient,
+    processor: &Processor,
+    response_headers: &[String],
+    output_dir: &Path,
+    url: &str,
+    skill: Option<&SkillSource>,
+) -> Result<SkillDiff> {
+    let new = match update::fetch_page(client, processor, response_headers, url, None).await? {
+        Fetched::Page(mut processed, _) => {
+            // Compare with the directory the skill was written to
+            if let Some(skill) = skill
+                && processed.metadata.skill_name != skill.name
+            {
+                processor.rename(&mut processed, &skill.name)?;
+            }
+            Some(processed)
+        }
+        Fetched::Missing(status) if skill.is_some() => {
+            debug!("{} returned {}", url, status);
+            None
+        }
+        Fetched::Missing(status) => anyhow::bail!("{} returned {}", url, status),
+        Fetched::NotModified => {
+            anyhow::bail!("{} answered 304 to an unconditional request", url)
+        }
+    };
+
+    let path = match (skill, &new) {
+        (Some(skill), _) => skill.skill_md.clone(),
+        (None, Some(processed)) => processor.skill_md_path(
+            &processor.group_dir(output_dir, &processed.metadata.url),
+            &processed.metadata.skill_name,
+        ),
+        (None, None) => unreachable!("missing pages without a skill bail above"),
+    };
+    let old = if path.is_file() {
+        Some(
+            fs_err::tokio::read_to_string(&path)
+                .await
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+        )
+    } else {
+        None
+    };
+
+    Ok(SkillDiff {
+        path,
+        old,
+        new: new.map(|processed| processed.skill_md),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::http::{build_http_client, ok_response, test_server};
+
+    fn docs_site(request: &str) -> String {
+        if request.starts_with("GET /gone ") {
+            return "HTTP/1.1 410 Gone\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                .to_string();
+        }
+        ok_response(
+            "<html><head><title>Install</title></head>\
+             <body><main><h1>Install</h1><p>Run the new installer.</p></main></body></html>",
+        )
+    }
+
+    #[test]
+    fn test_volatile_fields_are_ignored() {
+        let diff = SkillDiff {
+            path: PathBuf::from("install/SKILL.md"),
+            old: Some(
+                "---\nname: install\nmetadata:\n  processed_at: 2024-01-01T00:00:00Z\n---\n\n# Install\n"
+                    .to_string(),
+            ),
+            new: Some(
+                "---\nname: install\nmetadata:\n  processed_at: 2025-06-01T12:00:00Z\n---\n\n# Install\n"
+                    .to_string(),
+            ),
+        };
+        assert!(!diff.has_changes());
+        assert_eq!(diff.unified(false), "");
+    }
+
+    #[tokio::test]
+    async fn test_diff_reports_changed_and_gone_skills() {
+        let port = test_server(docs_site).await;
+        let output_dir =
+            std::env::temp_dir().join(format!("agent-skills-diff-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        let config = Config::default();
+        let client = build_http_client(&config).unwrap();
+        let processor = Processor::new(&config).unwrap();
+        let install_url = format!("http://127.0.0.1:{}/install", port);
+
+        // Generate the current version, then make it stale
+        let Fetched::Page(processed, _) =
+            update::fetch_page(&client, &processor, &[], &install_url, None)
+                .await
+                .unwrap()
+        else {
+            panic!("expected a page");
+        };
+        let skill_md = output_dir.join("install").join("SKILL.md");
+        std::fs::create_dir_all(skill_md.parent().unwrap()).unwrap();
+        std::fs::write(
+            &skill_md,
+            processed
+                .skill_md
+                .replace("Run the new installer.", "Run the old installer."),
+        )
+        .unwrap();
+
+        std::fs::create_dir_all(output_dir.join("gone")).unwrap();
+        std::fs::write(
+            output_dir.join("gone").join("SKILL.md"),
+            format!(
+                "---\nname: gone\nmetadata:\n  url: http://127.0.0.1:{}/gone\n---\n\n# Gone\n",
+                port
+            ),
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        let stats = diff_skills(
+            &client,
+            &processor,
+            &[],
+            &output_dir,
+            &DiffOptions::default(),
+            &mut out,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            stats,
+            DiffStats {
+                changed: 2,
+                ..Default::default()
+            }
+        );
+        let out = String::from_utf8(out).unwrap();
+        assert!(
+            out.contains("-Run the old installer.\n+Run the new installer.\n"),
+            "{}",
+            out
+        );
+        assert!(out.contains("+++ /dev/null"), "{}", out);
+        assert!(!out.contains('\x1b'), "{}", out);
+
+        let mut out = Vec::new();
+        let options = DiffOptions {
+            pattern: Some("inst*".to_string()),
+            stat: true,
+            ..Default::default()
+        };
+        diff_skills(&client, &processor, &[], &output_dir, &options, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("{} | +1 -1\n", skill_md.display())
+        );
+
+        // Nothing was written, and an up-to-date skill has no differences
+        assert!(output_dir.join("gone").exists());
+        std::fs::write(&skill_md, &processed.skill_md).unwrap();
+        let mut out = Vec::new();
+        let options = DiffOptions {
+            url: Some(install_url),
+            ..Default::default()
+        };
+        let stats = diff_skills(&client, &processor, &[], &output_dir, &options, &mut out)
+            .await
+            .unwrap();
+        assert_eq!((stats.changed, stats.unchanged), (0, 1));
+        assert!(out.is_empty());
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
@@ -0,0 +1,498 @@
+//! The `doctor` command of the agent-skills-generator.
+//!
+//! Diagnoses a setup before the first crawl: whether the config loads, the
+//! output directories can be written, the home directory is found for
+//! user-level skills, whether a newer release is out and, given a start
+//! URL, whether the site resolves,
+//! accepts connections, allows our user agent in robots.txt and answers
+//! with HTML. Each check passes, warns or fails; any failure makes the
+//! command exit non-zero, so it can gate scripts.
+
+use crate::config::{Config, SkillsScope, dirs_home, is_html_content_type};
+use crate::http::{self, DEFAULT_USER_AGENT, HttpClient, ProxySettings};
+use crate::robots;
+use crate::utils::check_writable;
+use crate::version_check::{self, ReleaseSource, VersionStatus};
+use anyhow::Result;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long DNS lookups and TCP connections may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pass => "PASS",
+            Self::Warn => "WARN",
+            Self::Fail => "FAIL",
+        })
+    }
+}
+
+/// One line of the report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Checks in the order they ran.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn push(&mut self, check: Check) {
+        self.checks.push(check);
+    }
+
+    /// Whether any check failed.
+    pub fn failed(&self) -> bool {
+        self.checks.iter().any(|check| check.status == Status::Fail)
+    }
+
+    /// Writes the checks as a table.
+    pub fn write(&self, out: &mut impl Write) -> Result<()> {
+        let width = self
+            .checks
+            .iter()
+            .map(|check| check.name.len())
+            .max()
+            .unwrap_or_default();
+        for check in &self.checks {
+            writeln!(
+                out,
+                "{:<width$}  {}  {}",
+                check.name, check.status, check.detail
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the config file at `path`, with `profile` merged over it: missing
+/// is fine without a profile (defaults apply), but it must parse and
+/// validate. Returns the config to check the rest with.
+pub fn check_config(path: &Path, profile: Option<&str>) -> (Check, Config) {
+    if !path.exists() && profile.is_none() {
+        let check = Check::new(
+            "config",
+            Status::Warn,
+            format!("{} not found, using defaults", path.display()),
+        );
+        return (check, Config::default());
+    }
+
+    match Config::load_profile(path, profile) {
+        Ok(config) => {
+            let name = match profile {
+                Some(profile) => format!("{} (profile {})", path.display(), profile),
+                None => path.display().to_string(),
+            };
+            let check = match config.validate() {
+                Ok(()) => Check::new("config", Status::Pass, name),
+                Err(e) => Check::new("config", Status::Fail, format!("{:#}", e)),
+            };
+            (check, config)
+        }
+        Err(e) => (
+            Check::new("config", Status::Fail, format!("{:#}", e)),
+            Config::default(),
+        ),
+    }
+}
+
+/// Checks that skills can be written to `output_dir`.
+pub fn check_output_dir(output_dir: &Path) -> Check {
+    match check_writable(output_dir) {
+        Ok(()) if output_dir.is_dir() => Check::new(
+            "output",
+            Status::Pass,
+            format!("{} is writable", output_dir.display()),
+        ),
+        Ok(()) => Check::new(
+            "output",
+            Status::Pass,
+            format!("{} will be created", output_dir.display()),
+        ),
+        Err(e) => Check::new("output", Status::Fail, format!("{:#}", e)),
+    }
+}
+
+/// Checks that the home directory is found. User-level skills are written
+/// under it, so only `scope: user` fails without one.
+pub fn check_home(scope: SkillsScope) -> Check {
+    match (dirs_home(), scope) {
+        (Some(home), _) => Check::new("home", Status::Pass, home.display().to_string()),
+        (None, SkillsScope::User) => Check::new(
+            "home",
+            Status::Fail,
+            "neither HOME nor USERPROFILE is set; user-level skills would go to the project directory",
+        ),
+        (None, SkillsScope::Project) => Check::new(
+            "home",
+            Status::Warn,
+            "neither HOME nor USERPROFILE is set; --user won't work",
+        ),
+    }
+}
+
+/// Checks `source` for a release newer than `current`. Unlike
+/// `--version-check`, a failed lookup is reported with its error.
+pub async fn check_version(source: &impl ReleaseSource, current: &str) -> Check {
+    match version_check::check_for_update(source, current).await {
+        Ok(VersionStatus::UpToDate) => Check::new(
+            "version",
+            Status::Pass,
+            format!("{} is the latest release", current),
+        ),
+        Ok(VersionStatus::UpdateAvailable { latest }) => Check::new(
+            "version",
+            Status::Warn,
+            format!("{} is available (you have {})", latest, current),
+        ),
+        Err(e) => Check::new("version", Status::Fail, format!("{:#}", e)),
+    }
+}
+
+/// Checks the site at `url`: DNS and TCP (unless a proxy is used),
+/// robots.txt, and whether the page answers with HTML over HTTP(S).
+pub async fn check_site(config: &Config, client: &HttpClient, url: &str) -> Vec<Check> {
+    let parsed = match url::Url::parse(url) {
+        Ok(parsed) if parsed.host_str().is_some() => parsed,
+        _ => {
+            return vec![Check::new(
+                "url",
+                Status::Fail,
+                format!("invalid URL: {}", url),
+            )];
+        }
+    };
+
+    let mut checks = Vec::new();
+    match ProxySettings::from_config(config).proxy_for(url) {
+        Some(proxy) => checks.push(Check::new(
+            "network",
+            Status::Pass,
+            format!(
+                "requests go through {}; DNS and TCP not checked",
+                http::redact_proxy(proxy)
+            ),
+        )),
+        None => {
+            let connected = check_connection(&parsed, &mut checks).await;
+            if !connected {
+                return checks;
+            }
+        }
+    }
+
+    checks.push(check_robots(config, client, &parsed).await);
+    checks.push(check_page(client, url, parsed.scheme() == "https").await);
+    checks
+}
+
+/// Resolves the host of `url` and connects to it. Returns whether it
+/// connected.
+async fn check_connection(url: &url::Url, checks: &mut Vec<Check>) -> bool {
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let lookup = tokio::time::timeout(CONNECT_TIMEOUT, tokio::net::lookup_host((host, port))).await;
+    let addresses: Vec<std::net::SocketAddr> = match lookup {
+        Ok(Ok(addresses)) => addresses.collect(),
+        Ok(Err(e)) => {
+            checks.push(Check::new("dns", Status::Fail, format!("{}: {}", host, e)));
+            return false;
+        }
+        Err(_) => {
+            checks.push(Check::new(
+                "dns",
+                Status::Fail,
+                format!("{}: timed out", host),
+            ));
+            return false;
+        }
+    };
+    let Some(&address) = addresses.first() else {
+        checks.push(Check::new(
+            "dns",
+            Status::Fail,
+            format!("{}: no addresses", host),
+        ));
+        return false;
+    };
+    checks.push(Check::new(
+        "dns",
+        Status::Pass,
+        format!("{} -> {}", host, address.ip()),
+    ));
+
+    let connect =
+        tokio::time::timeout(CONNECT_TIMEOUT, tokio::net::TcpStream::connect(address)).await;
+    let check = match connect {
+        Ok(Ok(_)) => Check::new("tcp", Status::Pass, format!("connected to {}", address)),
+        Ok(Err(e)) => Check::new("tcp", Status::Fail, format!("{}: {}", address, e)),
+        Err(_) => Check::new("tcp", Status::Fail, format!("{}: timed out", address)),
+    };
+    let connected = check.status == Status::Pass;
+    checks.push(check);
+    connected
+}
+
+/// Fetches robots.txt and checks that it allows our user agent to fetch
+/// `url`. A disallowed URL only warns when robots.txt isn't respected.
+async fn check_robots(config: &Config, client: &HttpClient, url: &url::Url) -> Check {
+    let user_agent = config.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+    let Ok(robots_url) = url.join("/robots.txt") else {
+        return Check::new("robots.txt", Status::Warn, "can't build robots.txt URL");
+    };
+
+    let response = match client.get(robots_url.as_str()).await {
+        Ok(response) => response,
+        Err(e) => return Check::new("robots.txt", Status::Warn, format!("{:#}", e)),
+    };
+    if !response.status().is_success() {
+        return Check::new(
+            "robots.txt",
+            Status::Pass,
+            format!("none ({}), everything allowed", response.status()),
+        );
+    }
+    let body = match response.text().await {
+        Ok(body) => body,
+        Err(e) => return Check::new("robots.txt", Status::Warn, e.to_string()),
+    };
+
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let delay = robots::parse_crawl_delay(&body, user_agent)
+        .map(|delay| format!(", Crawl-delay {}s", delay.as_secs_f64()))
+        .unwrap_or_default();
+    if robots::is_allowed(&body, user_agent, &path) {
+        Check::new(
+            "robots.txt",
+            Status::Pass,
+            format!("{} may fetch {}{}", user_agent, path, delay),
+        )
+    } else if config.respect_robots_txt {
+        Check::new(
+            "robots.txt",
+            Status::Fail,
+            format!("disallows {} for {}", path, user_agent),
+        )
+    } else {
+        Check::new(
+            "robots.txt",
+            Status::Warn,
+            format!(
+                "disallows {} for {}, but respect_robots_txt is false",
+                path, user_agent
+            ),
+        )
+    }
+}
+
+/// Fetches the start URL and checks it answers with HTML.
+async fn check_page(client: &HttpClient, url: &str, tls: bool) -> Check {
+    let name = if tls { "https" } else { "http" };
+    let response = match client.get(url).await {
+        Ok(response) => response,
+        Err(e) => return Check::new(name, Status::Fail, format!("{:#}", e)),
+    };
+
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    if !status.is_success() {
+        return Check::new(name, Status::Fail, format!("{} answered {}", url, status));
+    }
+    if !is_html_content_type(&content_type) {
+        return Check::new(
+            name,
+            Status::Warn,
+            format!("{} is not HTML ({})", url, content_type),
+        );
+    }
+    Check::new(name, Status::Pass, format!("{} {}", status, content_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::test_server;
+
+    fn respond(request: &str) -> String {
+        let (status, content_type, body) = if request.starts_with("GET /robots.txt") {
+            (
+                "200 OK",
+                "text/plain",
+                "User-agent: *\nDisallow: /private\n",
+            )
+        } else if request.starts_with("GET /data.json") {
+            ("200 OK", "application/json", "{}")
+        } else if request.starts_with("GET /missing") {
+            ("404 Not Found", "text/html", "")
+        } else {
+            ("200 OK", "text/html; charset=utf-8", "<html></html>")
+        };
+        format!(
+            "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        )
+    }
+
+    fn statuses(checks: &[Check]) -> Vec<(&str, Status)> {
+        checks
+            .iter()
+            .map(|check| (check.name, check.status))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_check_site() {
+        let port = test_server(respond).await;
+        let config = Config::default();
+        let client = http::build_http_client(&config).unwrap();
+        let site = |path: &str| format!("http://127.0.0.1:{}{}", port, path);
+
+        let checks = check_site(&config, &client, &site("/docs")).await;
+        assert_eq!(
+            statuses(&checks),
+            [
+                ("dns", Status::Pass),
+                ("tcp", Status::Pass),
+                ("robots.txt", Status::Pass),
+                ("http", Status::Pass),
+            ]
+        );
+
+        let checks = check_site(&config, &client, &site("/private/docs")).await;
+        assert_eq!(checks[2].status, Status::Fail);
+        assert!(checks[2].detail.contains("disallows /private/docs"));
+
+        let checks = check_site(&config, &client, &site("/data.json")).await;
+        assert_eq!(checks[3].status, Status::Warn);
+        let checks = check_site(&config, &client, &site("/missing")).await;
+        assert_eq!(checks[3].status, Status::Fail);
+
+        // Nothing listens on port 9 of localhost
+        let checks = check_site(&config, &client, "http://127.0.0.1:9/").await;
+        assert_eq!(
+            statuses(&checks),
+            [("dns", Status::Pass), ("tcp", Status::Fail)]
+        );
+    }
+
+    /// Release source returning a fixed answer.
+    struct StubReleases(Option<&'static str>);
+
+    impl ReleaseSource for StubReleases {
+        async fn latest_version(&self) -> Result<String> {
+            match self.0 {
+                Some(version) => Ok(version.to_string()),
+                None => anyhow::bail!("connection refused"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_version() {
+        let check = check_version(&StubReleases(Some("v0.2.1")), "0.2.1").await;
+        assert_eq!(check.status, Status::Pass);
+
+        let check = check_version(&StubReleases(Some("v0.3.0")), "0.2.1").await;
+        assert_eq!(check.status, Status::Warn);
+        assert_eq!(check.detail, "0.3.0 is available (you have 0.2.1)");
+
+        // The error is reported, not swallowed as with --version-check
+        let check = check_version(&StubReleases(None), "0.2.1").await;
+        assert_eq!(check.status, Status::Fail);
+        assert!(
+            check.detail.contains("connection refused"),
+            "{}",
+            check.detail
+        );
+    }
+
+    #[test]
+    fn test_check_config_and_output() {
+        let root = std::env::temp_dir().join(format!("doctor-test-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let (check, _) = check_config(&root.join("skills.yaml"), None);
+        assert_eq!(check.status, Status::Warn);
+        std::fs::write(root.join("skills.yaml"), "delay_ms: soon\n").unwrap();
+        assert_eq!(
+            check_config(&root.join("skills.yaml"), None).0.status,
+            Status::Fail
+        );
+        std::fs::write(root.join("skills.yaml"), "delay_ms: 100\n").unwrap();
+        let (check, config) = check_config(&root.join("skills.yaml"), None);
+        assert_eq!(check.status, Status::Pass);
+        assert_eq!(config.delay_ms, 100);
+
+        assert!(check_output_dir(&root).detail.ends_with("is writable"));
+        assert!(
+            check_output_dir(&root.join("skills"))
+                .detail
+                .ends_with("will be created")
+        );
+        assert_eq!(
+            check_output_dir(&root.join("skills.yaml/skills")).status,
+            Status::Fail
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_report() {
+        let mut report = Report::default();
+        report.push(Check::new("config", Status::Pass, "skills.yaml"));
+        report.push(Check::new("robots.txt", Status::Warn, "unreachable"));
+        assert!(!report.failed());
+
+        let mut out = Vec::new();
+        report.write(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "config      PASS  skills.yaml\nrobots.txt  WARN  unreachable\n"
+        );
+
+        report.push(Check::new("tcp", Status::Fail, "refused"));
+        assert!(report.failed());
+    }
+}
@@ -0,0 +1,286 @@
+//! Crawl size estimates for the agent-skills-generator.
+//!
+//! `crawl --dry-run --estimate` reports roughly how many pages a crawl will
+//! take, to help pick `--max-pages` and `--depth` before a long run. The
+//! URLs of the site's sitemaps (from robots.txt, or `/sitemap.xml`) are
+//! counted after applying the crawl's rules; sites without a sitemap fall
+//! back on the links of the start page, a depth-1 crawl. No page bodies are
+//! processed.
+
+use crate::config::{Config, UrlFilter};
+use crate::http::{self, HttpClient};
+use crate::links;
+use crate::robots;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::LazyLock;
+use tracing::debug;
+
+/// Most sitemaps read for one estimate, sitemap indexes included.
+const MAX_SITEMAPS: usize = 50;
+
+/// `<loc>` entries of a sitemap or sitemap index.
+static LOC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<loc>\s*(.*?)\s*</loc>").expect("Failed to compile loc regex")
+});
+
+/// Where the URLs of an [`Estimate`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EstimateSource {
+    Sitemap,
+    /// The links of the start page.
+    Links,
+}
+
+impl std::fmt::Display for EstimateSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sitemap => write!(f, "sitemap"),
+            Self::Links => write!(f, "start page links"),
+        }
+    }
+}
+
+/// Estimated size of a crawl.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    pub source: EstimateSource,
+    /// Unique URLs found.
+    pub found: usize,
+    /// URLs the rules of the crawl admit.
+    pub in_scope: usize,
+}
+
+/// URLs listed by a sitemap, or the sitemaps listed by a sitemap index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sitemap {
+    pub urls: Vec<String>,
+    pub sitemaps: Vec<String>,
+}
+
+/// Estimates how many pages a crawl of `base_url` under `config`, the
+/// crawl's scoped config, takes.
+pub async fn estimate(client: &HttpClient, config: &Config, base_url: &str) -> Result<Estimate> {
+    let filter = config.build_url_filter()?;
+
+    let urls = sitemap_urls(client, base_url).await?;
+    if !urls.is_empty() {
+        return Ok(count(EstimateSource::Sitemap, &urls, &filter));
+    }
+
+    debug!("No sitemap URLs for {}, counting its links", base_url);
+    let html = http::fetch_text(client, base_url).await?;
+    let mut urls = links::html_links(&html, base_url);
+    urls.insert(base_url.to_string());
+    Ok(count(EstimateSource::Links, &urls, &filter))
+}
+
+fn count(source: EstimateSource, urls: &BTreeSet<String>, filter: &UrlFilter) -> Estimate {
+    Estimate {
+        source,
+        found: urls.len(),
+        in_scope: urls.iter().filter(|url| filter.should_crawl(url)).count(),
+    }
+}
+
+/// The URLs of every sitemap of the host of `base_url`, following sitemap
+/// indexes. Sitemaps that can't be fetched are skipped.
+async fn sitemap_urls(client: &HttpClient, base_url: &str) -> Result<BTreeSet<String>> {
+    let base = url::Url::parse(base_url).with_context(|| format!("Invalid URL: {}", base_url))?;
+    let robots_url = base.join("/robots.txt")?;
+
+    let response = client.get(robots_url.as_str()).await?;
+    let mut listed = Vec::new();
+    if response.status().is_success() {
+        listed = robots::parse_sitemaps(&response.text().await.unwrap_or_default())
+            .iter()
+            .filter_map(|sitemap| base.join(sitemap).ok())
+            .map(String::from)
+            .collect();
+    }
+    if listed.is_empty() {
+        listed.push(base.join("/sitemap.xml")?.to_string());
+    }
+
+    let mut queue = VecDeque::from(listed);
+    let mut seen = BTreeSet::new();
+    let mut urls = BTreeSet::new();
+    while let Some(sitemap_url) = queue.pop_front() {
+        if seen.len() == MAX_SITEMAPS {
+            debug!("Read {} sitemaps, skipping the rest", MAX_SITEMAPS);
+            break;
+        }
+        if !seen.insert(sitemap_url.clone()) {
+            continue;
+        }
+
+        let response = client.get(&sitemap_url).await?;
+        if !response.status().is_success() {
+            debug!("No sitemap at {} ({})", sitemap_url, response.status());
+            continue;
+        }
+        let xml = response
+            .text()
+            .await
+            .with_context(|| format!("Failed to read {}", sitemap_url))?;
+        let sitemap = parse_sitemap(&xml);
+        queue.extend(sitemap.sitemaps);
+        urls.extend(sitemap.urls);
+    }
+    Ok(urls)
+}
+
+/// Parses a sitemap or sitemap index.
+pub fn parse_sitemap(xml: &str) -> Sitemap {
+    let locs = LOC
+        .captures_iter(xml)
+        .map(|captures| unescape_xml(&captures[1]))
+        .filter(|loc| !loc.is_empty())
+        .collect();
+    match xml.contains("<sitemapindex") {
+        true => Sitemap {
+            sitemaps: locs,
+            ..Default::default()
+        },
+        false => Sitemap {
+            urls: locs,
+            ..Default::default()
+        },
+    }
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{build_http_client, ok_response, test_server};
+
+    fn not_found() -> String {
+        "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_string()
+    }
+
+    /// The host a request was sent to, which responders can't capture.
+    fn host(request: &str) -> String {
+        request
+            .lines()
+            .find_map(|line| {
+                line.to_lowercase()
+                    .strip_prefix("host: ")
+                    .map(str::to_string)
+            })
+            .unwrap_or_default()
+    }
+
+    /// A sitemap, or sitemap index, of `paths` on the requested host.
+    fn sitemap(request: &str, paths: &[&str], index: bool) -> String {
+        let host = host(request);
+        let (outer, inner) = match index {
+            true => ("sitemapindex", "sitemap"),
+            false => ("urlset", "url"),
+        };
+        let entries: String = paths
+            .iter()
+            .map(|path| format!("<{inner}><loc>http://{host}{path}</loc></{inner}>"))
+            .collect();
+        ok_response(&format!(
+            "<?xml version=\"1.0\"?><{outer} xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">{entries}</{outer}>"
+        ))
+    }
+
+    #[test]
+    fn test_parse_sitemap() {
+        let sitemap = parse_sitemap(
+            "<urlset><url><loc> https://example.com/a?x=1&amp;y=2 </loc></url>\
+             <url><loc>https://example.com/b</loc></url></urlset>",
+        );
+        assert_eq!(
+            sitemap.urls,
+            ["https://example.com/a?x=1&y=2", "https://example.com/b"]
+        );
+        assert!(sitemap.sitemaps.is_empty());
+
+        let index = parse_sitemap(
+            "<sitemapindex><sitemap><loc>https://example.com/docs.xml</loc></sitemap></sitemapindex>",
+        );
+        assert_eq!(index.sitemaps, ["https://example.com/docs.xml"]);
+        assert!(index.urls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_from_sitemap() {
+        let port = test_server(|request| {
+            if request.starts_with("GET /robots.txt ") {
+                ok_response(&format!(
+                    "User-agent: *\nSitemap: http://{}/index.xml\n",
+                    host(request)
+                ))
+            } else if request.starts_with("GET /index.xml ") {
+                sitemap(request, &["/docs.xml", "/blog.xml"], true)
+            } else if request.starts_with("GET /docs.xml ") {
+                sitemap(
+                    request,
+                    &["/docs/", "/docs/install", "/docs/api", "/docs/changelog/v2"],
+                    false,
+                )
+            } else if request.starts_with("GET /blog.xml ") {
+                sitemap(request, &["/blog/launch", "/docs/install"], false)
+            } else {
+                not_found()
+            }
+        })
+        .await;
+
+        let base_url = format!("http://127.0.0.1:{}/docs/", port);
+        let config = Config::from_yaml(
+            r#"
+rules:
+  - url: "*/changelog/*"
+    action: ignore
+"#,
+        )
+        .unwrap();
+        let scoped = crate::scope::scope(&config, &base_url).config;
+        let client = build_http_client(&config).unwrap();
+
+        let estimate = estimate(&client, &scoped, &base_url).await.unwrap();
+        assert_eq!(estimate.source, EstimateSource::Sitemap);
+        assert_eq!(estimate.found, 5);
+        // /docs/, /docs/install and /docs/api; the changelog is ignored and
+        // the blog is out of scope
+        assert_eq!(estimate.in_scope, 3);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_from_links() {
+        let port = test_server(|request| {
+            if request.starts_with("GET /docs/ ") {
+                ok_response(
+                    "<html><body><a href=\"install\">Install</a><a href=\"api#top\">API</a>\
+                     <a href=\"/docs/api\">API</a><a href=\"/pricing\">Pricing</a>\
+                     <a href=\"mailto:docs@example.com\">Mail</a></body></html>",
+                )
+            } else {
+                not_found()
+            }
+        })
+        .await;
+
+        let base_url = format!("http://127.0.0.1:{}/docs/", port);
+        let config = Config::default();
+        let scoped = crate::scope::scope(&config, &base_url).config;
+        let client = build_http_client(&config).unwrap();
+
+        let estimate = estimate(&client, &scoped, &base_url).await.unwrap();
+        assert_eq!(estimate.source, EstimateSource::Links);
+        assert_eq!(estimate.found, 4);
+        assert_eq!(estimate.in_scope, 3);
+    }
+}
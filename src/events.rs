@@ -0,0 +1,445 @@
+//! Machine-readable crawl output for the agent-skills-generator.
+//!
+//! Crawls report what happens to each page as [`CrawlEvent`]s. A
+//! [`CrawlReporter`] turns those into one of two output formats on stdout:
+//! - `--json`: a single document printed once every seed has finished
+//! - `--json-stream`: one event per line, tagged with its seed
+//!
+//! All writes go through the reporter's lock, so seeds crawled concurrently
+//! never interleave partial lines and the two formats are never mixed.
+
+use crate::crawler::StatsSnapshot;
+use crate::naming::NameMapping;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Something that happened while crawling a seed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum CrawlEvent {
+    /// Processing of the seed's pages has started.
+    SeedStarted,
+    /// A page was converted and written to disk.
+    PageProcessed {
+        url: String,
+        skill_dir: PathBuf,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+    /// A page hasn't changed since the last incremental crawl, so its skill
+    /// was kept as is.
+    PageUnchanged { url: String, skill_dir: PathBuf },
+    /// A page was skipped because of the URL/content-type rules.
+    PageSkipped {
+        url: String,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+    /// A page had less content than `min_content_chars`, so no skill was
+    /// written.
+    PageTooSmall { url: String, chars: usize },
+    /// A page declared another page of the crawl canonical, with
+    /// `respect_canonical`, so no skill was written for it.
+    PageDeduplicated { url: String, canonical_url: String },
+    /// A page could not be processed or written.
+    PageFailed {
+        url: String,
+        error: String,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+    /// A page was answered with 401 or 403, so credentials are missing or
+    /// rejected.
+    PageUnauthorized {
+        url: String,
+        status: u16,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+    /// A skill was written under a name other than its canonical one.
+    SkillRenamed(NameMapping),
+    /// Pages were lost because the subscription buffer overflowed.
+    PagesDropped { count: usize },
+    /// Every page of the seed has been handled.
+    SeedFinished { stats: StatsSnapshot },
+}
+
+/// How crawl results are reported on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human-readable logs only.
+    #[default]
+    Text,
+    /// A single JSON document once the crawl has finished.
+    Json,
+    /// Newline-delimited JSON events as they happen.
+    JsonStream,
+}
+
+/// A streamed event, tagged with the seed it belongs to.
+#[derive(Serialize)]
+struct SeedEvent<'a> {
+    seed: &'a str,
+    #[serde(flatten)]
+    event: &'a CrawlEvent,
+}
+
+/// Outcome of a single page in the `--json` document.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PageStatus {
+    Processed,
+    Unchanged,
+    Skipped,
+    TooSmall,
+    Deduplicated,
+    Failed,
+    Unauthorized,
+}
+
+#[derive(Debug, Serialize)]
+struct PageRecord {
+    url: String,
+    status: PageStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skill_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Characters of content of a page under `min_content_chars`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_chars: Option<usize>,
+    /// Canonical page a deduplicated page is a copy of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canonical_url: Option<String>,
+    /// Response headers kept by the `response_headers` allowlist.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SeedReport {
+    seed: String,
+    stats: Option<StatsSnapshot>,
+    pages: Vec<PageRecord>,
+}
+
+/// The `--json` document.
+#[derive(Debug, Default, Serialize)]
+struct CrawlReport {
+    seeds: Vec<SeedReport>,
+    /// Skills whose final name differs from the canonical one, in any target.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    renamed_skills: Vec<NameMapping>,
+}
+
+/// Collects crawl events from any number of seeds and writes them in the
+/// selected [`OutputFormat`].
+pub struct CrawlReporter {
+    format: OutputFormat,
+    out: Mutex<Box<dyn Write + Send>>,
+    report: Mutex<CrawlReport>,
+}
+
+impl CrawlReporter {
+    /// Creates a reporter writing to `out`.
+    pub fn new(format: OutputFormat, out: Box<dyn Write + Send>) -> Self {
+        Self {
+            format,
+            out: Mutex::new(out),
+            report: Mutex::new(CrawlReport::default()),
+        }
+    }
+
+    /// Creates a reporter writing to stdout.
+    pub fn stdout(format: OutputFormat) -> Self {
+        Self::new(format, Box::new(std::io::stdout()))
+    }
+
+    /// Returns the output format.
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Records an event for `seed`.
+    pub fn emit(&self, seed: &str, event: CrawlEvent) {
+        match self.format {
+            OutputFormat::Text => {}
+            OutputFormat::JsonStream => {
+                let tagged = SeedEvent {
+                    seed,
+                    event: &event,
+                };
+                match serde_json::to_string(&tagged) {
+                    Ok(mut line) => {
+                        line.push('\n');
+                        // One write per line under the lock keeps lines whole
+                        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+                        if let Err(e) = out.write_all(line.as_bytes()).and_then(|_| out.flush()) {
+                            warn!("Failed to write crawl event: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("Failed to serialize crawl event: {}", e),
+                }
+            }
+            OutputFormat::Json => {
+                let mut report = self.report.lock().unwrap_or_else(|e| e.into_inner());
+                report.record(seed, event);
+            }
+        }
+    }
+
+    /// Writes the `--json` document. Does nothing for the other formats.
+    pub fn finish(&self) -> Result<()> {
+        if self.format != OutputFormat::Json {
+            return Ok(());
+        }
+
+        let report = self.report.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+
+        serde_json::to_writer_pretty(&mut *out, &*report)
+            .context("Failed to write JSON crawl report")?;
+        writeln!(out).context("Failed to write JSON crawl report")?;
+        out.flush().context("Failed to write JSON crawl report")
+    }
+}
+
+impl CrawlReport {
+    /// Folds an event into the report, creating the seed entry on first use.
+    fn record(&mut self, seed: &str, event: CrawlEvent) {
+        if let CrawlEvent::SkillRenamed(mapping) = event {
+            self.renamed_skills.push(mapping);
+            return;
+        }
+
+        let index = match self.seeds.iter().position(|s| s.seed == seed) {
+            Some(index) => index,
+            None => {
+                self.seeds.push(SeedReport {
+                    seed: seed.to_string(),
+                    stats: None,
+                    pages: Vec::new(),
+                });
+                self.seeds.len() - 1
+            }
+        };
+        let entry = &mut self.seeds[index];
+
+        let page = match event {
+            CrawlEvent::SeedStarted
+            | CrawlEvent::PagesDropped { .. }
+            | CrawlEvent::SkillRenamed(_) => None,
+            CrawlEvent::SeedFinished { stats } => {
+                entry.stats = Some(stats);
+                None
+            }
+            CrawlEvent::PageProcessed {
+                url,
+                skill_dir,
+                headers,
+            } => Some(PageRecord {
+                url,
+                status: PageStatus::Processed,
+                skill_dir: Some(skill_dir),
+                error: None,
+                content_chars: None,
+                canonical_url: None,
+                headers,
+            }),
+            CrawlEvent::PageUnchanged { url, skill_dir } => Some(PageRecord {
+                url,
+                status: PageStatus::Unchanged,
+                skill_dir: Some(skill_dir),
+                error: None,
+                content_chars: None,
+                canonical_url: None,
+                headers: BTreeMap::new(),
+            }),
+            CrawlEvent::PageSkipped { url, headers } => Some(PageRecord {
+                url,
+                status: PageStatus::Skipped,
+                skill_dir: None,
+                error: None,
+                content_chars: None,
+                canonical_url: None,
+                headers,
+            }),
+            CrawlEvent::PageTooSmall { url, chars } => Some(PageRecord {
+                url,
+                status: PageStatus::TooSmall,
+                skill_dir: None,
+                error: None,
+                content_chars: Some(chars),
+                canonical_url: None,
+                headers: BTreeMap::new(),
+            }),
+            CrawlEvent::PageDeduplicated { url, canonical_url } => Some(PageRecord {
+                url,
+                status: PageStatus::Deduplicated,
+                skill_dir: None,
+                error: None,
+                content_chars: None,
+                canonical_url: Some(canonical_url),
+                headers: BTreeMap::new(),
+            }),
+            CrawlEvent::PageFailed {
+                url,
+                error,
+                headers,
+            } => Some(PageRecord {
+                url,
+                status: PageStatus::Failed,
+                skill_dir: None,
+                error: Some(error),
+                content_chars: None,
+                canonical_url: None,
+                headers,
+            }),
+            CrawlEvent::PageUnauthorized {
+                url,
+                status,
+                headers,
+            } => Some(PageRecord {
+                url,
+                status: PageStatus::Unauthorized,
+                skill_dir: None,
+                error: Some(format!("HTTP {}", status)),
+                content_chars: None,
+                canonical_url: None,
+                headers,
+            }),
+        };
+
+        entry.pages.extend(page);
+    }
+}
+
+/// In-memory writer shared between a reporter and a test.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(std::sync::Arc<Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl SharedBuffer {
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_lines_are_tagged_with_seed() {
+        let buffer = SharedBuffer::default();
+        let reporter = CrawlReporter::new(OutputFormat::JsonStream, Box::new(buffer.clone()));
+
+        reporter.emit("https://a.example.com/", CrawlEvent::SeedStarted);
+        reporter.emit(
+            "https://a.example.com/",
+            CrawlEvent::PageSkipped {
+                url: "https://a.example.com/login".to_string(),
+                headers: BTreeMap::new(),
+            },
+        );
+        reporter.finish().unwrap();
+
+        let lines: Vec<serde_json::Value> = buffer
+            .contents()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["seed"], "https://a.example.com/");
+        assert_eq!(lines[0]["event"], "seed_started");
+        assert_eq!(lines[1]["event"], "page_skipped");
+        assert_eq!(lines[1]["url"], "https://a.example.com/login");
+    }
+
+    #[test]
+    fn test_json_report_is_written_once() {
+        let buffer = SharedBuffer::default();
+        let reporter = CrawlReporter::new(OutputFormat::Json, Box::new(buffer.clone()));
+
+        reporter.emit(
+            "https://a.example.com/",
+            CrawlEvent::PageFailed {
+                url: "https://a.example.com/broken".to_string(),
+                error: "Empty HTML content".to_string(),
+                headers: BTreeMap::new(),
+            },
+        );
+        reporter.emit(
+            "https://b.example.com/",
+            CrawlEvent::PageSkipped {
+                url: "https://b.example.com/login".to_string(),
+                headers: BTreeMap::new(),
+            },
+        );
+
+        // Nothing is printed until the crawl is over
+        assert!(buffer.contents().is_empty());
+        reporter.finish().unwrap();
+
+        let report: serde_json::Value = serde_json::from_str(&buffer.contents()).unwrap();
+        let seeds = report["seeds"].as_array().unwrap();
+        assert_eq!(seeds.len(), 2);
+        assert_eq!(seeds[0]["seed"], "https://a.example.com/");
+        assert_eq!(seeds[0]["pages"][0]["status"], "failed");
+        assert_eq!(seeds[0]["pages"][0]["error"], "Empty HTML content");
+        assert_eq!(seeds[1]["pages"][0]["status"], "skipped");
+    }
+
+    #[test]
+    fn test_json_report_lists_renamed_skills() {
+        let buffer = SharedBuffer::default();
+        let reporter = CrawlReporter::new(OutputFormat::Json, Box::new(buffer.clone()));
+
+        let names = crate::naming::SkillNames::new(&[crate::config::SkillsTarget::Cursor]);
+        names.assign("docs-api", "https://a.example.com/docs/api.html");
+        let mapping = names.assign("docs-api", "https://a.example.com/docs/api");
+        reporter.emit("https://a.example.com/", CrawlEvent::SkillRenamed(mapping));
+        reporter.finish().unwrap();
+
+        let report: serde_json::Value = serde_json::from_str(&buffer.contents()).unwrap();
+        let renamed = &report["renamed_skills"][0];
+        assert_eq!(renamed["canonical"], "docs-api");
+        assert_eq!(renamed["url"], "https://a.example.com/docs/api");
+        assert!(
+            renamed["targets"]["cursor"]
+                .as_str()
+                .unwrap()
+                .starts_with("docs-api-")
+        );
+    }
+
+    #[test]
+    fn test_text_format_writes_nothing() {
+        let buffer = SharedBuffer::default();
+        let reporter = CrawlReporter::new(OutputFormat::Text, Box::new(buffer.clone()));
+
+        reporter.emit("https://a.example.com/", CrawlEvent::SeedStarted);
+        reporter.finish().unwrap();
+
+        assert!(buffer.contents().is_empty());
+    }
+}
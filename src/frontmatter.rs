@@ -0,0 +1,612 @@
+//! SKILL.md frontmatter for the agent-skills-generator.
+//!
+//! The frontmatter is serialized with `serde_yaml`, so titles and
+//! descriptions with colons or quotes always produce valid YAML. Optional
+//! metadata lists (`sections`, `tags`, `related`) can grow with the page, so
+//! the rendered frontmatter is held to a byte budget:
+//! 1. `related` is truncated first, then `tags`, then `sections`
+//! 2. Each list keeps its first entries, as many as fit, followed by a
+//!    `truncated: N more` marker
+//! 3. A list is only touched if the frontmatter is still over budget after
+//!    the lists before it were truncated
+//!
+//! The order and the kept entries depend only on the input, so repeated runs
+//! produce identical files.
+//!
+//! Fields from the config's `frontmatter:` map are added after the built-in
+//! `name` and `description`, with `{domain}`, `{url}` and `{date}` in their
+//! string values replaced per page.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Default frontmatter budget in bytes.
+pub const DEFAULT_FRONTMATTER_BUDGET: usize = 2048;
+
+/// Frontmatter of a SKILL.md file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frontmatter {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Fields from the config's `frontmatter:` map, rendered for the page.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub metadata: FrontmatterMetadata,
+}
+
+/// Top-level keys every SKILL.md gets, which `frontmatter:` can't set.
+pub const BUILT_IN_KEYS: [&str; 3] = ["name", "description", "metadata"];
+
+/// Variables of `frontmatter:` values.
+const TEMPLATE_VARIABLES: [&str; 3] = ["domain", "url", "date"];
+
+static TEMPLATE_VARIABLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{([a-z_]+)\}").expect("Failed to compile variable regex"));
+
+/// Checks the config's `frontmatter:` map: no built-in keys, and no
+/// variables other than [`TEMPLATE_VARIABLES`].
+pub fn check_extra_fields(fields: &BTreeMap<String, Value>) -> Result<()> {
+    for (key, value) in fields {
+        if BUILT_IN_KEYS.contains(&key.as_str()) {
+            anyhow::bail!(
+                "frontmatter can't set '{}', which every SKILL.md gets already",
+                key
+            );
+        }
+        let mut strings = Vec::new();
+        collect_strings(value, &mut strings);
+        for caps in strings
+            .iter()
+            .flat_map(|string| TEMPLATE_VARIABLE.captures_iter(string))
+        {
+            if !TEMPLATE_VARIABLES.contains(&&caps[1]) {
+                anyhow::bail!(
+                    "Unknown variable {} in frontmatter.{}; use {{domain}}, {{url}} or {{date}}",
+                    &caps[0],
+                    key
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders the config's `frontmatter:` map for the page at `url`, processed
+/// on `date` (`YYYY-MM-DD`). Nested lists and maps are rendered too.
+pub fn render_extra_fields(
+    fields: &BTreeMap<String, Value>,
+    url: &str,
+    date: &str,
+) -> BTreeMap<String, Value> {
+    let domain = url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    let render_str = |string: &str| {
+        TEMPLATE_VARIABLE
+            .replace_all(string, |caps: &regex::Captures| match &caps[1] {
+                "domain" => domain.clone(),
+                "url" => url.to_string(),
+                "date" => date.to_string(),
+                _ => caps[0].to_string(),
+            })
+            .into_owned()
+    };
+    fields
+        .iter()
+        .map(|(key, value)| (key.clone(), render_value(value, &render_str)))
+        .collect()
+}
+
+fn render_value(value: &Value, render_str: &impl Fn(&str) -> String) -> Value {
+    match value {
+        Value::String(string) => Value::String(render_str(string)),
+        Value::Sequence(values) => Value::Sequence(
+            values
+                .iter()
+                .map(|value| render_value(value, render_str))
+                .collect(),
+        ),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .iter()
+                .map(|(key, value)| (key.clone(), render_value(value, render_str)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn collect_strings<'a>(value: &'a Value, strings: &mut Vec<&'a str>) {
+    match value {
+        Value::String(string) => strings.push(string),
+        Value::Sequence(values) => values
+            .iter()
+            .for_each(|value| collect_strings(value, strings)),
+        Value::Mapping(mapping) => mapping
+            .values()
+            .for_each(|value| collect_strings(value, strings)),
+        _ => {}
+    }
+}
+
+/// The `metadata` block of the frontmatter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrontmatterMetadata {
+    /// Page the skill was generated from.
+    #[serde(default)]
+    pub url: String,
+
+    /// URL that redirected to `url`, with `record_requested_url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_url: Option<String>,
+
+    /// Canonical URL the page declares, if it isn't `url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+
+    /// Language of the page, see [`crate::language`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Estimated tokens of the page's markdown, see [`crate::tokens`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_tokens: Option<usize>,
+
+    /// When the skill's content last changed, with
+    /// `frontmatter_metadata: full`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processed_at: Option<String>,
+
+    /// Tool and version that generated the content, with
+    /// `frontmatter_metadata: full`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+
+    /// Hash of the page's markdown, with `frontmatter_metadata: full`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+
+    /// Headings of the page, in document order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sections: Vec<String>,
+
+    /// Keywords describing the page.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Names of related skills.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<String>,
+
+    /// Reference files holding the content of a page split with
+    /// `max_skill_chars`, relative to the skill directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+}
+
+/// A metadata list that may be truncated to fit the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListField {
+    Related,
+    Tags,
+    Sections,
+}
+
+/// Lists in the order they are truncated.
+pub const TRUNCATION_ORDER: [ListField; 3] =
+    [ListField::Related, ListField::Tags, ListField::Sections];
+
+impl ListField {
+    /// Name of the field in the frontmatter.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Related => "related",
+            Self::Tags => "tags",
+            Self::Sections => "sections",
+        }
+    }
+
+    fn list_mut<'a>(&self, metadata: &'a mut FrontmatterMetadata) -> &'a mut Vec<String> {
+        match self {
+            Self::Related => &mut metadata.related,
+            Self::Tags => &mut metadata.tags,
+            Self::Sections => &mut metadata.sections,
+        }
+    }
+}
+
+/// A list shortened by [`Frontmatter::fit_to_budget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncation {
+    pub field: ListField,
+    /// Entries kept.
+    pub kept: usize,
+    /// Entries replaced by the marker.
+    pub dropped: usize,
+}
+
+/// Marker appended to a truncated list.
+pub fn truncation_marker(dropped: usize) -> String {
+    format!("truncated: {} more", dropped)
+}
+
+impl Frontmatter {
+    /// Serializes the frontmatter as YAML, without the `---` delimiters.
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(self).context("Failed to serialize frontmatter")
+    }
+
+    /// Size of the serialized frontmatter in bytes.
+    pub fn size(&self) -> Result<usize> {
+        Ok(self.to_yaml()?.len())
+    }
+
+    /// Truncates the metadata lists in [`TRUNCATION_ORDER`] until the
+    /// frontmatter fits in `budget` bytes, or every list is down to its
+    /// marker. Returns the lists that were shortened.
+    pub fn fit_to_budget(&mut self, budget: usize) -> Result<Vec<Truncation>> {
+        let mut truncations = Vec::new();
+
+        for field in TRUNCATION_ORDER {
+            if self.size()? <= budget {
+                break;
+            }
+
+            let full = std::mem::take(field.list_mut(&mut self.metadata));
+            if full.is_empty() {
+                continue;
+            }
+
+            // Fitting is monotonic in the number of kept entries, so binary
+            // search for the most that fit alongside the marker
+            let (mut low, mut high) = (0, full.len() - 1);
+            while low < high {
+                let mid = (low + high).div_ceil(2);
+                self.truncate_to(field, &full, mid);
+                if self.size()? <= budget {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+
+            self.truncate_to(field, &full, low);
+            truncations.push(Truncation {
+                field,
+                kept: low,
+                dropped: full.len() - low,
+            });
+        }
+
+        Ok(truncations)
+    }
+
+    fn truncate_to(&mut self, field: ListField, full: &[String], keep: usize) {
+        let list = field.list_mut(&mut self.metadata);
+        list.clear();
+        list.extend_from_slice(&full[..keep]);
+        list.push(truncation_marker(full.len() - keep));
+    }
+}
+
+/// Returns the frontmatter of a SKILL.md file (between the `---` lines), if
+/// it has one.
+pub fn extract(skill_md: &str) -> Option<&str> {
+    let rest = skill_md.strip_prefix("---\n")?;
+    let end = rest.find("\n---\n")?;
+    Some(&rest[..end + 1])
+}
+
+/// Parses the frontmatter of a SKILL.md file. Returns `None` if the file
+/// has no frontmatter.
+pub fn parse(skill_md: &str) -> Result<Option<Frontmatter>> {
+    extract(skill_md)
+        .map(|yaml| serde_yaml::from_str(yaml).context("Failed to parse frontmatter"))
+        .transpose()
+}
+
+/// Frontmatter fields that change on every run, or with every release,
+/// without the page changing. Comparisons of old and new SKILL.md content
+/// ignore them, so they keep the values of the last real change.
+pub const VOLATILE_FIELDS: &[&str] = &["processed_at", "generator"];
+
+/// Returns `skill_md` without the lines of its frontmatter that set a
+/// [`VOLATILE_FIELDS`] entry. A `skill.json` loses the same fields, and
+/// plain text has none.
+pub fn without_volatile_fields(skill_md: &str) -> String {
+    let Some(frontmatter) = extract(skill_md) else {
+        if skill_md.starts_with('{') {
+            return skill_md
+                .split_inclusive('\n')
+                .filter(|line| {
+                    !VOLATILE_FIELDS
+                        .iter()
+                        .any(|field| line.trim_start().starts_with(&format!("\"{}\":", field)))
+                })
+                .collect();
+        }
+        return skill_md.to_string();
+    };
+
+    let kept: String = frontmatter
+        .split_inclusive('\n')
+        .filter(|line| {
+            let key = line.trim_start().split(':').next().unwrap_or_default();
+            !VOLATILE_FIELDS.contains(&key)
+        })
+        .collect();
+    format!("---\n{}{}", kept, &skill_md[4 + frontmatter.len()..])
+}
+
+/// Page a generated skill file came from: `metadata.url` of a SKILL.md
+/// frontmatter, or `url` of a `skill.json`. Plain-text skills don't record
+/// it.
+pub fn source_url(content: &str) -> Option<String> {
+    let url = match parse(content) {
+        Ok(Some(frontmatter)) => frontmatter.metadata.url,
+        _ => serde_json::from_str::<serde_json::Value>(content)
+            .ok()?
+            .get("url")?
+            .as_str()?
+            .to_string(),
+    };
+    (!url.is_empty()).then_some(url)
+}
+
+/// When the content of a generated skill file last changed, like
+/// [`source_url`]. Only recorded with `frontmatter_metadata: full`.
+pub fn processed_at(content: &str) -> Option<DateTime<Utc>> {
+    let processed_at = match parse(content) {
+        Ok(Some(frontmatter)) => frontmatter.metadata.processed_at?,
+        _ => serde_json::from_str::<serde_json::Value>(content)
+            .ok()?
+            .get("processed_at")?
+            .as_str()?
+            .to_string(),
+    };
+    DateTime::parse_from_rfc3339(&processed_at)
+        .ok()
+        .map(|processed_at| processed_at.to_utc())
+}
+
+/// Estimated tokens recorded in a generated skill file, like
+/// [`source_url`]. Skills from older versions and plain-text skills don't
+/// record them.
+pub fn estimated_tokens(content: &str) -> Option<usize> {
+    match parse(content) {
+        Ok(Some(frontmatter)) => frontmatter.metadata.estimated_tokens,
+        _ => serde_json::from_str::<serde_json::Value>(content)
+            .ok()?
+            .get("estimated_tokens")?
+            .as_u64()
+            .map(|tokens| tokens as usize),
+    }
+}
+
+/// Size of a SKILL.md file's frontmatter if it exceeds `budget`, e.g. for
+/// skills written by older versions without a budget.
+pub fn exceeds_budget(skill_md: &str, budget: usize) -> Option<usize> {
+    extract(skill_md)
+        .map(str::len)
+        .filter(|&size| size > budget)
+}
+
+/// Lists the skills in `output_dir` whose frontmatter exceeds `budget`,
+/// with its size, sorted by path.
+pub fn oversized_skills(output_dir: &Path, budget: usize) -> Result<Vec<(PathBuf, usize)>> {
+    let mut oversized = Vec::new();
+    for skill in crate::utils::skill_files(output_dir)? {
+        let content = fs_err::read_to_string(&skill.skill_md)?;
+        if let Some(size) = exceeds_budget(&content, budget) {
+            oversized.push((skill.skill_md, size));
+        }
+    }
+
+    oversized.sort();
+    Ok(oversized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frontmatter(sections: usize, tags: usize, related: usize) -> Frontmatter {
+        Frontmatter {
+            name: "api-index".to_string(),
+            description: "Index of every API in the SDK".to_string(),
+            extra: BTreeMap::new(),
+            metadata: FrontmatterMetadata {
+                url: "https://docs.example.com/api/".to_string(),
+                requested_url: None,
+                canonical_url: None,
+                language: None,
+                estimated_tokens: None,
+                processed_at: None,
+                generator: None,
+                content_hash: None,
+                sections: (0..sections).map(|i| format!("Section {}", i)).collect(),
+                tags: (0..tags).map(|i| format!("tag-{}", i)).collect(),
+                related: (0..related)
+                    .map(|i| format!("related-skill-{}", i))
+                    .collect(),
+                references: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_processed_at() {
+        let skill_md = "---\nname: guide\nmetadata:\n  url: https://docs.example.com/guide/\n  processed_at: 2024-01-15T10:30:00Z\n---\n\n# Guide\n";
+        assert_eq!(
+            processed_at(skill_md).map(|processed_at| processed_at.to_rfc3339()),
+            Some("2024-01-15T10:30:00+00:00".to_string())
+        );
+        assert_eq!(
+            processed_at(
+                r#"{"url": "https://docs.example.com/", "processed_at": "2024-01-15T10:30:00Z"}"#
+            )
+            .map(|processed_at| processed_at.timestamp()),
+            Some(1705314600)
+        );
+        assert_eq!(processed_at("---\nname: guide\n---\n"), None);
+        assert_eq!(processed_at("plain text"), None);
+    }
+
+    #[test]
+    fn test_extra_fields() {
+        let fields: BTreeMap<String, Value> = serde_yaml::from_str(
+            r#"
+owner: docs-team
+version: 2
+source: "{domain}"
+allowed-tools: [Read, "Fetch {url}"]
+links:
+  docs: "{url}"
+  generated: "{date}"
+"#,
+        )
+        .unwrap();
+        check_extra_fields(&fields).unwrap();
+
+        let mut skill = frontmatter(0, 0, 0);
+        skill.extra = render_extra_fields(&fields, "https://docs.example.com/api/", "2025-06-01");
+        let yaml = skill.to_yaml().unwrap();
+
+        // After the built-in keys, nested values rendered too
+        assert!(
+            yaml.starts_with(
+                "name: api-index\n\
+                 description: Index of every API in the SDK\n\
+                 allowed-tools:\n\
+                 - Read\n\
+                 - Fetch https://docs.example.com/api/\n\
+                 links:\n  \
+                 docs: https://docs.example.com/api/\n  \
+                 generated: 2025-06-01\n\
+                 owner: docs-team\n\
+                 source: docs.example.com\n\
+                 version: 2\n\
+                 metadata:\n"
+            ),
+            "{}",
+            yaml
+        );
+        let parsed: Frontmatter = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, skill);
+    }
+
+    #[test]
+    fn test_small_frontmatter_is_untouched() {
+        let mut small = frontmatter(3, 2, 1);
+        let original = small.clone();
+
+        assert!(
+            small
+                .fit_to_budget(DEFAULT_FRONTMATTER_BUDGET)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(small, original);
+    }
+
+    #[test]
+    fn test_oversized_sections_are_capped_with_marker() {
+        let mut page = frontmatter(5000, 0, 0);
+
+        let truncations = page.fit_to_budget(DEFAULT_FRONTMATTER_BUDGET).unwrap();
+        assert!(page.size().unwrap() <= DEFAULT_FRONTMATTER_BUDGET);
+
+        assert_eq!(truncations.len(), 1);
+        let truncation = truncations[0];
+        assert_eq!(truncation.field, ListField::Sections);
+        assert_eq!(truncation.kept + truncation.dropped, 5000);
+
+        // The first entries are kept, in order, followed by the marker
+        let sections = &page.metadata.sections;
+        assert_eq!(sections[0], "Section 0");
+        assert_eq!(
+            sections[truncation.kept - 1],
+            format!("Section {}", truncation.kept - 1)
+        );
+        assert_eq!(
+            sections.last().unwrap(),
+            &truncation_marker(truncation.dropped)
+        );
+
+        // One more entry would not have fit
+        let mut bigger = page.clone();
+        bigger
+            .metadata
+            .sections
+            .insert(truncation.kept, format!("Section {}", truncation.kept));
+        assert!(bigger.size().unwrap() > DEFAULT_FRONTMATTER_BUDGET);
+    }
+
+    #[test]
+    fn test_related_and_tags_are_truncated_before_sections() {
+        let mut page = frontmatter(40, 200, 200);
+
+        let truncations = page.fit_to_budget(DEFAULT_FRONTMATTER_BUDGET).unwrap();
+        assert!(page.size().unwrap() <= DEFAULT_FRONTMATTER_BUDGET);
+
+        // Dropping every related skill isn't enough, some tags go too, and
+        // the sections then fit untouched
+        let fields: Vec<ListField> = truncations.iter().map(|t| t.field).collect();
+        assert_eq!(fields, [ListField::Related, ListField::Tags]);
+        assert_eq!(page.metadata.related, [truncation_marker(200)]);
+        assert_eq!(page.metadata.sections.len(), 40);
+
+        // Deterministic: the same input gives the same output
+        let mut again = frontmatter(40, 200, 200);
+        again.fit_to_budget(DEFAULT_FRONTMATTER_BUDGET).unwrap();
+        assert_eq!(again, page);
+    }
+
+    #[test]
+    fn test_exceeds_budget_reads_existing_skill() {
+        let yaml = frontmatter(500, 0, 0).to_yaml().unwrap();
+        let skill_md = format!("---\n{}---\n\n# API\n", yaml);
+
+        assert_eq!(extract(&skill_md), Some(yaml.as_str()));
+        assert_eq!(parse(&skill_md).unwrap(), Some(frontmatter(500, 0, 0)));
+        assert_eq!(
+            exceeds_budget(&skill_md, DEFAULT_FRONTMATTER_BUDGET),
+            Some(yaml.len())
+        );
+        assert_eq!(exceeds_budget(&skill_md, yaml.len()), None);
+        assert_eq!(exceeds_budget("# No frontmatter\n", 10), None);
+    }
+
+    #[test]
+    fn test_oversized_skills_in_output_dir() {
+        let dir =
+            std::env::temp_dir().join(format!("agent-skills-frontmatter-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        for (name, sections) in [("small", 2), ("huge", 500)] {
+            let yaml = frontmatter(sections, 0, 0).to_yaml().unwrap();
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            std::fs::write(
+                dir.join(name).join("SKILL.md"),
+                format!("---\n{}---\n\n# {}\n", yaml, name),
+            )
+            .unwrap();
+        }
+
+        let oversized = oversized_skills(&dir, DEFAULT_FRONTMATTER_BUDGET).unwrap();
+        assert_eq!(oversized.len(), 1);
+        assert_eq!(oversized[0].0, dir.join("huge").join("SKILL.md"));
+        assert!(oversized[0].1 > DEFAULT_FRONTMATTER_BUDGET);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+//! Link graph of a crawl for the agent-skills-generator.
+//!
+//! `crawl --graph site.dot` records which page links to which in-scope page
+//! and writes the graph in Graphviz DOT format once the crawl is done, e.g.
+//! for `dot -Tsvg site.dot`. Pages are nodes labeled with their skill name;
+//! pages that were linked to but got no skill (skipped, failed or beyond the
+//! crawl's limits) are labeled with their URL. Pages an incremental crawl
+//! kept without processing them again add no links.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// A page of the graph and the pages it links to.
+#[derive(Debug, Default)]
+struct Node {
+    skill_name: String,
+    links: BTreeSet<String>,
+}
+
+/// Link graph shared by the crawlers of a run.
+#[derive(Debug, Default)]
+pub struct LinkGraph {
+    nodes: Mutex<BTreeMap<String, Node>>,
+}
+
+impl LinkGraph {
+    /// Creates an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the page at `url`, written as `skill_name`, links to
+    /// `links`. Links to the page itself are left out.
+    pub fn record(&self, url: &str, skill_name: &str, links: impl IntoIterator<Item = String>) {
+        let mut nodes = self.nodes.lock().unwrap_or_else(|e| e.into_inner());
+        let node = nodes.entry(url.to_string()).or_default();
+        node.skill_name = skill_name.to_string();
+        node.links
+            .extend(links.into_iter().filter(|link| link != url));
+    }
+
+    /// Number of pages and links in the graph.
+    pub fn counts(&self) -> (usize, usize) {
+        let nodes = self.nodes.lock().unwrap_or_else(|e| e.into_inner());
+        let pages: BTreeSet<&String> = nodes
+            .iter()
+            .flat_map(|(url, node)| std::iter::once(url).chain(&node.links))
+            .collect();
+        let links = nodes.values().map(|node| node.links.len()).sum();
+        (pages.len(), links)
+    }
+
+    /// The graph in DOT format. Nodes are identified by URL, and listed in
+    /// URL order so the same crawl gives the same file.
+    pub fn to_dot(&self) -> String {
+        let nodes = self.nodes.lock().unwrap_or_else(|e| e.into_inner());
+        let linked: BTreeSet<&String> = nodes
+            .values()
+            .flat_map(|node| &node.links)
+            .filter(|url| !nodes.contains_key(*url))
+            .collect();
+
+        let mut dot = String::from("digraph crawl {\n    node [shape=box];\n");
+        for (url, node) in nodes.iter() {
+            dot.push_str(&format!(
+                "    {} [label={}, tooltip={}];\n",
+                quote(url),
+                quote(&node.skill_name),
+                quote(url)
+            ));
+        }
+        for url in linked {
+            dot.push_str(&format!(
+                "    {} [label={}, style=dashed];\n",
+                quote(url),
+                quote(url)
+            ));
+        }
+        for (url, node) in nodes.iter() {
+            for link in &node.links {
+                dot.push_str(&format!("    {} -> {};\n", quote(url), quote(link)));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes the graph in DOT format to `path`.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs_err::create_dir_all(parent)?;
+        }
+        fs_err::write(path, self.to_dot())
+            .with_context(|| format!("Failed to write link graph {}", path.display()))
+    }
+}
+
+/// A DOT quoted string.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let graph = LinkGraph::new();
+        graph.record(
+            "https://docs.example.com/",
+            "docs",
+            [
+                "https://docs.example.com/install".to_string(),
+                "https://docs.example.com/api".to_string(),
+                "https://docs.example.com/".to_string(),
+            ],
+        );
+        graph.record(
+            "https://docs.example.com/install",
+            "install",
+            ["https://docs.example.com/".to_string()],
+        );
+        assert_eq!(graph.counts(), (3, 3));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph crawl {\n"), "{}", dot);
+        assert!(
+            dot.contains(r#""https://docs.example.com/install" [label="install", tooltip="https://docs.example.com/install"];"#),
+            "{}",
+            dot
+        );
+        // Linked to, but never processed
+        assert!(
+            dot.contains(r#""https://docs.example.com/api" [label="https://docs.example.com/api", style=dashed];"#),
+            "{}",
+            dot
+        );
+        assert!(
+            dot.contains(r#""https://docs.example.com/install" -> "https://docs.example.com/";"#),
+            "{}",
+            dot
+        );
+        assert_eq!(dot.matches(" -> ").count(), 3);
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+    }
+}
@@ -0,0 +1,203 @@
+//! Hooks run once a crawl has finished, e.g. to notify a chat or start a
+//! build in CI.
+//!
+//! `post_crawl_command` runs a shell command with the crawl's stats in
+//! environment variables, `SKILLS_PROCESSED`, `SKILLS_FAILED` and so on for
+//! every counter of [`StatsSnapshot`], plus `SKILLS_OUTPUT_DIR`.
+//! `webhook_url` receives the same stats as a JSON POST, sent through the
+//! configured proxy and with the configured user agent. Hooks only run once
+//! every seed was crawled. A hook that fails is logged; the crawl has
+//! succeeded either way. Webhook URLs usually embed a token, so only their
+//! scheme and host are ever logged.
+
+use crate::config::Config;
+use crate::crawler::StatsSnapshot;
+use crate::http::{self, HttpClient};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Runs the hooks `config` sets up for a crawl that wrote to `output_dirs`.
+pub async fn run(config: &Config, stats: &StatsSnapshot, output_dirs: &[PathBuf]) {
+    if let Some(ref command) = config.post_crawl_command {
+        let env = stat_env(stats, &output_dirs[0]);
+        match run_command(command, &env).await {
+            Ok(()) => info!("Ran post-crawl command"),
+            Err(e) => warn!("Post-crawl command failed: {:#}", e),
+        }
+    }
+
+    if let Some(ref webhook_url) = config.webhook_url {
+        let posted = match http::build_http_client(config) {
+            Ok(client) => post_webhook(&client, webhook_url, stats, output_dirs).await,
+            Err(e) => Err(e),
+        };
+        match posted {
+            Ok(()) => info!("Posted crawl stats to {}", http::redact_url(webhook_url)),
+            Err(e) => warn!("Webhook failed: {:#}", e),
+        }
+    }
+}
+
+/// The environment of `post_crawl_command`: `SKILLS_<COUNTER>` for every
+/// counter of `stats`, and `SKILLS_OUTPUT_DIR`.
+pub fn stat_env(stats: &StatsSnapshot, output_dir: &Path) -> Vec<(String, String)> {
+    let mut env: Vec<(String, String)> = match serde_json::to_value(stats) {
+        Ok(serde_json::Value::Object(counters)) => counters
+            .into_iter()
+            .map(|(name, value)| (format!("SKILLS_{}", name.to_uppercase()), value.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    env.push((
+        "SKILLS_OUTPUT_DIR".to_string(),
+        output_dir.display().to_string(),
+    ));
+    env
+}
+
+/// Runs `command` through the shell with `env` added to its environment.
+/// Its output is logged; a non-zero exit is an error.
+pub async fn run_command(command: &str, env: &[(String, String)]) -> Result<()> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = tokio::process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = tokio::process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    // Captured, so --json output on stdout stays clean
+    let output = shell
+        .arg(command)
+        .envs(env.iter().map(|(name, value)| (name, value)))
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .with_context(|| format!("Failed to run '{}'", command))?;
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        info!("post_crawl_command: {}", line);
+    }
+    if !output.status.success() {
+        anyhow::bail!(
+            "'{}' exited with {}: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// POSTs `stats` and `output_dirs` to `url` with `client`, as JSON like
+/// `{"stats": {"processed": 12, ...}, "output_dirs": [".claude/skills"]}`.
+pub async fn post_webhook(
+    client: &HttpClient,
+    url: &str,
+    stats: &StatsSnapshot,
+    output_dirs: &[PathBuf],
+) -> Result<()> {
+    let body = serde_json::json!({
+        "stats": stats,
+        "output_dirs": output_dirs,
+    });
+    let response = client.post_json(url, &body).await?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("{} answered {}", http::redact_url(url), response.status());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn stats() -> StatsSnapshot {
+        StatsSnapshot {
+            visited: 12,
+            processed: 9,
+            failed: 2,
+            ..Default::default()
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_receives_stats() {
+        let dir = std::env::temp_dir().join(format!("post-crawl-hook-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let out = dir.join("stats.txt");
+
+        let env = stat_env(&stats(), Path::new(".claude/skills"));
+        let command = format!(
+            "echo \"$SKILLS_VISITED $SKILLS_PROCESSED $SKILLS_FAILED $SKILLS_TOO_SMALL $SKILLS_OUTPUT_DIR\" > '{}'",
+            out.display()
+        );
+        run_command(&command, &env).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "12 9 2 0 .claude/skills\n"
+        );
+
+        let err = run_command("echo broken >&2; exit 3", &env)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("broken"), "{}", err);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Whether the webhook test server got a JSON POST.
+    static POSTED: AtomicBool = AtomicBool::new(false);
+
+    #[tokio::test]
+    async fn test_post_webhook() {
+        let port = crate::http::test_server(|request| {
+            let head = request.to_lowercase();
+            if head.starts_with("post /hook-token ")
+                && head.contains("content-type: application/json")
+                && head.contains("user-agent: hookbot/1.0")
+            {
+                POSTED.store(true, Ordering::SeqCst);
+                return crate::http::ok_response("");
+            }
+            "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_string()
+        })
+        .await;
+        let output_dirs = [PathBuf::from("skills")];
+        let client = http::build_http_client(&Config {
+            user_agent: Some("HookBot/1.0".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let url = format!("http://127.0.0.1:{}/hook-token", port);
+        post_webhook(&client, &url, &stats(), &output_dirs)
+            .await
+            .unwrap();
+        assert!(POSTED.load(Ordering::SeqCst));
+
+        // The path is the secret of a webhook URL, so errors leave it out
+        let url = format!("http://127.0.0.1:{}/missing-token", port);
+        let err = post_webhook(&client, &url, &stats(), &output_dirs)
+            .await
+            .unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("404"), "{}", message);
+        assert!(!message.contains("token"), "{}", message);
+
+        // As do connection errors
+        let url = "http://127.0.0.1:1/missing-token";
+        let message = format!(
+            "{:#}",
+            post_webhook(&client, url, &stats(), &output_dirs)
+                .await
+                .unwrap_err()
+        );
+        assert!(!message.contains("token"), "{}", message);
+    }
+}
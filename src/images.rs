@@ -0,0 +1,308 @@
+//! Image handling for the agent-skills-generator.
+//!
+//! The `images` setting decides what happens to the images of a page's
+//! markdown:
+//! - `link` (the default) points them at absolute URLs, so they still
+//!   resolve outside the site
+//! - `strip` replaces them with their alt text in italics
+//! - `download` saves them into the skill's `assets/` directory, named by
+//!   content hash so repeated images are stored once, and links the copies
+//!
+//! Downloads share one concurrency limit across all pages, so a page with
+//! many images doesn't hold up the rest of the crawl.
+
+use crate::cache::content_hash;
+use crate::config::{Config, ImageMode};
+use crate::http::{self, HttpClient};
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+/// Directory of a skill that downloaded images are saved to.
+pub const ASSETS_DIR: &str = "assets";
+
+/// A markdown image: `![alt](src "title")`, the title being optional.
+static IMAGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"!\[([^\]]*)\]\(\s*<?([^\s)>]+)>?(\s+"[^"]*")?\s*\)"#)
+        .expect("Failed to compile markdown image regex")
+});
+
+/// An image saved next to a skill with `images: download`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageAsset {
+    /// File name within [`ASSETS_DIR`].
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Applies `mode` to the images in `markdown`, whose destinations
+/// [`links::absolutize`](crate::links::absolutize) already made absolute.
+/// Downloaded images are pointed at their local copies later, by
+/// [`ImageDownloader::download`].
+pub fn resolve(markdown: &str, mode: ImageMode) -> String {
+    match mode {
+        ImageMode::Link | ImageMode::Download => markdown.to_string(),
+        ImageMode::Strip => strip(markdown),
+    }
+}
+
+/// Replaces every image with its alt text in italics, or nothing if it has
+/// no alt text.
+pub fn strip(markdown: &str) -> String {
+    IMAGE
+        .replace_all(markdown, |caps: &Captures| {
+            let alt = caps[1].trim();
+            if alt.is_empty() {
+                String::new()
+            } else {
+                format!("*{}*", alt)
+            }
+        })
+        .into_owned()
+}
+
+fn image(alt: &str, src: &str, title: Option<&str>) -> String {
+    format!("![{}]({}{})", alt, src, title.unwrap_or_default())
+}
+
+/// Downloads images for `images: download`.
+#[derive(Clone)]
+pub struct ImageDownloader {
+    client: HttpClient,
+    /// Shared by every page, bounding the downloads in flight.
+    permits: Arc<Semaphore>,
+    max_bytes: u64,
+}
+
+impl ImageDownloader {
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Self {
+            client: http::build_http_client(config)?,
+            permits: Arc::new(Semaphore::new(config.image_concurrency.max(1))),
+            max_bytes: config.max_image_bytes,
+        })
+    }
+
+    /// Downloads the images of `markdown` and returns it
+    /// with the images pointed at `assets/`, along with the files to save
+    /// there. Images that fail to download or are too large stay linked.
+    pub async fn download(&self, markdown: &str) -> (String, Vec<ImageAsset>) {
+        let mut tasks = JoinSet::new();
+        let mut queued = Vec::new();
+        for caps in IMAGE.captures_iter(markdown) {
+            let src = caps[2].to_string();
+            if !src.starts_with("http://") && !src.starts_with("https://") || queued.contains(&src)
+            {
+                continue;
+            }
+            queued.push(src.clone());
+
+            let downloader = self.clone();
+            tasks.spawn(async move {
+                let asset = downloader.fetch(&src).await;
+                (src, asset)
+            });
+        }
+
+        let mut local: HashMap<String, String> = HashMap::new();
+        let mut assets: Vec<ImageAsset> = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            let (src, asset) = match result {
+                Ok(downloaded) => downloaded,
+                Err(e) => {
+                    warn!("Image download task panicked: {}", e);
+                    continue;
+                }
+            };
+            match asset {
+                Ok(asset) => {
+                    local.insert(src, format!("{}/{}", ASSETS_DIR, asset.file_name));
+                    if !assets.iter().any(|a| a.file_name == asset.file_name) {
+                        assets.push(asset);
+                    }
+                }
+                Err(e) => warn!("Keeping image linked: {:#}", e),
+            }
+        }
+        assets.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+        let markdown = IMAGE
+            .replace_all(markdown, |caps: &Captures| match local.get(&caps[2]) {
+                Some(path) => image(&caps[1], path, caps.get(3).map(|m| m.as_str())),
+                None => caps[0].to_string(),
+            })
+            .into_owned();
+        (markdown, assets)
+    }
+
+    /// Fetches one image, refusing it once it grows past `max_bytes`.
+    async fn fetch(&self, url: &str) -> Result<ImageAsset> {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .context("Image download limit closed")?;
+
+        let mut response = self.client.get(url).await?;
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to fetch image {}: HTTP {}", url, response.status());
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_bytes)
+        {
+            anyhow::bail!("Image {} is over max_image_bytes ({})", url, self.max_bytes);
+        }
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .with_context(|| format!("Failed to read image {}", url))?
+        {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() as u64 > self.max_bytes {
+                anyhow::bail!("Image {} is over max_image_bytes ({})", url, self.max_bytes);
+            }
+        }
+
+        let file_name = format!(
+            "{}.{}",
+            content_hash(&bytes),
+            extension(url, content_type.as_deref())
+        );
+        debug!(
+            "Downloaded {} ({} bytes) as {}",
+            url,
+            bytes.len(),
+            file_name
+        );
+        Ok(ImageAsset { file_name, bytes })
+    }
+}
+
+/// File extension of an image: the URL's if it has a plausible one, else
+/// derived from the `Content-Type`. Both come from the server, so anything
+/// but a short alphanumeric extension falls back to `img`.
+fn extension(url: &str, content_type: Option<&str>) -> String {
+    let plausible = |extension: &str| {
+        !extension.is_empty()
+            && extension.len() <= 5
+            && extension.chars().all(|c| c.is_ascii_alphanumeric())
+    };
+    let from_url = url::Url::parse(url).ok().and_then(|url| {
+        let (_, extension) = url.path().rsplit_once('.')?;
+        plausible(extension).then(|| extension.to_ascii_lowercase())
+    });
+    if let Some(extension) = from_url {
+        return extension;
+    }
+
+    let subtype = content_type
+        .and_then(|value| value.split(';').next())
+        .and_then(|mime| mime.trim().strip_prefix("image/"))
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match subtype.as_str() {
+        "jpeg" => "jpg".to_string(),
+        "svg+xml" => "svg".to_string(),
+        "x-icon" | "vnd.microsoft.icon" => "ico".to_string(),
+        other if plausible(other) => other.to_string(),
+        _ => "img".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{ok_response, test_server};
+
+    #[test]
+    fn test_strip() {
+        let markdown = "See ![Architecture](https://docs.example.com/img/arch.png \"Overview\") \
+                        and ![](https://cdn.example.com/x.svg).";
+
+        assert_eq!(resolve(markdown, ImageMode::Link), markdown);
+        assert_eq!(
+            resolve(markdown, ImageMode::Strip),
+            "See *Architecture* and ."
+        );
+    }
+
+    fn image_server(request: &str) -> String {
+        if request.starts_with("GET /big.png ") {
+            return ok_response(&"x".repeat(64));
+        }
+        if request.starts_with("GET /missing.png ") {
+            return "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                .to_string();
+        }
+        ok_response("PNGDATA")
+    }
+
+    #[tokio::test]
+    async fn test_download_images() {
+        let port = test_server(image_server).await;
+        let config = Config {
+            max_image_bytes: 32,
+            ..Default::default()
+        };
+        let downloader = ImageDownloader::new(&config).unwrap();
+
+        let base = format!("http://127.0.0.1:{}", port);
+        let markdown = format!(
+            "![A]({base}/a.png) ![Same bytes]({base}/copy.png) ![A again]({base}/a.png)\n\
+             ![Big]({base}/big.png) ![Gone]({base}/missing.png)"
+        );
+        let (markdown, assets) = downloader.download(&markdown).await;
+
+        let file_name = format!("{}.png", content_hash("PNGDATA"));
+        assert_eq!(
+            assets,
+            vec![ImageAsset {
+                file_name: file_name.clone(),
+                bytes: b"PNGDATA".to_vec(),
+            }]
+        );
+        assert_eq!(
+            markdown,
+            format!(
+                "![A](assets/{file_name}) ![Same bytes](assets/{file_name}) ![A again](assets/{file_name})\n\
+                 ![Big]({base}/big.png) ![Gone]({base}/missing.png)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(extension("https://x.dev/a/logo.PNG?v=1", None), "png");
+        assert_eq!(
+            extension("https://x.dev/render", Some("image/svg+xml")),
+            "svg"
+        );
+        assert_eq!(extension("https://x.dev/photo", Some("image/jpeg")), "jpg");
+        assert_eq!(extension("https://x.dev/blob", None), "img");
+    }
+
+    #[test]
+    fn test_extension_of_hostile_content_type() {
+        for content_type in ["image/../../x", "image/a\\b", "image/webpwebp", "image/"] {
+            assert_eq!(
+                extension("https://x.dev/blob", Some(content_type)),
+                "img",
+                "{}",
+                content_type
+            );
+        }
+        assert_eq!(extension("https://x.dev/blob", Some("image/WebP")), "webp");
+    }
+}
@@ -0,0 +1,572 @@
+//! Init command for the agent-skills-generator.
+//!
+//! Creates a `skills.yaml`, either from an interactive wizard or from the
+//! default template. When stdin/stdout aren't a terminal (docker builds, CI,
+//! piped input) the wizard can't run, so init falls back to the template
+//! unless `--require-interactive` is given.
+//!
+//! The target defaults to the agent tooling already in the project: a
+//! `.claude/` directory next to the config suggests `claude-code`, and so
+//! on (see [`detect_targets`]). `--target` overrides the detection.
+//!
+//! The config is generated as YAML and converted for `--config-format toml`
+//! or `json`.
+
+use crate::cli::{DEFAULT_CONFIG, InitArgs};
+use agent_skills_generator::config::{ConfigFormat, SkillsScope, SkillsTarget};
+use anyhow::{Context, Result};
+use inquire::{InquireError, Select, Text};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Menu entries of the target prompt, in order.
+const TARGET_OPTIONS: [(&str, SkillsTarget); 7] = [
+    ("Custom (specify output path)", SkillsTarget::Custom),
+    ("GitHub Copilot", SkillsTarget::GithubCopilot),
+    ("Claude Code", SkillsTarget::ClaudeCode),
+    ("Cursor", SkillsTarget::Cursor),
+    ("Antigravity (Gemini)", SkillsTarget::Antigravity),
+    ("OpenAI Codex", SkillsTarget::OpenAICodex),
+    ("OpenCode", SkillsTarget::OpenCode),
+];
+
+/// Whether both stdin and stdout are attached to a terminal.
+pub fn stdio_is_terminal() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+/// Targets whose tooling exists in the project directory `dir`: the
+/// top-level directory of their [`SkillsTarget::project_dir`] (`.claude`,
+/// `.cursor`...) or, for Copilot, `.github/copilot-instructions.md` or
+/// `.github/skills`. In menu order.
+pub fn detect_targets(dir: &Path) -> Vec<SkillsTarget> {
+    TARGET_OPTIONS
+        .iter()
+        .map(|(_, target)| *target)
+        .filter(|target| match target {
+            SkillsTarget::Custom => false,
+            SkillsTarget::GithubCopilot => {
+                dir.join(".github/copilot-instructions.md").is_file()
+                    || dir.join(target.project_dir()).is_dir()
+            }
+            _ => {
+                let tool_dir = target.project_dir().split('/').next().unwrap_or_default();
+                dir.join(tool_dir).is_dir()
+            }
+        })
+        .collect()
+}
+
+/// Run the init command - create a new configuration file.
+///
+/// `targets` are the ones passed with `--target`; without any, the target
+/// is detected. `terminal` says whether stdin and stdout are both attached
+/// to a terminal; see [`stdio_is_terminal`].
+pub fn run_init(args: &InitArgs, targets: &[SkillsTarget], terminal: bool) -> Result<()> {
+    let (path, format) = config_file(args)?;
+    if path.exists() && !args.force {
+        anyhow::bail!(
+            "Configuration file already exists: {}. Use --force to overwrite.",
+            path.display()
+        );
+    }
+
+    let project_dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let detected = if targets.is_empty() {
+        detect_targets(project_dir)
+    } else {
+        Vec::new()
+    };
+    if !detected.is_empty() {
+        let names: Vec<String> = detected.iter().map(ToString::to_string).collect();
+        info!("Detected agent tooling for: {}", names.join(", "));
+    }
+
+    // Prompts can't work without a terminal (docker build, CI, pipes)
+    let no_interactive = args.no_interactive || !terminal;
+    if !args.no_interactive && !terminal {
+        if args.require_interactive {
+            anyhow::bail!(
+                "Interactive init needs a terminal, but stdin/stdout is not one. \
+                 Pass --no-interactive to write the default configuration instead."
+            );
+        }
+        info!("Not running in a terminal; writing the default configuration instead of prompting");
+    }
+
+    // If --no-interactive, use default config
+    if no_interactive {
+        let targets = match (targets, detected.first()) {
+            ([], Some(target)) => {
+                info!("Using target {}; pass --target to choose another", target);
+                vec![*target]
+            }
+            _ => targets.to_vec(),
+        };
+        let content = convert_config(&default_config(&targets), format)?;
+        fs_err::write(&path, content)
+            .with_context(|| format!("Failed to write configuration file: {}", path.display()))?;
+
+        info!("Created configuration file: {}", path.display());
+        info!("Edit this file to customize crawling behavior, then run:");
+        info!("  agent-skills-generator crawl <URL>");
+
+        return Ok(());
+    }
+
+    // Interactive mode
+    let config_content = match run_interactive_init(targets, &detected) {
+        Ok(content) => content,
+        // The terminal check can be fooled (e.g. a pty without a usable
+        // tty), and a terminal that is resized or too narrow fails the
+        // prompt with an I/O error; neither is the user backing out
+        Err(e) if prompt_failed(&e) && !args.require_interactive => {
+            info!(
+                "Terminal can't show prompts ({:#}); writing the default configuration instead",
+                e
+            );
+            default_config(match targets {
+                [] => &detected[..detected.len().min(1)],
+                targets => targets,
+            })
+        }
+        Err(e) => return Err(e),
+    };
+
+    let config_content = convert_config(&config_content, format)?;
+    fs_err::write(&path, &config_content)
+        .with_context(|| format!("Failed to write configuration file: {}", path.display()))?;
+
+    info!("Created configuration file: {}", path.display());
+    info!("Run the following command to start crawling:");
+    info!("  agent-skills-generator crawl <URL>");
+
+    Ok(())
+}
+
+/// Where init writes the config and in which format: `--path` and
+/// `--config-format`, each defaulting to the other (and both to
+/// `skills.yaml`).
+fn config_file(args: &InitArgs) -> Result<(PathBuf, ConfigFormat)> {
+    match (&args.path, args.config_format) {
+        (Some(path), Some(format)) if ConfigFormat::from_path(path) != format => {
+            anyhow::bail!(
+                "{} has the extension of a {} config, not {}; config files are read by extension",
+                path.display(),
+                ConfigFormat::from_path(path),
+                format
+            )
+        }
+        (Some(path), format) => Ok((
+            path.clone(),
+            format.unwrap_or_else(|| ConfigFormat::from_path(path)),
+        )),
+        (None, format) => {
+            let format = format.unwrap_or_default();
+            Ok((
+                PathBuf::from(format!("skills.{}", format.extension())),
+                format,
+            ))
+        }
+    }
+}
+
+/// Converts `yaml`, a generated config, to `format`. Comments and unset
+/// (null) values are lost outside YAML.
+fn convert_config(yaml: &str, format: ConfigFormat) -> Result<String> {
+    if format == ConfigFormat::Yaml {
+        return Ok(yaml.to_string());
+    }
+
+    let mut value: serde_yaml::Value =
+        serde_yaml::from_str(yaml).context("Failed to read the generated configuration")?;
+    remove_nulls(&mut value);
+    match format {
+        ConfigFormat::Toml => toml::to_string_pretty(&value).context("Failed to write TOML"),
+        _ => Ok(serde_json::to_string_pretty(&value)? + "\n"),
+    }
+}
+
+/// Drops null values, which TOML can't hold, from every mapping.
+fn remove_nulls(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            mapping.retain(|_, value| !value.is_null());
+            mapping.values_mut().for_each(remove_nulls);
+        }
+        serde_yaml::Value::Sequence(items) => items.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+/// The default template with `targets` in place of `target: custom`.
+fn default_config(targets: &[SkillsTarget]) -> String {
+    let line = match targets {
+        [] => return DEFAULT_CONFIG.to_string(),
+        [target] => format!("\ntarget: {}\n", target),
+        targets => {
+            let names: Vec<String> = targets.iter().map(ToString::to_string).collect();
+            format!("\ntargets: [{}]\n", names.join(", "))
+        }
+    };
+    DEFAULT_CONFIG.replacen("\ntarget: custom\n", &line, 1)
+}
+
+/// The target menu: detected targets first and marked, then the rest.
+fn target_menu(detected: &[SkillsTarget]) -> Vec<(String, SkillsTarget)> {
+    let mut menu: Vec<(String, SkillsTarget)> = detected
+        .iter()
+        .filter_map(|target| TARGET_OPTIONS.iter().find(|(_, t)| t == target))
+        .map(|(name, target)| (format!("{} (detected)", name), *target))
+        .collect();
+    menu.extend(
+        TARGET_OPTIONS
+            .iter()
+            .filter(|(_, target)| !detected.contains(target))
+            .map(|(name, target)| (name.to_string(), *target)),
+    );
+    menu
+}
+
+/// Run interactive initialization prompts and return the generated YAML config.
+///
+/// The target prompt is skipped when `targets` were passed with `--target`;
+/// otherwise `detected` targets are offered first.
+fn run_interactive_init(targets: &[SkillsTarget], detected: &[SkillsTarget]) -> Result<String> {
+    // Target IDE selection
+    let target = match targets {
+        [] => {
+            let menu = target_menu(detected);
+            let target_names: Vec<&str> = menu.iter().map(|(name, _)| name.as_str()).collect();
+            let target_idx = Select::new("Select target IDE/agent:", target_names)
+                .with_help_message("Choose where your skills will be installed")
+                .prompt()
+                .context("Failed to get target selection")?;
+
+            let target = menu
+                .iter()
+                .find(|(name, _)| *name == target_idx)
+                .map(|(_, t)| *t)
+                .unwrap_or(SkillsTarget::Custom);
+            target.to_string()
+        }
+        [target] => target.to_string(),
+        targets => {
+            let names: Vec<String> = targets.iter().map(ToString::to_string).collect();
+            format!("[{}]", names.join(", "))
+        }
+    };
+    let target_key = if targets.len() > 1 {
+        "targets"
+    } else {
+        "target"
+    };
+    let custom = target == SkillsTarget::Custom.to_string();
+
+    // Scope selection
+    let scope_options = [
+        (
+            "Project (install to current directory)",
+            SkillsScope::Project,
+        ),
+        ("User (install to home directory)", SkillsScope::User),
+    ];
+
+    let scope_names: Vec<&str> = scope_options.iter().map(|(name, _)| *name).collect();
+    let scope_idx = Select::new("Install skills at project or user level?", scope_names)
+        .with_help_message("Project-level is recommended for team collaboration")
+        .prompt()
+        .context("Failed to get scope selection")?;
+
+    let scope = scope_options
+        .iter()
+        .find(|(name, _)| *name == scope_idx)
+        .map(|(_, s)| *s)
+        .unwrap_or(SkillsScope::Project);
+
+    // Output path (only for custom target)
+    let output = if custom {
+        Text::new("Output directory:")
+            .with_default(".agent/skills")
+            .with_help_message("Where to store generated skill files")
+            .prompt()
+            .context("Failed to get output path")?
+    } else {
+        ".agent/skills".to_string()
+    };
+
+    // Crawl settings
+    let delay_ms = Text::new("Request delay in milliseconds:")
+        .with_default("100")
+        .with_help_message("Delay between requests for polite crawling")
+        .prompt()
+        .context("Failed to get delay")?
+        .parse::<u64>()
+        .unwrap_or(100);
+
+    let max_depth = Text::new("Maximum crawl depth:")
+        .with_default("25")
+        .with_help_message("How deep to follow links from the starting URL")
+        .prompt()
+        .context("Failed to get max depth")?
+        .parse::<usize>()
+        .unwrap_or(25);
+
+    let concurrency = Text::new("Concurrency limit:")
+        .with_default("4")
+        .with_help_message("Number of parallel requests")
+        .prompt()
+        .context("Failed to get concurrency")?
+        .parse::<usize>()
+        .unwrap_or(4);
+
+    // Generate YAML configuration
+    let config_yaml = format!(
+        r##"# Agent Skills Generator Configuration
+# See https://github.com/agentskills/agentskills for documentation
+
+# Target IDE/agent for skills generation
+# Supported targets: github-copilot, claude-code, cursor, antigravity, openai-codex, opencode, custom
+{}: {}
+
+# Scope for skills installation
+# - project: Install to project directory (e.g., .cursor/skills/)
+# - user: Install to user home directory (e.g., ~/.cursor/skills/)
+scope: {}
+
+# Output directory for generated skills (only used when target is "custom")
+output: {}
+
+# Write <name>.md files instead of <name>/SKILL.md directories
+flat: false
+
+# Delay between requests in milliseconds (polite crawling)
+delay_ms: {}
+
+# Maximum crawl depth
+max_depth: {}
+
+# Request timeout in seconds
+request_timeout_secs: 30
+
+# Respect robots.txt
+respect_robots_txt: true
+
+# Allow subdomains
+subdomains: false
+
+# Only follow these subdomains of the start URL's host (implies subdomains)
+# allowed_subdomains: ["docs", "api", "*.docs"]
+
+# Concurrency limit for parallel page processing
+concurrency: {}
+
+# URL filtering rules (evaluated in order)
+rules:
+  # Example: Allow only documentation pages
+  # - url: "*/docs/*"
+  #   action: allow
+
+  # Example: Ignore API internals
+  # - url: "*/api/internal/*"
+  #   action: ignore
+
+# CSS selectors for elements to remove from content
+# These are already included by default, add more if needed:
+# remove_selectors:
+#   - ".custom-sidebar"
+#   - "#ad-container"
+"##,
+        target_key, target, scope, output, delay_ms, max_depth, concurrency
+    );
+
+    Ok(config_yaml)
+}
+
+/// Whether a prompt failed because of the terminal (not a usable tty, an
+/// I/O error while drawing) rather than because the user cancelled it
+/// with Esc or Ctrl-C.
+fn prompt_failed(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<InquireError>(),
+            Some(error) if !matches!(
+                error,
+                InquireError::OperationCanceled | InquireError::OperationInterrupted
+            )
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_skills_generator::config::Config;
+
+    fn init_args(path: PathBuf) -> InitArgs {
+        InitArgs {
+            force: false,
+            path: Some(path),
+            config_format: None,
+            no_interactive: false,
+            require_interactive: false,
+        }
+    }
+
+    /// `skills.yaml` in an empty project directory of its own.
+    fn temp_config_path(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("agent-skills-init-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("skills.yaml")
+    }
+
+    #[test]
+    fn test_piped_stdio_falls_back_to_default_config() {
+        let path = temp_config_path("fallback");
+
+        run_init(&init_args(path.clone()), &[], false).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), DEFAULT_CONFIG);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_require_interactive_fails_without_terminal() {
+        let path = temp_config_path("require");
+        let args = InitArgs {
+            require_interactive: true,
+            ..init_args(path.clone())
+        };
+
+        let err = run_init(&args, &[], false).unwrap_err();
+
+        assert!(err.to_string().contains("--no-interactive"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_existing_config_is_not_overwritten() {
+        let path = temp_config_path("existing");
+        std::fs::write(&path, "output: keep-me\n").unwrap();
+
+        assert!(run_init(&init_args(path.clone()), &[], false).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "output: keep-me\n");
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_detect_targets() {
+        let path = temp_config_path("detect");
+        let dir = path.parent().unwrap();
+        assert!(detect_targets(dir).is_empty());
+
+        std::fs::create_dir_all(dir.join(".cursor")).unwrap();
+        std::fs::create_dir_all(dir.join(".claude/commands")).unwrap();
+        // Any GitHub repo has .github; only Copilot's own files count
+        std::fs::create_dir_all(dir.join(".github/workflows")).unwrap();
+        assert_eq!(
+            detect_targets(dir),
+            [SkillsTarget::ClaudeCode, SkillsTarget::Cursor]
+        );
+        std::fs::write(dir.join(".github/copilot-instructions.md"), "").unwrap();
+        assert_eq!(detect_targets(dir)[0], SkillsTarget::GithubCopilot);
+
+        let menu = target_menu(&[SkillsTarget::Cursor]);
+        assert_eq!(
+            menu[0],
+            ("Cursor (detected)".to_string(), SkillsTarget::Cursor)
+        );
+        assert_eq!(menu[1].1, SkillsTarget::Custom);
+        assert_eq!(menu.len(), TARGET_OPTIONS.len());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_init_uses_detected_target() {
+        let path = temp_config_path("detected");
+        let dir = path.parent().unwrap();
+        std::fs::create_dir_all(dir.join(".claude")).unwrap();
+        std::fs::create_dir_all(dir.join(".cursor")).unwrap();
+
+        run_init(&init_args(path.clone()), &[], false).unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.targets(), [SkillsTarget::ClaudeCode]);
+
+        // --target wins over the detection
+        let args = InitArgs {
+            force: true,
+            ..init_args(path.clone())
+        };
+        run_init(
+            &args,
+            &[SkillsTarget::OpenCode, SkillsTarget::Cursor],
+            false,
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(
+            config.targets(),
+            [SkillsTarget::OpenCode, SkillsTarget::Cursor]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_init_config_formats() {
+        let path = temp_config_path("formats");
+        let dir = path.parent().unwrap();
+        std::fs::create_dir_all(dir.join(".cursor")).unwrap();
+        run_init(&init_args(path.clone()), &[], false).unwrap();
+        let yaml = format!("{:?}", Config::load(&path).unwrap());
+
+        for format in [ConfigFormat::Toml, ConfigFormat::Json] {
+            let path = dir.join(format!("skills.{}", format.extension()));
+            run_init(&init_args(path.clone()), &[], false).unwrap();
+            assert_eq!(format!("{:?}", Config::load(&path).unwrap()), yaml);
+        }
+        let toml = std::fs::read_to_string(dir.join("skills.toml")).unwrap();
+        assert!(toml.contains("target = \"cursor\""), "{}", toml);
+
+        let args = InitArgs {
+            config_format: Some(ConfigFormat::Json),
+            ..init_args(path.clone())
+        };
+        let err = run_init(&args, &[], false).unwrap_err();
+        assert!(err.to_string().contains("YAML config, not JSON"), "{}", err);
+        let args = InitArgs {
+            path: None,
+            config_format: Some(ConfigFormat::Toml),
+            ..init_args(path.clone())
+        };
+        assert_eq!(
+            config_file(&args).unwrap(),
+            (PathBuf::from("skills.toml"), ConfigFormat::Toml)
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_prompt_failure_detection() {
+        let failed = |error: InquireError| {
+            prompt_failed(&anyhow::Error::new(error).context("Failed to get target selection"))
+        };
+        assert!(failed(InquireError::NotTTY));
+        // What a resize or a too narrow terminal surfaces as
+        assert!(failed(InquireError::IO(std::io::Error::other(
+            "terminal size changed"
+        ))));
+        // Backing out of the wizard isn't a reason to write a config
+        assert!(!failed(InquireError::OperationCanceled));
+        assert!(!failed(InquireError::OperationInterrupted));
+        assert!(!prompt_failed(&anyhow::anyhow!("something else")));
+    }
+}
@@ -0,0 +1,232 @@
+//! Page languages for the agent-skills-generator.
+//!
+//! With `languages`, pages in other languages are skipped. A page's language
+//! is what it declares: `<html lang>`, then `og:locale`, then the
+//! `Content-Language` header. Pages that declare nothing are kept, unless
+//! `strict_language` is set, in which case their language is guessed from
+//! the text with [`detect`]. Tags are compared by their primary subtag, so
+//! `en` admits `en-US` and `en_GB`.
+
+use std::collections::HashMap;
+
+/// Trigrams a text needs before [`detect`] guesses its language.
+const MIN_TRIGRAMS: usize = 20;
+
+/// The most frequent trigrams of a few languages written in Latin script,
+/// with words padded by a space on either side.
+const TRIGRAM_PROFILES: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            " th", "the", "he ", " an", "and", "nd ", "ing", "ng ", " to", "to ", " of", "of ",
+            "ed ", " in", "ion", "tio", "es ", "er ", " is", "is ", "you", " yo", "re ", "hat",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "en ", "er ", " de", "der", "ie ", " di", "die", "ich", "ch ", "sch", "ein", " ei",
+            "und", " un", "nd ", "den", "in ", "cht", "te ", " da", "gen", " si", "sie", "ung",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            " de", "es ", "de ", " le", "le ", "ent", " la", "la ", "nt ", "les", "ion", " et",
+            "et ", "re ", " pa", " po", "our", "que", " qu", "ue ", " un", "des", " co", "tio",
+        ],
+    ),
+    (
+        "es",
+        &[
+            " de", "de ", "os ", " la", "la ", "el ", " el", "es ", " qu", "que", "ue ", " en",
+            "en ", "as ", " lo", "ent", "ión", "ció", " co", "ar ", " pa", "ado", "los", "par",
+        ],
+    ),
+    (
+        "it",
+        &[
+            " di", "di ", "re ", " la", "la ", "to ", " co", "che", " ch", "he ", " il", "il ",
+            "ell", "lla", "ne ", " de", "ion", "one", "zio", " pe", "per", "er ", "ato", "are",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            " de", "de ", "os ", " qu", "que", "ue ", "ão ", "ção", " co", "do ", " do", " pa",
+            "as ", " da", "da ", "ent", "ar ", "em ", " um", "um ", "par", "ara", "nte", "com",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "en ", " de", "de ", "et ", "het", " he", "van", " va", "an ", " ee", "een", "er ",
+            " en", "ij ", "oor", " vo", "voo", "aar", "ing", " ge", "cht", "ng ", "te ", "zij",
+        ],
+    ),
+];
+
+/// Normalizes a declared language tag: lowercase, with `-` between subtags
+/// (`og:locale` uses `_`). Of a list, like a `Content-Language` header, the
+/// first tag is kept. Returns `None` for anything that isn't a tag.
+pub fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.split(',').next()?.trim().replace('_', "-");
+    let valid = tag
+        .split('-')
+        .all(|subtag| !subtag.is_empty() && subtag.chars().all(|c| c.is_ascii_alphanumeric()));
+    let primary = tag.split('-').next()?;
+    (valid && (2..=8).contains(&primary.len())).then(|| tag.to_lowercase())
+}
+
+/// The primary subtag of a normalized tag: `en` of `en-us`.
+fn primary(tag: &str) -> &str {
+    tag.split('-').next().unwrap_or(tag)
+}
+
+/// Whether `language`, a normalized tag, is one of `languages`. Entries
+/// without a region admit every region: `en` matches `en-us`, while `pt-br`
+/// only matches `pt-br`.
+pub fn matches(languages: &[String], language: &str) -> bool {
+    languages
+        .iter()
+        .filter_map(|entry| normalize(entry))
+        .any(|entry| entry == language || (!entry.contains('-') && entry == primary(language)))
+}
+
+/// Guesses the language of `text`. Scripts used by a single language
+/// decide it outright; Latin text is compared with [`TRIGRAM_PROFILES`].
+/// Returns `None` for text too short to tell.
+pub fn detect(text: &str) -> Option<&'static str> {
+    if let Some(language) = detect_script(text) {
+        return Some(language);
+    }
+
+    let text = text.to_lowercase();
+    let words = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty());
+    let mut trigrams: HashMap<String, usize> = HashMap::new();
+    for word in words {
+        let padded: Vec<char> = format!(" {} ", word).chars().collect();
+        for window in padded.windows(3) {
+            *trigrams.entry(window.iter().collect()).or_default() += 1;
+        }
+    }
+    if trigrams.values().sum::<usize>() < MIN_TRIGRAMS {
+        return None;
+    }
+
+    TRIGRAM_PROFILES
+        .iter()
+        .map(|(language, profile)| {
+            let score: usize = profile
+                .iter()
+                .filter_map(|trigram| trigrams.get(*trigram))
+                .sum();
+            (*language, score)
+        })
+        .filter(|(_, score)| *score > 0)
+        // The last of equal maximums wins, so ties go to the first listed
+        .rev()
+        .max_by_key(|(_, score)| *score)
+        .map(|(language, _)| language)
+}
+
+/// The language of text mostly written in a script only one language uses.
+/// Kana decides Japanese over Chinese, which shares its Han characters.
+fn detect_script(text: &str) -> Option<&'static str> {
+    let mut letters = 0;
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    let mut kana = false;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let language = match c as u32 {
+            0x3040..=0x30FF => {
+                kana = true;
+                "ja"
+            }
+            0x4E00..=0x9FFF => "zh",
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => "ko",
+            0x0400..=0x04FF => "ru",
+            0x0600..=0x06FF => "ar",
+            0x0590..=0x05FF => "he",
+            0x0E00..=0x0E7F => "th",
+            0x0370..=0x03FF => "el",
+            _ => continue,
+        };
+        *counts.entry(language).or_default() += 1;
+    }
+
+    let (language, count) = counts.into_iter().max_by_key(|(_, count)| *count)?;
+    if count * 2 < letters {
+        return None;
+    }
+    match language {
+        "zh" if kana => Some("ja"),
+        language => Some(language),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("en-US").as_deref(), Some("en-us"));
+        assert_eq!(normalize(" en_GB ").as_deref(), Some("en-gb"));
+        assert_eq!(normalize("de, en").as_deref(), Some("de"));
+        assert_eq!(normalize("").as_deref(), None);
+        assert_eq!(normalize("*").as_deref(), None);
+        assert_eq!(normalize("e").as_deref(), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let languages = vec!["en".to_string(), "pt-BR".to_string()];
+        assert!(matches(&languages, "en"));
+        assert!(matches(&languages, "en-us"));
+        assert!(matches(&languages, "pt-br"));
+        assert!(!matches(&languages, "pt-pt"));
+        assert!(!matches(&languages, "ja"));
+        // Only whole subtags match
+        assert!(!matches(&languages, "eng"));
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            detect(
+                "Install the command line tools and add them to your path before you run the app."
+            ),
+            Some("en")
+        );
+        assert_eq!(
+            detect(
+                "Installieren Sie die Werkzeuge und fügen Sie sie zu Ihrem Pfad hinzu, bevor Sie die Anwendung starten."
+            ),
+            Some("de")
+        );
+        assert_eq!(
+            detect(
+                "Installez les outils de la ligne de commande et ajoutez-les à votre chemin avant de lancer l'application."
+            ),
+            Some("fr")
+        );
+        assert_eq!(
+            detect(
+                "Instale las herramientas de la línea de comandos y agréguelas a su ruta antes de ejecutar la aplicación."
+            ),
+            Some("es")
+        );
+        assert_eq!(
+            detect("コマンドラインツールをインストールします。"),
+            Some("ja")
+        );
+        assert_eq!(
+            detect("Установите инструменты командной строки."),
+            Some("ru")
+        );
+        assert_eq!(detect("Run it."), None);
+    }
+}
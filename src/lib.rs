@@ -0,0 +1,86 @@
+//! # Agent Skills Generator
+//!
+//! Crawls documentation sites and turns every page into an agent skill: a
+//! `SKILL.md` with YAML frontmatter (name, description, source URL)
+//! followed by the page's content as markdown.
+//!
+//! The `agent-skills-generator` binary is a thin layer over this crate.
+//! To crawl from your own tool, start with [`CrawlBuilder`]:
+//!
+//! ```no_run
+//! use agent_skills_generator::{CrawlBuilder, Config};
+//!
+//! # async fn crawl() -> anyhow::Result<()> {
+//! let report = CrawlBuilder::new("https://docs.example.com/guide/")
+//!     .config(Config::default())
+//!     .output("skills")
+//!     .max_pages(50)
+//!     .run()
+//!     .await?;
+//!
+//! println!("{} skills written", report.skills.len());
+//! for seed in &report.seeds {
+//!     if let Err(ref e) = seed.result {
+//!         eprintln!("{} failed: {:#}", seed.url, e);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Config`] carries every setting of the YAML config file, so
+//! [`Config::load`] reads an existing `.skills.yaml`. For finer control,
+//! [`Crawler`] crawls a single seed with shared state attached through its
+//! `with_*` methods, and [`Processor`] converts pages that were fetched
+//! elsewhere.
+//!
+//! Nothing in the library prompts or prints: progress is reported through
+//! [`tracing`] events, which are dropped unless the application installs a
+//! subscriber.
+
+pub mod admonitions;
+pub mod archive;
+pub mod auth;
+pub mod builder;
+pub mod cache;
+pub mod code_blocks;
+pub mod config;
+pub mod cookies;
+pub mod crawler;
+pub mod description;
+pub mod diff;
+pub mod doctor;
+pub mod estimate;
+pub mod events;
+pub mod frontmatter;
+pub mod graph;
+pub mod hooks;
+pub mod http;
+pub mod images;
+pub mod language;
+pub mod links;
+pub mod local;
+pub mod merge;
+pub mod naming;
+pub mod observer;
+pub mod outline;
+pub mod pdf;
+pub mod processor;
+pub mod rate_limit;
+pub mod references;
+pub mod robots;
+pub mod scope;
+pub mod sink;
+pub mod tables;
+pub mod tags;
+pub mod tokens;
+pub mod update;
+pub mod utils;
+pub mod version_check;
+pub mod watch;
+
+pub use builder::{CrawlBuilder, CrawlReport, ReportedSkill, SeedReport};
+pub use config::Config;
+pub use crawler::{CrawlStats, Crawler, StatsSnapshot};
+pub use observer::CrawlObserver;
+pub use processor::Processor;
@@ -0,0 +1,190 @@
+//! Link rewriting for the agent-skills-generator.
+//!
+//! Relative links (`../setup/install`, `./api`, `/docs`, `?tab=2`) mean
+//! nothing once a page's markdown lives in a skills directory, so every
+//! inline link and image destination is resolved against the page URL.
+//! Protocol-relative links (`//cdn.example.com/x`) take the page's scheme.
+//! Anchor-only links (`#section`) point within the page and are kept as they
+//! are, or reduced to their text with `drop_anchor_links`.
+//!
+//! The pages a page links to are collected the same way, for the crawl's
+//! link graph and size estimates.
+
+use regex::{Captures, Regex};
+use scraper::{Html, Selector};
+use std::collections::BTreeSet;
+use std::sync::LazyLock;
+
+/// Destination of an inline link or image: `](dest "title")`, the title
+/// being optional.
+static DESTINATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\]\(\s*<?([^\s)>]+)>?(\s+"[^"]*")?\s*\)"#)
+        .expect("Failed to compile link destination regex")
+});
+
+/// A link to an anchor of the same page: `[text](#section)`.
+static ANCHOR_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(!?)\[([^\]]*)\]\(\s*#[^\s)]*(\s+"[^"]*")?\s*\)"#)
+        .expect("Failed to compile anchor link regex")
+});
+
+/// A link, or with a leading `!` an image, and its destination.
+static LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(!?)\[[^\]]*\]\(\s*<?([^\s)>]+)>?"#).expect("Failed to compile link regex")
+});
+
+/// Resolves every relative link and image in `markdown` against
+/// `page_url`. Anchor-only links are left alone, or replaced by their text
+/// if `drop_anchors` is set.
+pub fn absolutize(markdown: &str, page_url: &str, drop_anchors: bool) -> String {
+    let Ok(base) = url::Url::parse(page_url) else {
+        return markdown.to_string();
+    };
+
+    let markdown = if drop_anchors {
+        // Images of an anchor are kept; only links are reduced to their text
+        ANCHOR_LINK.replace_all(markdown, |caps: &Captures| match &caps[1] {
+            "!" => caps[0].to_string(),
+            _ => caps[2].to_string(),
+        })
+    } else {
+        markdown.into()
+    };
+
+    DESTINATION
+        .replace_all(&markdown, |caps: &Captures| {
+            let destination = &caps[1];
+            if destination.starts_with('#') {
+                return caps[0].to_string();
+            }
+            match base.join(destination) {
+                Ok(url) => format!(
+                    "]({}{})",
+                    url,
+                    caps.get(2).map(|m| m.as_str()).unwrap_or_default()
+                ),
+                Err(_) => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// The pages the `<a href>` links of `html` point to: absolute http(s)
+/// URLs without fragments, leaving out `page_url` itself.
+pub fn html_links(html: &str, page_url: &str) -> BTreeSet<String> {
+    let anchor = Selector::parse("a[href]").expect("static selector");
+    let document = Html::parse_fragment(html);
+    let hrefs = document
+        .select(&anchor)
+        .filter_map(|a| a.value().attr("href"));
+    resolve_links(hrefs, page_url)
+}
+
+/// The pages the links of `markdown` point to, like [`html_links`].
+/// Images are left out.
+pub fn markdown_links(markdown: &str, page_url: &str) -> BTreeSet<String> {
+    let destinations = LINK
+        .captures_iter(markdown)
+        .filter(|caps| caps[1].is_empty())
+        .filter_map(|caps| caps.get(2).map(|m| m.as_str()));
+    resolve_links(destinations, page_url)
+}
+
+fn resolve_links<'a>(links: impl Iterator<Item = &'a str>, page_url: &str) -> BTreeSet<String> {
+    let Ok(mut base) = url::Url::parse(page_url) else {
+        return BTreeSet::new();
+    };
+    base.set_fragment(None);
+
+    let mut resolved = BTreeSet::new();
+    for link in links {
+        let Ok(mut url) = base.join(link.trim()) else {
+            continue;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            continue;
+        }
+        url.set_fragment(None);
+        if url != base {
+            resolved.insert(url.to_string());
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "https://docs.example.com/guide/setup/intro?lang=en";
+
+    #[test]
+    fn test_relative_links_become_absolute() {
+        let cases = [
+            ("../install", "https://docs.example.com/guide/install"),
+            ("./next", "https://docs.example.com/guide/setup/next"),
+            (
+                "next#usage",
+                "https://docs.example.com/guide/setup/next#usage",
+            ),
+            ("/api/client", "https://docs.example.com/api/client"),
+            (
+                "?lang=fr",
+                "https://docs.example.com/guide/setup/intro?lang=fr",
+            ),
+            ("//cdn.example.com/app.js", "https://cdn.example.com/app.js"),
+            ("https://other.dev/x", "https://other.dev/x"),
+            ("mailto:team@example.com", "mailto:team@example.com"),
+        ];
+        for (href, expected) in cases {
+            assert_eq!(
+                absolutize(&format!("See [the docs]({}).", href), PAGE, false),
+                format!("See [the docs]({}).", expected),
+                "{}",
+                href
+            );
+        }
+
+        assert_eq!(
+            absolutize("[![Logo](img/logo.png \"Home\")](/)", PAGE, false),
+            "[![Logo](https://docs.example.com/guide/setup/img/logo.png \"Home\")](https://docs.example.com/)"
+        );
+    }
+
+    #[test]
+    fn test_anchor_links() {
+        let markdown = "Jump to [usage](#usage)[^](#top) or read [next](next#usage).";
+        assert_eq!(
+            absolutize(markdown, PAGE, false),
+            "Jump to [usage](#usage)[^](#top) or read [next](https://docs.example.com/guide/setup/next#usage)."
+        );
+        assert_eq!(
+            absolutize(markdown, PAGE, true),
+            "Jump to usage^ or read [next](https://docs.example.com/guide/setup/next#usage)."
+        );
+    }
+
+    #[test]
+    fn test_page_links() {
+        let html = r##"<p><a href="../install">Install</a> <a href="next#usage">Next</a>
+            <a href="next">Next</a> <a href="#top">Top</a> <a href="?lang=en">Here</a>
+            <a href="mailto:team@example.com">Mail</a></p>"##;
+        assert_eq!(
+            html_links(html, PAGE),
+            BTreeSet::from([
+                "https://docs.example.com/guide/install".to_string(),
+                "https://docs.example.com/guide/setup/next".to_string(),
+            ])
+        );
+
+        let markdown =
+            "Read [install](../install) and [next](next#usage).\n\n![Diagram](img/flow.png)";
+        assert_eq!(
+            markdown_links(markdown, PAGE),
+            BTreeSet::from([
+                "https://docs.example.com/guide/install".to_string(),
+                "https://docs.example.com/guide/setup/next".to_string(),
+            ])
+        );
+    }
+}
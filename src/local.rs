@@ -0,0 +1,185 @@
+//! Local HTML files as crawl input.
+//!
+//! `crawl` and `single` accept a `file://` URL or a path on disk in place
+//! of an HTTP URL. A directory is walked for `.html` and `.htm` files,
+//! which go through the same pipeline as crawled pages under their
+//! `file://` URLs; other files are skipped. Skills are named after each
+//! file's path relative to the directory (see [`relative_path`]), so
+//! `docs/guide/install.html` becomes `guide-install` rather than a name
+//! spelling out the whole absolute path.
+
+use anyhow::Result;
+use spider::page::Page;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Extensions of the files read from a directory.
+const HTML_EXTENSIONS: [&str; 2] = ["html", "htm"];
+
+/// The absolute path a crawl input names: a `file://` URL, or a path that
+/// exists on disk. `None` for anything else, such as an HTTP URL.
+pub fn local_path(input: &str) -> Option<PathBuf> {
+    match Url::parse(input) {
+        Ok(url) if url.scheme() == "file" => url.to_file_path().ok(),
+        // A Windows drive letter parses as a one-letter scheme
+        Ok(url) if url.scheme().len() > 1 => None,
+        _ => {
+            let path = Path::new(input);
+            path.exists().then(|| std::path::absolute(path).ok())?
+        }
+    }
+}
+
+/// The directory skill names are relative to: `path` itself, or the
+/// directory holding it if it is a file.
+pub fn root_dir(path: &Path) -> PathBuf {
+    match path.is_dir() {
+        true => path.to_path_buf(),
+        false => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+    }
+}
+
+/// Path of the file at `url` relative to `root`, with `/` separators, like
+/// `guide/install.html`. `None` if `url` isn't a file under `root`.
+pub fn relative_path(url: &str, root: &Path) -> Option<String> {
+    let path = Url::parse(url).ok()?.to_file_path().ok()?;
+    let relative = path.strip_prefix(root).ok()?;
+    let segments: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Some(segments.join("/"))
+}
+
+/// The HTML files in `path` and its subdirectories, sorted by path, or
+/// `path` itself if it is a file. Hidden files and directories are left
+/// out.
+pub fn html_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs_err::read_dir(&dir)? {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                dirs.push(path);
+            } else if is_html_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `path` has an HTML extension.
+fn is_html_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        HTML_EXTENSIONS
+            .iter()
+            .any(|html| extension.eq_ignore_ascii_case(html))
+    })
+}
+
+/// `file://` URL of the absolute `path`.
+pub fn file_url(path: &Path) -> Result<String> {
+    Url::from_file_path(path)
+        .map(String::from)
+        .map_err(|_| anyhow::anyhow!("Can't make a file URL of {}", path.display()))
+}
+
+/// Reads the HTML file at `path` as if it had been crawled: a `200 OK`
+/// page at its `file://` URL with a `text/html` content type.
+pub fn read_page(path: &Path) -> Result<Page> {
+    let body = fs_err::read(path)?;
+    let url = file_url(path)?;
+
+    let mut headers = spider::reqwest::header::HeaderMap::new();
+    headers.insert(
+        spider::reqwest::header::CONTENT_TYPE,
+        spider::reqwest::header::HeaderValue::from_static("text/html; charset=utf-8"),
+    );
+    Ok(spider::page::build(
+        &url,
+        spider::utils::PageResponse {
+            content: Some(Box::new(body)),
+            headers: Some(headers),
+            status_code: spider::reqwest::StatusCode::OK,
+            ..Default::default()
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-skills-local-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_local_path() {
+        let dir = test_dir("path");
+        let url = file_url(&dir).unwrap();
+        assert!(url.starts_with("file:///"));
+
+        assert_eq!(local_path(&url), Some(dir.clone()));
+        assert_eq!(local_path(dir.to_str().unwrap()), Some(dir.clone()));
+        assert_eq!(local_path("https://docs.example.com/"), None);
+        assert_eq!(local_path("does/not/exist"), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_html_files() {
+        let dir = test_dir("files");
+        for file in [
+            "index.html",
+            "guide/install.HTM",
+            "guide/deep/faq.html",
+            "guide/notes.md",
+            "assets/logo.png",
+            ".cache/page.html",
+        ] {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "<html></html>").unwrap();
+        }
+
+        let files: Vec<String> = html_files(&dir)
+            .unwrap()
+            .iter()
+            .map(|file| relative_path(&file_url(file).unwrap(), &dir).unwrap())
+            .collect();
+        assert_eq!(
+            files,
+            ["guide/deep/faq.html", "guide/install.HTM", "index.html"]
+        );
+
+        // A file is its own list, named relative to its directory
+        let file = dir.join("guide/install.HTM");
+        assert_eq!(html_files(&file).unwrap(), std::slice::from_ref(&file));
+        assert_eq!(root_dir(&file), dir.join("guide"));
+        assert_eq!(root_dir(&dir), dir);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
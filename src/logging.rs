@@ -0,0 +1,135 @@
+//! Log output for the agent-skills-generator.
+//!
+//! Logs are compact, human-readable lines by default. With `--log-format
+//! json` (or `SKILLS_LOG_FORMAT=json`) every event is written as one JSON
+//! object per line instead, with its target and the spans it was recorded
+//! in, ready for a log aggregator:
+//!
+//! ```json
+//! {"timestamp":"2026-10-16T09:12:44.108Z","level":"INFO","fields":{"message":"Crawl complete: 42 visited, 40 processed, 0 unchanged, 2 skipped, 0 too small, 0 failed, 0 dropped, 0 unauthorized; ~61k tokens"},"target":"agent_skills_generator"}
+//! ```
+
+use tracing::Subscriber;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// How log lines are formatted (`--log-format`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Compact lines for humans.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "Unknown log format '{}'. Valid formats: text, json",
+                s
+            )),
+        }
+    }
+}
+
+/// Builds the subscriber writing the events `filter` lets through to
+/// `writer`, in `format`.
+pub fn subscriber<W>(
+    format: LogFormat,
+    filter: EnvFilter,
+    writer: W,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_thread_ids(false)
+        .with_writer(writer);
+
+    match format {
+        LogFormat::Text => Box::new(builder.with_target(false).finish()),
+        LogFormat::Json => Box::new(
+            builder
+                .json()
+                .with_target(true)
+                .with_current_span(true)
+                .with_span_list(true)
+                .finish(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+    use tracing::{info, info_span};
+
+    /// Log output kept in memory.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn log_line(format: LogFormat) -> String {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = subscriber(format, EnvFilter::new("info"), move || writer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = info_span!("crawl", seed = "https://docs.example.com");
+            let _entered = span.enter();
+            info!(pages = 3, "Crawl complete");
+        });
+
+        let output = buffer.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_json_format() {
+        let output = log_line(LogFormat::Json);
+        assert_eq!(output.lines().count(), 1, "{}", output);
+
+        let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "agent_skills_generator::logging::tests");
+        assert_eq!(line["fields"]["message"], "Crawl complete");
+        assert_eq!(line["fields"]["pages"], 3);
+        assert_eq!(line["span"]["name"], "crawl");
+        assert_eq!(line["span"]["seed"], "https://docs.example.com");
+        assert_eq!(line["spans"][0]["name"], "crawl");
+        assert!(line["timestamp"].is_string());
+    }
+
+    #[test]
+    fn test_text_format() {
+        let output = log_line(LogFormat::Text);
+        assert!(!output.starts_with('{'), "{}", output);
+        assert!(output.contains("Crawl complete"), "{}", output);
+        assert!(!output.contains("logging::tests"), "{}", output);
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("JSON".parse::<LogFormat>(), Ok(LogFormat::Json));
+        assert_eq!("text".parse::<LogFormat>(), Ok(LogFormat::Text));
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+}
@@ -21,6 +21,9 @@
 //! # Clean generated skills
 //! agent-skills-generator clean
 //!
+//! # Refresh existing skills from their source pages
+//! agent-skills-generator update
+//!
 //! # Validate configuration
 //! agent-skills-generator validate
 //! ```
@@ -38,232 +41,521 @@
 //! ```
 
 pub mod cli;
-pub mod config;
-pub mod crawler;
-pub mod processor;
-pub mod utils;
+pub mod init;
+pub mod logging;
+pub mod progress;
+
+use agent_skills_generator::{
+    archive, config, cookies, crawler, diff, doctor, estimate, events, frontmatter, graph, hooks,
+    http, language, local, merge, naming, observer, processor, rate_limit, robots, scope, tokens,
+    update, utils, version_check, watch,
+};
 
 use anyhow::{Context, Result};
-use cli::{Cli, Commands, DEFAULT_CONFIG};
-use config::{Action, Config, Rule, SkillsScope};
+use cli::{Cli, Commands};
+use config::{Config, SkillsScope};
 use crawler::{Crawler, clean_output_dir};
-use processor::Processor;
-use std::io::{self, Write};
-use tracing::{error, info, warn};
+use events::{CrawlReporter, OutputFormat};
+use logging::LogFormat;
+use processor::{PageContext, Processor};
+use rate_limit::RateLimiter;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::EnvFilter;
-use utils::{extract_domain_with_protocol, parse_url_pattern};
+use tracing_subscriber::util::SubscriberInitExt;
 
 /// Main entry point for the CLI application.
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     // Parse command-line arguments
     let cli = Cli::parse_args();
 
-    // Initialize logging
-    init_logging(&cli);
+    // Initialize logging, drawing around the crawl spinner if there is one
+    let progress = progress::CrawlProgress::new(show_progress(&cli));
+    init_logging(&cli, &progress);
+
+    // Opt-in release check, run alongside the command rather than before
+    // it; doctor runs its own
+    let doctor = matches!(cli.command, Commands::Doctor(_));
+    let version_check = (version_check_enabled(&cli) && !doctor).then(|| {
+        tokio::spawn(async {
+            match version_check::GithubReleases::new() {
+                Ok(source) => {
+                    version_check::notify_if_outdated(&source, version_check::CURRENT_VERSION).await
+                }
+                Err(e) => debug!("Version check failed: {:#}", e),
+            }
+        })
+    });
 
     // Execute the requested command
-    match &cli.command {
-        Commands::Crawl(args) => run_crawl(&cli, args).await,
+    let mut exit_code = ExitCode::SUCCESS;
+    let result = match &cli.command {
+        Commands::Crawl(args) => run_crawl(&cli, args, &progress).await,
         Commands::Clean(args) => run_clean(&cli, args).await,
+        Commands::Update(args) => run_update(&cli, args).await,
+        Commands::Diff(args) => run_diff(&cli, args).await.map(|changed| {
+            if changed {
+                exit_code = ExitCode::FAILURE;
+            }
+        }),
+        Commands::Merge(args) => run_merge(&cli, args),
+        Commands::Doctor(args) => run_doctor(&cli, args).await.map(|failed| {
+            if failed {
+                exit_code = ExitCode::FAILURE;
+            }
+        }),
         Commands::Validate(args) => run_validate(&cli, args),
         Commands::Single(args) => run_single(&cli, args).await,
-        Commands::Init(args) => run_init(args),
+        Commands::Init(args) => init::run_init(args, &cli.target, init::stdio_is_terminal()),
+        Commands::Completions(args) => {
+            Cli::write_completions(args.shell, &mut io::stdout());
+            Ok(())
+        }
+    };
+    progress.finish();
+
+    // Bounded by the check's own short timeout
+    if let Some(handle) = version_check {
+        let _ = handle.await;
     }
+
+    result.map(|()| exit_code)
+}
+
+/// Whether `--version-check` was passed or `version_check: true` is set in
+/// the config file.
+fn version_check_enabled(cli: &Cli) -> bool {
+    let path = cli.config_path();
+    cli.version_check
+        || (path.exists()
+            && Config::load_profile(&path, cli.profile.as_deref())
+                .is_ok_and(|config| config.version_check))
+}
+
+/// Whether to draw the crawl spinner: only for crawls that fetch pages,
+/// with human-readable output and logs on a terminal, and never with
+/// `--quiet`.
+fn show_progress(cli: &Cli) -> bool {
+    matches!(&cli.command, Commands::Crawl(args) if !args.dry_run || args.diff)
+        && !cli.quiet
+        && !cli.machine_output()
+        && cli.log_format == LogFormat::Text
+        && io::stdout().is_terminal()
 }
 
 /// Initialize the tracing subscriber for logging.
-fn init_logging(cli: &Cli) {
+fn init_logging(cli: &Cli, progress: &progress::CrawlProgress) {
     let level = cli.log_level();
 
     let filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level.to_string()));
 
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .with_target(false)
-        .with_thread_ids(false)
-        .init();
+    // Keep stdout clean for JSON output
+    let subscriber = if cli.machine_output() {
+        logging::subscriber(cli.log_format, filter, io::stderr)
+    } else {
+        logging::subscriber(cli.log_format, filter, progress.writer(io::stdout))
+    };
+    subscriber.init();
 }
 
 /// Run the crawl command.
-async fn run_crawl(cli: &Cli, args: &cli::CrawlArgs) -> Result<()> {
-    // Load configuration
-    let mut config = load_config(&cli.config)?;
+async fn run_crawl(
+    cli: &Cli,
+    args: &cli::CrawlArgs,
+    progress: &progress::CrawlProgress,
+) -> Result<()> {
+    let site_names = if args.all_sites {
+        let names = Config::site_names(cli.config_path(), cli.profile.as_deref())?;
+        if names.is_empty() {
+            anyhow::bail!(
+                "--all-sites needs a sites: section in {}",
+                cli.config_path().display()
+            );
+        }
+        names.into_iter().map(Some).collect()
+    } else {
+        vec![args.site.clone()]
+    };
 
-    // Apply command-line overrides
-    apply_cli_overrides(&mut config, cli);
+    let mut sites = Vec::new();
+    for site in site_names {
+        // Load configuration
+        let mut config = load_site_config(cli, site.as_deref())?;
 
-    if let Some(delay) = args.delay {
-        config.delay_ms = delay;
-    }
-    if let Some(depth) = args.depth {
-        config.max_depth = depth;
-    }
-    if args.subdomains {
-        config.subdomains = true;
-    }
+        // Apply command-line overrides
+        apply_cli_overrides(&mut config, cli);
 
-    // Determine output directory (CLI --output overrides resolve_output_path)
-    let output_dir = if let Some(ref output) = cli.output {
-        output.clone()
-    } else {
-        config.resolve_output_path()
-    };
+        args.apply_overrides(&mut config);
 
-    info!("Output directory: {}", output_dir.display());
+        // Each watch cycle only rewrites the pages that changed
+        if args.watch.is_some() {
+            config.incremental = true;
+        }
+
+        if args.diff && config.writer == config::SkillWriter::Jsonl {
+            anyhow::bail!(
+                "--diff compares the skill files on disk, which writer: jsonl doesn't write"
+            );
+        }
+
+        let urls = if args.urls.is_empty() {
+            config.urls.clone()
+        } else {
+            args.urls.clone()
+        };
+        if urls.is_empty() {
+            match site {
+                Some(ref site) => anyhow::bail!("Site '{}' has no urls to crawl", site),
+                None => anyhow::bail!("No URLs to crawl; pass them or set urls in the config"),
+            }
+        }
+
+        let output_dirs = output_dirs(cli, &config)?;
+        for output_dir in &output_dirs {
+            match site {
+                Some(ref site) => info!("Output directory of {}: {}", site, output_dir.display()),
+                None => info!("Output directory: {}", output_dir.display()),
+            }
+        }
+
+        sites.push(SiteCrawl {
+            name: site,
+            urls,
+            config,
+            output_dirs,
+        });
+    }
 
     if args.dry_run {
         info!("Dry run mode - no files will be written");
     }
 
-    // Process each URL - parse patterns and crawl
-    for url_input in &args.urls {
-        let (base_url, pattern) = parse_url_pattern(url_input);
+    match args.watch {
+        Some(minutes) => {
+            watch::Watch::new(Duration::from_secs(minutes * 60))
+                .run(|| crawl_sites(args, &sites, progress))
+                .await;
+            Ok(())
+        }
+        None => crawl_sites(args, &sites, progress).await.map(|_| ()),
+    }
+}
+
+/// One configured crawl: the top-level config, or an entry of `sites:`.
+struct SiteCrawl {
+    name: Option<String>,
+    urls: Vec<String>,
+    config: Config,
+    output_dirs: Vec<PathBuf>,
+}
+
+/// Crawls every site one after another. Returns the stats of all sites combined.
+async fn crawl_sites(
+    args: &cli::CrawlArgs,
+    sites: &[SiteCrawl],
+    progress: &progress::CrawlProgress,
+) -> Result<crawler::StatsSnapshot> {
+    let mut totals = crawler::StatsSnapshot::default();
+    for site in sites {
+        if let Some(ref name) = site.name {
+            info!("Site: {}", name);
+        }
+        totals += crawl_seeds(args, &site.urls, &site.config, &site.output_dirs, progress).await?;
+    }
+    Ok(totals)
+}
+
+/// Crawls every URL once, in one session: the seeds share their scope and
+/// the pages crawled, so a page reachable from several seeds is only
+/// fetched once. Returns the stats of all seeds combined.
+async fn crawl_seeds(
+    args: &cli::CrawlArgs,
+    urls: &[String],
+    config: &Config,
+    output_dirs: &[PathBuf],
+    progress: &progress::CrawlProgress,
+) -> Result<crawler::StatsSnapshot> {
+    let mut totals = crawler::StatsSnapshot::default();
+
+    // robots.txt Crawl-delay per host, fetched once and shared by all seeds
+    let honor_crawl_delay = config.respect_robots_txt && !args.ignore_crawl_delay;
+    let mut crawl_delays = HashMap::new();
+
+    // Every request of the run is paced per host and under one global rate
+    let rate_limiter = Arc::new(RateLimiter::from_config(config));
+    let robots_client =
+        http::build_http_client(config)?.with_rate_limiter(Arc::clone(&rate_limiter));
+
+    // One reporter for every seed so JSON output is never interleaved
+    let format = args.output_format();
+    let reporter = (format != OutputFormat::Text).then(|| Arc::new(CrawlReporter::stdout(format)));
+
+    // Shared by every crawler so seeds can't overwrite each other's skills
+    let skill_names =
+        Arc::new(naming::SkillNames::new(&config.targets()).with_group_by(config.group_by));
+
+    // Also shared, so no seed reports another's skills as removed
+    let skill_diff = args
+        .diff
+        .then(|| Arc::new(diff::CrawlDiff::new(&output_dirs[0], args.dry_run)));
+
+    // And one token budget, so max_total_tokens caps the whole run
+    let token_budget = Arc::new(tokens::TokenBudget::new(config.max_total_tokens));
+
+    // And one link graph, covering every seed
+    let link_graph = args
+        .graph
+        .as_ref()
+        .map(|_| Arc::new(graph::LinkGraph::new()));
+
+    // And the pages crawled, so no page is fetched from two seeds
+    let visited = Arc::new(crawler::VisitedUrls::new());
+    let mut seed_stats = Vec::new();
+    let mut failed_seeds = 0;
+
+    // Every seed is crawled in the scope of all of them
+    for (url_input, scope) in urls.iter().zip(scope::scope_all(config, urls)) {
+        let scope::CrawlScope {
+            base_url,
+            config: crawl_config,
+        } = scope;
 
         info!("Crawling: {} (base: {})", url_input, base_url);
 
-        // Auto-generate rules to scope crawling to the initial URL path
-        let crawl_config = if let Some(ref url_pattern) = pattern {
-            let mut crawl_config = config.clone();
-
-            // Get the domain to create a catch-all ignore rule
-            if let Some(domain) = extract_domain_with_protocol(&base_url) {
-                info!(
-                    "URL pattern detected. Allowing: {}, ignoring other paths on {}",
-                    url_pattern, domain
-                );
-
-                // Insert rules at the beginning (they take precedence)
-                // First: allow the exact base URL (for the starting page)
-                crawl_config.rules.insert(
-                    0,
-                    Rule {
-                        url: base_url.clone(),
-                        action: Action::Allow,
-                        content_type: None,
-                    },
-                );
-
-                // Second: allow the pattern (use ** for nested paths)
-                // Convert trailing /* to /** for recursive matching
-                let recursive_pattern = if url_pattern.ends_with("/*") {
-                    format!("{}**", &url_pattern[..url_pattern.len() - 1])
-                } else {
-                    url_pattern.clone()
-                };
-                crawl_config.rules.insert(
-                    1,
-                    Rule {
-                        url: recursive_pattern,
-                        action: Action::Allow,
-                        content_type: None,
-                    },
-                );
-
-                // Third: ignore everything else on this domain
-                crawl_config.rules.insert(
-                    2,
-                    Rule {
-                        url: format!("{}/**", domain),
-                        action: Action::Ignore,
-                        content_type: None,
-                    },
-                );
+        // Fan out from a docs-portal chooser page if an entry selector is configured
+        let mut seeds = vec![base_url.clone()];
+        if let Some(ref selector) = crawl_config.entry_selector {
+            match crawler::resolve_entry_seeds(&crawl_config, &base_url, selector).await {
+                Ok(derived) => {
+                    info!(
+                        "Derived {} seed(s) from entry page {}",
+                        derived.len(),
+                        base_url
+                    );
+                    seeds.extend(derived);
+                }
+                Err(e) => {
+                    error!("Failed to derive entry seeds from {}: {:?}", base_url, e);
+                }
             }
+        }
 
-            crawl_config
-        } else {
-            // No explicit pattern - auto-scope to the initial URL prefix
-            let mut crawl_config = config.clone();
-
-            // Only add scoping rules if we can extract the domain (valid URL)
-            if extract_domain_with_protocol(&base_url).is_some() {
-                // Normalize the base URL (ensure it ends with / for directory-style URLs)
-                let normalized_base = if base_url.ends_with('/') {
-                    base_url.clone()
-                } else {
-                    format!("{}/", base_url)
-                };
-
-                info!("Auto-scoping crawl to URL prefix: {}**", normalized_base);
-
-                // Allow the exact base URL
-                crawl_config.rules.insert(
-                    0,
-                    Rule {
-                        url: base_url.clone(),
-                        action: Action::Allow,
-                        content_type: None,
-                    },
-                );
-
-                // Allow all URLs under the base URL path
-                crawl_config.rules.insert(
-                    1,
-                    Rule {
-                        url: format!("{}**", normalized_base),
-                        action: Action::Allow,
-                        content_type: None,
-                    },
-                );
-
-                // Note: We don't add a domain-scope ignore rule here because:
-                // 1. The whitelist (allow rules) already restricts spider to matching URLs
-                // 2. should_crawl() returns false for URLs not matching any allow pattern
-                // 3. Adding a domain-scope ignore would conflict with user-defined ignore rules
+        if args.dry_run {
+            let mut seed_config = crawl_config.clone();
+            if honor_crawl_delay {
+                robots::apply_crawl_delay(
+                    &mut seed_config,
+                    &base_url,
+                    &robots_client,
+                    &mut crawl_delays,
+                )
+                .await;
             }
 
-            crawl_config
-        };
-
-        if args.dry_run {
             info!("Would crawl: {}", base_url);
+            info!("  Effective delay: {}ms", seed_config.delay_ms);
+            if seed_config.include_html {
+                info!("  Would also write content.html per skill");
+            }
+            for seed in seeds.iter().skip(1) {
+                info!("  Derived seed: {}", seed);
+            }
             info!("Active rules:");
             for (i, rule) in crawl_config.rules.iter().enumerate() {
                 info!("  {}. {} -> {:?}", i + 1, rule.url, rule.action);
             }
-            continue;
+            if args.estimate {
+                match estimate::estimate(&robots_client, &crawl_config, &base_url).await {
+                    Ok(estimate) => info!(
+                        "Estimated pages: {} in scope of {} found ({})",
+                        estimate.in_scope, estimate.found, estimate.source
+                    ),
+                    Err(e) => warn!("Failed to estimate the crawl of {}: {:#}", base_url, e),
+                }
+            }
+            if !args.diff {
+                continue;
+            }
         }
 
-        // Create crawler with the (possibly modified) config
-        let crawler = Crawler::new(crawl_config, output_dir.clone())?;
+        for seed in &seeds {
+            // Create crawler with the (possibly modified) config
+            let mut seed_config = crawl_config.clone();
+            if honor_crawl_delay {
+                robots::apply_crawl_delay(
+                    &mut seed_config,
+                    seed,
+                    &robots_client,
+                    &mut crawl_delays,
+                )
+                .await;
+            }
 
-        match crawler.crawl(&base_url).await {
-            Ok(stats) => {
-                info!("{}", stats.summary());
+            let mut crawler = Crawler::new(seed_config, output_dirs.to_vec())?
+                .with_names(Arc::clone(&skill_names));
+            if let Some(ref reporter) = reporter {
+                crawler = crawler.with_reporter(Arc::clone(reporter));
             }
-            Err(e) => {
-                error!("Failed to crawl {}: {:?}", base_url, e);
+            if let Some(ref skill_diff) = skill_diff {
+                crawler = crawler.with_diff(Arc::clone(skill_diff));
+            }
+            if let Some(ref link_graph) = link_graph {
+                crawler = crawler.with_link_graph(Arc::clone(link_graph));
+            }
+            crawler = crawler
+                .with_visited(Arc::clone(&visited))
+                .with_token_budget(Arc::clone(&token_budget))
+                .with_rate_limiter(Arc::clone(&rate_limiter))
+                .with_observer(Arc::new(observer::LogObserver));
+
+            let tracking = progress.track(seed, Arc::clone(crawler.stats()));
+            let result = crawler.crawl(seed).await;
+            drop(tracking);
+
+            match result {
+                Ok(stats) => {
+                    let stats = stats.snapshot();
+                    totals += stats;
+                    seed_stats.push((seed.clone(), stats));
+                }
+                Err(e) => {
+                    error!("Failed to crawl {}: {:?}", seed, e);
+                    failed_seeds += 1;
+                }
             }
         }
     }
 
-    Ok(())
+    if let Some(reporter) = reporter {
+        reporter.finish()?;
+    }
+
+    // One summary for the session, broken down by seed if there are several
+    if seed_stats.len() > 1 {
+        for (seed, stats) in &seed_stats {
+            info!("  {}: {}", seed, stats.summary());
+        }
+    }
+    if !seed_stats.is_empty() {
+        info!("Crawl complete: {}", totals.summary());
+    }
+
+    if totals.visited > 0 {
+        info!(
+            "Generated {} skills, ~{} tokens total",
+            totals.processed + totals.unchanged,
+            tokens::format_count(totals.tokens)
+        );
+    }
+
+    // Keep stdout clean for JSON output
+    if let Some(skill_diff) = skill_diff {
+        info!("Skill changes in {}:", skill_diff.output_dir().display());
+        let changes = skill_diff.changes()?;
+        if format == OutputFormat::Text {
+            diff::write_changes(&changes, &mut io::stdout())?;
+        } else {
+            diff::write_changes(&changes, &mut io::stderr())?;
+        }
+    }
+
+    if let (Some(path), Some(link_graph)) = (&args.graph, link_graph) {
+        // A dry run only crawls with --diff
+        if args.dry_run && !args.diff {
+            info!("Dry run - would write the link graph to {}", path.display());
+        } else {
+            link_graph.write(path)?;
+            let (pages, links) = link_graph.counts();
+            info!(
+                "Wrote link graph of {} pages and {} links to {}",
+                pages,
+                links,
+                path.display()
+            );
+        }
+    }
+
+    if let Some(ref archive) = args.archive {
+        if args.dry_run {
+            info!("Dry run - would archive skills to {}", archive.display());
+        } else {
+            let count = archive::write_skills(&output_dirs[0], archive)
+                .with_context(|| format!("Failed to write archive {}", archive.display()))?;
+            info!("Archived {} skills to {}", count, archive.display());
+        }
+    }
+
+    // Hooks announce a finished crawl, so a dry run or a failed seed has none
+    if failed_seeds > 0 {
+        warn!(
+            "Skipping post-crawl hooks: {} seed(s) failed to crawl",
+            failed_seeds
+        );
+    } else if !args.dry_run {
+        hooks::run(config, &totals, output_dirs).await;
+    }
+
+    Ok(totals)
 }
 
 /// Run the clean command.
 async fn run_clean(cli: &Cli, args: &cli::CleanArgs) -> Result<()> {
     // Load configuration to get output directory
-    let mut config = load_config_or_default(&cli.config);
+    let mut config = load_config_or_default(cli)?;
     apply_cli_overrides(&mut config, cli);
 
-    let output_dir = if let Some(ref output) = cli.output {
-        output.clone()
-    } else {
-        config.resolve_output_path()
+    let output_dirs: Vec<PathBuf> = output_dirs(cli, &config)?
+        .into_iter()
+        .filter(|output_dir| {
+            let exists = output_dir.exists();
+            if !exists {
+                info!("Output directory does not exist: {}", output_dir.display());
+            }
+            exists
+        })
+        .collect();
+    if output_dirs.is_empty() {
+        return Ok(());
+    }
+
+    let filter = crawler::CleanFilter {
+        pattern: update::skill_pattern(args.pattern.as_deref())?,
+        older_than: args.older_than,
     };
 
-    if !output_dir.exists() {
-        info!("Output directory does not exist: {}", output_dir.display());
+    let mut selected = Vec::new();
+    for output_dir in &output_dirs {
+        selected.extend(crawler::selected_skills(output_dir, &filter)?);
+    }
+    if args.dry_run {
+        for skill in &selected {
+            println!("{}", skill.path.display());
+        }
+        info!("Would remove {} skills", selected.len());
+        return Ok(());
+    }
+    if selected.is_empty() {
+        info!("No skills to clean");
         return Ok(());
     }
 
     // Confirm unless --force is specified
     if !args.force {
+        let listed: Vec<String> = output_dirs
+            .iter()
+            .map(|output_dir| output_dir.display().to_string())
+            .collect();
         print!(
-            "Are you sure you want to clean all skills in {}? [y/N] ",
-            output_dir.display()
+            "Are you sure you want to remove {} skills from {}? [y/N] ",
+            selected.len(),
+            listed.join(", ")
         );
         io::stdout().flush()?;
 
@@ -276,31 +568,193 @@ async fn run_clean(cli: &Cli, args: &cli::CleanArgs) -> Result<()> {
         }
     }
 
-    // Clean the directory
-    let count = clean_output_dir(&output_dir).await?;
+    // Clean the directories
+    let mut count = 0;
+    for output_dir in &output_dirs {
+        count += clean_output_dir(output_dir, &filter).await?;
+    }
     info!("Removed {} skill directories", count);
 
     Ok(())
 }
 
+/// Run the update command.
+async fn run_update(cli: &Cli, args: &cli::UpdateArgs) -> Result<()> {
+    let mut config = load_config_or_default(cli)?;
+    apply_cli_overrides(&mut config, cli);
+
+    let client = http::build_http_client(&config)?
+        .with_rate_limiter(Arc::new(RateLimiter::from_config(&config)));
+    let processor = Processor::new(&config)?;
+    let options = update::UpdateOptions {
+        pattern: args.pattern.clone(),
+        prune_missing: args.prune_missing,
+        incremental: config.incremental,
+    };
+
+    for output_dir in output_dirs(cli, &config)? {
+        let stats = update::update_skills(
+            &client,
+            &processor,
+            &config.response_headers,
+            &output_dir,
+            &options,
+        )
+        .await?;
+        info!("{}", stats.summary());
+    }
+
+    Ok(())
+}
+
+/// Run the diff command. Returns whether any skill would change.
+async fn run_diff(cli: &Cli, args: &cli::DiffArgs) -> Result<bool> {
+    let mut config = load_config_or_default(cli)?;
+    apply_cli_overrides(&mut config, cli);
+
+    let client = http::build_http_client(&config)?
+        .with_rate_limiter(Arc::new(RateLimiter::from_config(&config)));
+    let processor = Processor::new(&config)?;
+    let options = diff::DiffOptions {
+        url: args.url.clone(),
+        pattern: args.pattern.clone(),
+        stat: args.stat,
+        color: io::stdout().is_terminal(),
+    };
+
+    let mut stats = diff::DiffStats::default();
+    for output_dir in output_dirs(cli, &config)? {
+        let dir_stats = diff::diff_skills(
+            &client,
+            &processor,
+            &config.response_headers,
+            &output_dir,
+            &options,
+            &mut io::stdout(),
+        )
+        .await?;
+        stats.changed += dir_stats.changed;
+        stats.unchanged += dir_stats.unchanged;
+        stats.failed += dir_stats.failed;
+    }
+
+    if stats.failed > 0 {
+        anyhow::bail!("{} skills could not be diffed", stats.failed);
+    }
+    if args.stat {
+        println!("{} changed, {} unchanged", stats.changed, stats.unchanged);
+    }
+    Ok(stats.changed > 0)
+}
+
 /// Run the validate command.
 fn run_validate(cli: &Cli, args: &cli::ValidateArgs) -> Result<()> {
-    let mut config = load_config(&cli.config)?;
+    let mut config = load_config(cli)?;
     apply_cli_overrides(&mut config, cli);
+    config.validate()?;
+
+    let output_dirs = output_dirs(cli, &config)?;
+    for output_dir in &output_dirs {
+        utils::check_writable(output_dir)
+            .with_context(|| format!("Can't write skills to {}", output_dir.display()))?;
+    }
+    for warning in config.warnings() {
+        warn!("{}", warning);
+    }
+
+    validate_sites(cli)?;
 
     info!("Configuration is valid!");
 
+    // Skills from older runs may predate the frontmatter budget
+    for output_dir in &output_dirs {
+        for (skill_md, size) in
+            frontmatter::oversized_skills(output_dir, config.frontmatter_budget)?
+        {
+            warn!(
+                "Frontmatter of {} is {} bytes, over the {} byte budget; re-crawl to trim it",
+                skill_md.display(),
+                size,
+                config.frontmatter_budget
+            );
+        }
+    }
+
+    if !args.test_url.is_empty() {
+        let scoped = match args.base {
+            Some(ref base) => scope::scope(&config, base).config,
+            None => config.clone(),
+        };
+        for url in &args.test_url {
+            print_url_verdict(url, &scope::explain(&scoped, url)?);
+        }
+    }
+
     if args.show {
+        let targets: Vec<String> = config.targets().iter().map(ToString::to_string).collect();
+        let outputs: Vec<String> = output_dirs
+            .iter()
+            .map(|output_dir| output_dir.display().to_string())
+            .collect();
+
         println!("\n--- Parsed Configuration ---");
-        println!("Target: {}", config.target);
+        println!("Target: {}", targets.join(", "));
         println!("Scope: {}", config.scope);
-        println!("Output: {}", config.resolve_output_path().display());
+        println!("Output: {}", outputs.join(", "));
         println!("Flat: {}", config.flat);
+        println!("Output format: {}", config.output_format.name());
+        if config.max_total_tokens > 0 {
+            println!("Max total tokens: {}", config.max_total_tokens);
+        }
+        if !config.frontmatter.is_empty() {
+            let keys: Vec<&str> = config.frontmatter.keys().map(String::as_str).collect();
+            println!("Extra frontmatter: {}", keys.join(", "));
+        }
         println!("Delay: {}ms", config.delay_ms);
+        if let Some(rate) = config.max_requests_per_sec {
+            println!("Max requests per second: {}", rate);
+        }
         println!("Max Depth: {}", config.max_depth);
+        if config.max_pages > 0 {
+            println!("Max pages per seed: {}", config.max_pages);
+        }
         println!("Respect robots.txt: {}", config.respect_robots_txt);
         println!("Subdomains: {}", config.subdomains);
+        if !config.allowed_subdomains.is_empty() {
+            println!(
+                "Allowed subdomains: {}",
+                config.allowed_subdomains.join(", ")
+            );
+        }
+        // Hosts are relative to the start URL, so they need one to show
+        if let Some(start) = args.base.as_ref().or(args.test_url.first()) {
+            let base_url = scope::scope(&config, start).base_url;
+            if let Some(hosts) = scope::host_allowlist(&config, &base_url) {
+                println!("Hosts for {}: {}", base_url, hosts.join(", "));
+            }
+        }
         println!("Concurrency: {}", config.concurrency);
+        println!("Subscription buffer: {}", config.subscription_buffer);
+        println!(
+            "Proxy: {}",
+            http::ProxySettings::from_config(&config).describe()
+        );
+        if let Some(ref auth) = config.basic_auth {
+            println!("Basic auth: {} (password hidden)", auth.username);
+        }
+        if let Some(ref auth) = config.auth {
+            println!("Auth: {}", auth.describe());
+        }
+        if let Some(ref cookies) = config.cookies {
+            let jar = cookies::CookieJar::load(cookies)?;
+            println!("Cookies: {} loaded (values hidden)", jar.len());
+        }
+        if let Some(ref command) = config.post_crawl_command {
+            println!("Post-crawl command: {}", command);
+        }
+        if let Some(ref webhook_url) = config.webhook_url {
+            println!("Webhook: {}", http::redact_url(webhook_url));
+        }
         println!("Rules: {} defined", config.rules.len());
 
         for (i, rule) in config.rules.iter().enumerate() {
@@ -311,250 +765,291 @@ fn run_validate(cli: &Cli, args: &cli::ValidateArgs) -> Result<()> {
             "Remove selectors: {} defined",
             config.remove_selectors.len()
         );
+
+        if let Some(ref selector) = config.entry_selector {
+            println!(
+                "Entry selector: {} (max {} seeds)",
+                selector, config.max_entry_seeds
+            );
+        }
+
+        println!(
+            "Markdown strip patterns: {} defined",
+            config.markdown_strip_patterns.len()
+        );
+    }
+
+    if args.effective {
+        println!("\n--- Effective Configuration ---");
+        print!("{}", config.effective_yaml()?);
     }
 
     Ok(())
 }
 
-/// Run the single command - process a single URL.
-async fn run_single(cli: &Cli, args: &cli::SingleArgs) -> Result<()> {
-    let mut config = load_config_or_default(&cli.config);
+/// Run the merge command - combine all skills into one markdown file.
+fn run_merge(cli: &Cli, args: &cli::MergeArgs) -> Result<()> {
+    let mut config = load_config_or_default(cli)?;
     apply_cli_overrides(&mut config, cli);
 
-    let output_dir = if let Some(ref output) = cli.output {
-        output.clone()
+    let output_dir = &output_dirs(cli, &config)?[0];
+    let (merged, count) = merge::merge(output_dir, args.sort)?;
+    if count == 0 {
+        anyhow::bail!("No skills to merge in {}", output_dir.display());
+    }
+
+    match args.file {
+        Some(ref file) => {
+            fs_err::write(file, merged)?;
+            info!("Merged {} skills into {}", count, file.display());
+        }
+        None => print!("{}", merged),
+    }
+    Ok(())
+}
+
+/// Run the doctor command. Returns whether any check failed.
+async fn run_doctor(cli: &Cli, args: &cli::DoctorArgs) -> Result<bool> {
+    let mut report = doctor::Report::default();
+    let (check, mut config) = doctor::check_config(&cli.config_path(), cli.profile.as_deref());
+    report.push(check);
+    apply_cli_overrides(&mut config, cli);
+
+    match output_dirs(cli, &config) {
+        Ok(output_dirs) => {
+            for output_dir in &output_dirs {
+                report.push(doctor::check_output_dir(output_dir));
+            }
+        }
+        Err(e) => report.push(doctor::Check {
+            name: "output",
+            status: doctor::Status::Fail,
+            detail: format!("{:#}", e),
+        }),
+    }
+    report.push(doctor::check_home(config.scope));
+    report.push(match version_check::GithubReleases::new() {
+        Ok(source) => doctor::check_version(&source, version_check::CURRENT_VERSION).await,
+        Err(e) => doctor::Check {
+            name: "version",
+            status: doctor::Status::Fail,
+            detail: format!("{:#}", e),
+        },
+    });
+
+    if let Some(ref url) = args.url {
+        let client = http::build_http_client(&config)?;
+        for check in doctor::check_site(&config, &client, url).await {
+            report.push(check);
+        }
+    }
+
+    report.write(&mut io::stdout())?;
+    Ok(report.failed())
+}
+
+/// Prints how the rules treat a `validate --test-url` URL.
+fn print_url_verdict(url: &str, verdict: &scope::UrlVerdict) {
+    println!("\n{}", url);
+    match verdict.rule {
+        Some((i, ref rule)) => println!("  Rule: {}. {} -> {:?}", i + 1, rule.url, rule.action),
+        None => println!("  Rule: none matches"),
+    }
+    let whitelist = match verdict.whitelisted {
+        Some(true) => "admitted",
+        Some(false) => "rejected",
+        None => "not set",
+    };
+    let blacklist = if verdict.blacklisted {
+        "rejected"
     } else {
-        config.resolve_output_path()
+        "admitted"
     };
+    println!(
+        "  Spider whitelist: {}, blacklist: {}",
+        whitelist, blacklist
+    );
+    println!(
+        "  Verdict: {}",
+        if verdict.crawled { "crawl" } else { "skip" }
+    );
+}
 
-    info!("Processing single URL: {}", args.url);
+/// Run the single command - process a single URL.
+async fn run_single(cli: &Cli, args: &cli::SingleArgs) -> Result<()> {
+    let mut config = load_config_or_default(cli)?;
+    apply_cli_overrides(&mut config, cli);
+    args.apply_overrides(&mut config);
 
-    // Fetch the page
-    let client = reqwest::Client::builder()
-        .user_agent("AgentSkillsGenerator/1.0")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
+    let output_dirs = output_dirs(cli, &config)?;
 
-    let response = client
-        .get(&args.url)
-        .send()
-        .await
-        .with_context(|| format!("Failed to fetch URL: {}", args.url))?;
+    info!("Processing single URL: {}", args.url);
 
-    let html = response
-        .text()
-        .await
-        .with_context(|| format!("Failed to read response body from: {}", args.url))?;
+    // The page was asked for explicitly, so being excluded, thin content
+    // or another language only warns. The rules are for crawled URLs, so
+    // a local file is only checked for being a file.
+    let local_path = local::local_path(&args.url);
+    if let Some(ref path) = local_path {
+        anyhow::ensure!(
+            !path.is_dir(),
+            "{} is a directory; use crawl to process the HTML files in it",
+            path.display()
+        );
+        config.local_root = Some(local::root_dir(path));
+    } else if let Some(exclusion) = scope::exclusion(&config, &args.url)? {
+        warn!(
+            "{} is excluded by {}; a crawl would skip it",
+            args.url, exclusion
+        );
+    }
+    let min_content_chars = std::mem::take(&mut config.min_content_chars);
+    let languages = std::mem::take(&mut config.languages);
 
-    // Process the page
+    // Fetch the page, or read it from disk
     let processor = Processor::new(&config)?;
-    let processed = processor.process(&args.url, &html)?;
+    let (context, body) = match local_path {
+        Some(path) => {
+            let html = fs_err::read_to_string(&path)?;
+            (
+                PageContext::new(&local::file_url(&path)?),
+                processor::PageBody::Html(html),
+            )
+        }
+        None => {
+            let client = http::build_http_client(&config)?;
+            let response = client
+                .get_with_retries(&args.url, config.max_retries)
+                .await?;
+            // Named after where the page ended up if the URL redirected
+            let context = PageContext::new(response.url().as_str())
+                .with_requested_url(&args.url)
+                .with_headers(response.headers(), &config.response_headers);
+            let body = http::read_body(response, &processor, &context).await?;
+            (context, body)
+        }
+    };
+
+    // Process the page
+    let mut processed = match processor.process_body(&context, &body) {
+        Ok(processed) => processed,
+        Err(e) if e.downcast_ref::<processor::SkippedPage>().is_some() => {
+            info!("{:#}", e);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let chars = processed.markdown_content.chars().count();
+    if chars < min_content_chars {
+        warn!(
+            "{} has only {} characters of content, under min_content_chars ({}); a crawl would skip it",
+            context.url, chars, min_content_chars
+        );
+    }
+    if let Some(ref language) = processed.metadata.language
+        && !languages.is_empty()
+        && !language::matches(&languages, language)
+    {
+        warn!(
+            "{} is in {}, which isn't in languages; a crawl would skip it",
+            context.url, language
+        );
+    }
+
+    // Named per target as a crawl would, within each target's constraints
+    let targets = config.targets();
+    let mapping = naming::SkillNames::new(&targets)
+        .with_group_by(config.group_by)
+        .assign_with_fallback(
+            &processed.metadata.skill_name,
+            processed.metadata.fallback_skill_name.as_deref(),
+            &processed.metadata.url,
+        );
+    let rename = |processed: &mut processor::ProcessedPage, target| -> Result<()> {
+        if let Some(name) = mapping.name_for(target)
+            && name != processed.metadata.skill_name
+        {
+            processor.rename(processed, name)?;
+        }
+        Ok(())
+    };
 
     if args.stdout {
+        rename(&mut processed, targets[0])?;
+
         // Output to stdout
         println!("--- SKILL.md ---");
         println!("{}", processed.skill_md);
         println!("\n--- content.md ---");
         println!("{}", processed.markdown_content);
+    } else if args.dry_run {
+        for (output_dir, &target) in output_dirs.iter().zip(&targets) {
+            rename(&mut processed, target)?;
+            let dir = processor.group_dir(output_dir, &processed.metadata.url);
+            let path = processor.skill_md_path(&dir, &processed.metadata.skill_name);
+            let existed = path.is_file();
+            let changed = diff::CrawlDiff::new(output_dir, true)
+                .record(&path, &processed.skill_md)
+                .await?;
+            let status = match (existed, changed) {
+                (false, _) => "new",
+                (true, true) => "changed",
+                (true, false) => "unchanged",
+            };
+            println!("Would write: {} ({})", path.display(), status);
+        }
     } else {
-        // Write to disk
-        fs_err::tokio::create_dir_all(&output_dir).await?;
-        let skill_dir = processor.write_to_disk(&processed, &output_dir).await?;
-        info!("Written to: {}", skill_dir.display());
+        processor.download_images(&mut processed).await?;
+
+        // Write to disk, once per target
+        for (output_dir, &target) in output_dirs.iter().zip(&targets) {
+            rename(&mut processed, target)?;
+            let output_dir = processor.group_dir(output_dir, &processed.metadata.url);
+            fs_err::tokio::create_dir_all(&output_dir).await?;
+            let written = processor.write_to_disk(&processed, &output_dir).await?;
+            if written.changed {
+                info!("Written to: {}", written.skill_dir.display());
+            } else {
+                info!("Unchanged: {}", written.skill_dir.display());
+            }
+        }
     }
 
     Ok(())
 }
 
-/// Run the init command - create a new configuration file.
-fn run_init(args: &cli::InitArgs) -> Result<()> {
-    if args.path.exists() && !args.force {
-        anyhow::bail!(
-            "Configuration file already exists: {}. Use --force to overwrite.",
-            args.path.display()
-        );
-    }
-
-    // If --no-interactive, use default config
-    if args.no_interactive {
-        fs_err::write(&args.path, DEFAULT_CONFIG).with_context(|| {
-            format!(
-                "Failed to write configuration file: {}",
-                args.path.display()
-            )
-        })?;
-
-        info!("Created configuration file: {}", args.path.display());
-        info!("Edit this file to customize crawling behavior, then run:");
-        info!("  agent-skills-generator crawl <URL>");
-
-        return Ok(());
+/// Validates each entry of `sites:` on its own, since each is its own crawl.
+fn validate_sites(cli: &Cli) -> Result<()> {
+    for site in Config::site_names(cli.config_path(), cli.profile.as_deref())? {
+        let mut site_config = load_site_config(cli, Some(&site))?;
+        apply_cli_overrides(&mut site_config, cli);
+        site_config
+            .validate()
+            .with_context(|| format!("Invalid site '{}'", site))?;
+        for output_dir in output_dirs(cli, &site_config)? {
+            utils::check_writable(&output_dir).with_context(|| {
+                format!(
+                    "Can't write skills of site '{}' to {}",
+                    site,
+                    output_dir.display()
+                )
+            })?;
+        }
+        for warning in site_config.warnings() {
+            warn!("{}: {}", site, warning);
+        }
     }
-
-    // Interactive mode
-    let config_content = run_interactive_init()?;
-
-    fs_err::write(&args.path, &config_content).with_context(|| {
-        format!(
-            "Failed to write configuration file: {}",
-            args.path.display()
-        )
-    })?;
-
-    info!("Created configuration file: {}", args.path.display());
-    info!("Run the following command to start crawling:");
-    info!("  agent-skills-generator crawl <URL>");
-
     Ok(())
 }
 
-/// Run interactive initialization prompts and return the generated YAML config.
-fn run_interactive_init() -> Result<String> {
-    use config::{SkillsScope, SkillsTarget};
-    use inquire::{Select, Text};
-
-    // Target IDE selection
-    let target_options = [
-        ("Custom (specify output path)", SkillsTarget::Custom),
-        ("GitHub Copilot", SkillsTarget::GithubCopilot),
-        ("Claude Code", SkillsTarget::ClaudeCode),
-        ("Cursor", SkillsTarget::Cursor),
-        ("Antigravity (Gemini)", SkillsTarget::Antigravity),
-        ("OpenAI Codex", SkillsTarget::OpenAICodex),
-        ("OpenCode", SkillsTarget::OpenCode),
-    ];
-
-    let target_names: Vec<&str> = target_options.iter().map(|(name, _)| *name).collect();
-    let target_idx = Select::new("Select target IDE/agent:", target_names)
-        .with_help_message("Choose where your skills will be installed")
-        .prompt()
-        .map_err(|e| anyhow::anyhow!("Failed to get target selection: {}", e))?;
-
-    let target = target_options
-        .iter()
-        .find(|(name, _)| *name == target_idx)
-        .map(|(_, t)| *t)
-        .unwrap_or(SkillsTarget::Custom);
-
-    // Scope selection
-    let scope_options = [
-        (
-            "Project (install to current directory)",
-            SkillsScope::Project,
-        ),
-        ("User (install to home directory)", SkillsScope::User),
-    ];
-
-    let scope_names: Vec<&str> = scope_options.iter().map(|(name, _)| *name).collect();
-    let scope_idx = Select::new("Install skills at project or user level?", scope_names)
-        .with_help_message("Project-level is recommended for team collaboration")
-        .prompt()
-        .map_err(|e| anyhow::anyhow!("Failed to get scope selection: {}", e))?;
-
-    let scope = scope_options
-        .iter()
-        .find(|(name, _)| *name == scope_idx)
-        .map(|(_, s)| *s)
-        .unwrap_or(SkillsScope::Project);
-
-    // Output path (only for custom target)
-    let output = if matches!(target, SkillsTarget::Custom) {
-        Text::new("Output directory:")
-            .with_default(".agent/skills")
-            .with_help_message("Where to store generated skill files")
-            .prompt()
-            .map_err(|e| anyhow::anyhow!("Failed to get output path: {}", e))?
-    } else {
-        ".agent/skills".to_string()
-    };
-
-    // Crawl settings
-    let delay_ms = Text::new("Request delay in milliseconds:")
-        .with_default("100")
-        .with_help_message("Delay between requests for polite crawling")
-        .prompt()
-        .map_err(|e| anyhow::anyhow!("Failed to get delay: {}", e))?
-        .parse::<u64>()
-        .unwrap_or(100);
-
-    let max_depth = Text::new("Maximum crawl depth:")
-        .with_default("25")
-        .with_help_message("How deep to follow links from the starting URL")
-        .prompt()
-        .map_err(|e| anyhow::anyhow!("Failed to get max depth: {}", e))?
-        .parse::<usize>()
-        .unwrap_or(25);
-
-    let concurrency = Text::new("Concurrency limit:")
-        .with_default("4")
-        .with_help_message("Number of parallel requests")
-        .prompt()
-        .map_err(|e| anyhow::anyhow!("Failed to get concurrency: {}", e))?
-        .parse::<usize>()
-        .unwrap_or(4);
-
-    // Generate YAML configuration
-    let config_yaml = format!(
-        r##"# Agent Skills Generator Configuration
-# See https://github.com/agentskills/agentskills for documentation
-
-# Target IDE/agent for skills generation
-# Supported targets: github-copilot, claude-code, cursor, antigravity, openai-codex, opencode, custom
-target: {}
-
-# Scope for skills installation
-# - project: Install to project directory (e.g., .cursor/skills/)
-# - user: Install to user home directory (e.g., ~/.cursor/skills/)
-scope: {}
-
-# Output directory for generated skills (only used when target is "custom")
-output: {}
-
-# Create flat directory structure (no subdirectories)
-flat: false
-
-# Delay between requests in milliseconds (polite crawling)
-delay_ms: {}
-
-# Maximum crawl depth
-max_depth: {}
-
-# Request timeout in seconds
-request_timeout_secs: 30
-
-# Respect robots.txt
-respect_robots_txt: true
-
-# Allow subdomains
-subdomains: false
-
-# Concurrency limit for parallel page processing
-concurrency: {}
-
-# URL filtering rules (evaluated in order)
-rules:
-  # Example: Allow only documentation pages
-  # - url: "*/docs/*"
-  #   action: allow
-
-  # Example: Ignore API internals
-  # - url: "*/api/internal/*"
-  #   action: ignore
-
-# CSS selectors for elements to remove from content
-# These are already included by default, add more if needed:
-# remove_selectors:
-#   - ".custom-sidebar"
-#   - "#ad-container"
-"##,
-        target, scope, output, delay_ms, max_depth, concurrency
-    );
-
-    Ok(config_yaml)
+/// Load configuration from file, with the `--profile` merged over it.
+fn load_config(cli: &Cli) -> Result<Config> {
+    load_site_config(cli, None)
 }
 
-/// Load configuration from file.
-fn load_config(path: &std::path::Path) -> Result<Config> {
+/// Load configuration from file, with the `--profile` and then the named
+/// entry of `sites:` merged over it.
+fn load_site_config(cli: &Cli, site: Option<&str>) -> Result<Config> {
+    let path = &cli.config_path();
     if !path.exists() {
         anyhow::bail!(
             "Configuration file not found: {}. Run 'agent-skills-generator init' to create one.",
@@ -562,37 +1057,63 @@ fn load_config(path: &std::path::Path) -> Result<Config> {
         );
     }
 
-    Config::load(path)
+    Config::load_site(path, cli.profile.as_deref(), site)
 }
 
 /// Load configuration from file, or return default if file doesn't exist.
-fn load_config_or_default(path: &std::path::Path) -> Config {
+/// A `--profile` needs the file, so it is loaded strictly then.
+fn load_config_or_default(cli: &Cli) -> Result<Config> {
+    if cli.profile.is_some() {
+        return load_config(cli);
+    }
+
+    let path = &cli.config_path();
     if path.exists() {
         match Config::load(path) {
-            Ok(config) => config,
+            Ok(config) => Ok(config),
             Err(e) => {
                 warn!("Failed to load config, using defaults: {:?}", e);
-                Config::default()
+                Ok(Config::default())
             }
         }
     } else {
-        Config::default()
+        Ok(Config::default())
+    }
+}
+
+/// Output directory of each configured target. `--output` replaces the
+/// resolved directory, which only makes sense for a single target.
+fn output_dirs(cli: &Cli, config: &Config) -> Result<Vec<PathBuf>> {
+    match cli.output {
+        Some(_) if config.targets().len() > 1 => anyhow::bail!(
+            "--output can't be used with several targets; each target is written to its own directory"
+        ),
+        Some(ref output) => Ok(vec![output.clone()]),
+        None => Ok(config.resolve_output_paths()),
     }
 }
 
 /// Apply CLI overrides to configuration.
 ///
 /// This applies the following CLI flags to the configuration:
-/// - `--target`: Sets the target IDE/agent
+/// - `--target`: Sets the target IDE/agent(s)
 /// - `--user`: Sets the scope to user-level
+/// - `--flat`: Writes `<name>.md` files instead of skill directories
+/// - `--format`: Sets the format skills are written in
 fn apply_cli_overrides(config: &mut Config, cli: &Cli) {
     // Apply target override
-    if let Some(target) = cli.target {
-        config.target = target;
-    }
+    config.set_targets(&cli.target);
 
     // Apply user-level scope override
     if cli.user_level {
         config.scope = SkillsScope::User;
     }
+
+    if cli.flat {
+        config.flat = true;
+    }
+
+    if let Some(format) = cli.format {
+        config.output_format = format;
+    }
 }
@@ -0,0 +1,257 @@
+//! The `merge` command of the agent-skills-generator.
+//!
+//! Some agents work best with one large knowledge file rather than a
+//! directory of skills. `merge` concatenates every SKILL.md in the output
+//! directory into a single markdown file:
+//!
+//! ```markdown
+//! ---
+//! skills: 2
+//! owner: docs-team
+//! ---
+//!
+//! # Contents
+//!
+//! - [api-routes](#api-routes): Routes map URLs to handlers.
+//! - [docs-install](#docs-install): Install the CLI.
+//!
+//! # api-routes
+//!
+//! Source: https://docs.example.com/api/routes
+//!
+//! ## Routing
+//! ...
+//! ```
+//!
+//! Frontmatter fields every skill shares (from the config's `frontmatter:`
+//! map) move to the top block; names, descriptions and source URLs go into
+//! the table of contents and section headers. Each skill's headings are
+//! moved down a level to nest under its section, and the reference files of
+//! split skills are appended to it. Skills written as `skill.json` or
+//! `skill.txt` have no frontmatter and are skipped.
+
+use crate::frontmatter::{self, Frontmatter};
+use crate::outline::{is_fence, is_heading};
+use crate::utils::skill_files;
+use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+use tracing::warn;
+
+/// Order of the skills in the merged file (`--sort`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeOrder {
+    /// By skill name.
+    #[default]
+    Name,
+    /// By source URL, so pages of the same section stay together.
+    Url,
+}
+
+impl std::str::FromStr for MergeOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "name" => Ok(Self::Name),
+            "url" => Ok(Self::Url),
+            _ => Err(format!(
+                "Unknown sort order '{}'. Valid orders: name, url",
+                s
+            )),
+        }
+    }
+}
+
+/// A skill read for merging.
+struct MergedSkill {
+    frontmatter: Frontmatter,
+    /// SKILL.md after the frontmatter, followed by its reference files.
+    body: String,
+}
+
+/// Merges the markdown skills in `output_dir` into one file. Returns its
+/// content and how many skills it holds.
+pub fn merge(output_dir: &Path, order: MergeOrder) -> Result<(String, usize)> {
+    let mut skills = Vec::new();
+    for skill in skill_files(output_dir)? {
+        let content = fs_err::read_to_string(&skill.skill_md)?;
+        let Some(frontmatter) = frontmatter::parse(&content)
+            .with_context(|| format!("Failed to read {}", skill.skill_md.display()))?
+        else {
+            warn!("Skipping {}: no frontmatter", skill.skill_md.display());
+            continue;
+        };
+        let yaml_len = frontmatter::extract(&content).unwrap_or_default().len();
+        let mut body = content[yaml_len + "---\n---\n".len()..].trim().to_string();
+
+        for reference in &frontmatter.metadata.references {
+            let path = skill.path.join(reference);
+            match fs_err::read_to_string(&path) {
+                Ok(part) => {
+                    body.push_str("\n\n");
+                    body.push_str(part.trim());
+                }
+                Err(e) => warn!("Skipping reference file: {}", e),
+            }
+        }
+        skills.push(MergedSkill { frontmatter, body });
+    }
+
+    match order {
+        MergeOrder::Name => skills.sort_by(|a, b| a.frontmatter.name.cmp(&b.frontmatter.name)),
+        MergeOrder::Url => skills.sort_by(|a, b| {
+            (&a.frontmatter.metadata.url, &a.frontmatter.name)
+                .cmp(&(&b.frontmatter.metadata.url, &b.frontmatter.name))
+        }),
+    }
+
+    Ok((render(&skills)?, skills.len()))
+}
+
+/// Renders the merged file: shared frontmatter, contents, then each skill.
+fn render(skills: &[MergedSkill]) -> Result<String> {
+    let mut top = Mapping::new();
+    top.insert("skills".into(), skills.len().into());
+    for (key, value) in shared_fields(skills) {
+        top.insert(key.into(), value);
+    }
+    let mut merged = format!(
+        "---\n{}---\n\n# Contents\n\n",
+        serde_yaml::to_string(&top).context("Failed to serialize frontmatter")?
+    );
+
+    for skill in skills {
+        let name = &skill.frontmatter.name;
+        merged.push_str(&format!("- [{}](#{})", name, name));
+        if !skill.frontmatter.description.is_empty() {
+            merged.push_str(&format!(": {}", skill.frontmatter.description));
+        }
+        merged.push('\n');
+    }
+
+    for skill in skills {
+        merged.push_str(&format!("\n# {}\n\n", skill.frontmatter.name));
+        if !skill.frontmatter.metadata.url.is_empty() {
+            merged.push_str(&format!("Source: {}\n\n", skill.frontmatter.metadata.url));
+        }
+        merged.push_str(&demote_headings(&skill.body));
+        merged.push('\n');
+    }
+
+    Ok(merged)
+}
+
+/// Extra frontmatter fields with the same value in every skill.
+fn shared_fields(skills: &[MergedSkill]) -> Vec<(String, Value)> {
+    let Some((first, rest)) = skills.split_first() else {
+        return Vec::new();
+    };
+    first
+        .frontmatter
+        .extra
+        .iter()
+        .filter(|(key, value)| {
+            rest.iter()
+                .all(|skill| skill.frontmatter.extra.get(*key) == Some(*value))
+        })
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// Moves every heading outside code blocks one level down, up to `######`.
+fn demote_headings(markdown: &str) -> String {
+    let mut fenced = false;
+    markdown
+        .lines()
+        .map(|line| {
+            if is_fence(line.trim_start()) {
+                fenced = !fenced;
+            }
+            if !fenced && is_heading(line) && !line.starts_with("######") {
+                format!("#{}", line)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_skill(dir: &Path, name: &str, url: &str, extra: &str, body: &str) {
+        std::fs::create_dir_all(dir.join(name)).unwrap();
+        std::fs::write(
+            dir.join(name).join("SKILL.md"),
+            format!(
+                "---\nname: {name}\ndescription: About {name}.\n{extra}metadata:\n  url: {url}\n---\n\n{body}\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_merge() {
+        let output_dir = std::env::temp_dir().join(format!("merge-test-{}", std::process::id()));
+        write_skill(
+            &output_dir,
+            "docs-install",
+            "https://docs.example.com/install",
+            "owner: docs-team\nsource: https://docs.example.com/install\n",
+            "# Install\n\nRun the installer.\n\n## Linux\n\n```sh\n# not a heading\n```",
+        );
+        write_skill(
+            &output_dir,
+            "api-routes",
+            "https://docs.example.com/zz/routes",
+            "owner: docs-team\nsource: https://docs.example.com/zz/routes\n",
+            "# Routes\n\nThe full page is split into 1 reference files.",
+        );
+        std::fs::create_dir_all(output_dir.join("api-routes/references")).unwrap();
+        let skill_md = output_dir.join("api-routes/SKILL.md");
+        let content = std::fs::read_to_string(&skill_md)
+            .unwrap()
+            .replace("  url:", "  references:\n  - references/part-01.md\n  url:");
+        std::fs::write(&skill_md, content).unwrap();
+        std::fs::write(
+            output_dir.join("api-routes/references/part-01.md"),
+            "# Routes (part 1)\n\nRoutes map URLs to handlers.",
+        )
+        .unwrap();
+        // Not skills
+        std::fs::create_dir_all(output_dir.join("drafts")).unwrap();
+        std::fs::write(output_dir.join(".crawl-cache.json"), "{}").unwrap();
+
+        let (merged, count) = merge(&output_dir, MergeOrder::Name).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(
+            merged,
+            "---\nskills: 2\nowner: docs-team\n---\n\n\
+             # Contents\n\n\
+             - [api-routes](#api-routes): About api-routes.\n\
+             - [docs-install](#docs-install): About docs-install.\n\
+             \n# api-routes\n\n\
+             Source: https://docs.example.com/zz/routes\n\n\
+             ## Routes\n\nThe full page is split into 1 reference files.\n\n\
+             ## Routes (part 1)\n\nRoutes map URLs to handlers.\n\
+             \n# docs-install\n\n\
+             Source: https://docs.example.com/install\n\n\
+             ## Install\n\nRun the installer.\n\n### Linux\n\n```sh\n# not a heading\n```\n"
+        );
+
+        let (merged, _) = merge(&output_dir, MergeOrder::Url).unwrap();
+        assert!(merged.find("# docs-install\n").unwrap() < merged.find("# api-routes\n").unwrap());
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_merge_order() {
+        assert_eq!("URL".parse::<MergeOrder>(), Ok(MergeOrder::Url));
+        assert_eq!("name".parse::<MergeOrder>(), Ok(MergeOrder::Name));
+        assert!("date".parse::<MergeOrder>().is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Skill name allocation for the agent-skills-generator.
+//!
+//! The processor derives a canonical skill name from each page's URL or
+//! title (`skill_name_source`). Before a skill is written, [`SkillNames`]
+//! turns that into the final directory name for every output target:
+//! 1. The canonical name is truncated to the target's [`NameConstraints`]
+//! 2. If a different page already holds that name in the target, the
+//!    page's fallback name is tried instead, if it has one
+//!    (`title_fallback_url` names pages after their URL then)
+//! 3. If that is taken too, an 8-character hash of the page URL is
+//!    appended; titles repeat far more often than paths, so should the
+//!    hashed name be taken as well, the hash is salted until it is free
+//!
+//! Collisions are tracked per target, so a suffix is only added in the
+//! targets where names actually clash, and per `group_by` directory, so
+//! pages in different groups may share a name. Every page whose final name differs
+//! from its canonical name is recorded in a [`NameMapping`] so a skill can be
+//! traced across outputs.
+
+use crate::config::{GroupBy, SkillsTarget};
+use crate::utils::{MAX_SKILL_NAME_LENGTH, truncate_at_word_boundary};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Length of the URL hash appended to disambiguate colliding names.
+const HASH_SUFFIX_LENGTH: usize = 8;
+
+/// Naming rules a target imposes on skill directory names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameConstraints {
+    /// Maximum name length in bytes.
+    pub max_length: usize,
+}
+
+impl Default for NameConstraints {
+    fn default() -> Self {
+        Self {
+            max_length: MAX_SKILL_NAME_LENGTH,
+        }
+    }
+}
+
+impl SkillsTarget {
+    /// Returns the naming rules for this target's skill directories.
+    ///
+    /// Every target currently follows the Agent Skills limit of 64
+    /// characters; targets with stricter rules override it here.
+    pub fn name_constraints(&self) -> NameConstraints {
+        NameConstraints::default()
+    }
+}
+
+/// How a page's canonical skill name maps onto each target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NameMapping {
+    /// The name the processor derived for the page.
+    pub canonical: String,
+    /// The page the skill was generated from.
+    pub url: String,
+    /// Final name per target.
+    pub targets: BTreeMap<String, String>,
+}
+
+/// Names already handed out in one target.
+#[derive(Debug)]
+struct TargetNames {
+    target: SkillsTarget,
+    constraints: NameConstraints,
+    /// Group and final name -> normalized URL of the page holding it.
+    owners: HashMap<(String, String), String>,
+}
+
+/// Allocates collision-free skill names across targets. Shared between all
+/// crawlers of a run so pages from different seeds can't clash either.
+#[derive(Debug)]
+pub struct SkillNames {
+    targets: Mutex<Vec<TargetNames>>,
+    /// Names are only unique within the group of a page.
+    group_by: GroupBy,
+}
+
+impl SkillNames {
+    /// Creates an allocator for `targets`, each with its own constraints.
+    pub fn new(targets: &[SkillsTarget]) -> Self {
+        Self::with_constraints(
+            targets
+                .iter()
+                .map(|target| (*target, target.name_constraints())),
+        )
+    }
+
+    /// Creates an allocator with explicit constraints per target.
+    pub fn with_constraints(
+        targets: impl IntoIterator<Item = (SkillsTarget, NameConstraints)>,
+    ) -> Self {
+        let targets = targets
+            .into_iter()
+            .map(|(target, constraints)| TargetNames {
+                target,
+                constraints,
+                owners: HashMap::new(),
+            })
+            .collect();
+
+        Self {
+            targets: Mutex::new(targets),
+            group_by: GroupBy::None,
+        }
+    }
+
+    /// Tracks names per `group_by` directory instead of across the output.
+    pub fn with_group_by(mut self, group_by: GroupBy) -> Self {
+        self.group_by = group_by;
+        self
+    }
+
+    /// Returns the final name of `url`'s skill in every target, in target
+    /// order. Asking again for the same page returns the same names.
+    pub fn assign(&self, canonical: &str, url: &str) -> NameMapping {
+        self.assign_with_fallback(canonical, None, url)
+    }
+
+    /// Like [`Self::assign`], but a target where `canonical` is taken gives
+    /// the page `fallback` if that is free, before resorting to a hash
+    /// suffix.
+    pub fn assign_with_fallback(
+        &self,
+        canonical: &str,
+        fallback: Option<&str>,
+        url: &str,
+    ) -> NameMapping {
+        let key = normalize_url(url);
+        let group = self.group_by.group(url).unwrap_or_default();
+        let mut targets = self.targets.lock().unwrap_or_else(|e| e.into_inner());
+
+        let names = targets
+            .iter_mut()
+            .map(|names| {
+                let name = names.allocate(&group, canonical, fallback, &key);
+                (names.target.to_string(), name)
+            })
+            .collect();
+
+        NameMapping {
+            canonical: canonical.to_string(),
+            url: url.to_string(),
+            targets: names,
+        }
+    }
+}
+
+impl NameMapping {
+    /// Whether any target got a name other than the canonical one.
+    pub fn is_renamed(&self) -> bool {
+        self.targets.values().any(|name| *name != self.canonical)
+    }
+
+    /// Final name in `target`, if the allocator knows it.
+    pub fn name_for(&self, target: SkillsTarget) -> Option<&str> {
+        self.targets.get(&target.to_string()).map(String::as_str)
+    }
+}
+
+impl TargetNames {
+    fn allocate(
+        &mut self,
+        group: &str,
+        canonical: &str,
+        fallback: Option<&str>,
+        key: &str,
+    ) -> String {
+        let candidates = std::iter::once(canonical).chain(fallback);
+        let stem_length = self
+            .constraints
+            .max_length
+            .saturating_sub(HASH_SUFFIX_LENGTH + 1);
+        let stem = fit(fallback.unwrap_or(canonical), stem_length);
+        let suffixed = (0u32..).map(|salt| {
+            let hash = match salt {
+                0 => fnv1a(key),
+                _ => fnv1a(&format!("{}#{}", key, salt)),
+            };
+            if stem.is_empty() {
+                format!("{:08x}", hash)
+            } else {
+                format!("{}-{:08x}", stem, hash)
+            }
+        });
+
+        let name = candidates
+            .map(|name| fit(name, self.constraints.max_length))
+            .chain(suffixed)
+            .find(|name| {
+                self.owners
+                    .get(&(group.to_string(), name.clone()))
+                    .is_none_or(|owner| owner == key)
+            })
+            .expect("salted hash suffixes never run out");
+
+        self.owners
+            .insert((group.to_string(), name.clone()), key.to_string());
+        name
+    }
+}
+
+/// Truncates `name` to `max_length`, preferring a hyphen boundary, without
+/// leaving a trailing hyphen.
+fn fit(name: &str, max_length: usize) -> String {
+    truncate_at_word_boundary(name, max_length)
+        .trim_end_matches('-')
+        .to_string()
+}
+
+/// Drops the fragment and trailing slash so `/page`, `/page/` and
+/// `/page#intro` count as the same page.
+fn normalize_url(url: &str) -> String {
+    let url = url.split('#').next().unwrap_or(url);
+    url.trim_end_matches('/').to_string()
+}
+
+/// 32-bit FNV-1a, stable across Rust releases unlike `DefaultHasher`.
+fn fnv1a(input: &str) -> u32 {
+    input.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distinct_pages_keep_canonical_names() {
+        let names = SkillNames::new(&[SkillsTarget::Cursor]);
+
+        let a = names.assign("docs-install", "https://example.com/docs/install");
+        let b = names.assign("docs-usage", "https://example.com/docs/usage");
+
+        assert_eq!(a.name_for(SkillsTarget::Cursor), Some("docs-install"));
+        assert_eq!(b.name_for(SkillsTarget::Cursor), Some("docs-usage"));
+        assert!(!a.is_renamed() && !b.is_renamed());
+    }
+
+    #[test]
+    fn test_same_page_gets_same_name() {
+        let names = SkillNames::new(&[SkillsTarget::Cursor]);
+
+        let first = names.assign("docs-install", "https://example.com/docs/install");
+        let again = names.assign("docs-install", "https://example.com/docs/install/#setup");
+
+        assert_eq!(first.targets, again.targets);
+        assert!(!again.is_renamed());
+    }
+
+    #[test]
+    fn test_collision_gets_hash_suffix() {
+        let names = SkillNames::new(&[SkillsTarget::Cursor]);
+
+        names.assign("docs-api", "https://example.com/docs/api.html");
+        let second = names.assign("docs-api", "https://example.com/docs/api");
+
+        let name = second.name_for(SkillsTarget::Cursor).unwrap();
+        assert!(name.starts_with("docs-api-"), "{}", name);
+        assert_eq!(name.len(), "docs-api-".len() + HASH_SUFFIX_LENGTH);
+        assert!(second.is_renamed());
+    }
+
+    #[test]
+    fn test_collisions_are_per_group() {
+        let names = SkillNames::new(&[SkillsTarget::Cursor]).with_group_by(GroupBy::Domain);
+
+        names.assign("install", "https://docs.flutter.dev/install");
+        let other_site = names.assign("install", "https://supabase.com/install");
+        assert_eq!(other_site.name_for(SkillsTarget::Cursor), Some("install"));
+
+        let same_site = names.assign("install", "https://docs.flutter.dev/install.html");
+        assert!(same_site.is_renamed());
+    }
+
+    #[test]
+    fn test_collision_only_in_stricter_target() {
+        let names = SkillNames::with_constraints([
+            (SkillsTarget::ClaudeCode, NameConstraints { max_length: 64 }),
+            (SkillsTarget::Cursor, NameConstraints { max_length: 20 }),
+        ]);
+
+        // Both fit in 64 characters but share their first 20
+        let first = names.assign(
+            "reference-widgets-layout-row",
+            "https://example.com/reference/widgets/layout/row",
+        );
+        let second = names.assign(
+            "reference-widgets-layout-column",
+            "https://example.com/reference/widgets/layout/column",
+        );
+
+        assert_eq!(
+            first.name_for(SkillsTarget::Cursor),
+            Some("reference-widgets")
+        );
+        assert_eq!(
+            second.name_for(SkillsTarget::ClaudeCode),
+            Some("reference-widgets-layout-column")
+        );
+
+        let cursor_name = second.name_for(SkillsTarget::Cursor).unwrap();
+        assert_ne!(cursor_name, "reference-widgets");
+        assert!(cursor_name.len() <= 20, "{}", cursor_name);
+        assert!(cursor_name.ends_with(&format!("{:08x}", fnv1a(&second.url))));
+    }
+
+    #[test]
+    fn test_fallback_name_on_collision() {
+        let names = SkillNames::new(&[SkillsTarget::Cursor]);
+
+        let first = names.assign_with_fallback(
+            "overview",
+            Some("p-48219-xyz"),
+            "https://example.com/p/48219-xyz",
+        );
+        let second = names.assign_with_fallback(
+            "overview",
+            Some("p-50113-abc"),
+            "https://example.com/p/50113-abc",
+        );
+        // Its fallback is taken by now too
+        let third = names.assign_with_fallback(
+            "overview",
+            Some("p-50113-abc"),
+            "https://example.com/p/50113-abc/v2",
+        );
+
+        assert_eq!(first.name_for(SkillsTarget::Cursor), Some("overview"));
+        assert_eq!(second.name_for(SkillsTarget::Cursor), Some("p-50113-abc"));
+        let name = third.name_for(SkillsTarget::Cursor).unwrap();
+        assert!(name.starts_with("p-50113-abc-"), "{}", name);
+
+        // Asking again keeps the fallback
+        let again = names.assign_with_fallback(
+            "overview",
+            Some("p-50113-abc"),
+            "https://example.com/p/50113-abc",
+        );
+        assert_eq!(again.targets, second.targets);
+    }
+
+    #[test]
+    fn test_repeated_titles_get_distinct_names() {
+        let names = SkillNames::new(&[SkillsTarget::Cursor]);
+
+        let assigned: Vec<String> = (0..50)
+            .map(|i| {
+                let url = format!("https://example.com/p/{}", i);
+                let mapping = names.assign("overview", &url);
+                mapping.name_for(SkillsTarget::Cursor).unwrap().to_string()
+            })
+            .collect();
+
+        let unique: std::collections::HashSet<&String> = assigned.iter().collect();
+        assert_eq!(unique.len(), assigned.len());
+        assert!(assigned.iter().all(|name| name.starts_with("overview")));
+    }
+
+    #[test]
+    fn test_taken_hash_suffix_is_salted() {
+        let names = SkillNames::new(&[SkillsTarget::Cursor]);
+        let url = "https://example.com/guide";
+        let hashed = format!("overview-{:08x}", fnv1a(url));
+
+        // Another page's canonical name happens to be our hashed one
+        names.assign(&hashed, "https://example.com/other");
+        names.assign("overview", "https://example.com/first");
+        let mapping = names.assign("overview", url);
+
+        let name = mapping.name_for(SkillsTarget::Cursor).unwrap();
+        assert!(name.starts_with("overview-"), "{}", name);
+        assert_ne!(name, hashed);
+        assert_eq!(names.assign("overview", url).targets, mapping.targets);
+    }
+}
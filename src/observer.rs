@@ -0,0 +1,62 @@
+//! Callbacks for page and skill events of a crawl.
+//!
+//! A [`CrawlObserver`] registered with
+//! [`Crawler::with_observer`](crate::crawler::Crawler::with_observer) hears
+//! about every page the crawl processes: when it was fetched, when its skill
+//! was written and when processing it failed. Hooks run in the page's
+//! processing task and are awaited there, so a slow hook holds up that
+//! page (and, once every `concurrency` slot is taken, the ones queued behind
+//! it) rather than losing events. [`LogObserver`] logs the outcomes, as the
+//! command line does.
+
+use crate::processor::ProcessedPage;
+use spider::page::Page;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use tracing::{error, info};
+
+/// The future returned by the hooks of [`CrawlObserver`].
+pub type ObserverFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// Hooks called as a crawl processes pages. Each does nothing unless
+/// overridden.
+pub trait CrawlObserver: Send + Sync {
+    /// `page` came back from the server, passed the rules and is about to
+    /// be processed.
+    fn page_fetched<'a>(&'a self, _page: &'a Page) -> ObserverFuture<'a> {
+        Box::pin(std::future::ready(()))
+    }
+
+    /// The skill of `page` was written to `path`: its skill directory, or
+    /// its file with `flat`, in the first target's output directory. Not
+    /// called for skills regenerated unchanged, or in a dry run.
+    fn skill_written<'a>(
+        &'a self,
+        _page: &'a ProcessedPage,
+        _path: &'a Path,
+    ) -> ObserverFuture<'a> {
+        Box::pin(std::future::ready(()))
+    }
+
+    /// Processing the page at `url` failed with `error`. Pages skipped on
+    /// purpose (by the rules, as too small, as duplicates) aren't failures.
+    fn page_failed<'a>(&'a self, _url: &'a str, _error: &'a anyhow::Error) -> ObserverFuture<'a> {
+        Box::pin(std::future::ready(()))
+    }
+}
+
+/// Logs each written skill and failed page.
+pub struct LogObserver;
+
+impl CrawlObserver for LogObserver {
+    fn skill_written<'a>(&'a self, page: &'a ProcessedPage, path: &'a Path) -> ObserverFuture<'a> {
+        info!("Processed: {} -> {}", page.metadata.url, path.display());
+        Box::pin(std::future::ready(()))
+    }
+
+    fn page_failed<'a>(&'a self, url: &'a str, error: &'a anyhow::Error) -> ObserverFuture<'a> {
+        error!("Failed to process {}: {:?}", url, error);
+        Box::pin(std::future::ready(()))
+    }
+}
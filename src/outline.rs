@@ -0,0 +1,196 @@
+//! Heading outlines for the agent-skills-generator.
+//!
+//! The `h1`–`h3` headings of a page's cleaned markdown are listed as
+//! `metadata.sections` in the frontmatter, so an agent can judge a skill's
+//! relevance without reading its body. With `contents_section` they are
+//! also rendered as a `## Contents` list at the top of SKILL.md, linking to
+//! each heading's GitHub-style anchor; repeated headings get `-1`, `-2`
+//! suffixes as they do on GitHub.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Deepest heading level in the outline.
+pub const MAX_LEVEL: usize = 3;
+
+/// Fewer headings than this aren't worth a contents section.
+const MIN_CONTENTS_ENTRIES: usize = 2;
+
+/// Inline links, whose text is kept.
+static LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").expect("Failed to compile link regex"));
+
+/// A heading of the outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    pub level: usize,
+    /// Text without links or emphasis.
+    pub text: String,
+    /// Anchor of the heading, unique within the page.
+    pub anchor: String,
+}
+
+/// The `h1`–`h3` headings of `markdown` outside code fences, in document
+/// order.
+pub fn outline(markdown: &str) -> Vec<Heading> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    headings(markdown)
+        .filter(|(level, _)| *level <= MAX_LEVEL)
+        .map(|(level, text)| {
+            let text = plain_text(&text);
+            let slug = slug(&text);
+            let count = seen.entry(slug.clone()).or_default();
+            let anchor = match *count {
+                0 => slug,
+                n => format!("{}-{}", slug, n),
+            };
+            *count += 1;
+            Heading {
+                level,
+                text,
+                anchor,
+            }
+        })
+        .filter(|heading| !heading.text.is_empty())
+        .collect()
+}
+
+/// The heading texts of `outline` for the frontmatter, each listed once.
+pub fn sections(outline: &[Heading]) -> Vec<String> {
+    let mut sections: Vec<String> = Vec::new();
+    for heading in outline {
+        if !sections.contains(&heading.text) {
+            sections.push(heading.text.clone());
+        }
+    }
+    sections
+}
+
+/// A `## Contents` section linking to every heading of `outline`, indented
+/// by level. `None` if the page has too few headings for one.
+pub fn contents(outline: &[Heading]) -> Option<String> {
+    if outline.len() < MIN_CONTENTS_ENTRIES {
+        return None;
+    }
+    let top = outline.iter().map(|heading| heading.level).min()?;
+    let entries: Vec<String> = outline
+        .iter()
+        .map(|heading| {
+            format!(
+                "{}- [{}](#{})",
+                "  ".repeat(heading.level - top),
+                heading.text.replace('[', "\\[").replace(']', "\\]"),
+                heading.anchor
+            )
+        })
+        .collect();
+    Some(format!("## Contents\n\n{}", entries.join("\n")))
+}
+
+/// Headings of `markdown` outside code fences, with their level.
+pub fn headings(markdown: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    let mut fenced = false;
+    markdown.lines().filter_map(move |line| {
+        let line = line.trim();
+        if is_fence(line) {
+            fenced = !fenced;
+        }
+        if fenced || !is_heading(line) {
+            return None;
+        }
+        let level = line.chars().take_while(|&c| c == '#').count();
+        Some((level, line[level..].trim().to_string()))
+    })
+}
+
+/// Whether a trimmed line is an ATX heading (`## Title`).
+pub fn is_heading(line: &str) -> bool {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    (1..=6).contains(&level) && line[level..].starts_with(' ')
+}
+
+/// Whether a trimmed line opens or closes a code fence.
+pub fn is_fence(line: &str) -> bool {
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// Heading text without links, emphasis or closing `#`s.
+fn plain_text(heading: &str) -> String {
+    let text = LINK.replace_all(heading, "$1");
+    text.trim_end_matches('#')
+        .replace("**", "")
+        .replace("__", "")
+        .trim()
+        .to_string()
+}
+
+/// GitHub's anchor for a heading: lowercase, punctuation dropped, spaces
+/// as hyphens.
+fn slug(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "# Client API\n\nIntro.\n\n\
+                        ## Install\n\n```sh\n# not a heading\n```\n\n\
+                        ## [`connect()`](https://docs.example.com/api#connect)\n\n\
+                        ### Example\n\n#### Deep detail\n\n\
+                        ## **Errors & Retries**\n\n### Example\n";
+
+    #[test]
+    fn test_outline() {
+        let outline = outline(PAGE);
+        let entries: Vec<(usize, &str, &str)> = outline
+            .iter()
+            .map(|h| (h.level, h.text.as_str(), h.anchor.as_str()))
+            .collect();
+
+        assert_eq!(
+            entries,
+            [
+                (1, "Client API", "client-api"),
+                (2, "Install", "install"),
+                (2, "`connect()`", "connect"),
+                (3, "Example", "example"),
+                (2, "Errors & Retries", "errors--retries"),
+                (3, "Example", "example-1"),
+            ]
+        );
+        assert_eq!(
+            sections(&outline),
+            [
+                "Client API",
+                "Install",
+                "`connect()`",
+                "Example",
+                "Errors & Retries"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_contents() {
+        assert_eq!(
+            contents(&outline(PAGE)).unwrap(),
+            "## Contents\n\n\
+             - [Client API](#client-api)\n  \
+             - [Install](#install)\n  \
+             - [`connect()`](#connect)\n    \
+             - [Example](#example)\n  \
+             - [Errors & Retries](#errors--retries)\n    \
+             - [Example](#example-1)"
+        );
+        assert_eq!(contents(&outline("## Only\n\nOne heading.")), None);
+    }
+}
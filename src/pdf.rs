@@ -0,0 +1,336 @@
+//! PDF support for the agent-skills-generator.
+//!
+//! With `process_pdfs: true`, pages served as `application/pdf` are turned
+//! into skills like any other page: the text of every PDF page is extracted
+//! and shaped into markdown paragraphs, with short title-like lines promoted
+//! to headings. The title in the PDF's metadata, if any, heads the markdown
+//! and so becomes the skill's title; otherwise the first heading does. Text
+//! extraction needs the `pdf` cargo feature; PDFs over
+//! `max_pdf_bytes` or `max_pdf_pages` are skipped with a warning.
+
+use crate::processor::SkippedPage;
+use anyhow::Result;
+use tracing::warn;
+
+/// Limits beyond which a PDF is skipped rather than converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PdfLimits {
+    pub max_bytes: u64,
+    pub max_pages: usize,
+}
+
+/// Extracts the text of the PDF in `bytes` as markdown, or warns and fails
+/// with [`SkippedPage`] if it exceeds `limits`.
+pub fn to_markdown(url: &str, bytes: &[u8], limits: PdfLimits) -> Result<String> {
+    if bytes.len() as u64 > limits.max_bytes {
+        return Err(too_large(
+            url,
+            format!(
+                "{} bytes is over max_pdf_bytes ({})",
+                bytes.len(),
+                limits.max_bytes
+            ),
+        ));
+    }
+
+    let (title, pages) = extract_pages(url, bytes, limits.max_pages)?;
+    let markdown = format_pages(&pages);
+    Ok(match title {
+        Some(title) => {
+            // The text often opens with the title too
+            let heading = format!("## {}", title);
+            let body = markdown
+                .strip_prefix(&heading)
+                .map_or(markdown.as_str(), str::trim_start);
+            format!("# {}\n\n{}", title, body)
+        }
+        None => markdown,
+    })
+}
+
+/// Extracts the title in the document's metadata and the text of every page.
+#[cfg(feature = "pdf")]
+fn extract_pages(
+    url: &str,
+    bytes: &[u8],
+    max_pages: usize,
+) -> Result<(Option<String>, Vec<String>)> {
+    use pdf_extract::{Document, PlainTextOutput, output_doc_page};
+
+    let document = Document::load_mem(bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to read PDF {}: {}", url, e))?;
+    if document.is_encrypted() {
+        anyhow::bail!("Encrypted PDFs aren't supported: {}", url);
+    }
+
+    let page_count = document.get_pages().len();
+    if page_count > max_pages {
+        return Err(too_large(
+            url,
+            format!("{} pages is over max_pdf_pages ({})", page_count, max_pages),
+        ));
+    }
+
+    let pages = (1..=page_count as u32)
+        .map(|page| {
+            let mut text = String::new();
+            output_doc_page(&document, &mut PlainTextOutput::new(&mut text), page).map_err(
+                |e| anyhow::anyhow!("Failed to extract page {} of {}: {}", page, url, e),
+            )?;
+            Ok(text)
+        })
+        .collect::<Result<_>>()?;
+    Ok((metadata_title(&document), pages))
+}
+
+/// The `Title` entry of the document information dictionary, if it isn't
+/// blank.
+#[cfg(feature = "pdf")]
+fn metadata_title(document: &pdf_extract::Document) -> Option<String> {
+    let info = document.trailer.get_deref(b"Info", document).ok()?;
+    let title = info.as_dict().ok()?.get_deref(b"Title", document).ok()?;
+    let title = decode_text_string(title.as_str().ok()?);
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Decodes a PDF text string: UTF-16BE after a byte order mark, otherwise
+/// PDFDocEncoding, read as Latin-1 which it matches for printable text.
+#[cfg(feature = "pdf")]
+fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => bytes.iter().map(|&byte| char::from(byte)).collect(),
+    }
+}
+
+/// Warns about a PDF over the limits and returns the error skipping it.
+fn too_large(url: &str, reason: String) -> anyhow::Error {
+    let skipped = SkippedPage {
+        url: url.to_string(),
+        reason: format!("PDF {}", reason),
+    };
+    warn!("{}", skipped);
+    skipped.into()
+}
+
+/// Without the `pdf` feature there is no text extraction.
+#[cfg(not(feature = "pdf"))]
+fn extract_pages(
+    url: &str,
+    _bytes: &[u8],
+    _max_pages: usize,
+) -> Result<(Option<String>, Vec<String>)> {
+    anyhow::bail!(
+        "Can't convert PDF {}: this build lacks PDF support (rebuild with `--features pdf`)",
+        url
+    )
+}
+
+/// Shapes extracted page text into markdown: blank-line separated blocks
+/// become paragraphs with their line breaks joined, and single short lines
+/// that read like titles become `##` headings.
+pub fn format_pages(pages: &[String]) -> String {
+    let mut blocks = Vec::new();
+
+    for page in pages {
+        let mut lines: Vec<&str> = Vec::new();
+        for line in page.lines().map(str::trim).chain([""]) {
+            if !line.is_empty() {
+                lines.push(line);
+                continue;
+            }
+            if lines.is_empty() {
+                continue;
+            }
+
+            let block = if lines.len() == 1 && is_heading(lines[0]) {
+                format!("## {}", lines[0])
+            } else {
+                join_lines(&lines)
+            };
+            blocks.push(block);
+            lines.clear();
+        }
+    }
+
+    blocks.join("\n\n")
+}
+
+/// Joins wrapped lines, rejoining words hyphenated at the line break.
+fn join_lines(lines: &[&str]) -> String {
+    let mut text = String::new();
+    for line in lines {
+        if let Some(stem) = text.strip_suffix('-')
+            && stem.ends_with(char::is_alphabetic)
+            && line.starts_with(char::is_lowercase)
+        {
+            text.truncate(stem.len());
+        } else if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(line);
+    }
+    text
+}
+
+/// Whether a standalone line looks like a heading: short, capitalized and
+/// not ending like a sentence.
+fn is_heading(line: &str) -> bool {
+    let words = line.split_whitespace().count();
+    (1..=10).contains(&words)
+        && line.len() <= 80
+        && line.starts_with(|c: char| c.is_uppercase() || c.is_ascii_digit())
+        && !line.ends_with(['.', ',', ';', ':', '!', '?'])
+        && line.chars().any(char::is_alphabetic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: PdfLimits = PdfLimits {
+        max_bytes: 1024,
+        max_pages: 10,
+    };
+
+    #[test]
+    fn test_format_pages() {
+        let pages = [
+            "API Reference\n\nThe client connects to the server and re-\ntries on failure.\n\n\n1.2 Authentication\n\nTokens expire after one hour.\n".to_string(),
+            "\nPage 2 continues here.\n".to_string(),
+        ];
+
+        assert_eq!(
+            format_pages(&pages),
+            "## API Reference\n\n\
+             The client connects to the server and retries on failure.\n\n\
+             ## 1.2 Authentication\n\n\
+             Tokens expire after one hour.\n\n\
+             Page 2 continues here."
+        );
+    }
+
+    #[test]
+    fn test_oversized_pdf_is_skipped() {
+        let error = to_markdown("https://example.com/api.pdf", &[0; 2048], LIMITS).unwrap_err();
+        let skipped = error.downcast_ref::<SkippedPage>().unwrap();
+        assert!(
+            skipped.reason.contains("max_pdf_bytes"),
+            "{}",
+            skipped.reason
+        );
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn test_pdf_text_is_extracted() {
+        let markdown = to_markdown("https://example.com/api.pdf", &test_pdf(None), LIMITS).unwrap();
+        assert!(markdown.contains("Hello from the PDF"), "{}", markdown);
+
+        let error = to_markdown(
+            "https://example.com/api.pdf",
+            &test_pdf(None),
+            PdfLimits {
+                max_pages: 0,
+                ..LIMITS
+            },
+        )
+        .unwrap_err();
+        assert!(error.downcast_ref::<SkippedPage>().is_some());
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn test_pdf_becomes_skill() {
+        use crate::config::Config;
+        use crate::processor::{PageBody, PageContext, Processor};
+
+        let config = Config {
+            process_pdfs: true,
+            ..Default::default()
+        };
+        let processor = Processor::new(&config).unwrap();
+        let mut context = PageContext::new("https://example.com/specs/widget-spec.pdf");
+        context.content_type = Some("application/pdf".to_string());
+        assert!(processor.handles_pdf(&context));
+
+        // The metadata title is the skill's title
+        let pdf = PageBody::Pdf(test_pdf(Some("Widget Protocol")));
+        let processed = processor.process_body(&context, &pdf).unwrap();
+        assert_eq!(processed.metadata.title, "Widget Protocol");
+        assert!(processed.skill_md.starts_with("---\n"));
+        assert!(processed.skill_md.contains("Hello from the PDF"));
+
+        // Without one, the first line is the title
+        let pdf = PageBody::Pdf(test_pdf(None));
+        let processed = processor.process_body(&context, &pdf).unwrap();
+        assert_eq!(processed.metadata.title, "Hello from the PDF");
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn test_decode_text_string() {
+        assert_eq!(decode_text_string(b"Caf\xe9 Guide"), "Café Guide");
+        assert_eq!(
+            decode_text_string(&[0xFE, 0xFF, 0x00, 0x41, 0x00, 0xE9]),
+            "Aé"
+        );
+    }
+
+    /// A one-page PDF showing a line of Helvetica text, with `title` in its
+    /// metadata.
+    #[cfg(feature = "pdf")]
+    fn test_pdf(title: Option<&str>) -> Vec<u8> {
+        let stream = "BT /F1 12 Tf 72 720 Td (Hello from the PDF) Tj ET";
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+             /Resources << /Font << /F1 5 0 R >> >> /Contents 4 0 R >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                stream.len(),
+                stream
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ];
+        let info = match title {
+            Some(title) => {
+                objects.push(format!("<< /Title ({}) >>", title));
+                format!(" /Info {} 0 R", objects.len())
+            }
+            None => String::new(),
+        };
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).bytes());
+        }
+
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R{} >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                info,
+                xref
+            )
+            .bytes(),
+        );
+        pdf
+    }
+}
@@ -12,25 +12,240 @@
 //! - YAML frontmatter with metadata
 //! - Page title
 //! - Full converted markdown content
-
-use crate::config::Config;
-use crate::utils::{extract_url_path, sanitize_skill_name, truncate_description};
+//!
+//! Pages over `max_skill_chars` are split into [`references`] files
+//! instead, and SKILL.md keeps a summary linking to them. With
+//! `output_format` the skill is written as `skill.json` or `skill.txt`
+//! instead of SKILL.md.
+
+use crate::admonitions;
+use crate::cache::content_hash;
+use crate::code_blocks;
+use crate::config::{
+    Action, CalloutKind, CalloutStyle, Config, GroupBy, ImageMode, MAX_DESCRIPTION_CHARS,
+    MetadataLevel, SkillFormat, SkillNameSource, UrlMatcher, is_html_content_type,
+    is_included_content_type, is_pdf_content_type, parse_css_selectors, query_param_matches,
+};
+use crate::description;
+use crate::frontmatter::{self, Frontmatter, FrontmatterMetadata};
+use crate::images::{self, ASSETS_DIR, ImageAsset, ImageDownloader};
+use crate::language;
+use crate::links;
+use crate::outline;
+use crate::pdf::{self, PdfLimits};
+use crate::references::{self, REFERENCES_DIR, ReferenceFile};
+use crate::tables;
+use crate::tags;
+use crate::tokens;
+use crate::utils::{
+    extract_url_path, percent_decode, query_path, sanitize_skill_name, title_to_skill_name, touch,
+    transliterate, truncate_description,
+};
+use crate::version_check;
 use anyhow::{Context, Result};
 use chrono::Utc;
 use htmd::HtmlToMarkdown;
-use scraper::{Html, Selector};
-use std::path::Path;
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
 use tracing::{debug, warn};
 
-/// Maximum description length in frontmatter.
-const MAX_DESCRIPTION_LENGTH: usize = 1024;
+/// Title of a page that has none.
+const UNTITLED: &str = "Untitled";
+
+/// Marker for tables swapped out of the HTML before conversion.
+const TABLE_PLACEHOLDER: &str = "AGENTSKILLSTABLE";
+
+/// File the cleaned HTML is written to with `include_html`.
+pub const CLEANED_HTML_FILE: &str = "content.html";
 
 /// Character threshold for large content warning.
 /// ~20,000 characters is roughly 5,000 tokens.
 const LARGE_CONTENT_THRESHOLD: usize = 20_000;
 
-/// Metadata extracted from a page.
-#[derive(Debug, Clone)]
+/// Noise removed from HTML by [`Processor::clean_html`], as groups of
+/// inline flags and patterns. Every pattern is applied on its own, in this
+/// order: which of two nested noise elements is removed first decides what
+/// is left, so they can't be combined into one regex.
+const NOISE_PASSES: &[(&str, &[&str])] = &[
+    // Scripts, styles, templates and noise elements by tag name
+    (
+        "(?is)",
+        &[
+            r"<script[^>]*>.*?</script>",
+            r"<style[^>]*>.*?</style>",
+            r"<noscript[^>]*>.*?</noscript>",
+            r"<template[^>]*>.*?</template>",
+            r"<nav[^>]*>.*?</nav>",
+            r"<footer[^>]*>.*?</footer>",
+            r"<header[^>]*>.*?</header>",
+            r"<aside[^>]*>.*?</aside>",
+            r"<iframe[^>]*>.*?</iframe>",
+            r"<svg[^>]*>.*?</svg>",
+            r"<canvas[^>]*>.*?</canvas>",
+            r"<video[^>]*>.*?</video>",
+            r"<audio[^>]*>.*?</audio>",
+            r"<form[^>]*>.*?</form>", // Search, feedback, etc.
+            r"<dialog[^>]*>.*?</dialog>",
+        ],
+    ),
+    // Elements with common noise IDs
+    (
+        "(?is)",
+        &[
+            r#"<[^>]+id="[^"]*\b(cookie|consent|banner|popup|modal|overlay|gdpr|privacy-notice|skip-link|feedback|newsletter|subscribe)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+        ],
+    ),
+    // Elements with common noise class names
+    (
+        "(?is)",
+        &[
+            // Navigation and menus
+            r#"<[^>]+class="[^"]*\b(nav|navigation|menu|sidebar|toc|table-of-contents|breadcrumb|breadcrumbs)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+            // Cookie and consent banners
+            r#"<[^>]+class="[^"]*\b(cookie|consent|gdpr|privacy-notice|cookie-banner|cookie-consent)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+            // Ads and promotional content
+            r#"<[^>]+class="[^"]*\b(ads?|advertisement|promo|promotional|banner|announcement)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+            // Modals and popups
+            r#"<[^>]+class="[^"]*\b(modal|popup|dialog)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+            r#"<[^>]+role="(dialog|alertdialog)"[^>]*>.*?</[^>]+>"#,
+            // Feedback and ratings
+            r#"<[^>]+class="[^"]*\b(feedback|rating|ratings|helpful|thumbs|vote|voting)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+            // Skip links and accessibility shortcuts
+            r#"<[^>]+class="[^"]*\b(skip-link|skip-to-content|sr-only|visually-hidden)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+            // Social sharing
+            r#"<[^>]+class="[^"]*\b(social|share|sharing|follow-us)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+            // Page metadata/footer info
+            r#"<[^>]+class="[^"]*\b(page-meta|page-info|last-updated|edit-page|view-source|report-issue)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+        ],
+    ),
+    // Skip links (often standalone anchor tags)
+    ("(?is)", &[r##"<a[^>]+href="#[^"]*"[^>]*>Skip[^<]*</a>"##]),
+    // Material icons and icon fonts (span/i elements with icon classes)
+    (
+        "(?is)",
+        &[
+            r"<span[^>]+class=[^>]*(material-icons|icon|fa|fas|far|fab|glyphicon)[^>]*>[^<]*</span>",
+            r"<i[^>]+class=[^>]*(material-icons|icon|fa|fas|far|fab|glyphicon)[^>]*>[^<]*</i>",
+            // Inline material icon text that might be in any element
+            r"<[^>]+class=[^>]*material-symbols[^>]*>[^<]*</[^>]+>",
+        ],
+    ),
+    // Buttons, likely UI controls (copy buttons, etc.)
+    ("(?is)", &[r"<button[^>]*>.*?</button>"]),
+    // HTML comments
+    ("(?s)", &[r"<!--.*?-->"]),
+    // Data attributes that might contain noise
+    ("", &[r#"\s+data-[a-z-]+="[^"]*""#]),
+];
+
+/// [`NOISE_PASSES`], one regex per pattern.
+static NOISE_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    NOISE_PASSES
+        .iter()
+        .flat_map(|(flags, patterns)| {
+            patterns.iter().map(move |pattern| {
+                Regex::new(&format!("{}{}", flags, pattern)).expect("Failed to compile noise regex")
+            })
+        })
+        .collect()
+});
+
+/// Icon-font ligature names that leak into the text of many docs sites.
+const BUILTIN_ICON_NAMES: &[&str] = &[
+    "chevron_right",
+    "chevron_left",
+    "arrow_forward",
+    "arrow_back",
+    "arrow_drop_down",
+    "arrow_drop_up",
+    "content_copy",
+    "content_paste",
+    "thumb_up",
+    "thumb_down",
+    "thumbs_up",
+    "thumbs_down",
+    "vertical_align_top",
+    "vertical_align_bottom",
+    "expand_more",
+    "expand_less",
+    "menu",
+    "close",
+    "search",
+    "home",
+    "settings",
+    "check",
+    "check_circle",
+    "error",
+    "warning",
+    "info",
+    "list",
+    "share",
+    "edit",
+    "delete",
+    "add",
+    "remove",
+    "star",
+    "star_border",
+    "favorite",
+    "favorite_border",
+    "bookmark",
+    "bookmark_border",
+    "visibility",
+    "visibility_off",
+    "lock",
+    "lock_open",
+    "person",
+    "people",
+    "notifications",
+    "email",
+    "phone",
+    "location_on",
+    "calendar_today",
+    "schedule",
+    "more_vert",
+    "more_horiz",
+    "open_in_new",
+    "launch",
+    "link",
+    "file_download",
+    "file_upload",
+    "cloud_download",
+    "cloud_upload",
+    "play_arrow",
+    "pause",
+    "stop",
+    "skip_next",
+    "skip_previous",
+    "fast_forward",
+    "fast_rewind",
+    "volume_up",
+    "volume_down",
+    "volume_mute",
+    "fullscreen",
+    "fullscreen_exit",
+    "zoom_in",
+    "zoom_out",
+    "refresh",
+    "sync",
+    "cached",
+    "done",
+    "done_all",
+    "clear",
+    "cancel",
+    "help",
+    "help_outline",
+    "code",
+];
+
+/// Metadata extracted from a page. Serialized as the fields of a
+/// `skill.json`.
+#[derive(Debug, Clone, Serialize)]
 pub struct PageMetadata {
     /// Page title from <title> or <h1> element.
     pub title: String,
@@ -38,18 +253,170 @@ pub struct PageMetadata {
     /// Meta description from <meta name="description">.
     pub description: String,
 
-    /// Original URL of the page.
+    /// Original URL of the page, after any redirects.
     pub url: String,
 
+    /// URL that redirected to `url`, if recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_url: Option<String>,
+
+    /// URL the page declares canonical with `<link rel="canonical">`, if it
+    /// isn't `url`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+
+    /// Language the page declares, or with `strict_language` is written in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
     /// Sanitized skill name (kebab-case, max 64 chars).
     pub skill_name: String,
 
+    /// Name to use instead if another page already holds `skill_name`
+    /// (`skill_name_source: title_fallback_url`).
+    #[serde(skip)]
+    pub fallback_skill_name: Option<String>,
+
     /// Timestamp when the page was processed.
     pub processed_at: String,
+
+    /// Keywords of the page, listed as `metadata.tags`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// What is known about a page besides its HTML. Header-driven processing
+/// decisions read the response headers from here rather than the raw
+/// response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageContext {
+    /// URL the page was fetched from, after any redirects.
+    pub url: String,
+
+    /// URL that was requested, if it redirected to `url`.
+    pub requested_url: Option<String>,
+
+    /// Response headers kept by the `response_headers` allowlist, keyed by
+    /// lowercase name. Repeated headers are joined with `, `.
+    pub headers: BTreeMap<String, String>,
+
+    /// The response's `Content-Type`, whether allowlisted or not. Pages
+    /// without one are treated as HTML.
+    pub content_type: Option<String>,
+
+    /// The response's `Content-Language`, normalized, whether allowlisted
+    /// or not.
+    pub content_language: Option<String>,
+}
+
+impl PageContext {
+    /// Creates a context for `url` without any headers.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            requested_url: None,
+            headers: BTreeMap::new(),
+            content_type: None,
+            content_language: None,
+        }
+    }
+
+    /// Records that `requested` was asked for and redirected here. Does
+    /// nothing if it is the page's own URL.
+    pub fn with_requested_url(mut self, requested: &str) -> Self {
+        self.requested_url = (requested != self.url).then(|| requested.to_string());
+        self
+    }
+
+    /// Keeps the headers of `response` named in `allowlist`, and its
+    /// `Content-Type` and `Content-Language`.
+    pub fn with_headers(mut self, response: &HeaderMap, allowlist: &[String]) -> Self {
+        self.content_type = response
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        self.content_language = response
+            .get(reqwest::header::CONTENT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(language::normalize);
+
+        for name in allowlist {
+            let name = name.to_lowercase();
+            let values: Vec<String> = response
+                .get_all(name.as_str())
+                .iter()
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+                .collect();
+
+            if !values.is_empty() {
+                self.headers.insert(name, values.join(", "));
+            }
+        }
+        self
+    }
+
+    /// Returns a recorded header by name, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+/// Body of a fetched page: decoded text, or the raw bytes of a PDF to be
+/// converted with `process_pdfs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageBody {
+    Html(String),
+    Pdf(Vec<u8>),
+}
+
+impl PageBody {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            PageBody::Html(html) => html.is_empty(),
+            PageBody::Pdf(bytes) => bytes.is_empty(),
+        }
+    }
+
+    /// The body as text, lossily decoded for a PDF.
+    pub fn text(&self) -> Cow<'_, str> {
+        match self {
+            PageBody::Html(html) => Cow::Borrowed(html),
+            PageBody::Pdf(bytes) => String::from_utf8_lossy(bytes),
+        }
+    }
+}
+
+/// A page deliberately not turned into a skill, e.g. a PDF over the size
+/// limits. Callers count it as skipped rather than failed.
+#[derive(Debug, thiserror::Error)]
+#[error("Skipping {url}: {reason}")]
+pub struct SkippedPage {
+    pub url: String,
+    pub reason: String,
+}
+
+/// A page whose content is under `min_content_chars`: a redirect stub, a
+/// "page moved" notice or a bare tag listing.
+#[derive(Debug, thiserror::Error)]
+#[error("Skipping {url}: {chars} characters of content is under min_content_chars ({min_chars})")]
+pub struct PageTooSmall {
+    pub url: String,
+    pub chars: usize,
+    pub min_chars: usize,
+}
+
+/// A page in a language that isn't one of `languages`.
+#[derive(Debug, thiserror::Error)]
+#[error("Skipping {url}: its language ({language}) isn't in languages")]
+pub struct OtherLanguage {
+    pub url: String,
+    /// The page's language, or `unknown` if `strict_language` couldn't
+    /// tell.
+    pub language: String,
 }
 
 /// Result of processing a page.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ProcessedPage {
     /// Metadata extracted from the page.
     pub metadata: PageMetadata,
@@ -60,8 +427,42 @@ pub struct ProcessedPage {
     /// Markdown-converted content.
     pub markdown_content: String,
 
-    /// Generated SKILL.md content (includes full markdown).
+    /// Generated SKILL.md content (includes full markdown), or the content
+    /// of `skill.json` / `skill.txt` with `output_format`.
     pub skill_md: String,
+
+    /// Images to save in the skill's `assets/` directory, filled in by
+    /// [`Processor::download_images`].
+    pub assets: Vec<ImageAsset>,
+
+    /// Parts of a page over `max_skill_chars`, to save in the skill's
+    /// `references/` directory. Empty if SKILL.md holds all the content.
+    pub references: Vec<ReferenceFile>,
+
+    /// Pages the page links to, see [`links::html_links`].
+    pub links: BTreeSet<String>,
+}
+
+impl ProcessedPage {
+    /// Estimated tokens of the skill's content, as recorded in it.
+    pub fn estimated_tokens(&self) -> usize {
+        tokens::estimate(&self.markdown_content)
+    }
+}
+
+/// A skill written by [`Processor::write_to_disk`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenSkill {
+    /// Directory of the skill, or its `<name>.md` file with `flat`.
+    pub skill_dir: PathBuf,
+
+    /// Final name of the skill.
+    pub skill_name: String,
+
+    /// Whether anything was written. `false` if SKILL.md (and `content.html`)
+    /// already had this content, apart from volatile frontmatter fields, and
+    /// were left untouched.
+    pub changed: bool,
 }
 
 /// Content processor that cleans HTML and generates skill files.
@@ -74,6 +475,116 @@ pub struct Processor {
 
     /// HTML to Markdown converter.
     converter: HtmlToMarkdown,
+
+    /// Lowercased phrases that disqualify a title/description candidate.
+    consent_phrases: Vec<String>,
+
+    /// User-supplied markdown cleanup patterns, applied after the built-ins.
+    markdown_strip_patterns: Vec<regex::Regex>,
+
+    /// Global content selectors, tried in order.
+    content_selectors: Vec<Selector>,
+
+    /// Selector overrides from allow rules, in rule order.
+    rule_selectors: Vec<RuleSelectors>,
+
+    /// Whole-word match of every icon name to strip, if any.
+    icon_names_re: Option<regex::Regex>,
+
+    /// User-supplied feedback prompt patterns.
+    extra_feedback_patterns: Vec<regex::Regex>,
+
+    /// Maximum frontmatter size in bytes.
+    frontmatter_budget: usize,
+
+    /// Start SKILL.md with a `## Contents` list of the page's headings.
+    contents_section: bool,
+
+    /// Whether the frontmatter records crawl metadata.
+    frontmatter_metadata: MetadataLevel,
+
+    /// Extra frontmatter fields, before rendering.
+    frontmatter_fields: BTreeMap<String, serde_yaml::Value>,
+
+    /// Non-HTML content types whose body is used as-is.
+    include_content_types: Vec<String>,
+
+    /// Write the cleaned HTML next to each SKILL.md.
+    include_html: bool,
+
+    /// Limits of PDFs turned into skills, `None` unless `process_pdfs`.
+    pdf_limits: Option<PdfLimits>,
+
+    /// What happens to images in the markdown.
+    images: ImageMode,
+
+    /// Fetches images with `images: download`.
+    image_downloader: Option<ImageDownloader>,
+
+    /// Pages with less markdown than this are skipped.
+    min_content_chars: usize,
+
+    /// Reduce links to anchors of the same page to their text.
+    drop_anchor_links: bool,
+
+    /// Write `<name>.md` files instead of `<name>/SKILL.md`.
+    flat: bool,
+
+    /// Tables with more grid cells than this are kept as text.
+    max_table_cells: usize,
+
+    /// Pages with more markdown than this are split into reference files.
+    max_skill_chars: usize,
+
+    /// Format skills are written in.
+    output_format: SkillFormat,
+
+    /// Site-specific admonition classes and their callout types.
+    admonition_classes: BTreeMap<String, CalloutKind>,
+
+    /// How admonitions are written.
+    callout_style: CalloutStyle,
+
+    /// What skill names are derived from.
+    skill_name_source: SkillNameSource,
+
+    /// Directories skills are grouped in.
+    group_by: GroupBy,
+
+    /// Directory of a crawl of local files, which their names are
+    /// relative to.
+    local_root: Option<PathBuf>,
+
+    /// Spell non-ASCII characters of skill names in ASCII.
+    transliterate_names: bool,
+
+    /// Query parameters that are part of skill names.
+    significant_query_params: Vec<String>,
+
+    /// Sentences of content describing a page without a meta description.
+    description_sentences: usize,
+
+    /// Maximum description length.
+    description_max_chars: usize,
+
+    /// Record the requested URL of redirected pages.
+    record_requested_url: bool,
+
+    /// Tag pages without keywords after their headings.
+    heading_tags: bool,
+
+    /// Pages in other languages are skipped.
+    languages: Vec<String>,
+
+    /// Guess the language of pages that don't declare one.
+    strict_language: bool,
+}
+
+/// Selector overrides of a single allow rule, parsed once at startup.
+struct RuleSelectors {
+    matcher: UrlMatcher,
+    content_selectors: Option<Vec<Selector>>,
+    extra_remove_selectors: Vec<Selector>,
 }
 
 impl Processor {
@@ -95,102 +606,486 @@ impl Processor {
 
         let converter = HtmlToMarkdown::new();
 
+        let consent_phrases = config
+            .consent_phrases
+            .iter()
+            .map(|phrase| phrase.to_lowercase())
+            .collect();
+
+        let markdown_strip_patterns = config.build_markdown_strip_patterns()?;
+        let content_selectors =
+            parse_css_selectors(&config.content_selectors, "content_selectors")?;
+
+        let extra_feedback_patterns = config.build_feedback_patterns()?;
+        let icon_names_re = build_icon_names_regex(config)?;
+
+        let mut rule_selectors = Vec::new();
+        for (i, rule) in config.rules.iter().enumerate() {
+            if rule.action != Action::Allow || !rule.has_selector_overrides() {
+                continue;
+            }
+
+            let context = format!("rule {}", i + 1);
+            rule_selectors.push(RuleSelectors {
+                matcher: rule.build_matcher(i)?,
+                content_selectors: rule
+                    .content_selectors
+                    .as_deref()
+                    .map(|selectors| parse_css_selectors(selectors, &context))
+                    .transpose()?,
+                extra_remove_selectors: parse_css_selectors(
+                    &rule.extra_remove_selectors,
+                    &context,
+                )?,
+            });
+        }
+
         Ok(Self {
             remove_selectors,
             converter,
+            consent_phrases,
+            markdown_strip_patterns,
+            content_selectors,
+            rule_selectors,
+            icon_names_re,
+            extra_feedback_patterns,
+            frontmatter_budget: config.frontmatter_budget,
+            contents_section: config.contents_section,
+            frontmatter_metadata: config.frontmatter_metadata,
+            frontmatter_fields: config.frontmatter.clone(),
+            include_content_types: config.include_content_types.clone(),
+            include_html: config.include_html,
+            pdf_limits: config.process_pdfs.then(|| config.pdf_limits()),
+            images: config.images,
+            image_downloader: match config.images {
+                ImageMode::Download => Some(ImageDownloader::new(config)?),
+                ImageMode::Link | ImageMode::Strip => None,
+            },
+            min_content_chars: config.min_content_chars,
+            drop_anchor_links: config.drop_anchor_links,
+            flat: config.flat,
+            max_table_cells: config.max_table_cells,
+            max_skill_chars: config.max_skill_chars,
+            output_format: config.output_format,
+            admonition_classes: config.admonition_classes.clone(),
+            callout_style: config.callout_style,
+            skill_name_source: config.skill_name_source,
+            group_by: config.group_by,
+            local_root: config.local_root.clone(),
+            transliterate_names: config.transliterate_names,
+            significant_query_params: config.significant_query_params.clone(),
+            description_sentences: config.description_sentences.max(1),
+            description_max_chars: config.description_max_chars.clamp(1, MAX_DESCRIPTION_CHARS),
+            record_requested_url: config.record_requested_url,
+            heading_tags: config.heading_tags,
+            languages: config.languages.clone(),
+            strict_language: config.strict_language,
         })
     }
 
+    /// Processes a page known only by its URL. See [`Self::process_page`].
+    pub fn process(&self, url: &str, html: &str) -> Result<ProcessedPage> {
+        self.process_page(&PageContext::new(url), html)
+    }
+
     /// Processes a page: cleans HTML, extracts metadata, generates skill file.
     ///
     /// # Arguments
-    /// * `url` - The original URL of the page
+    /// * `context` - The page's URL and recorded response headers
     /// * `html` - The raw HTML content
     ///
     /// # Returns
     /// A `ProcessedPage` containing all generated content.
-    pub fn process(&self, url: &str, html: &str) -> Result<ProcessedPage> {
+    pub fn process_page(&self, context: &PageContext, html: &str) -> Result<ProcessedPage> {
+        let url = context.url.as_str();
+
+        // Markdown and plain text pages are already what we want
+        if let Some(ref content_type) = context.content_type
+            && !is_html_content_type(content_type)
+            && is_included_content_type(&self.include_content_types, Some(content_type))
+        {
+            return self.process_text(context, html);
+        }
+
         // Step 1: Parse HTML
         let document = Html::parse_document(html);
 
-        // Step 2: Extract metadata before cleaning
-        let metadata = self.extract_metadata(url, &document)?;
-
-        // Step 3: Clean HTML by removing noise elements
-        let cleaned_html = self.clean_html(html)?;
+        // Step 2: Narrow to the main content and clean out noise elements
+        let content_html = self.select_content(url, &document);
+        // Admonitions and highlighted code blocks are rewritten first, while
+        // their classes and `data-` attributes are still there
+        let content_html = admonitions::mark(
+            content_html.as_deref().unwrap_or(html),
+            &self.admonition_classes,
+        );
+        let content_html = code_blocks::normalize(&content_html);
+        let cleaned_html = self.clean_html(&content_html)?;
+
+        // Step 3: Extract metadata. Cleaning may drop the <head>, so its tags
+        // are read from the original page; anything from the body comes
+        // from the cleaned content, not a banner or dialog
+        let content = Html::parse_fragment(&cleaned_html);
+        let mut metadata = self.extract_metadata(url, &document, &content)?;
+        metadata.requested_url = self.requested_url(context);
+        metadata.language = self.page_language(context, declared_language(&document), || {
+            content.root_element().text().collect::<Vec<_>>().join(" ")
+        })?;
 
         // Step 4: Convert to Markdown
         let raw_markdown = self
-            .converter
-            .convert(&cleaned_html)
+            .html_to_markdown(&cleaned_html)
             .with_context(|| format!("Failed to convert HTML to markdown for: {}", url))?;
+        let raw_markdown = admonitions::callouts(&raw_markdown, self.callout_style);
 
         // Step 5: Post-process markdown to remove remaining artifacts
         let markdown_content = self.clean_markdown(&raw_markdown);
+        let markdown_content = links::absolutize(&markdown_content, url, self.drop_anchor_links);
+        let markdown_content = images::resolve(&markdown_content, self.images);
+        self.check_content_length(url, &markdown_content)?;
+
+        // Step 6: Describe the page by its content if it has no meta
+        // description; the raw page's first paragraph is too often a banner
+        if metadata.description.is_empty() {
+            metadata.description = description::from_markdown(
+                &markdown_content,
+                self.description_sentences,
+                self.description_max_chars,
+                |text| self.is_consent_text(text),
+            )
+            .unwrap_or_default();
+        }
 
-        // Step 6: Generate consolidated SKILL.md content with full markdown
-        let skill_md = self.generate_skill_md(&metadata, &markdown_content);
+        // Step 7: Generate consolidated SKILL.md content with full markdown
+        let (skill_md, references) = self.generate_skill(&metadata, &markdown_content)?;
 
         Ok(ProcessedPage {
+            links: links::html_links(&cleaned_html, url),
             metadata,
             cleaned_html,
             markdown_content,
             skill_md,
+            assets: Vec::new(),
+            references,
         })
     }
 
-    /// Extracts metadata from the parsed HTML document.
-    fn extract_metadata(&self, url: &str, document: &Html) -> Result<PageMetadata> {
-        // Extract title
-        let title = self
-            .extract_title(document)
-            .unwrap_or_else(|| "Untitled".to_string());
+    /// Processes a page from its body. See [`Self::process_page`] and
+    /// [`Self::process_pdf`].
+    pub fn process_body(&self, context: &PageContext, body: &PageBody) -> Result<ProcessedPage> {
+        match body {
+            PageBody::Html(html) => self.process_page(context, html),
+            PageBody::Pdf(bytes) => self.process_pdf(context, bytes),
+        }
+    }
+
+    /// Whether the page is a PDF to convert with [`Self::process_pdf`].
+    pub fn handles_pdf(&self, context: &PageContext) -> bool {
+        self.pdf_limits.is_some()
+            && context
+                .content_type
+                .as_deref()
+                .is_some_and(is_pdf_content_type)
+    }
 
-        // Extract meta description
-        let description = self.extract_meta_description(document).unwrap_or_else(|| {
-            // Fall back to first paragraph if no meta description
-            self.extract_first_paragraph(document).unwrap_or_default()
+    /// Processes a PDF page from its raw bytes: the extracted text is
+    /// handled like a markdown page. Fails with [`SkippedPage`] if the PDF
+    /// exceeds the configured limits.
+    pub fn process_pdf(&self, context: &PageContext, bytes: &[u8]) -> Result<ProcessedPage> {
+        let limits = self
+            .pdf_limits
+            .context("PDF processing is off (set process_pdfs: true)")?;
+        let markdown = pdf::to_markdown(&context.url, bytes, limits)?;
+        self.process_text(context, &markdown)
+    }
+
+    /// The requested URL to record for a redirected page.
+    fn requested_url(&self, context: &PageContext) -> Option<String> {
+        context
+            .requested_url
+            .clone()
+            .filter(|_| self.record_requested_url)
+    }
+
+    /// Processes a page whose body is already markdown or plain text. The
+    /// body is used as-is; the title comes from its first heading, or the
+    /// URL if it has none.
+    fn process_text(&self, context: &PageContext, text: &str) -> Result<ProcessedPage> {
+        let url = context.url.as_str();
+        let text = text.trim();
+        let heading = text.lines().enumerate().find_map(|(i, line)| {
+            let title = line.trim_start_matches('#');
+            let level = line.len() - title.len();
+            ((1..=6).contains(&level) && title.starts_with(' ') && !title.trim().is_empty())
+                .then(|| (i, title.trim().to_string()))
         });
 
-        // Generate skill name from URL path
-        let url_path = extract_url_path(url);
-        let skill_name = sanitize_skill_name(&url_path);
+        // A leading heading becomes the title rather than being repeated
+        let (title, content) = match heading {
+            Some((0, title)) => (
+                title,
+                text.split_once('\n')
+                    .map(|(_, rest)| rest)
+                    .unwrap_or_default(),
+            ),
+            Some((_, title)) => (title, text),
+            None => (title_from_url(url), text),
+        };
+        let (skill_name, fallback_skill_name) = self.skill_names(&title, url);
+        let language = self.page_language(context, None, || content.to_string())?;
 
-        // Handle edge case where skill_name is empty (e.g., root URL)
-        let skill_name = if skill_name.is_empty() {
-            // Use domain as skill name
-            crate::utils::extract_domain(url)
-                .map(|d| sanitize_skill_name(&d))
-                .unwrap_or_else(|| "index".to_string())
-        } else {
-            skill_name
+        let description = content
+            .split("\n\n")
+            .map(str::trim)
+            .find(|block| !block.is_empty() && !block.starts_with('#'))
+            .map(|block| block.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_default();
+
+        let metadata = PageMetadata {
+            title,
+            description,
+            url: url.to_string(),
+            requested_url: self.requested_url(context),
+            canonical_url: None,
+            language,
+            skill_name,
+            fallback_skill_name,
+            processed_at: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            tags: match self.heading_tags {
+                true => {
+                    let headings: Vec<(usize, String)> = outline::headings(content).collect();
+                    tags::from_headings(headings.iter().map(|(_, text)| text.as_str()))
+                }
+                false => Vec::new(),
+            },
+        };
+        let markdown_content = links::absolutize(content.trim(), url, self.drop_anchor_links);
+        let markdown_content = images::resolve(&markdown_content, self.images);
+        self.check_content_length(url, &markdown_content)?;
+        let (skill_md, references) = self.generate_skill(&metadata, &markdown_content)?;
+
+        Ok(ProcessedPage {
+            links: links::markdown_links(&markdown_content, url),
+            metadata,
+            cleaned_html: String::new(),
+            markdown_content,
+            skill_md,
+            assets: Vec::new(),
+            references,
+        })
+    }
+
+    /// The language of a page: what it `declared` in its HTML, else its
+    /// `Content-Language`, else with `strict_language` what its `text` is
+    /// written in. Fails with [`OtherLanguage`] if that isn't one of
+    /// `languages`; pages of unknown language are kept unless
+    /// `strict_language` is set.
+    fn page_language(
+        &self,
+        context: &PageContext,
+        declared: Option<String>,
+        text: impl FnOnce() -> String,
+    ) -> Result<Option<String>> {
+        let declared = declared.or_else(|| context.content_language.clone());
+        if self.languages.is_empty() {
+            return Ok(declared);
+        }
+
+        let language = match declared {
+            Some(language) => Some(language),
+            None if self.strict_language => language::detect(&text()).map(str::to_string),
+            None => return Ok(None),
         };
+        match language {
+            Some(language) if language::matches(&self.languages, &language) => Ok(Some(language)),
+            language => Err(OtherLanguage {
+                url: context.url.clone(),
+                language: language.unwrap_or_else(|| "unknown".to_string()),
+            }
+            .into()),
+        }
+    }
+
+    /// Fails with [`PageTooSmall`] if the markdown is below
+    /// `min_content_chars`.
+    fn check_content_length(&self, url: &str, markdown: &str) -> Result<()> {
+        let chars = markdown.chars().count();
+        if chars < self.min_content_chars {
+            return Err(PageTooSmall {
+                url: url.to_string(),
+                chars,
+                min_chars: self.min_content_chars,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Converts HTML to Markdown, rendering tables with [`tables`] instead
+    /// of htmd.
+    ///
+    /// Each outermost table is swapped for a placeholder paragraph before
+    /// conversion and the placeholder line replaced with the rendered table
+    /// afterwards. Cells go through this method too, so nested tables are
+    /// handled the same way.
+    fn html_to_markdown(&self, html: &str) -> Result<String> {
+        if !html.contains("<table") {
+            return Ok(self.converter.convert(html)?);
+        }
+
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("table").expect("valid selector");
+        let convert_cell = |cell: &str| self.html_to_markdown(cell);
+
+        let mut source = document.html();
+        let mut rendered = Vec::new();
+        for table in document.select(&selector) {
+            let nested = table
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|e| e.value().name() == "table");
+            if nested {
+                continue;
+            }
+
+            let table_html = table.html();
+            if !source.contains(&table_html) {
+                continue;
+            }
+            let Some(markdown) = tables::render_table(table, &convert_cell, self.max_table_cells)?
+            else {
+                continue;
+            };
+
+            let placeholder = format!("<p>{}{}</p>", TABLE_PLACEHOLDER, rendered.len());
+            source = source.replacen(&table_html, &placeholder, 1);
+            rendered.push(markdown);
+        }
+
+        let markdown = self.converter.convert(&source)?;
+
+        // Keep the placeholder's indentation so tables inside lists stay put
+        let lines: Vec<String> = markdown
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                let table = trimmed
+                    .strip_prefix(TABLE_PLACEHOLDER)
+                    .and_then(|index| index.trim_end().parse::<usize>().ok())
+                    .and_then(|index| rendered.get(index));
+
+                match table {
+                    Some(table) => {
+                        let indent = &line[..line.len() - trimmed.len()];
+                        table
+                            .lines()
+                            .map(|l| format!("{}{}", indent, l))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    }
+                    None => line.to_string(),
+                }
+            })
+            .collect();
+
+        Ok(lines.join("\n"))
+    }
+
+    /// Extracts metadata from the `<head>` of the original `document`,
+    /// falling back on its cleaned `content`.
+    fn extract_metadata(&self, url: &str, document: &Html, content: &Html) -> Result<PageMetadata> {
+        // Extract title
+        let title = self
+            .extract_title(document, content)
+            .unwrap_or_else(|| UNTITLED.to_string());
+        let (skill_name, fallback_skill_name) = self.skill_names(&title, url);
+
+        // Extract meta description; pages without one are described by
+        // their cleaned content later on
+        let description = self.extract_meta_description(document).unwrap_or_default();
 
         Ok(PageMetadata {
             title,
             description,
             url: url.to_string(),
+            requested_url: None,
+            canonical_url: canonical_url(document, url),
+            language: None,
             skill_name,
+            fallback_skill_name,
             processed_at: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            tags: self.extract_tags(document, content),
         })
     }
 
-    /// Extracts the page title.
-    fn extract_title(&self, document: &Html) -> Option<String> {
+    /// Tags from the keywords meta tag of `document`, or with
+    /// `heading_tags` from the headings of its cleaned `content`.
+    fn extract_tags(&self, document: &Html, content: &Html) -> Vec<String> {
+        let keywords = self
+            .best_meta_content(document, "meta[name='keywords']")
+            .map(|keywords| tags::from_keywords(&keywords))
+            .unwrap_or_default();
+        if !keywords.is_empty() || !self.heading_tags {
+            return keywords;
+        }
+
+        let Ok(selector) = Selector::parse("h1, h2, h3, h4, h5, h6") else {
+            return Vec::new();
+        };
+        let headings: Vec<String> = content
+            .select(&selector)
+            .map(|element| element.text().collect())
+            .collect();
+        tags::from_headings(headings.iter().map(String::as_str))
+    }
+
+    /// The skill name of a page titled `title`, per `skill_name_source`,
+    /// and the name to fall back on if another page holds it. Pages without
+    /// a title, or whose title leaves nothing to name a skill after (only
+    /// emoji, say), are named after their URL.
+    fn skill_names(&self, title: &str, url: &str) -> (String, Option<String>) {
+        let relative = self
+            .local_root
+            .as_deref()
+            .and_then(|root| crate::local::relative_path(url, root));
+        let from_url = match relative {
+            Some(path) => sanitize_skill_name(&path),
+            None => skill_name_for(
+                url,
+                &self.significant_query_params,
+                self.transliterate_names,
+            ),
+        };
+        let from_title = match self.transliterate_names {
+            true => title_to_skill_name(&transliterate(title)),
+            false => title_to_skill_name(title),
+        };
+        match self.skill_name_source {
+            SkillNameSource::Url => (from_url, None),
+            _ if from_title.is_empty() || title == UNTITLED => (from_url, None),
+            SkillNameSource::Title => (from_title, None),
+            SkillNameSource::TitleFallbackUrl => (from_title, Some(from_url)),
+        }
+    }
+
+    /// Extracts the page title from the original `document`, or the first
+    /// `<h1>` of its cleaned `content`.
+    ///
+    /// SPA hydration can leave several `<title>` elements behind, so all of
+    /// them are ranked rather than taking the first.
+    fn extract_title(&self, document: &Html, content: &Html) -> Option<String> {
         // Try <title> first
-        if let Ok(selector) = Selector::parse("title")
-            && let Some(element) = document.select(&selector).next()
-        {
-            let title: String = element.text().collect();
-            let title = title.trim();
-            if !title.is_empty() {
-                return Some(title.to_string());
+        if let Ok(selector) = Selector::parse("title") {
+            let candidates = document
+                .select(&selector)
+                .map(|element| element.text().collect::<String>());
+            if let Some(title) = self.best_candidate(candidates) {
+                return Some(title);
             }
         }
 
-        // Fall back to first <h1>
+        // Fall back to first <h1> that survived cleaning
         if let Ok(selector) = Selector::parse("h1")
-            && let Some(element) = document.select(&selector).next()
+            && let Some(element) = content.select(&selector).next()
         {
             let title: String = element.text().collect();
             let title = title.trim();
@@ -203,44 +1098,102 @@ impl Processor {
     }
 
     /// Extracts the meta description.
+    ///
+    /// Consent managers sometimes inject their own description tags ahead
+    /// of the real one, so every candidate is collected and ranked.
     fn extract_meta_description(&self, document: &Html) -> Option<String> {
-        if let Ok(selector) = Selector::parse("meta[name='description']")
-            && let Some(element) = document.select(&selector).next()
-            && let Some(content) = element.value().attr("content")
-        {
-            let content = content.trim();
-            if !content.is_empty() {
-                return Some(content.to_string());
-            }
-        }
+        self.best_meta_content(document, "meta[name='description']")
+            // Try og:description as fallback
+            .or_else(|| self.best_meta_content(document, "meta[property='og:description']"))
+    }
 
-        // Try og:description as fallback
-        if let Ok(selector) = Selector::parse("meta[property='og:description']")
-            && let Some(element) = document.select(&selector).next()
-            && let Some(content) = element.value().attr("content")
-        {
-            let content = content.trim();
-            if !content.is_empty() {
-                return Some(content.to_string());
+    /// Returns the best `content` value among all elements matching `selector`.
+    fn best_meta_content(&self, document: &Html, selector: &str) -> Option<String> {
+        let selector = Selector::parse(selector).ok()?;
+        let candidates = document
+            .select(&selector)
+            .filter_map(|element| element.value().attr("content"))
+            .map(str::to_string);
+        self.best_candidate(candidates)
+    }
+
+    /// Picks the longest non-empty candidate that doesn't contain a consent
+    /// phrase. Ties go to the candidate that appears first.
+    fn best_candidate(&self, candidates: impl Iterator<Item = String>) -> Option<String> {
+        let mut best: Option<String> = None;
+
+        for candidate in candidates {
+            let candidate = candidate.trim();
+            if candidate.is_empty() || self.is_consent_text(candidate) {
+                continue;
+            }
+            if best.as_ref().is_none_or(|b| candidate.len() > b.len()) {
+                best = Some(candidate.to_string());
             }
         }
 
-        None
+        best
+    }
+
+    /// Checks whether text matches one of the configured consent phrases.
+    fn is_consent_text(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.consent_phrases
+            .iter()
+            .any(|phrase| text.contains(phrase.as_str()))
     }
 
-    /// Extracts the first paragraph as a fallback description.
-    fn extract_first_paragraph(&self, document: &Html) -> Option<String> {
-        if let Ok(selector) = Selector::parse("p") {
-            for element in document.select(&selector) {
-                let text: String = element.text().collect();
-                let text = text.trim();
-                if text.len() > 50 {
-                    // Only use if it's substantial
-                    return Some(truncate_description(text, 200));
+    /// Applies content and removal selectors for the page.
+    ///
+    /// The first allow rule with selector overrides that matches `url`
+    /// supplies them; its `content_selectors` replace the global ones and its
+    /// `extra_remove_selectors` are removed first. Returns `None` when no
+    /// selector applies, so the raw HTML is used unchanged.
+    fn select_content(&self, url: &str, document: &Html) -> Option<String> {
+        let rule = self.rule_selectors.iter().find(|r| r.matcher.is_match(url));
+        let content_selectors = rule
+            .and_then(|r| r.content_selectors.as_deref())
+            .unwrap_or(&self.content_selectors);
+        let remove_selectors = rule
+            .map(|r| r.extra_remove_selectors.as_slice())
+            .unwrap_or_default();
+
+        if content_selectors.is_empty() && remove_selectors.is_empty() {
+            return None;
+        }
+
+        let mut document = document.clone();
+        for selector in remove_selectors {
+            let ids: Vec<_> = document.select(selector).map(|e| e.id()).collect();
+            for id in ids {
+                if let Some(mut node) = document.tree.get_mut(id) {
+                    node.detach();
                 }
             }
         }
-        None
+
+        for selector in content_selectors {
+            let matches: Vec<_> = document.select(selector).collect();
+            // Skip matches nested inside another match so nothing is duplicated
+            let parts: Vec<String> = matches
+                .iter()
+                .filter(|e| {
+                    !e.ancestors()
+                        .any(|a| matches.iter().any(|m| m.id() == a.id()))
+                })
+                .map(|e| e.html())
+                .collect();
+
+            if !parts.is_empty() {
+                return Some(parts.join("\n"));
+            }
+        }
+
+        if !content_selectors.is_empty() {
+            debug!("No content selector matched {}, using the whole page", url);
+        }
+
+        Some(document.html())
     }
 
     /// Cleans HTML by removing noise elements.
@@ -254,257 +1207,64 @@ impl Processor {
     /// - Ads and cookie banners
     /// - Skip links and accessibility shortcuts
     /// - Material icons and icon fonts
+    ///
+    /// The patterns are compiled once ([`NOISE_REGEXES`]), and a pattern
+    /// that matches nothing doesn't copy the HTML, so a page is only copied
+    /// for the noise it actually has.
     fn clean_html(&self, html: &str) -> Result<String> {
-        // We'll use regex patterns to remove noise elements from HTML.
-        // Note: For production, consider using a proper HTML manipulation library
-        // but regex works well for removing well-structured noise elements.
-
-        let mut cleaned = html.to_string();
-
-        // Remove script tags and their content
-        let script_re = regex::Regex::new(r"(?is)<script[^>]*>.*?</script>").unwrap();
-        cleaned = script_re.replace_all(&cleaned, "").to_string();
-
-        // Remove style tags and their content
-        let style_re = regex::Regex::new(r"(?is)<style[^>]*>.*?</style>").unwrap();
-        cleaned = style_re.replace_all(&cleaned, "").to_string();
-
-        // Remove noscript tags and their content
-        let noscript_re = regex::Regex::new(r"(?is)<noscript[^>]*>.*?</noscript>").unwrap();
-        cleaned = noscript_re.replace_all(&cleaned, "").to_string();
-
-        // Remove template tags (often used for dynamic content)
-        let template_re = regex::Regex::new(r"(?is)<template[^>]*>.*?</template>").unwrap();
-        cleaned = template_re.replace_all(&cleaned, "").to_string();
-
-        // Remove common noise elements by tag name
-        let noise_patterns = [
-            r"(?is)<nav[^>]*>.*?</nav>",
-            r"(?is)<footer[^>]*>.*?</footer>",
-            r"(?is)<header[^>]*>.*?</header>",
-            r"(?is)<aside[^>]*>.*?</aside>",
-            r"(?is)<iframe[^>]*>.*?</iframe>",
-            r"(?is)<svg[^>]*>.*?</svg>",
-            r"(?is)<canvas[^>]*>.*?</canvas>",
-            r"(?is)<video[^>]*>.*?</video>",
-            r"(?is)<audio[^>]*>.*?</audio>",
-            r"(?is)<form[^>]*>.*?</form>", // Remove forms (search, feedback, etc.)
+        let mut cleaned = Cow::Borrowed(html);
+        for re in NOISE_REGEXES.iter() {
+            if let Cow::Owned(replaced) = re.replace_all(&cleaned, "") {
+                cleaned = Cow::Owned(replaced);
+            }
+        }
+
+        debug!("Cleaned HTML: {} -> {} bytes", html.len(), cleaned.len());
+
+        Ok(cleaned.into_owned())
+    }
+
+    /// Post-processes markdown to remove remaining noise artifacts.
+    ///
+    /// This handles content that slips through HTML cleaning, such as:
+    /// - Material icon names (chevron_right, content_copy, etc.)
+    /// - Skip link text
+    /// - Cookie consent text
+    /// - Feedback prompts
+    /// - Page metadata footers
+    fn clean_markdown(&self, markdown: &str) -> String {
+        let mut cleaned = markdown.to_string();
+
+        // Remove common material icon names that appear as text
+        if let Some(ref re) = self.icon_names_re {
+            cleaned = re.replace_all(&cleaned, "").to_string();
+        }
+
+        // Clean up lines that become empty or only whitespace after icon removal
+        let empty_lines_re = regex::Regex::new(r"(?m)^\s*$").unwrap();
+        cleaned = empty_lines_re.replace_all(&cleaned, "").to_string();
+
+        // Remove skip link patterns
+        let skip_patterns = [
+            r"(?m)^\[Skip to main content\]\([^)]*\)\s*$",
+            r"(?m)^\[Skip to content\]\([^)]*\)\s*$",
+            r"(?m)^Skip to (main )?content\s*$",
         ];
 
-        for pattern in noise_patterns {
+        for pattern in skip_patterns {
             if let Ok(re) = regex::Regex::new(pattern) {
                 cleaned = re.replace_all(&cleaned, "").to_string();
             }
         }
 
-        // Remove elements by ID patterns (common noise IDs)
-        let id_patterns = [
-            r#"(?is)<[^>]+id="[^"]*\b(cookie|consent|banner|popup|modal|overlay|gdpr|privacy-notice|skip-link|feedback|newsletter|subscribe)\b[^"]*"[^>]*>.*?</[^>]+>"#,
+        // Remove cookie consent notices
+        let cookie_patterns = [
+            r"(?is).*uses cookies.*\n.*Learn more.*OK,? got it\s*",
+            r"(?is)This site uses cookies.*\n?.*Accept\s*",
+            r"(?is)We use cookies.*\n?.*Got it\s*",
         ];
 
-        for pattern in id_patterns {
-            if let Ok(re) = regex::Regex::new(pattern) {
-                cleaned = re.replace_all(&cleaned, "").to_string();
-            }
-        }
-
-        // Remove elements with common noise class names
-        let class_patterns = [
-            // Navigation and menus
-            r#"(?is)<[^>]+class="[^"]*\b(nav|navigation|menu|sidebar|toc|table-of-contents|breadcrumb|breadcrumbs)\b[^"]*"[^>]*>.*?</[^>]+>"#,
-            // Cookie and consent banners
-            r#"(?is)<[^>]+class="[^"]*\b(cookie|consent|gdpr|privacy-notice|cookie-banner|cookie-consent)\b[^"]*"[^>]*>.*?</[^>]+>"#,
-            // Ads and promotional content
-            r#"(?is)<[^>]+class="[^"]*\b(ads?|advertisement|promo|promotional|banner|announcement)\b[^"]*"[^>]*>.*?</[^>]+>"#,
-            // Feedback and ratings
-            r#"(?is)<[^>]+class="[^"]*\b(feedback|rating|ratings|helpful|thumbs|vote|voting)\b[^"]*"[^>]*>.*?</[^>]+>"#,
-            // Skip links and accessibility shortcuts
-            r#"(?is)<[^>]+class="[^"]*\b(skip-link|skip-to-content|sr-only|visually-hidden)\b[^"]*"[^>]*>.*?</[^>]+>"#,
-            // Social sharing
-            r#"(?is)<[^>]+class="[^"]*\b(social|share|sharing|follow-us)\b[^"]*"[^>]*>.*?</[^>]+>"#,
-            // Page metadata/footer info
-            r#"(?is)<[^>]+class="[^"]*\b(page-meta|page-info|last-updated|edit-page|view-source|report-issue)\b[^"]*"[^>]*>.*?</[^>]+>"#,
-        ];
-
-        for pattern in class_patterns {
-            if let Ok(re) = regex::Regex::new(pattern) {
-                cleaned = re.replace_all(&cleaned, "").to_string();
-            }
-        }
-
-        // Remove skip links (often standalone anchor tags)
-        // Using r##""## because the pattern contains # character
-        if let Ok(skip_link_re) =
-            regex::Regex::new(r##"(?is)<a[^>]+href="#[^"]*"[^>]*>Skip[^<]*</a>"##)
-        {
-            cleaned = skip_link_re.replace_all(&cleaned, "").to_string();
-        }
-
-        // Remove material icons and icon fonts (span/i elements with icon classes)
-        let icon_patterns = [
-            r"(?is)<span[^>]+class=[^>]*(material-icons|icon|fa|fas|far|fab|glyphicon)[^>]*>[^<]*</span>",
-            r"(?is)<i[^>]+class=[^>]*(material-icons|icon|fa|fas|far|fab|glyphicon)[^>]*>[^<]*</i>",
-            // Remove inline material icon text that might be in any element
-            r"(?is)<[^>]+class=[^>]*material-symbols[^>]*>[^<]*</[^>]+>",
-        ];
-
-        for pattern in icon_patterns {
-            if let Ok(re) = regex::Regex::new(pattern) {
-                cleaned = re.replace_all(&cleaned, "").to_string();
-            }
-        }
-
-        // Remove button elements that are likely UI controls (copy buttons, etc.)
-        if let Ok(button_re) = regex::Regex::new(r"(?is)<button[^>]*>.*?</button>") {
-            cleaned = button_re.replace_all(&cleaned, "").to_string();
-        }
-
-        // Remove HTML comments
-        let comment_re = regex::Regex::new(r"(?s)<!--.*?-->").unwrap();
-        cleaned = comment_re.replace_all(&cleaned, "").to_string();
-
-        // Remove data attributes that might contain noise
-        if let Ok(data_attr_re) = regex::Regex::new(r#"\s+data-[a-z-]+="[^"]*""#) {
-            cleaned = data_attr_re.replace_all(&cleaned, "").to_string();
-        }
-
-        debug!("Cleaned HTML: {} -> {} bytes", html.len(), cleaned.len());
-
-        Ok(cleaned)
-    }
-
-    /// Post-processes markdown to remove remaining noise artifacts.
-    ///
-    /// This handles content that slips through HTML cleaning, such as:
-    /// - Material icon names (chevron_right, content_copy, etc.)
-    /// - Skip link text
-    /// - Cookie consent text
-    /// - Feedback prompts
-    /// - Page metadata footers
-    fn clean_markdown(&self, markdown: &str) -> String {
-        let mut cleaned = markdown.to_string();
-
-        // Remove common material icon names that appear as text
-        let icon_names = [
-            "chevron_right",
-            "chevron_left",
-            "arrow_forward",
-            "arrow_back",
-            "arrow_drop_down",
-            "arrow_drop_up",
-            "content_copy",
-            "content_paste",
-            "thumb_up",
-            "thumb_down",
-            "thumbs_up",
-            "thumbs_down",
-            "vertical_align_top",
-            "vertical_align_bottom",
-            "expand_more",
-            "expand_less",
-            "menu",
-            "close",
-            "search",
-            "home",
-            "settings",
-            "check",
-            "check_circle",
-            "error",
-            "warning",
-            "info",
-            "list",
-            "share",
-            "edit",
-            "delete",
-            "add",
-            "remove",
-            "star",
-            "star_border",
-            "favorite",
-            "favorite_border",
-            "bookmark",
-            "bookmark_border",
-            "visibility",
-            "visibility_off",
-            "lock",
-            "lock_open",
-            "person",
-            "people",
-            "notifications",
-            "email",
-            "phone",
-            "location_on",
-            "calendar_today",
-            "schedule",
-            "more_vert",
-            "more_horiz",
-            "open_in_new",
-            "launch",
-            "link",
-            "file_download",
-            "file_upload",
-            "cloud_download",
-            "cloud_upload",
-            "play_arrow",
-            "pause",
-            "stop",
-            "skip_next",
-            "skip_previous",
-            "fast_forward",
-            "fast_rewind",
-            "volume_up",
-            "volume_down",
-            "volume_mute",
-            "fullscreen",
-            "fullscreen_exit",
-            "zoom_in",
-            "zoom_out",
-            "refresh",
-            "sync",
-            "cached",
-            "done",
-            "done_all",
-            "clear",
-            "cancel",
-            "help",
-            "help_outline",
-            "code",
-        ];
-
-        for icon in icon_names {
-            // Remove icon name with word boundaries (handles inline occurrences)
-            // This catches icons appearing anywhere in text
-            let pattern = format!(r"\b{}\b", regex::escape(icon));
-            if let Ok(re) = regex::Regex::new(&pattern) {
-                cleaned = re.replace_all(&cleaned, "").to_string();
-            }
-        }
-
-        // Clean up lines that become empty or only whitespace after icon removal
-        let empty_lines_re = regex::Regex::new(r"(?m)^\s*$").unwrap();
-        cleaned = empty_lines_re.replace_all(&cleaned, "").to_string();
-
-        // Remove skip link patterns
-        let skip_patterns = [
-            r"(?m)^\[Skip to main content\]\([^)]*\)\s*$",
-            r"(?m)^\[Skip to content\]\([^)]*\)\s*$",
-            r"(?m)^Skip to (main )?content\s*$",
-        ];
-
-        for pattern in skip_patterns {
-            if let Ok(re) = regex::Regex::new(pattern) {
-                cleaned = re.replace_all(&cleaned, "").to_string();
-            }
-        }
-
-        // Remove cookie consent notices
-        let cookie_patterns = [
-            r"(?is).*uses cookies.*\n.*Learn more.*OK,? got it\s*",
-            r"(?is)This site uses cookies.*\n?.*Accept\s*",
-            r"(?is)We use cookies.*\n?.*Got it\s*",
-        ];
-
-        for pattern in cookie_patterns {
+        for pattern in cookie_patterns {
             if let Ok(re) = regex::Regex::new(pattern) {
                 cleaned = re.replace_all(&cleaned, "").to_string();
             }
@@ -524,6 +1284,10 @@ impl Processor {
             }
         }
 
+        for re in &self.extra_feedback_patterns {
+            cleaned = re.replace_all(&cleaned, "").to_string();
+        }
+
         // Remove page metadata footers (common patterns)
         let footer_patterns = [
             r"(?m)^Unless stated otherwise.*Page last updated.*$",
@@ -552,6 +1316,11 @@ impl Processor {
             }
         }
 
+        // Remove site-specific boilerplate configured by the user
+        for re in &self.markdown_strip_patterns {
+            cleaned = re.replace_all(&cleaned, "").to_string();
+        }
+
         // Clean up excessive blank lines (more than 2 consecutive)
         let blank_lines_re = regex::Regex::new(r"\n{4,}").unwrap();
         cleaned = blank_lines_re.replace_all(&cleaned, "\n\n\n").to_string();
@@ -563,47 +1332,218 @@ impl Processor {
         cleaned.trim().to_string()
     }
 
+    /// Gives a processed page a new skill name, regenerating its SKILL.md so
+    /// the frontmatter matches the directory it is written to.
+    pub fn rename(&self, processed: &mut ProcessedPage, skill_name: &str) -> Result<()> {
+        processed.metadata.skill_name = skill_name.to_string();
+        (processed.skill_md, processed.references) =
+            self.generate_skill(&processed.metadata, &processed.markdown_content)?;
+        Ok(())
+    }
+
+    /// Downloads the images of a processed page with `images: download`,
+    /// pointing its markdown at the local copies. A no-op otherwise.
+    pub async fn download_images(&self, processed: &mut ProcessedPage) -> Result<()> {
+        let Some(ref downloader) = self.image_downloader else {
+            return Ok(());
+        };
+
+        let (markdown_content, assets) = downloader.download(&processed.markdown_content).await;
+        if !assets.is_empty() {
+            processed.markdown_content = markdown_content;
+            processed.assets = assets;
+            (processed.skill_md, processed.references) =
+                self.generate_skill(&processed.metadata, &processed.markdown_content)?;
+        }
+        Ok(())
+    }
+
+    /// Generates the skill file in `output_format`, along with the reference
+    /// files of a split page.
+    fn generate_skill(
+        &self,
+        metadata: &PageMetadata,
+        markdown_content: &str,
+    ) -> Result<(String, Vec<ReferenceFile>)> {
+        match self.output_format {
+            SkillFormat::Markdown => self.generate_skill_md(metadata, markdown_content),
+            SkillFormat::Json => Ok((
+                self.generate_skill_json(metadata, markdown_content)?,
+                Vec::new(),
+            )),
+            SkillFormat::Plain => Ok((
+                self.generate_skill_text(metadata, markdown_content),
+                Vec::new(),
+            )),
+        }
+    }
+
+    /// Generates a `skill.json`: the fields of [`PageMetadata`] and the
+    /// markdown as `body`.
+    fn generate_skill_json(
+        &self,
+        metadata: &PageMetadata,
+        markdown_content: &str,
+    ) -> Result<String> {
+        #[derive(Serialize)]
+        struct SkillJson<'a> {
+            #[serde(flatten)]
+            metadata: &'a PageMetadata,
+            estimated_tokens: usize,
+            body: &'a str,
+        }
+
+        let json = serde_json::to_string_pretty(&SkillJson {
+            metadata,
+            estimated_tokens: tokens::estimate(markdown_content),
+            body: markdown_content.trim(),
+        })
+        .with_context(|| format!("Failed to serialize skill '{}'", metadata.skill_name))?;
+        Ok(format!("{}\n", json))
+    }
+
+    /// Generates a `skill.txt`: the title and the markdown, without
+    /// frontmatter.
+    fn generate_skill_text(&self, metadata: &PageMetadata, markdown_content: &str) -> String {
+        format!("# {}\n\n{}\n", metadata.title, markdown_content.trim())
+    }
+
     /// Generates the consolidated SKILL.md content with full markdown.
     ///
     /// The SKILL.md file now contains ALL content directly:
-    /// - YAML frontmatter with metadata
+    /// - YAML frontmatter with metadata, including the heading outline
     /// - Page title
+    /// - A `## Contents` list of the headings, with `contents_section`
     /// - Full converted markdown content
     ///
     /// This simplifies the output structure to a single file per skill.
-    fn generate_skill_md(&self, metadata: &PageMetadata, markdown_content: &str) -> String {
+    ///
+    /// Content over `max_skill_chars` (unless `flat`) goes to the returned
+    /// reference files instead, and SKILL.md only has the description and
+    /// a link to each part, which the frontmatter lists too.
+    fn generate_skill_md(
+        &self,
+        metadata: &PageMetadata,
+        markdown_content: &str,
+    ) -> Result<(String, Vec<ReferenceFile>)> {
+        let split = !self.flat
+            && self.max_skill_chars > 0
+            && markdown_content.chars().count() > self.max_skill_chars;
+        let references = if split {
+            references::split(&metadata.title, markdown_content, self.max_skill_chars)
+        } else {
+            Vec::new()
+        };
+
         let truncated_description =
-            truncate_description(&metadata.description, MAX_DESCRIPTION_LENGTH);
+            truncate_description(&metadata.description, self.description_max_chars);
+        let outline = outline::outline(markdown_content);
+
+        let mut frontmatter = Frontmatter {
+            name: metadata.skill_name.clone(),
+            description: truncated_description.replace('\n', " ").replace('\r', ""),
+            extra: frontmatter::render_extra_fields(
+                &self.frontmatter_fields,
+                &metadata.url,
+                metadata.processed_at.split('T').next().unwrap_or_default(),
+            ),
+            metadata: FrontmatterMetadata {
+                url: metadata.url.clone(),
+                requested_url: metadata.requested_url.clone(),
+                canonical_url: metadata.canonical_url.clone(),
+                language: metadata.language.clone(),
+                estimated_tokens: Some(tokens::estimate(markdown_content)),
+                sections: outline::sections(&outline),
+                tags: metadata.tags.clone(),
+                references: references.iter().map(ReferenceFile::path).collect(),
+                ..Default::default()
+            },
+        };
+        if self.frontmatter_metadata == MetadataLevel::Full {
+            frontmatter.metadata.processed_at = Some(metadata.processed_at.clone());
+            frontmatter.metadata.generator = Some(format!(
+                "agent-skills-generator {}",
+                version_check::CURRENT_VERSION
+            ));
+            frontmatter.metadata.content_hash = Some(content_hash(markdown_content));
+        }
+        for truncation in frontmatter.fit_to_budget(self.frontmatter_budget)? {
+            debug!(
+                "Frontmatter of '{}' over budget: kept {} of {} {}",
+                metadata.skill_name,
+                truncation.kept,
+                truncation.kept + truncation.dropped,
+                truncation.field.name()
+            );
+        }
+
+        if !references.is_empty() {
+            debug!(
+                "Split '{}' into {} reference files",
+                metadata.skill_name,
+                references.len()
+            );
+            let skill_md = format!(
+                "---\n{frontmatter}---\n\n# {title}\n\n{summary}\n",
+                frontmatter = frontmatter.to_yaml()?,
+                title = metadata.title,
+                summary = reference_summary(metadata, &references),
+            );
+            return Ok((skill_md, references));
+        }
 
         // Warn if content is large (may consume many tokens)
         let total_chars = markdown_content.len();
         if total_chars > LARGE_CONTENT_THRESHOLD {
             warn!(
-                "Large skill '{}': {} characters (~{} tokens). Consider splitting into smaller sections.",
+                "Large skill '{}': {} characters (~{} tokens). Consider setting max_skill_chars to split it.",
                 metadata.skill_name,
                 total_chars,
                 total_chars / 4 // Rough token estimate
             );
         }
 
-        format!(
-            r#"---
-name: {name}
-description: {description}
-metadata:
-  url: {url}
----
-
-# {title}
-
-{content}
-"#,
-            name = metadata.skill_name,
-            description = truncated_description.replace('\n', " ").replace('\r', ""),
-            url = metadata.url,
+        let contents = outline::contents(&outline)
+            .filter(|_| self.contents_section)
+            .map(|contents| format!("{}\n\n", contents))
+            .unwrap_or_default();
+        let skill_md = format!(
+            "---\n{frontmatter}---\n\n# {title}\n\n{contents}{content}\n",
+            frontmatter = frontmatter.to_yaml()?,
             title = metadata.title,
             content = markdown_content.trim(),
-        )
+        );
+        Ok((skill_md, references))
+    }
+
+    /// Directory in `output_dir` the skill of the page at `url` is written
+    /// to: its `group_by` directory, or `output_dir` itself.
+    pub fn group_dir(&self, output_dir: &Path, url: &str) -> PathBuf {
+        match self.group_by.group(url) {
+            Some(group) => output_dir.join(group),
+            None => output_dir.to_path_buf(),
+        }
+    }
+
+    /// Path a skill is written to: its directory, or its `<name>.md` file
+    /// (`.json`, `.txt` with `output_format`) with `flat`.
+    pub fn skill_path(&self, output_dir: &Path, skill_name: &str) -> PathBuf {
+        if self.flat {
+            output_dir.join(format!("{}.{}", skill_name, self.output_format.extension()))
+        } else {
+            output_dir.join(skill_name)
+        }
+    }
+
+    /// Markdown file of a skill: `<name>/SKILL.md`, or `<name>.md` with
+    /// `flat`. With `output_format` it is `skill.json` or `skill.txt`.
+    pub fn skill_md_path(&self, output_dir: &Path, skill_name: &str) -> PathBuf {
+        let path = self.skill_path(output_dir, skill_name);
+        if self.flat {
+            path
+        } else {
+            path.join(self.output_format.file_name())
+        }
     }
 
     /// Writes the processed page to the output directory.
@@ -614,34 +1554,283 @@ metadata:
     ///   skill-name/
     ///     SKILL.md  <-- Contains ALL content
     /// ```
+    ///
+    /// A page split with `max_skill_chars` also gets its parts in
+    /// `skill-name/references/`, SKILL.md holding the summary.
+    ///
+    /// With `flat` the content goes to `output_dir/skill-name.md` instead,
+    /// and the cleaned HTML to `skill-name.html`. Downloaded images are
+    /// always saved in an `assets/` directory next to the markdown.
+    ///
+    /// An existing SKILL.md with the same content is not rewritten, so
+    /// re-crawls leave unchanged skills' `processed_at` and git history
+    /// alone. Its modification time is still bumped, which is how
+    /// `clean --older-than` tells it was seen by this crawl.
     pub async fn write_to_disk(
         &self,
         processed: &ProcessedPage,
         output_dir: &Path,
-    ) -> Result<std::path::PathBuf> {
+    ) -> Result<WrittenSkill> {
         use fs_err::tokio as fs;
 
+        let skill_name = &processed.metadata.skill_name;
+        let skill_dir = self.skill_path(output_dir, skill_name);
+        let skill_md_path = self.skill_md_path(output_dir, skill_name);
+        let (base_dir, html_path) = if self.flat {
+            (
+                output_dir.to_path_buf(),
+                output_dir.join(format!("{}.html", skill_name)),
+            )
+        } else {
+            (skill_dir.clone(), skill_dir.join(CLEANED_HTML_FILE))
+        };
+
         // Create skill directory
-        let skill_dir = output_dir.join(&processed.metadata.skill_name);
-        fs::create_dir_all(&skill_dir).await.with_context(|| {
-            format!("Failed to create skill directory: {}", skill_dir.display())
-        })?;
+        fs::create_dir_all(&base_dir)
+            .await
+            .with_context(|| format!("Failed to create skill directory: {}", base_dir.display()))?;
+
+        // Write SKILL.md with full content, unless it's already there
+        let skill_md_changed = match fs::read_to_string(&skill_md_path).await {
+            Ok(existing) => {
+                frontmatter::without_volatile_fields(&existing)
+                    != frontmatter::without_volatile_fields(&processed.skill_md)
+            }
+            Err(_) => true,
+        };
+        if skill_md_changed {
+            fs::write(&skill_md_path, &processed.skill_md)
+                .await
+                .with_context(|| {
+                    format!("Failed to write SKILL.md: {}", skill_md_path.display())
+                })?;
+
+            debug!(
+                "Wrote skill '{}' ({} chars) to {}",
+                skill_name,
+                processed.skill_md.len(),
+                skill_md_path.display()
+            );
+        } else {
+            debug!(
+                "Skill '{}' is unchanged in {}",
+                skill_name,
+                skill_md_path.display()
+            );
+            touch(&skill_md_path)?;
+        }
+
+        // Keep the cleaned HTML next to it; text pages have none
+        let mut html_changed = false;
+        if self.include_html && !processed.cleaned_html.is_empty() {
+            html_changed = fs::read_to_string(&html_path).await.ok().as_deref()
+                != Some(processed.cleaned_html.as_str());
+            if html_changed {
+                fs::write(&html_path, &processed.cleaned_html)
+                    .await
+                    .with_context(|| format!("Failed to write {}", html_path.display()))?;
+            }
+        }
+
+        // Downloaded images are named by content, so existing ones are kept
+        let mut assets_changed = false;
+        if !processed.assets.is_empty() {
+            let assets_dir = base_dir.join(ASSETS_DIR);
+            fs::create_dir_all(&assets_dir)
+                .await
+                .with_context(|| format!("Failed to create {}", assets_dir.display()))?;
+            for asset in &processed.assets {
+                let path = assets_dir.join(&asset.file_name);
+                if !path.exists() {
+                    fs::write(&path, &asset.bytes)
+                        .await
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    assets_changed = true;
+                }
+            }
+        }
+
+        let references_changed = self.write_references(processed, &base_dir).await?;
+
+        Ok(WrittenSkill {
+            skill_dir,
+            skill_name: skill_name.clone(),
+            changed: skill_md_changed || html_changed || assets_changed || references_changed,
+        })
+    }
+
+    /// Writes the reference files of a split page to `references/`,
+    /// removing parts left over from a longer version of the page (and the
+    /// directory once it is empty). Returns whether anything changed.
+    async fn write_references(&self, processed: &ProcessedPage, base_dir: &Path) -> Result<bool> {
+        use fs_err::tokio as fs;
+
+        let references_dir = base_dir.join(REFERENCES_DIR);
+        if processed.references.is_empty() && !references_dir.is_dir() {
+            return Ok(false);
+        }
+
+        let mut changed = false;
+        if !processed.references.is_empty() {
+            fs::create_dir_all(&references_dir)
+                .await
+                .with_context(|| format!("Failed to create {}", references_dir.display()))?;
+        }
+        for reference in &processed.references {
+            let path = references_dir.join(&reference.file_name);
+            if fs::read_to_string(&path).await.ok().as_deref() != Some(reference.content.as_str()) {
+                fs::write(&path, &reference.content)
+                    .await
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                changed = true;
+            }
+        }
 
-        // Write SKILL.md with full content
-        let skill_md_path = skill_dir.join("SKILL.md");
-        fs::write(&skill_md_path, &processed.skill_md)
+        let mut entries = fs::read_dir(&references_dir)
             .await
-            .with_context(|| format!("Failed to write SKILL.md: {}", skill_md_path.display()))?;
+            .with_context(|| format!("Failed to read {}", references_dir.display()))?;
+        let mut remaining = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let stale = file_name.starts_with("part-")
+                && file_name.ends_with(".md")
+                && !processed
+                    .references
+                    .iter()
+                    .any(|reference| reference.file_name == file_name);
+            if stale {
+                fs::remove_file(entry.path()).await?;
+                changed = true;
+            } else {
+                remaining += 1;
+            }
+        }
+        if remaining == 0 {
+            fs::remove_dir(&references_dir).await?;
+        }
 
-        debug!(
-            "Wrote skill '{}' ({} chars) to {}",
-            processed.metadata.skill_name,
-            processed.skill_md.len(),
-            skill_dir.display()
-        );
+        Ok(changed)
+    }
+}
+
+/// Body of a split page's SKILL.md: the description, then one link per
+/// reference file with the headings it covers.
+fn reference_summary(metadata: &PageMetadata, references: &[ReferenceFile]) -> String {
+    let mut summary = String::new();
+    if !metadata.description.trim().is_empty() {
+        summary.push_str(metadata.description.trim());
+        summary.push_str("\n\n");
+    }
+    summary.push_str(&format!(
+        "The full page is split into {} reference files:\n\n",
+        references.len()
+    ));
+    for (i, reference) in references.iter().enumerate() {
+        summary.push_str(&format!("- [Part {}]({})", i + 1, reference.path()));
+        if !reference.headings.is_empty() {
+            summary.push_str(&format!(": {}", reference.headings.join(", ")));
+        }
+        summary.push('\n');
+    }
+    summary.trim_end().to_string()
+}
+
+/// Generates a skill name from the URL path and its `query_params`,
+/// spelled in ASCII with `transliterate`.
+fn skill_name_for(url: &str, query_params: &[String], transliterate: bool) -> String {
+    let query = query_path(url, |name| {
+        query_params
+            .iter()
+            .any(|pattern| query_param_matches(pattern, name))
+    });
+    let url_path = match transliterate {
+        true => {
+            crate::utils::transliterate(&percent_decode(&extract_url_path(url)))
+                + &crate::utils::transliterate(&query)
+        }
+        false => extract_url_path(url) + &query,
+    };
+    let skill_name = sanitize_skill_name(&url_path);
+
+    // Handle edge case where skill_name is empty (e.g., root URL)
+    if skill_name.is_empty() {
+        // Use domain as skill name
+        crate::utils::extract_domain(url)
+            .map(|d| sanitize_skill_name(&d))
+            .unwrap_or_else(|| "index".to_string())
+    } else {
+        skill_name
+    }
+}
+
+/// Title for a page without a heading: its file name without extension,
+/// e.g. `llms` for `/llms.txt`, or the domain for the root.
+fn title_from_url(url: &str) -> String {
+    let path = extract_url_path(url);
+    let file_name = path.rsplit('/').find(|segment| !segment.is_empty());
+    match file_name {
+        Some(name) => {
+            let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+            stem.replace(['-', '_'], " ")
+        }
+        None => crate::utils::extract_domain(url).unwrap_or_else(|| UNTITLED.to_string()),
+    }
+}
+
+/// The language `document` declares with `<html lang>` or `og:locale`.
+fn declared_language(document: &Html) -> Option<String> {
+    let lang = Selector::parse("html[lang]").ok()?;
+    let locale = Selector::parse("meta[property='og:locale'][content]").ok()?;
+    document
+        .select(&lang)
+        .filter_map(|element| element.value().attr("lang"))
+        .chain(
+            document
+                .select(&locale)
+                .filter_map(|element| element.value().attr("content")),
+        )
+        .find_map(language::normalize)
+}
+
+/// The URL `document` declares canonical with `<link rel="canonical">`,
+/// resolved against the page's `url`, unless it is `url` itself.
+fn canonical_url(document: &Html, url: &str) -> Option<String> {
+    let selector = Selector::parse("link[rel~='canonical'][href]").ok()?;
+    let href = document
+        .select(&selector)
+        .find_map(|element| element.value().attr("href"))?;
+    let base = url::Url::parse(url).ok()?;
+    let mut canonical = base.join(href.trim()).ok()?;
+    canonical.set_fragment(None);
+    (matches!(canonical.scheme(), "http" | "https") && canonical != base)
+        .then(|| canonical.to_string())
+}
 
-        Ok(skill_dir)
+/// Builds a single whole-word regex over the built-in (unless disabled) and
+/// configured icon names. Returns `None` when there is nothing to strip.
+fn build_icon_names_regex(config: &Config) -> Result<Option<regex::Regex>> {
+    let builtin = if config.disable_builtin_icon_strip {
+        &[][..]
+    } else {
+        BUILTIN_ICON_NAMES
+    };
+
+    let alternatives: Vec<String> = builtin
+        .iter()
+        .copied()
+        .chain(config.extra_icon_names.iter().map(String::as_str))
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| regex::escape(name.trim()))
+        .collect();
+
+    if alternatives.is_empty() {
+        return Ok(None);
     }
+
+    let pattern = format!(r"\b(?:{})\b", alternatives.join("|"));
+    regex::Regex::new(&pattern)
+        .map(Some)
+        .context("Failed to build icon name pattern")
 }
 
 #[cfg(test)]
@@ -653,31 +1842,463 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_metadata() {
-        let processor = Processor::new(&test_config()).unwrap();
+    fn test_extract_metadata() {
+        let processor = Processor::new(&test_config()).unwrap();
+
+        let html = r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <title>Test Page Title</title>
+    <meta name="description" content="This is a test description.">
+</head>
+<body>
+    <h1>Main Heading</h1>
+    <p>Some content here.</p>
+</body>
+</html>
+"#;
+
+        let document = Html::parse_document(html);
+        let metadata = processor
+            .extract_metadata("https://example.com/docs/test", &document, &document)
+            .unwrap();
+
+        assert_eq!(metadata.title, "Test Page Title");
+        assert_eq!(metadata.description, "This is a test description.");
+        assert_eq!(metadata.skill_name, "docs-test");
+    }
+
+    #[test]
+    fn test_extract_metadata_tags() {
+        let html = r#"
+<html>
+<head>
+    <title>Routing</title>
+    <meta name="keywords" content="Routing, Navigator, deep links, routing">
+</head>
+<body>
+    <h1>Routing</h1>
+    <h2>Nested routes</h2>
+    <h2>Route guards</h2>
+</body>
+</html>
+"#;
+        let no_keywords = html.replace(r#"name="keywords""#, r#"name="x-keywords""#);
+        let tags = |heading_tags: bool, html: &str| {
+            let processor = Processor::new(&Config {
+                heading_tags,
+                ..test_config()
+            })
+            .unwrap();
+            let document = Html::parse_document(html);
+            processor
+                .extract_metadata("https://example.com/docs/routing", &document, &document)
+                .unwrap()
+                .tags
+        };
+
+        assert_eq!(tags(false, html), ["routing", "navigator", "deep links"]);
+        // Keywords win over headings
+        assert_eq!(tags(true, html), ["routing", "navigator", "deep links"]);
+        assert!(tags(false, &no_keywords).is_empty());
+        assert_eq!(
+            tags(true, &no_keywords),
+            ["routing", "nested", "routes", "route", "guards"]
+        );
+
+        let processed = Processor::new(&test_config())
+            .unwrap()
+            .process("https://example.com/docs/routing", html)
+            .unwrap();
+        assert!(
+            processed.skill_md.contains("tags:"),
+            "{}",
+            processed.skill_md
+        );
+        assert!(
+            processed.skill_md.contains("- deep links"),
+            "{}",
+            processed.skill_md
+        );
+    }
+
+    #[test]
+    fn test_page_language() {
+        let german = "Installieren Sie die Werkzeuge und fügen Sie sie zu Ihrem Pfad hinzu, \
+                      bevor Sie die Anwendung starten. Die Anleitung zeigt, wie es geht.";
+        let process = |config: &Config, context: &PageContext, head: &str| {
+            let html = format!(
+                "<html{head}><head><title>Install</title></head><body><p>{german}</p></body></html>"
+            );
+            Processor::new(config).unwrap().process_page(context, &html)
+        };
+        let language = |config: &Config, context: &PageContext, head: &str| {
+            process(config, context, head).unwrap().metadata.language
+        };
+        let english = Config {
+            languages: vec!["en".to_string()],
+            ..test_config()
+        };
+        let strict = Config {
+            strict_language: true,
+            ..english.clone()
+        };
+        let context = PageContext::new("https://example.com/docs/install");
+        let content_language = PageContext {
+            content_language: Some("en-gb".to_string()),
+            ..context.clone()
+        };
+
+        // Recorded whether or not languages are filtered
+        assert_eq!(
+            language(&test_config(), &context, r#" lang="de-DE""#).as_deref(),
+            Some("de-de")
+        );
+        assert_eq!(
+            language(&english, &context, r#" lang="en_US""#).as_deref(),
+            Some("en-us")
+        );
+        assert_eq!(
+            language(&english, &content_language, "").as_deref(),
+            Some("en-gb")
+        );
+        // <html lang> wins over the header
+        let error = process(&english, &content_language, r#" lang="de""#).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<OtherLanguage>().unwrap().language,
+            "de"
+        );
+
+        // Undeclared pages are kept, unless strict_language tells otherwise
+        assert_eq!(language(&english, &context, ""), None);
+        let error = process(&strict, &context, "").unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<OtherLanguage>().unwrap().language,
+            "de"
+        );
+
+        let processed = process(&english, &content_language, "").unwrap();
+        assert!(
+            processed.skill_md.contains("language: en-gb"),
+            "{}",
+            processed.skill_md
+        );
+    }
+
+    #[test]
+    fn test_extract_metadata_canonical_url() {
+        let canonical = |url: &str, head: &str| {
+            let html = format!("<html><head>{head}</head><body><h1>Install</h1></body></html>");
+            let document = Html::parse_document(&html);
+            Processor::new(&test_config())
+                .unwrap()
+                .extract_metadata(url, &document, &document)
+                .unwrap()
+                .canonical_url
+        };
+
+        let url = "https://example.com/docs/install/print";
+        assert_eq!(
+            canonical(url, r#"<link rel="canonical" href="../install#top">"#).as_deref(),
+            Some("https://example.com/docs/install")
+        );
+        assert_eq!(
+            canonical(
+                url,
+                r#"<link rel="alternate canonical" href="/docs/install">"#
+            )
+            .as_deref(),
+            Some("https://example.com/docs/install")
+        );
+        // Pages that are their own canonical have nothing to record
+        assert_eq!(
+            canonical(url, r#"<link rel="canonical" href="print">"#),
+            None
+        );
+        assert_eq!(
+            canonical(url, r#"<link rel="alternate" href="/amp">"#),
+            None
+        );
+
+        let processed = Processor::new(&test_config())
+            .unwrap()
+            .process(
+                url,
+                r#"<html><head><title>Install</title><link rel="canonical" href="/docs/install"></head>
+                   <body><h1>Install</h1><p>Run the installer.</p></body></html>"#,
+            )
+            .unwrap();
+        assert!(
+            processed
+                .skill_md
+                .contains("canonical_url: https://example.com/docs/install"),
+            "{}",
+            processed.skill_md
+        );
+    }
+
+    #[test]
+    fn test_extract_metadata_ignores_consent_injected_tags() {
+        let processor = Processor::new(&test_config()).unwrap();
+
+        let html = r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <title></title>
+    <meta name="description" content="We value your privacy. We and our partners use cookies to personalise content and ads.">
+    <meta property="og:description" content="Manage your privacy settings for this site and all partner sites.">
+    <meta property="og:description" content="Routing overview">
+    <title>Routing - Example Docs</title>
+    <meta name="description" content="Learn how routes are matched and rendered.">
+</head>
+<body><h1>Routing</h1></body>
+</html>
+"#;
+
+        let document = Html::parse_document(html);
+        let metadata = processor
+            .extract_metadata("https://example.com/docs/routing", &document, &document)
+            .unwrap();
+
+        assert_eq!(metadata.title, "Routing - Example Docs");
+        assert_eq!(
+            metadata.description,
+            "Learn how routes are matched and rendered."
+        );
+
+        // og:description gets the same ranking when there's no usable description
+        let og_only = html.replace(r#"name="description""#, r#"name="x-description""#);
+        let metadata = processor
+            .extract_metadata(
+                "https://example.com/docs/routing",
+                &Html::parse_document(&og_only),
+                &Html::new_document(),
+            )
+            .unwrap();
+        assert_eq!(metadata.description, "Routing overview");
+    }
+
+    #[test]
+    fn test_description_from_content() {
+        let processor = Processor::new(&test_config()).unwrap();
+        let describe = |html: &str| {
+            processor
+                .process("https://docs.example.com/guide", html)
+                .unwrap()
+                .metadata
+                .description
+        };
+
+        // Sphinx on Read the Docs, browsing an old release
+        let sphinx = r##"<html><head><title>Configuration — Acme 2.1 documentation</title></head><body>
+<div role="main" class="document">
+<p>You are not reading the most recent version of this documentation. 3.2 is the latest version available.</p>
+<section id="configuration"><h1>Configuration<a class="headerlink" href="#configuration">¶</a></h1>
+<p>Acme reads its options from <code>acme.toml</code> in the project root. Every setting can also be overridden with an environment variable. Unknown keys are rejected at startup.</p>
+<pre>[server]
+port = 8080</pre>
+</section></div></body></html>"##;
+        assert_eq!(
+            describe(sphinx),
+            "Acme reads its options from `acme.toml` in the project root. \
+             Every setting can also be overridden with an environment variable."
+        );
+
+        // Docusaurus with a consent banner and a version badge in the content
+        let docusaurus = r#"<html><head><title>Installation | Acme</title></head><body>
+<main><div class="banner"><p>We use cookies to understand how you use our site and to improve your experience.</p></div>
+<article><span class="badge">Version: 2.x</span>
+<header><h1>Installation</h1></header>
+<p>Install the CLI with <a href="/docs/npm">npm</a> or your preferred package manager. It requires <strong>Node.js 18</strong> or newer.</p>
+<ul><li>macOS, Linux and Windows are supported on every release</li></ul>
+</article></main></body></html>"#;
+        assert_eq!(
+            describe(docusaurus),
+            "Install the CLI with npm or your preferred package manager. \
+             It requires Node.js 18 or newer."
+        );
+
+        // MkDocs with a breadcrumb and a pre-release disclaimer after the heading
+        let mkdocs = r#"<html><head><title>Webhooks - Acme Docs</title></head><body>
+<article class="md-content__inner">
+<h1>Webhooks</h1>
+<p>This is the documentation for an unreleased version of Acme. For the latest stable version, see the main site.</p>
+<div class="admonition note"><p class="admonition-title">Note</p><p>Webhooks need a paid plan on the hosted service.</p></div>
+<p>Short intro.</p>
+<p>Webhooks notify your server whenever an event happens in your account. Acme retries failed deliveries for up to three days. Each delivery is signed.</p>
+</article></body></html>"#;
+        assert_eq!(
+            describe(mkdocs),
+            "Webhooks notify your server whenever an event happens in your account. \
+             Acme retries failed deliveries for up to three days."
+        );
+
+        // A meta description still wins
+        let with_meta = sphinx.replace(
+            "<title>",
+            r#"<meta name="description" content="Configuring Acme."><title>"#,
+        );
+        assert_eq!(describe(&with_meta), "Configuring Acme.");
+    }
+
+    #[test]
+    fn test_metadata_from_cleaned_content() {
+        let processor = Processor::new(&test_config()).unwrap();
+
+        // A GDPR banner and a sign-in dialog come before the content, and
+        // the page has neither a <title> nor a meta description
+        let html = r#"<html><head></head><body>
+<div id="gdpr-notice"><p>This site stores data such as identifiers on your device to deliver its services and measure audiences.</p></div>
+<div class="modal" role="dialog"><h1>Sign in to continue</h1></div>
+<dialog open><h1>Choose your region</h1></dialog>
+<main><h1>Rate Limits</h1>
+<p>Every API key may send up to one hundred requests per minute. Requests over the limit are rejected with a 429 status.</p>
+</main></body></html>"#;
+
+        let processed = processor
+            .process("https://docs.example.com/api/limits", html)
+            .unwrap();
+        assert_eq!(processed.metadata.title, "Rate Limits");
+        assert_eq!(
+            processed.metadata.description,
+            "Every API key may send up to one hundred requests per minute. \
+             Requests over the limit are rejected with a 429 status."
+        );
+
+        // Tags in the <head> still come from the original page, even when
+        // the content selectors leave it behind
+        let processor = Processor::new(&Config {
+            content_selectors: vec!["main".to_string()],
+            ..test_config()
+        })
+        .unwrap();
+        let html = html.replace(
+            "<head></head>",
+            r#"<head><title>Limits | Acme</title><meta name="description" content="API rate limits."></head>"#,
+        );
+        let processed = processor
+            .process("https://docs.example.com/api/limits", &html)
+            .unwrap();
+        assert_eq!(processed.metadata.title, "Limits | Acme");
+        assert_eq!(processed.metadata.description, "API rate limits.");
+    }
+
+    #[test]
+    fn test_description_knobs() {
+        let processor = Processor::new(&Config {
+            description_sentences: 1,
+            description_max_chars: 60,
+            ..test_config()
+        })
+        .unwrap();
+        let html = "<html><body><main><h1>Routing</h1>\
+                    <p>Routes map incoming URLs to handlers. Each route has a method and a path.</p>\
+                    </main></body></html>";
+
+        let processed = processor
+            .process("https://docs.example.com/routing", html)
+            .unwrap();
+        assert_eq!(
+            processed.metadata.description,
+            "Routes map incoming URLs to handlers."
+        );
+
+        let processor = Processor::new(&Config {
+            description_max_chars: 20,
+            ..test_config()
+        })
+        .unwrap();
+        let processed = processor
+            .process("https://docs.example.com/routing", html)
+            .unwrap();
+        assert_eq!(processed.metadata.description, "Routes map...");
+        assert!(processed.skill_md.contains("description: Routes map...\n"));
+    }
+
+    #[test]
+    fn test_consent_phrases_are_configurable() {
+        let config =
+            Config::from_yaml("consent_phrases: [\"subscribe to our newsletter\"]").unwrap();
+        let processor = Processor::new(&config).unwrap();
+
+        let html = r#"<html><head>
+<meta name="description" content="Subscribe to our newsletter for weekly updates on every release.">
+<meta name="description" content="We use cookies.">
+</head><body></body></html>"#;
+
+        let document = Html::parse_document(html);
+        let metadata = processor
+            .extract_metadata("https://example.com/docs/news", &document, &document)
+            .unwrap();
+        assert_eq!(metadata.description, "We use cookies.");
+    }
+
+    #[test]
+    fn test_clean_html_matches_pattern_by_pattern() {
+        // What clean_html did before its regexes were precompiled: every
+        // pattern on its own, copying the HTML each time
+        let sequential = |html: &str| {
+            let mut cleaned = html.to_string();
+            for (flags, patterns) in NOISE_PASSES {
+                for pattern in *patterns {
+                    let re = Regex::new(&format!("{}{}", flags, pattern)).unwrap();
+                    cleaned = re.replace_all(&cleaned, "").to_string();
+                }
+            }
+            cleaned
+        };
 
-        let html = r#"
+        let html = r##"
 <!DOCTYPE html>
 <html>
-<head>
-    <title>Test Page Title</title>
-    <meta name="description" content="This is a test description.">
-</head>
+<head><script>var nav = "<nav>";</script><style>p { margin: 0 }</style></head>
 <body>
-    <h1>Main Heading</h1>
-    <p>Some content here.</p>
+    <a href="#main" class="skip">Skip to content</a>
+    <header><nav class="navbar">Home</nav></header>
+    <div id="cookie-consent">We use cookies <button>OK</button></div>
+    <aside class="sidebar">Sidebar</aside>
+    <main data-page="guide">
+        <!-- rendered by the docs theme -->
+        <h1 data-anchor="intro">Getting Started <span class="material-icons">link</span></h1>
+        <p>Install the SDK and <i class="fa fa-copy"></i>run it.</p>
+        <div class="admonition note"><p>Keep this note.</p></div>
+        <div role="dialog">Subscribe?</div>
+        <pre><code>flutter run</code></pre>
+        <div class="page-meta">Last updated yesterday</div>
+        <form><input name="q"></form>
+        <svg viewBox="0 0 1 1"><path d="M0 0"/></svg>
+    </main>
+    <footer>Footer</footer>
 </body>
 </html>
-"#;
+"##;
 
-        let document = Html::parse_document(html);
-        let metadata = processor
-            .extract_metadata("https://example.com/docs/test", &document)
-            .unwrap();
+        let processor = Processor::new(&test_config()).unwrap();
+        let cleaned = processor.clean_html(html).unwrap();
+        assert_eq!(cleaned, sequential(html));
+        assert!(cleaned.contains("Keep this note."));
+        assert!(!cleaned.contains("cookies"));
+
+        // Nested noise: the element the earlier pattern matches goes first
+        for html in [
+            r#"<div class="share"><div class="nav">x</div>y</div><p>Keep</p>"#,
+            r#"<header><script>var end = "</header>";</script>Title</header><p>Keep</p>"#,
+            r#"<div class="promo"><nav class="toc">a</nav>b</div><p>Keep</p>"#,
+        ] {
+            assert_eq!(processor.clean_html(html).unwrap(), sequential(html));
+        }
+        assert_eq!(
+            processor
+                .clean_html(r#"<div class="share"><div class="nav">x</div>y</div><p>Keep</p>"#)
+                .unwrap(),
+            "<p>Keep</p>"
+        );
 
-        assert_eq!(metadata.title, "Test Page Title");
-        assert_eq!(metadata.description, "This is a test description.");
-        assert_eq!(metadata.skill_name, "docs-test");
+        // Nothing to remove leaves the HTML as it was
+        let plain = "<main><p>Plain.</p></main>";
+        assert_eq!(processor.clean_html(plain).unwrap(), plain);
     }
 
     #[test]
@@ -720,13 +2341,21 @@ mod tests {
             title: "Flutter Installation Guide".to_string(),
             description: "Learn how to install Flutter on your system.".to_string(),
             url: "https://docs.flutter.dev/get-started/install".to_string(),
+            requested_url: None,
+            canonical_url: None,
+            language: None,
             skill_name: "get-started-install".to_string(),
+            fallback_skill_name: None,
             processed_at: "2024-01-15T10:30:00Z".to_string(),
+            tags: Vec::new(),
         };
 
         let markdown_content =
             "## Installation Steps\n\n1. Download Flutter\n2. Extract the archive\n3. Add to PATH";
-        let skill_md = processor.generate_skill_md(&metadata, markdown_content);
+        let (skill_md, references) = processor
+            .generate_skill_md(&metadata, markdown_content)
+            .unwrap();
+        assert!(references.is_empty());
 
         // Check frontmatter
         assert!(skill_md.contains("name: get-started-install"));
@@ -746,6 +2375,269 @@ mod tests {
         assert!(!skill_md.contains("[View Documentation]"));
     }
 
+    #[test]
+    fn test_process_page_converts_admonitions() {
+        let processor = Processor::new(&test_config()).unwrap();
+
+        let html = r#"<html><head><title>Upgrade</title></head><body><main>
+<h1>Upgrade</h1>
+<div class="admonition danger"><p class="admonition-title">Breaking change</p><p>The <code>v1</code> API is gone.</p></div>
+<p>Then run the migration.</p>
+</main></body></html>"#;
+
+        let processed = processor
+            .process("https://example.com/docs/upgrade", html)
+            .unwrap();
+
+        assert!(
+            processed.skill_md.contains(
+                "> [!CAUTION]\n> **Breaking change**\n> \n> The `v1` API is gone.\n\nThen run the migration."
+            ),
+            "{}",
+            processed.skill_md
+        );
+    }
+
+    #[test]
+    fn test_skill_name_source() {
+        let names = |source: SkillNameSource, html: &str| {
+            let processor = Processor::new(&Config {
+                skill_name_source: source,
+                ..test_config()
+            })
+            .unwrap();
+            let processed = processor
+                .process("https://example.com/p/48219-xyz", html)
+                .unwrap();
+            (
+                processed.metadata.skill_name,
+                processed.metadata.fallback_skill_name,
+            )
+        };
+        let titled = "<html><head><title>Configure Webhooks | Acme Docs</title></head>\
+                      <body><main><p>Webhooks notify you of events.</p></main></body></html>";
+        let untitled =
+            "<html><body><main><p>Webhooks notify you of events.</p></main></body></html>";
+        let emoji = "<html><head><title>🪝 | Acme Docs</title></head>\
+                     <body><main><p>Webhooks notify you of events.</p></main></body></html>";
+
+        assert_eq!(
+            names(SkillNameSource::Url, titled),
+            ("p-48219-xyz".to_string(), None)
+        );
+        assert_eq!(
+            names(SkillNameSource::Title, titled),
+            ("configure-webhooks".to_string(), None)
+        );
+        assert_eq!(
+            names(SkillNameSource::TitleFallbackUrl, titled),
+            (
+                "configure-webhooks".to_string(),
+                Some("p-48219-xyz".to_string())
+            )
+        );
+        // Nothing to name the skill after in the title
+        for html in [untitled, emoji] {
+            for source in [SkillNameSource::Title, SkillNameSource::TitleFallbackUrl] {
+                assert_eq!(names(source, html), ("p-48219-xyz".to_string(), None));
+            }
+        }
+    }
+
+    #[test]
+    fn test_significant_query_params_in_names() {
+        let name = |significant_query_params: &[&str], url: &str| {
+            let processor = Processor::new(&Config {
+                significant_query_params: significant_query_params
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                ..test_config()
+            })
+            .unwrap();
+            let html = "<html><body><main><p>Call the endpoint.</p></main></body></html>";
+            processor.process(url, html).unwrap().metadata.skill_name
+        };
+
+        let v1 = "https://example.com/docs/api?version=1&utm_source=x";
+        let v2 = "https://example.com/docs/api?version=2&utm_source=y";
+        assert_eq!(name(&[], v1), name(&[], v2));
+        assert_eq!(name(&["version"], v1), "docs-api-version-1");
+        assert_eq!(name(&["version"], v2), "docs-api-version-2");
+        assert_eq!(
+            name(&["*"], "https://example.com/docs/api?tab=errors"),
+            "docs-api-tab-errors"
+        );
+        // Pages without the parameter keep their usual name
+        assert_eq!(
+            name(&["version"], "https://example.com/docs/api"),
+            "docs-api"
+        );
+    }
+
+    #[test]
+    fn test_transliterate_names() {
+        let name = |transliterate_names: bool, url: &str, title: &str| {
+            let processor = Processor::new(&Config {
+                transliterate_names,
+                skill_name_source: SkillNameSource::TitleFallbackUrl,
+                ..test_config()
+            })
+            .unwrap();
+            let html = format!(
+                "<html><head><title>{}</title></head>\
+                 <body><main><p>Run the installer and follow the prompts.</p></main></body></html>",
+                title
+            );
+            let processed = processor.process(url, &html).unwrap();
+            (
+                processed.metadata.skill_name,
+                processed.metadata.fallback_skill_name.unwrap_or_default(),
+            )
+        };
+
+        let url = "https://example.com/gu%C3%ADa/configuraci%C3%B3n";
+        assert_eq!(
+            name(true, url, "Guía de configuración"),
+            (
+                "guia-de-configuracion".to_string(),
+                "guia-configuracion".to_string()
+            )
+        );
+        let url = "https://example.cn/%E6%96%87%E6%A1%A3/%E5%AE%89%E8%A3%85";
+        assert_eq!(
+            name(true, url, "安装指南"),
+            (
+                "an-zhuang-zhi-nan".to_string(),
+                "wendang-anzhuang".to_string()
+            )
+        );
+
+        // Off by default: the title falls back to the URL, whose escapes are
+        // dropped
+        let (skill_name, _) = name(false, url, "安装指南");
+        assert!(skill_name.starts_with("e6"), "{}", skill_name);
+    }
+
+    #[test]
+    fn test_process_page_labels_admonitions() {
+        let processor = Processor::new(&Config {
+            callout_style: CalloutStyle::Labeled,
+            ..test_config()
+        })
+        .unwrap();
+
+        // MkDocs Material, then Docusaurus with its icon
+        let html = r#"<html><head><title>Deploy</title></head><body><main>
+<h1>Deploy</h1>
+<div class="admonition tip"><p class="admonition-title">Tip</p><p>Use a staging project.</p></div>
+<div class="theme-admonition theme-admonition-warning alert alert--warning"><div class="admonitionHeading_Gvgb"><span class="admonitionIcon_Rf37"><svg viewBox="0 0 16 16"><path d="M8 1"></path></svg></span>Quotas</div><div class="admonitionContent_BuS1"><p>Deploys are rate limited.</p></div></div>
+</main></body></html>"#;
+
+        let processed = processor
+            .process("https://example.com/docs/deploy", html)
+            .unwrap();
+
+        assert!(
+            processed.skill_md.contains(
+                "> **Tip:** Use a staging project.\n\n> **Warning: Quotas**\n> \n> Deploys are rate limited."
+            ),
+            "{}",
+            processed.skill_md
+        );
+    }
+
+    #[test]
+    fn test_process_page_keeps_code_languages() {
+        let processor = Processor::new(&test_config()).unwrap();
+
+        let html = r#"<html><head><title>Install</title></head><body><main>
+<h1>Install</h1>
+<div class="language-bash theme-code-block"><pre class="prism-code language-bash"><code><span class="token-line"><span class="token function">cargo</span><span class="token plain"> </span><span class="token function">install</span><span class="token plain"> app</span><br></span></code></pre></div>
+<pre class="shiki github-dark" data-language="toml"><code><span class="line"><span style="color:#E1E4E8">[profile.release]</span></span>
+<span class="line"><span style="color:#E1E4E8">lto = </span><span style="color:#79B8FF">true</span></span></code></pre>
+</main></body></html>"#;
+
+        let processed = processor
+            .process("https://example.com/docs/install", html)
+            .unwrap();
+
+        assert!(
+            processed
+                .skill_md
+                .contains("```bash\ncargo install app\n```"),
+            "{}",
+            processed.skill_md
+        );
+        assert!(
+            processed
+                .skill_md
+                .contains("```toml\n[profile.release]\nlto = true\n```"),
+            "{}",
+            processed.skill_md
+        );
+    }
+
+    #[test]
+    fn test_extra_frontmatter_fields() {
+        let config =
+            Config::from_yaml("frontmatter:\n  owner: docs-team\n  source: \"{domain}\"\n")
+                .unwrap();
+        let processor = Processor::new(&config).unwrap();
+
+        let processed = processor
+            .process(
+                "https://example.com/docs/api",
+                "<html><head><title>API</title></head><body><main><p>Calls.</p></main></body></html>",
+            )
+            .unwrap();
+
+        let frontmatter = frontmatter::parse(&processed.skill_md).unwrap().unwrap();
+        assert_eq!(frontmatter.name, "docs-api");
+        assert_eq!(frontmatter.extra["owner"], "docs-team");
+        assert_eq!(frontmatter.extra["source"], "example.com");
+    }
+
+    #[test]
+    fn test_heading_outline() {
+        let processor = Processor::new(&Config {
+            contents_section: true,
+            ..test_config()
+        })
+        .unwrap();
+
+        let html = r#"<html><head><title>Client</title></head><body><main>
+<h1>Client</h1><p>Talks to the API.</p>
+<h2>Connect</h2><p>Open a session.</p>
+<h3>Example</h3><p>A call.</p>
+<h4>Details</h4><p>Too deep for the outline.</p>
+<h2>Retry</h2><p>Back off.</p>
+<h3>Example</h3><p>Another call.</p>
+</main></body></html>"#;
+
+        let processed = processor
+            .process("https://example.com/docs/client", html)
+            .unwrap();
+
+        let frontmatter = frontmatter::parse(&processed.skill_md).unwrap().unwrap();
+        assert_eq!(
+            frontmatter.metadata.sections,
+            ["Client", "Connect", "Example", "Retry"]
+        );
+        assert!(
+            processed.skill_md.contains(
+                "---\n\n# Client\n\n## Contents\n\n\
+                 - [Client](#client)\n  \
+                 - [Connect](#connect)\n    \
+                 - [Example](#example)\n  \
+                 - [Retry](#retry)\n    \
+                 - [Example](#example-1)\n\n"
+            ),
+            "{}",
+            processed.skill_md
+        );
+    }
+
     #[test]
     fn test_process_page() {
         let processor = Processor::new(&test_config()).unwrap();
@@ -785,6 +2677,356 @@ mod tests {
         assert!(!processed.skill_md.contains("references/"));
     }
 
+    #[test]
+    fn test_frontmatter_metadata_levels() {
+        let html = "<html><head><title>Install</title></head>\
+                    <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>";
+
+        let processed = Processor::new(&test_config())
+            .unwrap()
+            .process("https://example.com/docs/install", html)
+            .unwrap();
+        let metadata = frontmatter::parse(&processed.skill_md)
+            .unwrap()
+            .unwrap()
+            .metadata;
+        assert_eq!(
+            metadata.processed_at.as_deref(),
+            Some(processed.metadata.processed_at.as_str())
+        );
+        assert_eq!(
+            metadata.generator.unwrap(),
+            format!("agent-skills-generator {}", version_check::CURRENT_VERSION)
+        );
+        assert_eq!(
+            metadata.content_hash.unwrap(),
+            content_hash(&processed.markdown_content)
+        );
+
+        let minimal = Processor::new(&Config {
+            frontmatter_metadata: MetadataLevel::Minimal,
+            ..test_config()
+        })
+        .unwrap()
+        .process("https://example.com/docs/install", html)
+        .unwrap();
+        let metadata = frontmatter::parse(&minimal.skill_md)
+            .unwrap()
+            .unwrap()
+            .metadata;
+        assert_eq!(metadata.processed_at, None);
+        assert_eq!(metadata.generator, None);
+        assert_eq!(metadata.content_hash, None);
+    }
+
+    #[tokio::test]
+    async fn test_write_to_disk_skips_unchanged_skill() {
+        let processor = Processor::new(&test_config()).unwrap();
+        let output_dir =
+            std::env::temp_dir().join(format!("agent-skills-write-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        let html = "<html><head><title>Install</title></head>\
+                    <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>";
+        let processed = processor
+            .process("https://example.com/docs/install", html)
+            .unwrap();
+
+        let first = processor
+            .write_to_disk(&processed, &output_dir)
+            .await
+            .unwrap();
+        assert!(first.changed);
+        let skill_md = first.skill_dir.join("SKILL.md");
+        let written = std::fs::read_to_string(&skill_md).unwrap();
+
+        // A later run, maybe by a newer version, isn't a change either
+        let mut later = processed.clone();
+        later.skill_md = processed
+            .skill_md
+            .replace(&processed.metadata.processed_at, "2099-01-01T00:00:00Z")
+            .replace(version_check::CURRENT_VERSION, "99.0.0");
+        assert_ne!(later.skill_md, processed.skill_md);
+        let second = processor.write_to_disk(&later, &output_dir).await.unwrap();
+        assert_eq!(second.skill_dir, first.skill_dir);
+        assert!(!second.changed);
+        assert_eq!(std::fs::read_to_string(&skill_md).unwrap(), written);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_write_to_disk_splits_large_skill() {
+        let config = Config {
+            max_skill_chars: 140,
+            ..test_config()
+        };
+        let processor = Processor::new(&config).unwrap();
+        let output_dir =
+            std::env::temp_dir().join(format!("agent-skills-split-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        let html = "<html><head><title>Guide</title>\
+                    <meta name=\"description\" content=\"Setting up the CLI.\"></head>\
+                    <body><main><h1>Guide</h1>\
+                    <h2>Install</h2><p>Download the installer for your platform and run it once.</p>\
+                    <h2>Configure</h2><p>Write a config file next to the project and commit it.</p>\
+                    <h2>Upgrade</h2><p>Run the installer again; your config is kept.</p>\
+                    </main></body></html>";
+        let processed = processor
+            .process("https://example.com/docs/guide", html)
+            .unwrap();
+        let written = processor
+            .write_to_disk(&processed, &output_dir)
+            .await
+            .unwrap();
+
+        let skill_md = std::fs::read_to_string(written.skill_dir.join("SKILL.md")).unwrap();
+        let frontmatter = frontmatter::parse(&skill_md).unwrap().unwrap();
+        assert_eq!(
+            frontmatter.metadata.references,
+            ["references/part-01.md", "references/part-02.md"]
+        );
+        assert!(
+            skill_md.ends_with(
+                "# Guide\n\nSetting up the CLI.\n\n\
+                 The full page is split into 2 reference files:\n\n\
+                 - [Part 1](references/part-01.md): Install\n\
+                 - [Part 2](references/part-02.md): Configure, Upgrade\n"
+            ),
+            "{}",
+            skill_md
+        );
+        assert!(!skill_md.contains("Download the installer"));
+
+        let part =
+            std::fs::read_to_string(written.skill_dir.join("references/part-02.md")).unwrap();
+        assert_eq!(
+            part,
+            "# Guide (part 2 of 2)\n\n\
+             ## Configure\n\nWrite a config file next to the project and commit it.\n\n\
+             ## Upgrade\n\nRun the installer again; your config is kept.\n"
+        );
+
+        // Once the page fits in SKILL.md again, the parts are removed
+        let processed = processor
+            .process(
+                "https://example.com/docs/guide",
+                "<html><head><title>Guide</title></head><body><main><h1>Guide</h1>\
+                 <p>Moved to the handbook.</p></main></body></html>",
+            )
+            .unwrap();
+        let rewritten = processor
+            .write_to_disk(&processed, &output_dir)
+            .await
+            .unwrap();
+        assert!(rewritten.changed);
+        assert!(!written.skill_dir.join("references").exists());
+        assert_eq!(crate::utils::skill_files(&output_dir).unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_json_and_plain_output() {
+        let html = "<html><head><title>Install</title>\
+                    <meta name=\"description\" content=\"Installing the CLI.\"></head>\
+                    <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>";
+        let context = PageContext::new("https://example.com/docs/install")
+            .with_requested_url("https://example.com/install");
+
+        let processor = Processor::new(&Config {
+            output_format: SkillFormat::Json,
+            ..test_config()
+        })
+        .unwrap();
+        let processed = processor.process_page(&context, html).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&processed.skill_md).unwrap();
+        let fields: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "body",
+                "description",
+                "estimated_tokens",
+                "processed_at",
+                "requested_url",
+                "skill_name",
+                "title",
+                "url"
+            ]
+        );
+        assert_eq!(json["title"], "Install");
+        assert_eq!(json["description"], "Installing the CLI.");
+        assert_eq!(json["estimated_tokens"], processed.estimated_tokens());
+        assert_eq!(json["url"], "https://example.com/docs/install");
+        assert_eq!(json["requested_url"], "https://example.com/install");
+        assert_eq!(json["skill_name"], "docs-install");
+        assert_eq!(
+            json["processed_at"],
+            processed.metadata.processed_at.as_str()
+        );
+        assert_eq!(json["body"], processed.markdown_content.trim());
+        assert_eq!(
+            processor.skill_md_path(Path::new("out"), "docs-install"),
+            Path::new("out/docs-install/skill.json")
+        );
+
+        let processor = Processor::new(&Config {
+            output_format: SkillFormat::Plain,
+            flat: true,
+            ..test_config()
+        })
+        .unwrap();
+        let processed = processor.process_page(&context, html).unwrap();
+        assert_eq!(
+            processed.skill_md,
+            format!("# Install\n\n{}\n", processed.markdown_content.trim())
+        );
+        assert_eq!(
+            processor.skill_md_path(Path::new("out"), "docs-install"),
+            Path::new("out/docs-install.txt")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_to_disk_with_include_html() {
+        let html = "<html><head><title>Install</title></head>\
+                    <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>";
+        let output_dir =
+            std::env::temp_dir().join(format!("agent-skills-html-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        for include_html in [false, true] {
+            let config = Config {
+                include_html,
+                ..test_config()
+            };
+            let processor = Processor::new(&config).unwrap();
+            let processed = processor
+                .process("https://example.com/docs/install", html)
+                .unwrap();
+            let skill_dir = processor
+                .write_to_disk(&processed, &output_dir.join(include_html.to_string()))
+                .await
+                .unwrap()
+                .skill_dir;
+
+            let mut files: Vec<String> = std::fs::read_dir(&skill_dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+                .collect();
+            files.sort();
+            if include_html {
+                assert_eq!(files, ["SKILL.md", CLEANED_HTML_FILE]);
+                let written = std::fs::read_to_string(skill_dir.join(CLEANED_HTML_FILE)).unwrap();
+                assert_eq!(written, processed.cleaned_html);
+                assert!(written.contains("Run the installer."));
+            } else {
+                assert_eq!(files, ["SKILL.md"]);
+            }
+        }
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_downloaded_images_are_written_to_assets() {
+        let port = crate::http::test_server(|_| crate::http::ok_response("GIF89a")).await;
+        let html = format!(
+            "<html><head><title>Overview</title></head><body><main><h1>Overview</h1>\
+             <p><img src=\"http://127.0.0.1:{}/img/arch.gif\" alt=\"Architecture\"></p>\
+             </main></body></html>",
+            port
+        );
+        let output_dir =
+            std::env::temp_dir().join(format!("agent-skills-images-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        let config = Config {
+            images: ImageMode::Download,
+            ..test_config()
+        };
+        let processor = Processor::new(&config).unwrap();
+        let mut processed = processor
+            .process("https://example.com/docs/overview", &html)
+            .unwrap();
+        processor.download_images(&mut processed).await.unwrap();
+
+        let file_name = format!("{}.gif", crate::cache::content_hash("GIF89a"));
+        assert!(
+            processed
+                .skill_md
+                .contains(&format!("![Architecture](assets/{})", file_name)),
+            "{}",
+            processed.skill_md
+        );
+
+        let written = processor
+            .write_to_disk(&processed, &output_dir)
+            .await
+            .unwrap();
+        let asset = written.skill_dir.join(ASSETS_DIR).join(&file_name);
+        assert_eq!(std::fs::read(&asset).unwrap(), b"GIF89a");
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_parameter_table_becomes_markdown_table() {
+        let processor = Processor::new(&test_config()).unwrap();
+
+        let html = r#"
+<html>
+<head><title>Parameters</title></head>
+<body>
+    <main>
+        <h2>Parameters</h2>
+        <table>
+            <thead>
+                <tr><th>Name</th><th>Type</th><th>Description</th></tr>
+            </thead>
+            <tbody>
+                <tr><td><code>id</code></td><td>string</td><td>The resource ID.</td></tr>
+                <tr><td><code>limit</code></td><td>integer</td><td>Maximum results,
+                    defaults to <code>10</code>.</td></tr>
+            </tbody>
+        </table>
+        <p>After the table.</p>
+    </main>
+</body>
+</html>
+"#;
+
+        let processed = processor
+            .process("https://example.com/docs/list", html)
+            .unwrap();
+        let markdown = &processed.markdown_content;
+
+        let table: Vec<&str> = markdown
+            .lines()
+            .filter(|line| line.starts_with('|'))
+            .collect();
+        assert_eq!(
+            table,
+            [
+                "| Name    | Type    | Description                        |",
+                "| ------- | ------- | ---------------------------------- |",
+                "| `id`    | string  | The resource ID.                   |",
+                "| `limit` | integer | Maximum results, defaults to `10`. |",
+            ],
+            "{}",
+            markdown
+        );
+        assert!(markdown.contains("After the table."));
+        assert!(!markdown.contains(TABLE_PLACEHOLDER));
+    }
+
     #[test]
     fn test_clean_markdown_removes_icon_names() {
         let processor = Processor::new(&test_config()).unwrap();
@@ -819,6 +3061,49 @@ Was this page's content helpful?
         assert!(!cleaned.contains("Was this page's content helpful"));
     }
 
+    #[test]
+    fn test_builtin_icon_strip_can_be_disabled() {
+        let config = Config::from_yaml(
+            r#"
+disable_builtin_icon_strip: true
+extra_icon_names: ["keyboard_arrow_right"]
+"#,
+        )
+        .unwrap();
+        let processor = Processor::new(&config).unwrap();
+
+        let markdown =
+            "Open the menu, then search and check the settings list. keyboard_arrow_right";
+        let cleaned = processor.clean_markdown(markdown);
+
+        assert_eq!(
+            cleaned,
+            "Open the menu, then search and check the settings list."
+        );
+    }
+
+    #[test]
+    fn test_extra_feedback_patterns() {
+        let config = Config::from_yaml(
+            r#"
+extra_feedback_patterns:
+  - "(?m)^War diese Seite hilfreich\\?\\s*$"
+"#,
+        )
+        .unwrap();
+        let processor = Processor::new(&config).unwrap();
+
+        let markdown = "# Einstieg\n\nInhalt der Seite.\n\nWar diese Seite hilfreich?\n";
+        let cleaned = processor.clean_markdown(markdown);
+
+        assert!(!cleaned.contains("hilfreich"));
+        assert!(cleaned.contains("Inhalt der Seite."));
+
+        let invalid = Config::from_yaml("extra_feedback_patterns: [\"(oops\"]").unwrap();
+        assert!(Processor::new(&invalid).is_err());
+        assert!(invalid.validate().is_err());
+    }
+
     #[test]
     fn test_clean_markdown_removes_skip_links() {
         let processor = Processor::new(&test_config()).unwrap();
@@ -880,6 +3165,110 @@ Unless stated otherwise, the documentation on this site reflects Flutter 3.38.6.
         assert!(cleaned.contains("Content here"));
     }
 
+    #[test]
+    fn test_clean_markdown_applies_user_strip_patterns() {
+        let config = Config::from_yaml(
+            r#"
+markdown_strip_patterns:
+  - "(?m)^\\[Edit this page on GitHub\\]\\([^)]*\\)\\s*$"
+"#,
+        )
+        .unwrap();
+        let processor = Processor::new(&config).unwrap();
+
+        let markdown = r#"
+# Routing
+
+Routes are matched in order. See [GitHub](https://github.com/example/repo) for the source.
+
+[Edit this page on GitHub](https://github.com/example/docs/edit/main/routing.md)
+"#;
+
+        let cleaned = processor.clean_markdown(markdown);
+
+        assert!(!cleaned.contains("Edit this page"));
+        assert!(cleaned.contains("# Routing"));
+        assert!(cleaned.contains("Routes are matched in order."));
+        assert!(cleaned.contains("[GitHub](https://github.com/example/repo)"));
+    }
+
+    #[test]
+    fn test_invalid_strip_pattern_is_rejected() {
+        let config = Config::from_yaml("markdown_strip_patterns: [\"(unclosed\"]").unwrap();
+
+        let err = Processor::new(&config).err().unwrap();
+        assert!(format!("{:#}", err).contains("markdown_strip_patterns entry 1"));
+        assert!(config.validate().is_err());
+    }
+
+    const SECTIONED_CONFIG: &str = r#"
+content_selectors: ["main"]
+rules:
+  - url: "*/blog/*"
+    action: allow
+    content_selectors: [".post-content"]
+    extra_remove_selectors: [".author-bio"]
+  - url: "*/docs/*"
+    action: allow
+"#;
+
+    #[test]
+    fn test_rule_selectors_per_section() {
+        let config = Config::from_yaml(SECTIONED_CONFIG).unwrap();
+        let processor = Processor::new(&config).unwrap();
+
+        let blog_html = r#"<html><head><title>Release notes</title></head><body>
+<main>
+    <aside><p>Related posts you might like</p></aside>
+    <div class="post-content">
+        <p>Version 2 ships a new router.</p>
+        <div class="author-bio"><p>Written by the core team</p></div>
+    </div>
+</main>
+</body></html>"#;
+
+        let docs_html = r#"<html><head><title>Routing</title></head><body>
+<div class="promo-strip"><p>Try the hosted plan today</p></div>
+<main><p>Routes are matched in order.</p></main>
+</body></html>"#;
+
+        let blog = processor
+            .process("https://example.com/blog/v2", blog_html)
+            .unwrap();
+        assert!(
+            blog.markdown_content
+                .contains("Version 2 ships a new router.")
+        );
+        assert!(!blog.markdown_content.contains("Related posts"));
+        assert!(!blog.markdown_content.contains("Written by the core team"));
+
+        let docs = processor
+            .process("https://example.com/docs/routing", docs_html)
+            .unwrap();
+        assert!(
+            docs.markdown_content
+                .contains("Routes are matched in order.")
+        );
+        assert!(!docs.markdown_content.contains("hosted plan"));
+    }
+
+    #[test]
+    fn test_invalid_rule_selector_is_rejected() {
+        let config = Config::from_yaml(
+            r#"
+rules:
+  - url: "*/blog/*"
+    action: allow
+    content_selectors: ["div["]
+"#,
+        )
+        .unwrap();
+
+        let err = Processor::new(&config).err().unwrap();
+        assert!(format!("{:#}", err).contains("Invalid CSS selector in rule 1"));
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_clean_html_removes_buttons() {
         let processor = Processor::new(&test_config()).unwrap();
@@ -921,4 +3310,73 @@ Unless stated otherwise, the documentation on this site reflects Flutter 3.38.6.
         assert!(cleaned.contains("Welcome"));
         assert!(cleaned.contains("Main content"));
     }
+
+    #[test]
+    fn test_page_context_keeps_allowlisted_headers() {
+        let mut response = HeaderMap::new();
+        response.insert("Content-Language", "fr".parse().unwrap());
+        response.insert("ETag", "\"v1\"".parse().unwrap());
+        response.append("x-robots-tag", "noindex".parse().unwrap());
+        response.append("x-robots-tag", "nofollow".parse().unwrap());
+
+        let allowlist = ["X-Robots-Tag".to_string(), "content-language".to_string()];
+        let context =
+            PageContext::new("https://example.com/docs/").with_headers(&response, &allowlist);
+
+        assert_eq!(context.headers.len(), 2);
+        assert_eq!(context.header("x-robots-tag"), Some("noindex, nofollow"));
+        assert_eq!(context.header("Content-Language"), Some("fr"));
+        assert_eq!(context.header("etag"), None);
+    }
+
+    #[test]
+    fn test_text_pages_are_used_as_is() {
+        let config = Config {
+            include_content_types: vec!["text/markdown".to_string(), "text/plain".to_string()],
+            ..test_config()
+        };
+        let processor = Processor::new(&config).unwrap();
+        let context = |url: &str, content_type: &str| PageContext {
+            content_type: Some(content_type.to_string()),
+            ..PageContext::new(url)
+        };
+
+        let markdown =
+            "# Getting Started\n\nInstall the *CLI* first.\n\n## Next\n\nRead <b>on</b>.\n";
+        let processed = processor
+            .process_page(
+                &context("https://example.com/docs/start.md", "text/markdown"),
+                markdown,
+            )
+            .unwrap();
+        assert_eq!(processed.metadata.title, "Getting Started");
+        assert_eq!(processed.metadata.skill_name, "docs-start");
+        assert_eq!(processed.metadata.description, "Install the *CLI* first.");
+        assert_eq!(
+            processed.markdown_content,
+            "Install the *CLI* first.\n\n## Next\n\nRead <b>on</b>."
+        );
+        assert!(processed.skill_md.ends_with(
+            "---\n\n# Getting Started\n\nInstall the *CLI* first.\n\n## Next\n\nRead <b>on</b>.\n"
+        ));
+
+        let processed = processor
+            .process_page(
+                &context("https://example.com/llms.txt", "text/plain; charset=utf-8"),
+                "Docs for agents.\n",
+            )
+            .unwrap();
+        assert_eq!(processed.metadata.title, "llms");
+        assert_eq!(processed.markdown_content, "Docs for agents.");
+
+        // Without the include list, the body is treated as HTML as before
+        let processed = Processor::new(&test_config())
+            .unwrap()
+            .process_page(
+                &context("https://example.com/docs/start.md", "text/markdown"),
+                markdown,
+            )
+            .unwrap();
+        assert!(!processed.markdown_content.contains("<b>"));
+    }
 }
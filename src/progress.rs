@@ -0,0 +1,202 @@
+//! Live crawl progress for the agent-skills-generator.
+//!
+//! Long crawls show a spinner with the running page counts of the current
+//! seed, polled from its [`CrawlStats`]. The spinner is only drawn when
+//! stdout is a terminal and the output is meant for humans; otherwise the
+//! bar is hidden and everything behaves as if it didn't exist.
+//!
+//! Log lines are written through [`ProgressWriter`], which clears the
+//! spinner while a line is printed so the two never end up on one line.
+
+use agent_skills_generator::crawler::CrawlStats;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// How often the counters are read and the spinner redrawn.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Spinner shared by the crawl and the log writer.
+#[derive(Clone)]
+pub struct CrawlProgress {
+    bar: ProgressBar,
+    enabled: bool,
+}
+
+impl CrawlProgress {
+    /// Creates a spinner drawn on stdout once a seed is tracked, or one
+    /// that is never drawn if `enabled` is false.
+    pub fn new(enabled: bool) -> Self {
+        let bar = ProgressBar::hidden();
+        bar.set_style(
+            ProgressStyle::with_template("{spinner} [{elapsed}] {prefix} {msg}")
+                .expect("valid progress template"),
+        );
+
+        Self { bar, enabled }
+    }
+
+    /// Whether the spinner is drawn while tracking a seed.
+    pub fn is_visible(&self) -> bool {
+        self.enabled
+    }
+
+    /// Shows the progress of `seed` until the returned guard is dropped.
+    pub fn track(&self, seed: &str, stats: Arc<CrawlStats>) -> ProgressGuard {
+        if self.enabled && self.bar.is_hidden() {
+            self.bar.set_draw_target(ProgressDrawTarget::stdout());
+        }
+        self.bar.reset_elapsed();
+        self.bar.set_prefix(seed.to_string());
+        self.bar.set_message(counts(&stats));
+
+        let bar = self.bar.clone();
+        let poller = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+            loop {
+                interval.tick().await;
+                bar.set_message(counts(&stats));
+                bar.tick();
+            }
+        });
+
+        ProgressGuard { poller }
+    }
+
+    /// Removes the spinner so the crawl summary is the last thing shown.
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+
+    /// Wraps `inner` so every write clears the spinner first.
+    pub fn writer<M>(&self, inner: M) -> ProgressWriter<M> {
+        ProgressWriter {
+            bar: self.bar.clone(),
+            inner,
+        }
+    }
+
+    #[cfg(test)]
+    fn message(&self) -> String {
+        self.bar.message()
+    }
+}
+
+/// Stops updating the spinner from a seed's stats when dropped.
+pub struct ProgressGuard {
+    poller: JoinHandle<()>,
+}
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        self.poller.abort();
+    }
+}
+
+fn counts(stats: &CrawlStats) -> String {
+    let stats = stats.snapshot();
+    format!(
+        "{} visited, {} processed, {} skipped, {} failed",
+        stats.visited, stats.processed, stats.skipped, stats.failed
+    )
+}
+
+/// Log writer that suspends the spinner around each write.
+pub struct ProgressWriter<M> {
+    bar: ProgressBar,
+    inner: M,
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for ProgressWriter<M> {
+    type Writer = SuspendedWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SuspendedWriter {
+            bar: self.bar.clone(),
+            inner: self.inner.make_writer(),
+        }
+    }
+}
+
+/// A single log write made through [`ProgressWriter`].
+pub struct SuspendedWriter<W> {
+    bar: ProgressBar,
+    inner: W,
+}
+
+impl<W: Write> Write for SuspendedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inner = &mut self.inner;
+        self.bar.suspend(|| inner.write(buf))
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let inner = &mut self.inner;
+        self.bar.suspend(|| inner.write_all(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::Ordering;
+
+    /// In-memory log output shared with the test.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hidden_progress_leaves_output_untouched() {
+        let progress = CrawlProgress::new(false);
+        assert!(!progress.is_visible());
+
+        let buffer = SharedBuffer::default();
+        let log_buffer = buffer.clone();
+        let writer = progress.writer(move || log_buffer.clone());
+
+        let stats = Arc::new(CrawlStats::new());
+        let guard = progress.track("https://docs.example.com/", Arc::clone(&stats));
+
+        writer
+            .make_writer()
+            .write_all(b"INFO Processed: https://docs.example.com/a\n")
+            .unwrap();
+        stats.pages_visited.fetch_add(3, Ordering::Relaxed);
+        stats.pages_processed.fetch_add(2, Ordering::Relaxed);
+        tokio::time::sleep(REFRESH_INTERVAL * 3).await;
+        drop(guard);
+        progress.finish();
+
+        // Counts are still tracked, but only the log line reaches the output
+        assert!(progress.message().starts_with("3 visited, 2 processed"));
+        assert_eq!(
+            buffer.contents(),
+            "INFO Processed: https://docs.example.com/a\n"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Request pacing for the agent-skills-generator.
+//!
+//! Requests made outside spider (revalidation, `update`, `diff`, robots.txt)
+//! go through one [`RateLimiter`] per run. Each host is paced on its own:
+//! consecutive requests to it are at least `delay_ms` apart, or its
+//! robots.txt `Crawl-delay` if larger, so a slow, polite host never holds
+//! back requests to another one. `max_requests_per_sec` additionally
+//! spaces out all requests, whatever their host.
+//!
+//! Callers reserve the next free slot under a lock and sleep outside it, so
+//! concurrent requests queue up in the order they asked.
+
+use crate::config::Config;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Paces requests per host, and globally with `max_requests_per_sec`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Minimum spacing between requests to the same host.
+    delay: Duration,
+    /// Minimum spacing between any two requests.
+    interval: Option<Duration>,
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    hosts: HashMap<String, HostState>,
+    /// Earliest time of the next request to any host.
+    next: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct HostState {
+    /// Spacing for this host when larger than the default, e.g. its
+    /// robots.txt `Crawl-delay`.
+    delay: Option<Duration>,
+    /// Earliest time of the next request to this host.
+    next: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(delay: Duration, interval: Option<Duration>) -> Self {
+        Self {
+            delay,
+            interval,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Paces hosts by `delay_ms`, and all requests by
+    /// `max_requests_per_sec`.
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            Duration::from_millis(config.delay_ms),
+            config.request_interval(),
+        )
+    }
+
+    /// Spaces requests to `host` at least `delay` apart from now on, if that
+    /// is more than it already is.
+    pub fn raise_host_delay(&self, host: &str, delay: Duration) {
+        if delay <= self.delay {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let host = state.hosts.entry(host.to_lowercase()).or_default();
+        host.delay = host.delay.max(Some(delay));
+    }
+
+    /// Waits until a request to `url` may be sent.
+    pub async fn acquire(&self, url: &str) {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .unwrap_or_default();
+        let slot = self.reserve(&host, Instant::now());
+        tokio::time::sleep_until(slot).await;
+    }
+
+    /// Reserves the earliest slot at or after `now` that keeps `host`'s
+    /// spacing and the global one, and returns it.
+    fn reserve(&self, host: &str, now: Instant) -> Instant {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let global_next = state.next;
+        let host_state = state.hosts.entry(host.to_string()).or_default();
+
+        let slot = [Some(now), host_state.next, global_next]
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap_or(now);
+        host_state.next = Some(slot + host_state.delay.unwrap_or(self.delay));
+        if let Some(interval) = self.interval {
+            state.next = Some(slot + interval);
+        }
+        slot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(slots: &[Instant], start: Instant) -> Vec<u64> {
+        slots
+            .iter()
+            .map(|slot| (*slot - start).as_millis() as u64)
+            .collect()
+    }
+
+    #[test]
+    fn test_hosts_are_paced_independently() {
+        let limiter = RateLimiter::new(Duration::from_millis(100), None);
+        limiter.raise_host_delay("slow.example.com", Duration::from_millis(300));
+        // Lowering is ignored
+        limiter.raise_host_delay("docs.example.com", Duration::from_millis(10));
+
+        // Three requests to each host, asked for at the same time
+        let start = Instant::now();
+        let mut docs = Vec::new();
+        let mut slow = Vec::new();
+        for _ in 0..3 {
+            docs.push(limiter.reserve("docs.example.com", start));
+            slow.push(limiter.reserve("slow.example.com", start));
+        }
+
+        // The slow host doesn't hold back the other one
+        assert_eq!(millis(&docs, start), [0, 100, 200]);
+        assert_eq!(millis(&slow, start), [0, 300, 600]);
+
+        // A request after a pause goes out right away
+        let later = start + Duration::from_secs(5);
+        assert_eq!(limiter.reserve("docs.example.com", later), later);
+    }
+
+    #[test]
+    fn test_global_rate() {
+        // 20 requests per second: one every 50ms, whatever the host
+        let limiter = RateLimiter::new(Duration::from_millis(100), Some(Duration::from_millis(50)));
+
+        let start = Instant::now();
+        let slots: Vec<Instant> = ["a", "b", "a", "b"]
+            .iter()
+            .map(|host| limiter.reserve(host, start))
+            .collect();
+
+        // b waits for the global slot, the second a for its host
+        assert_eq!(millis(&slots, start), [0, 50, 100, 150]);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_per_host() {
+        let limiter = RateLimiter::new(Duration::from_millis(80), None);
+        let start = Instant::now();
+
+        limiter.acquire("https://docs.example.com/a").await;
+        limiter.acquire("https://other.example.com/a").await;
+        assert!(start.elapsed() < Duration::from_millis(80));
+
+        limiter.acquire("https://DOCS.example.com/b").await;
+        assert!(start.elapsed() >= Duration::from_millis(80));
+    }
+}
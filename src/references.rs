@@ -0,0 +1,168 @@
+//! Reference files for the agent-skills-generator.
+//!
+//! A page with more markdown than `max_skill_chars` would fill an agent's
+//! context as one SKILL.md, so it is split into `references/part-01.md`,
+//! `references/part-02.md` and so on, and SKILL.md only keeps a summary
+//! linking to them. Pages are split between sections, at headings, and a
+//! section longer than a whole part between paragraphs, so no sentence or
+//! code block is cut in two.
+
+use crate::images::ASSETS_DIR;
+use crate::outline::{headings, is_fence, is_heading};
+
+/// Directory of a skill that reference files are written to.
+pub const REFERENCES_DIR: &str = "references";
+
+/// One part of a split page, written to [`REFERENCES_DIR`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceFile {
+    /// File name within [`REFERENCES_DIR`], e.g. `part-01.md`.
+    pub file_name: String,
+    /// Headings of the part's main sections, for the summary.
+    pub headings: Vec<String>,
+    pub content: String,
+}
+
+impl ReferenceFile {
+    /// Path relative to the skill directory, as linked from SKILL.md.
+    pub fn path(&self) -> String {
+        format!("{}/{}", REFERENCES_DIR, self.file_name)
+    }
+}
+
+/// Splits `markdown` into reference files of at most `max_chars`
+/// characters each, titled after the page. A single paragraph or code block
+/// longer than that is kept whole.
+pub fn split(title: &str, markdown: &str, max_chars: usize) -> Vec<ReferenceFile> {
+    // The page title is often the only heading of its level, so parts are
+    // summed up by the first level with several headings
+    let levels: Vec<usize> = headings(markdown).map(|(level, _)| level).collect();
+    let top_level = (1..=6)
+        .find(|level| levels.iter().filter(|l| *l == level).count() > 1)
+        .or_else(|| levels.iter().min().copied());
+
+    let parts = pack(markdown, max_chars);
+    let count = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, part)| {
+            let part_headings = headings(&part)
+                .filter(|(level, _)| Some(*level) == top_level)
+                .map(|(_, text)| text)
+                .collect::<Vec<_>>();
+            let headings = if part_headings.is_empty() {
+                headings(&part).map(|(_, text)| text).take(1).collect()
+            } else {
+                part_headings
+            };
+            // Downloaded images are one directory up from the part
+            let content = part.replace(
+                &format!("]({}/", ASSETS_DIR),
+                &format!("](../{}/", ASSETS_DIR),
+            );
+            ReferenceFile {
+                file_name: format!("part-{:02}.md", i + 1),
+                headings,
+                content: format!("# {} (part {} of {})\n\n{}\n", title, i + 1, count, content),
+            }
+        })
+        .collect()
+}
+
+/// Groups the sections of `markdown` into parts of at most `max_chars`
+/// characters, breaking sections that don't fit in a part of their own
+/// between paragraphs.
+fn pack(markdown: &str, max_chars: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut push = |current: &mut String, piece: &str| {
+        let len = current.chars().count() + piece.chars().count();
+        if !current.trim().is_empty() && len > max_chars {
+            parts.push(std::mem::take(current).trim().to_string());
+        }
+        current.push_str(piece);
+    };
+
+    for section in blocks(markdown, is_heading) {
+        if section.chars().count() <= max_chars {
+            push(&mut current, section);
+            continue;
+        }
+        // The heading stays with the paragraph after it
+        let mut paragraphs = blocks(section, str::is_empty);
+        if paragraphs.len() > 1 && is_heading(paragraphs[0].trim()) {
+            let heading = paragraphs.remove(0);
+            paragraphs[0] = &section[..heading.len() + paragraphs[0].len()];
+        }
+        for paragraph in paragraphs {
+            push(&mut current, paragraph);
+        }
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+/// Cuts `markdown` into consecutive slices, a new one starting at each
+/// line outside a code fence that `starts_block` accepts (trimmed).
+fn blocks(markdown: &str, starts_block: fn(&str) -> bool) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    let mut fenced = false;
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if is_fence(trimmed) {
+            fenced = !fenced;
+        } else if !fenced && offset > start && starts_block(trimmed) {
+            blocks.push(&markdown[start..offset]);
+            start = offset;
+        }
+        offset += line.len();
+    }
+    if start < markdown.len() {
+        blocks.push(&markdown[start..]);
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "Intro paragraph.\n\n\
+                        ## Install\n\nDownload it.\n\n```sh\n# not a heading\nrun\n```\n\n\
+                        ## Configure\n\nFirst sentence of a long section, which goes on for a while.\n\nSecond paragraph of it.\n\n\
+                        ### Options\n\nSee ![diagram](assets/abc.png).\n";
+
+    #[test]
+    fn test_split_at_headings() {
+        let parts = split("Guide", PAGE, 80);
+        let contents: Vec<&str> = parts.iter().map(|part| part.content.as_str()).collect();
+
+        // Configure doesn't fit in a part, so it is split after a paragraph
+        assert_eq!(
+            contents,
+            [
+                "# Guide (part 1 of 3)\n\nIntro paragraph.\n\n## Install\n\nDownload it.\n\n```sh\n# not a heading\nrun\n```\n",
+                "# Guide (part 2 of 3)\n\n## Configure\n\nFirst sentence of a long section, which goes on for a while.\n",
+                "# Guide (part 3 of 3)\n\nSecond paragraph of it.\n\n### Options\n\nSee ![diagram](../assets/abc.png).\n",
+            ]
+        );
+        assert_eq!(parts[0].path(), "references/part-01.md");
+        assert_eq!(parts[0].headings, ["Install"]);
+        assert_eq!(parts[1].headings, ["Configure"]);
+        // Without a top-level heading, the first one names the part
+        assert_eq!(parts[2].headings, ["Options"]);
+    }
+
+    #[test]
+    fn test_split_keeps_sections_together() {
+        let parts = split("Guide", PAGE, 1000);
+
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].headings, ["Install", "Configure"]);
+    }
+}
@@ -0,0 +1,352 @@
+//! robots.txt helpers for the agent-skills-generator.
+//!
+//! Spider already honours allow/disallow when `respect_robots_txt` is set,
+//! but not the `Crawl-delay` directive. This module fetches robots.txt for a
+//! seed's host and extracts the delay that applies to our user agent, and
+//! lets `doctor` check whether a start URL is disallowed. Its `Sitemap:`
+//! lines point dry-run estimates at the site's sitemaps.
+
+use crate::config::Config;
+use crate::http::HttpClient;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Longest `Crawl-delay` honoured; larger values are capped to it.
+pub const MAX_CRAWL_DELAY: Duration = Duration::from_secs(60);
+
+/// Fetches `/robots.txt` for the host of `url` and returns the crawl delay
+/// that applies to `user_agent`, if any. A missing robots.txt is not an error.
+pub async fn fetch_crawl_delay(
+    client: &HttpClient,
+    url: &str,
+    user_agent: &str,
+) -> Result<Option<Duration>> {
+    let robots_url = url::Url::parse(url)
+        .and_then(|base| base.join("/robots.txt"))
+        .with_context(|| format!("Invalid URL: {}", url))?;
+
+    let response = client.get(robots_url.as_str()).await?;
+
+    if !response.status().is_success() {
+        debug!("No robots.txt at {} ({})", robots_url, response.status());
+        return Ok(None);
+    }
+
+    let body = response
+        .text()
+        .await
+        .with_context(|| format!("Failed to read {}", robots_url))?;
+
+    Ok(parse_crawl_delay(&body, user_agent))
+}
+
+/// Raises `config.delay_ms` to the robots.txt `Crawl-delay` of the seed's
+/// host when that is larger. Results are cached per host in `cache`.
+pub async fn apply_crawl_delay(
+    config: &mut Config,
+    seed: &str,
+    client: &HttpClient,
+    cache: &mut HashMap<String, Option<Duration>>,
+) {
+    let Some(host) = crate::utils::extract_domain_with_protocol(seed) else {
+        return;
+    };
+
+    let delay = match cache.get(&host) {
+        Some(delay) => *delay,
+        None => {
+            let user_agent = config
+                .user_agent
+                .as_deref()
+                .unwrap_or(crate::http::DEFAULT_USER_AGENT);
+            let delay = match fetch_crawl_delay(client, seed, user_agent).await {
+                Ok(delay) => delay,
+                Err(e) => {
+                    warn!("Failed to read robots.txt for {}: {:?}", host, e);
+                    None
+                }
+            };
+            cache.insert(host.clone(), delay);
+            delay
+        }
+    };
+
+    if let Some(delay) = delay {
+        let delay_ms = delay.as_millis() as u64;
+        if delay_ms > config.delay_ms {
+            info!(
+                "robots.txt for {} sets Crawl-delay {}s; using {}ms instead of {}ms",
+                host,
+                delay.as_secs_f64(),
+                delay_ms,
+                config.delay_ms
+            );
+            config.delay_ms = delay_ms;
+        }
+    }
+}
+
+/// Extracts the `Crawl-delay` for `user_agent` from a robots.txt body.
+///
+/// A group naming our product token (the part of the user agent before the
+/// first `/`, matched case-insensitively) takes precedence over the `*`
+/// group. Fractional delays such as `0.5` are accepted; delays over
+/// [`MAX_CRAWL_DELAY`] are capped to it.
+pub fn parse_crawl_delay(robots_txt: &str, user_agent: &str) -> Option<Duration> {
+    let token = product_token(user_agent);
+
+    let mut specific = None;
+    let mut wildcard = None;
+    let mut agents: Vec<String> = Vec::new();
+    let mut in_rules = false;
+
+    for line in robots_txt.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+
+        match key.trim().to_lowercase().as_str() {
+            "user-agent" => {
+                // A user-agent line after rules starts a new group
+                if in_rules {
+                    agents.clear();
+                    in_rules = false;
+                }
+                agents.push(value.to_lowercase());
+            }
+            "crawl-delay" => {
+                in_rules = true;
+                let Some(secs) = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                else {
+                    continue;
+                };
+                let delay = match Duration::try_from_secs_f64(secs) {
+                    Ok(delay) if delay <= MAX_CRAWL_DELAY => delay,
+                    _ => {
+                        warn!(
+                            "Crawl-delay {} is too long; using {}s",
+                            value,
+                            MAX_CRAWL_DELAY.as_secs()
+                        );
+                        MAX_CRAWL_DELAY
+                    }
+                };
+
+                for agent in &agents {
+                    if agent == "*" {
+                        wildcard.get_or_insert(delay);
+                    } else if !agent.is_empty() && token.contains(agent.as_str()) {
+                        specific.get_or_insert(delay);
+                    }
+                }
+            }
+            _ => in_rules = true,
+        }
+    }
+
+    specific.or(wildcard)
+}
+
+/// Whether a robots.txt body lets `user_agent` fetch `path` (with its
+/// query, if any).
+///
+/// The group naming our product token applies if there is one, otherwise
+/// the `*` group. The longest matching `Allow` or `Disallow` pattern wins,
+/// `Allow` on a tie; `*` and a trailing `$` work as wildcard and anchor.
+pub fn is_allowed(robots_txt: &str, user_agent: &str, path: &str) -> bool {
+    let token = product_token(user_agent);
+
+    let mut specific: Vec<(bool, String)> = Vec::new();
+    let mut wildcard: Vec<(bool, String)> = Vec::new();
+    let mut has_specific_group = false;
+    let mut agents: Vec<String> = Vec::new();
+    let mut in_rules = false;
+
+    for line in robots_txt.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+
+        match key.trim().to_lowercase().as_str() {
+            "user-agent" => {
+                if in_rules {
+                    agents.clear();
+                    in_rules = false;
+                }
+                let agent = value.to_lowercase();
+                has_specific_group |= agent != "*" && !agent.is_empty() && token.contains(&agent);
+                agents.push(agent);
+            }
+            key @ ("allow" | "disallow") => {
+                in_rules = true;
+                // An empty Disallow allows everything
+                if value.is_empty() {
+                    continue;
+                }
+                let rule = (key == "allow", value.to_string());
+                for agent in &agents {
+                    if agent == "*" {
+                        wildcard.push(rule.clone());
+                    } else if !agent.is_empty() && token.contains(agent.as_str()) {
+                        specific.push(rule.clone());
+                    }
+                }
+            }
+            _ => in_rules = true,
+        }
+    }
+
+    let rules = if has_specific_group {
+        specific
+    } else {
+        wildcard
+    };
+    rules
+        .iter()
+        .filter(|(_, pattern)| path_matches(pattern, path))
+        .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+        .is_none_or(|(allow, _)| *allow)
+}
+
+/// Whether a robots.txt path pattern matches `path` from its start.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let regex = pattern
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(".*");
+    let regex = format!("^{}{}", regex, if anchored { "$" } else { "" });
+    regex::Regex::new(&regex).is_ok_and(|regex| regex.is_match(path))
+}
+
+/// The `Sitemap:` URLs of a robots.txt body, which apply to every user
+/// agent.
+pub fn parse_sitemaps(robots_txt: &str) -> Vec<String> {
+    robots_txt
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .filter(|(key, _)| key.trim().eq_ignore_ascii_case("sitemap"))
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// The product token of a user agent (the part before the first `/`),
+/// lowercased, which robots.txt groups are matched against.
+fn product_token(user_agent: &str) -> String {
+    user_agent
+        .split('/')
+        .next()
+        .unwrap_or(user_agent)
+        .trim()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UA: &str = "AgentSkillsGenerator/1.0";
+
+    #[test]
+    fn test_wildcard_crawl_delay() {
+        let robots = "User-agent: *\nDisallow: /private\nCrawl-delay: 5\n";
+        assert_eq!(parse_crawl_delay(robots, UA), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_specific_group_wins_over_wildcard() {
+        let robots = r#"
+User-agent: *
+Crawl-delay: 10
+
+# Be nicer to us
+User-agent: Googlebot
+User-agent: agentskillsgenerator
+Crawl-delay: 0.5
+Disallow: /search
+"#;
+        assert_eq!(
+            parse_crawl_delay(robots, UA),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            parse_crawl_delay(robots, "OtherBot/2.0"),
+            Some(Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        let robots = r#"
+User-agent: *
+Disallow: /private
+Disallow: /*.json$
+Allow: /private/docs
+
+User-agent: agentskillsgenerator
+Disallow: /search
+Disallow:
+"#;
+        // Our own group applies, not the wildcard one
+        assert!(!is_allowed(robots, UA, "/search?q=routing"));
+        assert!(is_allowed(robots, UA, "/private/keys"));
+
+        let other = "OtherBot/2.0";
+        assert!(!is_allowed(robots, other, "/private/keys"));
+        // The longer Allow wins
+        assert!(is_allowed(robots, other, "/private/docs/intro"));
+        assert!(!is_allowed(robots, other, "/api/index.json"));
+        assert!(is_allowed(robots, other, "/api/index.json.html"));
+        assert!(is_allowed(robots, other, "/guide"));
+
+        assert!(is_allowed("", UA, "/"));
+        assert!(!is_allowed("User-agent: *\nDisallow: /\n", UA, "/docs"));
+    }
+
+    #[test]
+    fn test_no_or_invalid_crawl_delay() {
+        assert_eq!(parse_crawl_delay("User-agent: *\nDisallow: /\n", UA), None);
+        assert_eq!(
+            parse_crawl_delay("User-agent: *\nCrawl-delay: soon\n", UA),
+            None
+        );
+        assert_eq!(parse_crawl_delay("", UA), None);
+    }
+
+    #[test]
+    fn test_oversized_crawl_delay_is_capped() {
+        for delay in ["1e20", "3600"] {
+            let robots = format!("User-agent: *\nCrawl-delay: {}\n", delay);
+            assert_eq!(parse_crawl_delay(&robots, UA), Some(MAX_CRAWL_DELAY));
+        }
+    }
+
+    #[test]
+    fn test_parse_sitemaps() {
+        let robots = "User-agent: *\nDisallow: /private\n\n\
+                      SITEMAP: https://example.com/sitemap.xml\n\
+                      Sitemap:   https://example.com/docs.xml  \nSitemap:\n";
+        assert_eq!(
+            parse_sitemaps(robots),
+            [
+                "https://example.com/sitemap.xml",
+                "https://example.com/docs.xml"
+            ]
+        );
+        assert!(parse_sitemaps("User-agent: *\n").is_empty());
+    }
+}
@@ -0,0 +1,488 @@
+//! Which URLs a crawl visits.
+//!
+//! A crawl is scoped to the URL it starts from: rules allowing that URL and
+//! everything under it are put in front of the configured ones, and with a
+//! `*` pattern in the start URL, the rest of its domain is ignored.
+//! [`scope`] builds that config, so `crawl` and `validate --test-url` see
+//! the same rules, and [`explain`] tells which rule decides a URL. A crawl
+//! of several start URLs is scoped to all of them by [`scope_all`]. With
+//! `allowed_subdomains`, the crawl is also limited to the hosts of
+//! [`host_allowlist`].
+
+use crate::config::{Action, Config, Rule};
+use crate::utils::{extract_domain_with_protocol, parse_url_pattern};
+use anyhow::{Context, Result};
+use regex::Regex;
+use tracing::info;
+
+/// A crawl's start URL and the config it is crawled with.
+#[derive(Debug, Clone)]
+pub struct CrawlScope {
+    /// The start URL without its pattern.
+    pub base_url: String,
+    pub config: Config,
+}
+
+/// Scopes `config` to a crawl starting at `url_input`, a URL that may
+/// contain a glob pattern such as `https://docs.example.com/*/widgets`.
+/// Local files and directories (see [`crate::local`]) are crawled as a
+/// `file://` URL, with `local_root` set instead of any scope rules.
+pub fn scope(config: &Config, url_input: &str) -> CrawlScope {
+    if let Some(path) = crate::local::local_path(url_input)
+        && let Ok(base_url) = crate::local::file_url(&path)
+    {
+        let mut config = config.clone();
+        // Like the prefix of a URL, so allow rules for the site don't
+        // shut the files out
+        let allowed = match path.is_dir() {
+            true => format!("{}/**", base_url.trim_end_matches('/')),
+            false => base_url.clone(),
+        };
+        config.rules.insert(0, Rule::new(allowed, Action::Allow));
+        config.local_root = Some(crate::local::root_dir(&path));
+        return CrawlScope { base_url, config };
+    }
+
+    let (base_url, pattern) = parse_url_pattern(url_input);
+    let original_rules = config.rules.len();
+    let mut config = config.clone();
+
+    if let Some(ref url_pattern) = pattern {
+        // Get the domain to create a catch-all ignore rule
+        if let Some(domain) = extract_domain_with_protocol(&base_url) {
+            info!(
+                "URL pattern detected. Allowing: {}, ignoring other paths on {}",
+                url_pattern, domain
+            );
+
+            // Insert rules at the beginning (they take precedence)
+            // First: allow the exact base URL (for the starting page)
+            config
+                .rules
+                .insert(0, Rule::new(base_url.clone(), Action::Allow));
+
+            // Second: allow the pattern (use ** for nested paths)
+            // Convert trailing /* to /** for recursive matching
+            let recursive_pattern = if url_pattern.ends_with("/*") {
+                format!("{}**", &url_pattern[..url_pattern.len() - 1])
+            } else {
+                url_pattern.clone()
+            };
+            config
+                .rules
+                .insert(1, Rule::new(recursive_pattern, Action::Allow));
+
+            // Third: ignore everything else on this domain
+            config
+                .rules
+                .insert(2, Rule::new(format!("{}/**", domain), Action::Ignore));
+        }
+    } else if extract_domain_with_protocol(&base_url).is_some() {
+        // No explicit pattern - auto-scope to the initial URL prefix, if
+        // it's a valid URL
+        // Normalize the base URL (ensure it ends with / for directory-style URLs)
+        let normalized_base = if base_url.ends_with('/') {
+            base_url.clone()
+        } else {
+            format!("{}/", base_url)
+        };
+
+        info!("Auto-scoping crawl to URL prefix: {}**", normalized_base);
+
+        // Allow the exact base URL
+        config
+            .rules
+            .insert(0, Rule::new(base_url.clone(), Action::Allow));
+
+        // Allow all URLs under the base URL path
+        config.rules.insert(
+            1,
+            Rule::new(format!("{}**", normalized_base), Action::Allow),
+        );
+
+        // Note: We don't add a domain-scope ignore rule here because:
+        // 1. The whitelist (allow rules) already restricts spider to matching URLs
+        // 2. should_crawl() returns false for URLs not matching any allow pattern
+        // 3. Adding a domain-scope ignore would conflict with user-defined ignore rules
+    }
+
+    if let Some(hosts) = host_allowlist(&config, &base_url)
+        && !config.allowed_subdomains.is_empty()
+    {
+        info!("Limiting crawl to hosts: {}", hosts.join(", "));
+        // Spider's whitelist is built from the allow rules, so each
+        // subdomain needs one to be followed at all
+        let scheme = url::Url::parse(&base_url)
+            .map(|url| url.scheme().to_string())
+            .unwrap_or_else(|_| "https".to_string());
+        // Kept in front of the configured rules, with the other scope rules
+        let at = config.rules.len() - original_rules;
+        for (i, host) in hosts[1..].iter().enumerate() {
+            config.rules.insert(
+                at + i,
+                Rule::new(format!("{}://{}/**", scheme, host), Action::Allow),
+            );
+        }
+        config.subdomains = true;
+        config.allowed_hosts = hosts;
+    }
+
+    CrawlScope { base_url, config }
+}
+
+/// Scopes `config` to a crawl starting at each of `url_inputs`. The scopes
+/// share one config holding the scope rules of every start URL, so a page
+/// in scope of any of them is crawled from all of them. The catch-all
+/// ignore rule of a start URL pattern is left out when another start URL
+/// is on its domain, since it would shut that one out.
+pub fn scope_all(config: &Config, url_inputs: &[String]) -> Vec<CrawlScope> {
+    let scopes: Vec<CrawlScope> = url_inputs
+        .iter()
+        .map(|url_input| scope(config, url_input))
+        .collect();
+    if scopes.len() < 2 {
+        return scopes;
+    }
+
+    let domains: Vec<Option<String>> = scopes
+        .iter()
+        .map(|scope| extract_domain_with_protocol(&scope.base_url))
+        .collect();
+    let mut union = config.clone();
+    let mut rules: Vec<Rule> = Vec::new();
+    for (i, scope) in scopes.iter().enumerate() {
+        let shared_domain = domains
+            .iter()
+            .enumerate()
+            .any(|(j, domain)| j != i && domain.is_some() && *domain == domains[i]);
+        let added = scope.config.rules.len() - config.rules.len();
+        for rule in &scope.config.rules[..added] {
+            if rule.action == Action::Ignore && shared_domain {
+                continue;
+            }
+            if !rules
+                .iter()
+                .any(|existing| existing.url == rule.url && existing.action == rule.action)
+            {
+                rules.push(rule.clone());
+            }
+        }
+        union.subdomains |= scope.config.subdomains;
+        for host in &scope.config.allowed_hosts {
+            if !union.allowed_hosts.contains(host) {
+                union.allowed_hosts.push(host.clone());
+            }
+        }
+    }
+    rules.append(&mut union.rules);
+    union.rules = rules;
+
+    scopes
+        .into_iter()
+        .map(|scope| CrawlScope {
+            base_url: scope.base_url,
+            config: Config {
+                local_root: scope.config.local_root,
+                ..union.clone()
+            },
+        })
+        .collect()
+}
+
+/// The hosts a crawl starting at `base_url` may visit: its host, followed
+/// by `<entry>.<host>` for each of `allowed_subdomains`, or `*.<host>` for
+/// `subdomains` without a list. `None` if `base_url` has no host.
+pub fn host_allowlist(config: &Config, base_url: &str) -> Option<Vec<String>> {
+    let url = url::Url::parse(base_url).ok()?;
+    let host = url.host_str()?.to_lowercase();
+
+    let mut hosts = vec![host.clone()];
+    if !config.allowed_subdomains.is_empty() {
+        hosts.extend(
+            config
+                .allowed_subdomains
+                .iter()
+                .map(|entry| format!("{}.{}", entry.to_lowercase(), host)),
+        );
+    } else if config.subdomains {
+        hosts.push(format!("*.{}", host));
+    }
+    Some(hosts)
+}
+
+/// Why a URL is or isn't crawled.
+#[derive(Debug, Clone)]
+pub struct UrlVerdict {
+    /// The rule deciding the URL and its index: the first ignore rule
+    /// matching it, or else the first allow rule. Content-type rules are
+    /// left out, since they are only judged once the page is fetched.
+    pub rule: Option<(usize, Rule)>,
+    /// Whether spider's whitelist, built from the allow rules, admits the
+    /// URL. `None` without allow rules.
+    pub whitelisted: Option<bool>,
+    /// Whether spider's blacklist, built from the ignore rules, holds the
+    /// URL back.
+    pub blacklisted: bool,
+    /// Whether the URL is crawled and processed.
+    pub crawled: bool,
+}
+
+/// Explains how the rules of `config` treat `url`.
+pub fn explain(config: &Config, url: &str) -> Result<UrlVerdict> {
+    let mut ignored = None;
+    let mut allowed = None;
+    for (i, rule) in config.rules.iter().enumerate() {
+        if rule.content_type.is_some() || !rule.build_matcher(i)?.is_match(url) {
+            continue;
+        }
+        let first = match rule.action {
+            Action::Ignore => &mut ignored,
+            Action::Allow => &mut allowed,
+        };
+        first.get_or_insert((i, rule.clone()));
+    }
+
+    let matches_any = |patterns: Vec<String>| -> Result<bool> {
+        for pattern in patterns {
+            let regex = Regex::new(&pattern)
+                .with_context(|| format!("Invalid spider pattern: {}", pattern))?;
+            if regex.is_match(url) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    };
+    let whitelist = config.get_whitelist_regex_patterns();
+    let whitelisted = match whitelist.is_empty() {
+        true => None,
+        false => Some(matches_any(whitelist)?),
+    };
+
+    Ok(UrlVerdict {
+        rule: ignored.or(allowed),
+        whitelisted,
+        blacklisted: matches_any(config.get_blacklist_patterns())?,
+        crawled: config.build_url_filter()?.should_crawl(url),
+    })
+}
+
+/// Describes what keeps the rules of `config` from crawling `url`, or
+/// `None` if they crawl it.
+pub fn exclusion(config: &Config, url: &str) -> Result<Option<String>> {
+    let verdict = explain(config, url)?;
+    if verdict.crawled {
+        return Ok(None);
+    }
+    Ok(Some(match verdict.rule {
+        Some((i, rule)) if rule.action == Action::Ignore => {
+            format!("ignore rule {} ({})", i + 1, rule.url)
+        }
+        _ if verdict.whitelisted == Some(false) => "no allow rule matches it".to_string(),
+        _ => "the config's rules".to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config::from_yaml(
+            r#"
+rules:
+  - url: "*/changelog/*"
+    action: ignore
+  - url: "*.pdf"
+    action: allow
+    content_type: application/pdf
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_scope_prefix() {
+        let scope = scope(&config(), "https://docs.example.com/guide");
+        assert_eq!(scope.base_url, "https://docs.example.com/guide");
+
+        let rules: Vec<(&str, Action)> = scope
+            .config
+            .rules
+            .iter()
+            .map(|rule| (rule.url.as_str(), rule.action))
+            .collect();
+        assert_eq!(
+            rules[..3],
+            [
+                ("https://docs.example.com/guide", Action::Allow),
+                ("https://docs.example.com/guide/**", Action::Allow),
+                ("*/changelog/*", Action::Ignore),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scope_pattern() {
+        let scope = scope(&config(), "https://docs.example.com/*/widgets");
+        assert_eq!(scope.base_url, "https://docs.example.com/");
+        assert_eq!(
+            scope.config.rules[1].url,
+            "https://docs.example.com/*/widgets"
+        );
+        assert_eq!(scope.config.rules[2].url, "https://docs.example.com/**");
+        assert_eq!(scope.config.rules[2].action, Action::Ignore);
+    }
+
+    #[test]
+    fn test_scope_allowed_subdomains() {
+        let mut config = config();
+        config.allowed_subdomains = vec!["api".to_string(), "*.docs".to_string()];
+        let scope = scope(&config, "https://example.com/");
+        assert!(scope.config.subdomains);
+        assert_eq!(
+            scope.config.allowed_hosts,
+            ["example.com", "api.example.com", "*.docs.example.com"]
+        );
+
+        let rules: Vec<&str> = scope
+            .config
+            .rules
+            .iter()
+            .map(|rule| rule.url.as_str())
+            .collect();
+        assert!(rules.contains(&"https://api.example.com/**"), "{:?}", rules);
+        assert!(
+            rules.contains(&"https://*.docs.example.com/**"),
+            "{:?}",
+            rules
+        );
+
+        let filter = scope.config.build_url_filter().unwrap();
+        assert!(filter.should_crawl("https://example.com/guide"));
+        assert!(filter.should_crawl("https://api.example.com/reference"));
+        assert!(filter.should_crawl("https://v2.docs.example.com/intro"));
+        assert!(!filter.should_crawl("https://blog.example.com/post"));
+        // Only the listed subdomains, not the ones below them
+        assert!(!filter.should_crawl("https://docs.example.com/intro"));
+    }
+
+    #[test]
+    fn test_scope_all() {
+        let scopes = scope_all(
+            &config(),
+            &[
+                "https://docs.example.com/guide".to_string(),
+                "https://docs.example.com/*/widgets".to_string(),
+                "https://api.example.com/".to_string(),
+            ],
+        );
+        let base_urls: Vec<&str> = scopes.iter().map(|scope| scope.base_url.as_str()).collect();
+        assert_eq!(
+            base_urls,
+            [
+                "https://docs.example.com/guide",
+                "https://docs.example.com/",
+                "https://api.example.com/"
+            ]
+        );
+
+        let union = &scopes[0].config;
+        assert!(
+            scopes
+                .iter()
+                .all(|scope| scope.config.rules.len() == union.rules.len())
+        );
+        let filter = union.build_url_filter().unwrap();
+        assert!(filter.should_crawl("https://docs.example.com/guide/install"));
+        assert!(filter.should_crawl("https://docs.example.com/v2/widgets"));
+        assert!(filter.should_crawl("https://api.example.com/reference"));
+        assert!(!filter.should_crawl("https://docs.example.com/blog/post"));
+        // The configured rules still apply
+        assert!(!filter.should_crawl("https://docs.example.com/guide/changelog/v2"));
+
+        // The pattern's catch-all ignore rule is kept when it shuts no one out
+        let apart = scope_all(
+            &config(),
+            &[
+                "https://docs.example.com/*/widgets".to_string(),
+                "https://api.example.com/".to_string(),
+            ],
+        );
+        assert!(
+            apart[0]
+                .config
+                .rules
+                .iter()
+                .any(|rule| rule.url == "https://docs.example.com/**"
+                    && rule.action == Action::Ignore)
+        );
+    }
+
+    #[test]
+    fn test_host_allowlist() {
+        let mut config = Config::default();
+        assert_eq!(
+            host_allowlist(&config, "https://Docs.example.com/guide").unwrap(),
+            ["docs.example.com"]
+        );
+        config.subdomains = true;
+        assert_eq!(
+            host_allowlist(&config, "https://docs.example.com/guide").unwrap(),
+            ["docs.example.com", "*.docs.example.com"]
+        );
+        assert!(host_allowlist(&config, "not a url").is_none());
+    }
+
+    #[test]
+    fn test_explain() {
+        let config = scope(&config(), "https://docs.example.com/guide").config;
+
+        let verdict = explain(&config, "https://docs.example.com/guide/install").unwrap();
+        let (index, rule) = verdict.rule.unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(rule.url, "https://docs.example.com/guide/**");
+        assert_eq!(verdict.whitelisted, Some(true));
+        assert!(!verdict.blacklisted);
+        assert!(verdict.crawled);
+
+        // The ignore rule wins over the scope
+        let verdict = explain(&config, "https://docs.example.com/guide/changelog/v2").unwrap();
+        assert_eq!(verdict.rule.unwrap().0, 2);
+        assert!(verdict.blacklisted);
+        assert!(!verdict.crawled);
+
+        // Out of scope: no rule matches, and the allow rules keep it out
+        let verdict = explain(&config, "https://docs.example.com/blog/post").unwrap();
+        assert!(verdict.rule.is_none());
+        assert_eq!(verdict.whitelisted, Some(false));
+        assert!(!verdict.crawled);
+
+        // Without allow rules everything not ignored is crawled
+        let verdict = explain(&Config::default(), "https://example.com/any").unwrap();
+        assert_eq!(verdict.whitelisted, None);
+        assert!(verdict.crawled);
+    }
+
+    #[test]
+    fn test_exclusion() {
+        let config = config();
+        assert_eq!(
+            exclusion(&config, "https://docs.example.com/changelog/v2").unwrap(),
+            Some("ignore rule 1 (*/changelog/*)".to_string())
+        );
+        assert_eq!(
+            exclusion(&Config::default(), "https://docs.example.com/guide/install").unwrap(),
+            None
+        );
+
+        let config = scope(&config, "https://docs.example.com/guide").config;
+        assert_eq!(
+            exclusion(&config, "https://docs.example.com/guide/install").unwrap(),
+            None
+        );
+        assert_eq!(
+            exclusion(&config, "https://docs.example.com/blog/post").unwrap(),
+            Some("no allow rule matches it".to_string())
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! Skill destinations for the agent-skills-generator.
+//!
+//! The crawler hands every processed page to an [`OutputSink`] per target.
+//! [`FsSink`] writes skills to an output directory, as
+//! [`Processor::write_to_disk`] does, [`JsonlSink`] gathers them all in one
+//! JSON Lines file (`writer: jsonl`) and [`CollectSink`] keeps them in
+//! memory; other sinks can upload them or pack them into an archive instead.
+
+use crate::frontmatter::{VOLATILE_FIELDS, without_volatile_fields};
+use crate::processor::{PageMetadata, ProcessedPage, Processor, WrittenSkill};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// File of an output directory [`JsonlSink`] writes skills to.
+pub const JSONL_FILE: &str = "skills.jsonl";
+
+/// The future returned by [`OutputSink::write_skill`].
+pub type WriteFuture<'a> = Pin<Box<dyn Future<Output = Result<WrittenSkill>> + Send + 'a>>;
+
+/// The future returned by [`OutputSink::finish`].
+pub type FinishFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Somewhere processed pages are written as skills.
+pub trait OutputSink: Send + Sync {
+    /// Writes `processed` under its `skill_name`, reporting where it went
+    /// and whether anything changed.
+    fn write_skill<'a>(&'a self, processed: &'a ProcessedPage) -> WriteFuture<'a>;
+
+    /// Called once every page of a crawl was written, to flush whatever
+    /// the sink buffered. Does nothing unless overridden.
+    fn finish(&self) -> FinishFuture<'_> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+/// Writes skills to an output directory, in their `group_by` directory.
+pub struct FsSink {
+    processor: Arc<Processor>,
+    output_dir: PathBuf,
+}
+
+impl FsSink {
+    /// Writes skills to `output_dir` in the layout `processor` is
+    /// configured for (`flat`, `output_format`, `include_html`).
+    pub fn new(processor: Arc<Processor>, output_dir: PathBuf) -> Self {
+        Self {
+            processor,
+            output_dir,
+        }
+    }
+}
+
+impl OutputSink for FsSink {
+    fn write_skill<'a>(&'a self, processed: &'a ProcessedPage) -> WriteFuture<'a> {
+        let url = &processed.metadata.url;
+        let output_dir = self.processor.group_dir(&self.output_dir, url);
+        Box::pin(async move { self.processor.write_to_disk(processed, &output_dir).await })
+    }
+}
+
+/// Writes every skill to a JSON Lines file, one object per page: the page
+/// metadata, as in a `skill.json`, the skill file as `content` and the parts
+/// of a split skill as `references`.
+///
+/// The file already there is read on the first write, and a page keeps its
+/// line unless its skill changed. The file is rewritten once, by
+/// [`OutputSink::finish`], with one line per page sorted by URL; pages no
+/// longer crawled keep their lines, as skill directories stay until
+/// `clean`.
+pub struct JsonlSink {
+    path: PathBuf,
+    /// Line of every page by URL, read from the file on the first write,
+    /// and whether any changed since.
+    lines: tokio::sync::Mutex<Option<(BTreeMap<String, String>, bool)>>,
+}
+
+/// A line of [`JsonlSink`]'s file.
+#[derive(Serialize)]
+struct JsonlSkill<'a> {
+    #[serde(flatten)]
+    metadata: &'a PageMetadata,
+    content: &'a str,
+    references: Vec<JsonlReference<'a>>,
+}
+
+/// A part of a split skill, in a line of [`JsonlSink`]'s file.
+#[derive(Serialize)]
+struct JsonlReference<'a> {
+    /// Path relative to the skill, as linked from `content`.
+    path: String,
+    content: &'a str,
+}
+
+impl JsonlSink {
+    /// Writes skills to the file at `path`, created along with its
+    /// directory when the crawl finishes.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lines: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Lines of the file at `path` by page URL, the last one winning.
+    /// Lines that can't be read are dropped with a warning.
+    async fn read_lines(path: &Path) -> Result<BTreeMap<String, String>> {
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+
+        let mut lines = BTreeMap::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let url = serde_json::from_str::<serde_json::Value>(line)
+                .ok()
+                .and_then(|value| value["url"].as_str().map(str::to_string));
+            match url {
+                Some(url) => {
+                    lines.insert(url, line.to_string());
+                }
+                None => warn!("Dropping unreadable line of {}", path.display()),
+            }
+        }
+        Ok(lines)
+    }
+
+    /// `line` without the fields that change on every run, to compare
+    /// skills by.
+    fn comparable(line: &str) -> Option<serde_json::Value> {
+        let mut value: serde_json::Value = serde_json::from_str(line).ok()?;
+        let object = value.as_object_mut()?;
+        for field in VOLATILE_FIELDS {
+            object.remove(*field);
+        }
+        if let Some(serde_json::Value::String(content)) = object.get_mut("content") {
+            *content = without_volatile_fields(content);
+        }
+        Some(value)
+    }
+}
+
+impl OutputSink for JsonlSink {
+    fn write_skill<'a>(&'a self, processed: &'a ProcessedPage) -> WriteFuture<'a> {
+        Box::pin(async move {
+            let references = processed
+                .references
+                .iter()
+                .map(|reference| JsonlReference {
+                    path: reference.path(),
+                    content: &reference.content,
+                })
+                .collect();
+            let line = serde_json::to_string(&JsonlSkill {
+                metadata: &processed.metadata,
+                content: &processed.skill_md,
+                references,
+            })?;
+
+            let mut lines = self.lines.lock().await;
+            if lines.is_none() {
+                *lines = Some((Self::read_lines(&self.path).await?, false));
+            }
+            let (lines, dirty) = lines.as_mut().expect("lines were just read");
+            let url = &processed.metadata.url;
+            let changed = lines
+                .get(url)
+                .is_none_or(|existing| Self::comparable(existing) != Self::comparable(&line));
+            if changed {
+                lines.insert(url.clone(), line);
+                *dirty = true;
+            }
+
+            Ok(WrittenSkill {
+                skill_dir: self.path.clone(),
+                skill_name: processed.metadata.skill_name.clone(),
+                changed,
+            })
+        })
+    }
+
+    fn finish(&self) -> FinishFuture<'_> {
+        Box::pin(async move {
+            let mut lines = self.lines.lock().await;
+            let Some((lines, dirty)) = lines.as_mut() else {
+                return Ok(());
+            };
+            if !*dirty {
+                return Ok(());
+            }
+
+            let mut content = String::new();
+            for line in lines.values() {
+                content.push_str(line);
+                content.push('\n');
+            }
+            // Replaced whole, so an interrupted write leaves the old file
+            if let Some(dir) = self.path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            let temp = self.path.with_extension("jsonl.tmp");
+            tokio::fs::write(&temp, content)
+                .await
+                .with_context(|| format!("Failed to write {}", temp.display()))?;
+            tokio::fs::rename(&temp, &self.path)
+                .await
+                .with_context(|| format!("Failed to write {}", self.path.display()))?;
+            *dirty = false;
+            Ok(())
+        })
+    }
+}
+
+/// Keeps processed pages in memory instead of writing them, as
+/// [`Crawler::crawl_collect`](crate::crawler::Crawler::crawl_collect) does.
+#[derive(Default)]
+pub struct CollectSink {
+    pages: Mutex<Vec<ProcessedPage>>,
+}
+
+impl CollectSink {
+    /// Creates an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the pages collected so far, in the order they were written.
+    pub fn take(&self) -> Vec<ProcessedPage> {
+        std::mem::take(&mut *self.pages.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl OutputSink for CollectSink {
+    fn write_skill<'a>(&'a self, processed: &'a ProcessedPage) -> WriteFuture<'a> {
+        let name = processed.metadata.skill_name.clone();
+        self.pages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(processed.clone());
+
+        Box::pin(async move {
+            Ok(WrittenSkill {
+                skill_dir: PathBuf::from(&name),
+                skill_name: name,
+                changed: true,
+            })
+        })
+    }
+}
+
+/// Keeps skills in memory, by name, for tests.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemorySink {
+    skills: std::sync::Mutex<std::collections::BTreeMap<String, String>>,
+}
+
+#[cfg(test)]
+impl MemorySink {
+    /// The SKILL.md of every skill written, by name.
+    pub(crate) fn skills(&self) -> std::collections::BTreeMap<String, String> {
+        self.skills.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl OutputSink for MemorySink {
+    fn write_skill<'a>(&'a self, processed: &'a ProcessedPage) -> WriteFuture<'a> {
+        use crate::frontmatter::without_volatile_fields;
+
+        let name = processed.metadata.skill_name.clone();
+        let mut skills = self.skills.lock().unwrap();
+        let changed = skills.get(&name).is_none_or(|existing| {
+            without_volatile_fields(existing) != without_volatile_fields(&processed.skill_md)
+        });
+        skills.insert(name.clone(), processed.skill_md.clone());
+
+        Box::pin(async move {
+            Ok(WrittenSkill {
+                skill_dir: PathBuf::from(&name),
+                skill_name: name,
+                changed,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::references::ReferenceFile;
+
+    const PAGE: &str = "<html><head><title>Install</title></head>\
+                        <body><main><h1>Install</h1><p>Run the installer and follow the prompts.</p></main></body></html>";
+
+    #[tokio::test]
+    async fn test_fs_sink_writes_like_write_to_disk() {
+        let processor = Arc::new(Processor::new(&Config::default()).unwrap());
+        let processed = processor
+            .process("https://example.com/docs/install", PAGE)
+            .unwrap();
+        let output_dir = std::env::temp_dir().join(format!("fs-sink-test-{}", std::process::id()));
+        let sink = FsSink::new(Arc::clone(&processor), output_dir.clone());
+
+        let written = sink.write_skill(&processed).await.unwrap();
+        assert_eq!(written.skill_dir, output_dir.join("docs-install"));
+        assert!(written.changed);
+        assert_eq!(
+            std::fs::read_to_string(output_dir.join("docs-install/SKILL.md")).unwrap(),
+            processed.skill_md
+        );
+
+        // Same content, so nothing is rewritten
+        assert!(!sink.write_skill(&processed).await.unwrap().changed);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_sink_keeps_a_line_per_page() {
+        let processor = Processor::new(&Config::default()).unwrap();
+        let mut install = processor
+            .process("https://example.com/docs/install", PAGE)
+            .unwrap();
+        install.references.push(ReferenceFile {
+            file_name: "part-01.md".to_string(),
+            headings: vec!["Prompts".to_string()],
+            content: "## Prompts\n\nAnswer every prompt.".to_string(),
+        });
+        let upgrade = processor
+            .process(
+                "https://example.com/docs/upgrade",
+                &PAGE.replace("Install", "Upgrade"),
+            )
+            .unwrap();
+        let output_dir =
+            std::env::temp_dir().join(format!("jsonl-sink-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_dir);
+        let path = output_dir.join(JSONL_FILE);
+        let read_lines = || -> Vec<serde_json::Value> {
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        };
+
+        let sink = JsonlSink::new(path.clone());
+        let written = sink.write_skill(&upgrade).await.unwrap();
+        assert_eq!(written.skill_dir, path);
+        assert_eq!(written.skill_name, "docs-upgrade");
+        assert!(written.changed);
+        assert!(sink.write_skill(&install).await.unwrap().changed);
+        // Nothing is written before the crawl finishes
+        assert!(!path.exists());
+        sink.finish().await.unwrap();
+
+        let lines = read_lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["skill_name"], "docs-install");
+        assert_eq!(lines[0]["content"], install.skill_md.as_str());
+        assert_eq!(lines[0]["references"][0]["path"], "references/part-01.md");
+        assert_eq!(
+            lines[0]["references"][0]["content"],
+            "## Prompts\n\nAnswer every prompt."
+        );
+        assert_eq!(lines[1]["title"], "Upgrade");
+
+        // A recrawl replaces changed lines rather than adding to the file
+        let later = processor
+            .process(
+                "https://example.com/docs/upgrade",
+                &PAGE
+                    .replace("Install", "Upgrade")
+                    .replace("prompts", "steps"),
+            )
+            .unwrap();
+        let sink = JsonlSink::new(path.clone());
+        assert!(!sink.write_skill(&install).await.unwrap().changed);
+        assert!(sink.write_skill(&later).await.unwrap().changed);
+        sink.finish().await.unwrap();
+
+        let lines = read_lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["content"], later.skill_md.as_str());
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_memory_sink() {
+        let processor = Processor::new(&Config::default()).unwrap();
+        let processed = processor
+            .process("https://example.com/docs/install", PAGE)
+            .unwrap();
+        let sink = MemorySink::default();
+
+        let written = sink.write_skill(&processed).await.unwrap();
+        assert_eq!(written.skill_name, "docs-install");
+        assert!(written.changed);
+        assert!(!sink.write_skill(&processed).await.unwrap().changed);
+
+        let skills = sink.skills();
+        assert_eq!(skills.len(), 1);
+        assert_eq!(skills["docs-install"], processed.skill_md);
+    }
+
+    #[tokio::test]
+    async fn test_collect_sink() {
+        let processor = Processor::new(&Config::default()).unwrap();
+        let processed = processor
+            .process("https://example.com/docs/install", PAGE)
+            .unwrap();
+        let sink = CollectSink::new();
+
+        let written = sink.write_skill(&processed).await.unwrap();
+        assert_eq!(written.skill_name, "docs-install");
+
+        let pages = sink.take();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].skill_md, processed.skill_md);
+        assert!(sink.take().is_empty());
+    }
+}
@@ -0,0 +1,476 @@
+//! HTML table conversion for the agent-skills-generator.
+//!
+//! htmd flattens tables badly: it ignores `colspan` and `rowspan`, drops
+//! `<th>` cells in body rows and spreads block content (paragraphs, lists)
+//! across lines. Tables are rendered here instead, as GitHub-flavored
+//! Markdown tables with one line per row: spanned cells are repeated in
+//! every grid slot they cover and column alignment comes from `align` /
+//! `text-align`. Tables that can't be represented that way (nested,
+//! single-column or wider than [`MAX_TABLE_COLUMNS`]) become bulleted lists,
+//! and tables over `max_table_cells` become an indented code block of their
+//! text, so nothing is silently lost.
+
+use anyhow::Result;
+use scraper::ElementRef;
+
+/// Tables with more columns than this are rendered as bulleted lists.
+pub const MAX_TABLE_COLUMNS: usize = 8;
+
+/// A table row in document order.
+struct Row<'a> {
+    element: ElementRef<'a>,
+    /// Whether the row sits in `<thead>`.
+    in_head: bool,
+}
+
+/// Column alignment, from the header cells' `align` attribute or
+/// `text-align` style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+/// Renders `table` as Markdown, using `convert` to turn each cell's inner
+/// HTML into Markdown. Tables whose grid has more than `max_cells` cells are
+/// rendered as plain text. Returns `None` for tables without rows.
+pub fn render_table(
+    table: ElementRef,
+    convert: &dyn Fn(&str) -> Result<String>,
+    max_cells: usize,
+) -> Result<Option<String>> {
+    let rows = table_rows(table);
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let caption = table
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|e| e.value().name() == "caption")
+        .map(|caption| caption.text().collect::<String>().trim().to_string())
+        .filter(|caption| !caption.is_empty());
+    let with_caption = |body: String| match &caption {
+        Some(caption) => format!("{}\n\n{}", caption, body),
+        None => body,
+    };
+
+    // Count the grid before converting any cell, so huge tables stay cheap
+    let cell_count = grid(&rows, |_| Ok(()))?
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        * rows.len();
+    if cell_count > max_cells {
+        return Ok(Some(with_caption(render_text(&rows))));
+    }
+
+    // A header is a <thead> row, or a first row made only of <th> cells
+    let header_index = rows.iter().position(|row| row.in_head).or_else(|| {
+        let mut cells = cell_elements(rows[0].element).peekable();
+        (cells.peek().is_some() && cells.all(|cell| cell.value().name() == "th")).then_some(0)
+    });
+
+    let mut cells = grid(&rows, |cell| {
+        Ok(convert(&cell.inner_html())?.trim().to_string())
+    })?;
+    let alignments = header_index
+        .map(|index| grid(&rows[index..=index], |cell| Ok(alignment(cell))))
+        .transpose()?
+        .and_then(|mut grid| grid.pop())
+        .unwrap_or_default();
+
+    let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+    let nested = table
+        .descendants()
+        .skip(1)
+        .filter_map(ElementRef::wrap)
+        .any(|e| e.value().name() == "table");
+
+    let header = header_index.map(|index| cells.remove(index));
+    let body = if nested || !(2..=MAX_TABLE_COLUMNS).contains(&columns) {
+        render_list(header.as_deref(), &cells)
+    } else {
+        render_markdown_table(header, cells, &alignments, columns)
+    };
+
+    Ok(Some(with_caption(body)))
+}
+
+/// Collects the rows owned by `table`, skipping rows of nested tables.
+fn table_rows(table: ElementRef) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for child in table.children().filter_map(ElementRef::wrap) {
+        match child.value().name() {
+            "tr" => rows.push(Row {
+                element: child,
+                in_head: false,
+            }),
+            section @ ("thead" | "tbody" | "tfoot") => rows.extend(
+                child
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|e| e.value().name() == "tr")
+                    .map(|element| Row {
+                        element,
+                        in_head: section == "thead",
+                    }),
+            ),
+            _ => {}
+        }
+    }
+    rows
+}
+
+/// The `<td>`/`<th>` cells of a row.
+fn cell_elements(row: ElementRef) -> impl Iterator<Item = ElementRef> {
+    row.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|e| matches!(e.value().name(), "td" | "th"))
+}
+
+/// Lays `rows` out on a grid, mapping every cell through `value` and
+/// repeating it in each slot its `colspan` and `rowspan` cover, so later
+/// cells stay in their column.
+fn grid<T: Clone>(
+    rows: &[Row],
+    mut value: impl FnMut(ElementRef) -> Result<T>,
+) -> Result<Vec<Vec<T>>> {
+    // Cells spanning down into later rows, by column: value and rows left
+    let mut spanning: Vec<Option<(T, usize)>> = Vec::new();
+    let mut grid = Vec::with_capacity(rows.len());
+
+    for (index, row) in rows.iter().enumerate() {
+        let rows_left = rows.len() - index;
+        let mut cells = Vec::new();
+
+        for cell in cell_elements(row.element) {
+            fill_spanned(&mut cells, &mut spanning);
+
+            let colspan = span(cell, "colspan")
+                .unwrap_or(1)
+                .clamp(1, MAX_TABLE_COLUMNS + 1);
+            // rowspan="0" spans the rest of the table
+            let rowspan = match span(cell, "rowspan") {
+                Some(0) => rows_left,
+                rowspan => rowspan.unwrap_or(1).clamp(1, rows_left),
+            };
+
+            let value = value(cell)?;
+            for _ in 0..colspan {
+                if rowspan > 1 {
+                    if spanning.len() <= cells.len() {
+                        spanning.resize(cells.len() + 1, None);
+                    }
+                    spanning[cells.len()] = Some((value.clone(), rowspan - 1));
+                }
+                cells.push(value.clone());
+            }
+        }
+        // Cells spanning into the end of the row
+        fill_spanned(&mut cells, &mut spanning);
+
+        grid.push(cells);
+    }
+    Ok(grid)
+}
+
+/// Fills the slots from the end of `cells` on that are taken by cells
+/// spanning down from earlier rows.
+fn fill_spanned<T: Clone>(cells: &mut Vec<T>, spanning: &mut [Option<(T, usize)>]) {
+    while let Some(Some((value, left))) = spanning.get_mut(cells.len()) {
+        cells.push(value.clone());
+        *left -= 1;
+        if *left == 0 {
+            spanning[cells.len() - 1] = None;
+        }
+    }
+}
+
+fn span(cell: ElementRef, attribute: &str) -> Option<usize> {
+    cell.value()
+        .attr(attribute)
+        .and_then(|span| span.trim().parse::<usize>().ok())
+}
+
+fn alignment(cell: ElementRef) -> Alignment {
+    let style = cell
+        .value()
+        .attr("style")
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let from_style = style.split(';').find_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        (property.trim() == "text-align").then(|| value.trim().to_string())
+    });
+    let align = from_style.or_else(|| cell.value().attr("align").map(str::to_ascii_lowercase));
+
+    match align.as_deref().map(str::trim) {
+        Some("left" | "start") => Alignment::Left,
+        Some("center") => Alignment::Center,
+        Some("right" | "end") => Alignment::Right,
+        _ => Alignment::None,
+    }
+}
+
+/// Squashes cell Markdown onto one line and escapes pipes.
+fn inline_cell(cell: &str) -> String {
+    cell.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("<br>")
+        .replace('|', "\\|")
+}
+
+/// Renders a GitHub-flavored Markdown table. Without a header row the first
+/// row is used as the header, since the format requires one.
+fn render_markdown_table(
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+    alignments: &[Alignment],
+    columns: usize,
+) -> String {
+    let mut rows: Vec<Vec<String>> = header
+        .into_iter()
+        .chain(rows)
+        .map(|row| {
+            let mut row: Vec<String> = row.iter().map(|cell| inline_cell(cell)).collect();
+            row.resize(columns, String::new());
+            row
+        })
+        .collect();
+
+    let mut widths = vec![3; columns];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let separator = widths
+        .iter()
+        .enumerate()
+        .map(
+            |(column, width)| match alignments.get(column).copied().unwrap_or(Alignment::None) {
+                Alignment::None => "-".repeat(*width),
+                Alignment::Left => format!(":{}", "-".repeat(width - 1)),
+                Alignment::Center => format!(":{}:", "-".repeat(width - 2)),
+                Alignment::Right => format!("{}:", "-".repeat(width - 1)),
+            },
+        )
+        .collect();
+    rows.insert(1, separator);
+
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders the table's text as an indented code block, one line per row
+/// with cells separated by ` | `.
+fn render_text(rows: &[Row]) -> String {
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = cell_elements(row.element)
+                .map(|cell| {
+                    cell.text()
+                        .flat_map(str::split_whitespace)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+            format!("    {}", cells.join(" | ").trim_end())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders each row as a bullet for its first cell, with the remaining
+/// cells as sub-bullets labelled by their header.
+fn render_list(header: Option<&[String]>, rows: &[Vec<String>]) -> String {
+    let label = |column: usize, cell: &str| match header
+        .and_then(|header| header.get(column))
+        .map(|label| inline_cell(label))
+        .filter(|label| !label.is_empty())
+    {
+        Some(label) => format!("{}: {}", label, cell),
+        None => cell.to_string(),
+    };
+
+    let mut items = Vec::new();
+    for row in rows {
+        let mut cells = row.iter().enumerate().filter(|(_, cell)| !cell.is_empty());
+        let Some((column, first)) = cells.next() else {
+            continue;
+        };
+
+        items.push(indent_item(&label(column, first), "- ", "  "));
+        for (column, cell) in cells {
+            items.push(indent_item(&label(column, cell), "  - ", "    "));
+        }
+    }
+
+    items.join("\n")
+}
+
+/// Prefixes the first line of `text` with `marker` and indents the rest so
+/// multi-line cells stay inside their list item.
+fn indent_item(text: &str, marker: &str, indent: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| match (i, line.is_empty()) {
+            (0, _) => format!("{}{}", marker, line),
+            (_, true) => String::new(),
+            _ => format!("{}{}", indent, line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::{Html, Selector};
+
+    /// Renders the first table in `html`, treating cell HTML as plain text.
+    fn render(html: &str) -> String {
+        render_with_limit(html, 1000)
+    }
+
+    fn render_with_limit(html: &str, max_cells: usize) -> String {
+        let document = Html::parse_fragment(html);
+        let table = document
+            .select(&Selector::parse("table").unwrap())
+            .next()
+            .unwrap();
+        let convert = |cell: &str| Ok(htmd::convert(cell)?);
+
+        render_table(table, &convert, max_cells).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_colspan_keeps_columns_aligned() {
+        let markdown = render(
+            r#"<table>
+<tr><th>A</th><th>B</th><th>C</th></tr>
+<tr><td colspan="2">wide</td><td>c</td></tr>
+</table>"#,
+        );
+
+        assert_eq!(
+            markdown,
+            "| A    | B    | C   |\n| ---- | ---- | --- |\n| wide | wide | c   |"
+        );
+    }
+
+    #[test]
+    fn test_rowspan_repeats_cells() {
+        let markdown = render(
+            r#"<table>
+<thead><tr><th>Parameter</th><th>Type</th><th>Description</th></tr></thead>
+<tbody>
+<tr><td rowspan="2"><code>timeout</code></td><td>int</td><td>Seconds</td></tr>
+<tr><td>string</td><td>Duration like <code>5s</code></td></tr>
+<tr><td>retries</td><td colspan="2" rowspan="0">Not supported</td></tr>
+<tr><td>backoff</td></tr>
+</tbody>
+</table>"#,
+        );
+
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(
+            lines[2..],
+            [
+                "| `timeout` | int           | Seconds            |",
+                "| `timeout` | string        | Duration like `5s` |",
+                "| retries   | Not supported | Not supported      |",
+                "| backoff   | Not supported | Not supported      |",
+            ],
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn test_header_alignment() {
+        let markdown = render(
+            r#"<table><tr><th align="left">Name</th><th style="text-align: center">Default</th><th style="color: red; text-align:right">Max</th><th>Notes</th></tr>
+<tr><td>a</td><td>1</td><td>10</td><td>-</td></tr></table>"#,
+        );
+
+        assert_eq!(
+            markdown.lines().nth(1),
+            Some("| :--- | :-----: | --: | ----- |"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn test_large_table_falls_back_to_text() {
+        let markdown = render_with_limit(
+            r#"<table><caption>Limits</caption>
+<tr><th>Plan</th><th>Requests</th></tr>
+<tr><td><p>Free</p></td><td>1 <b>000</b> / day</td></tr>
+<tr><td>Pro</td><td>Unlimited</td></tr></table>"#,
+            4,
+        );
+
+        assert_eq!(
+            markdown,
+            "Limits\n\n    Plan | Requests\n    Free | 1 000 / day\n    Pro | Unlimited"
+        );
+    }
+
+    #[test]
+    fn test_block_content_stays_on_one_row() {
+        let markdown = render(
+            "<table><tr><th>Key</th><th>Notes</th></tr>\
+             <tr><th>a|b</th><td><p>one</p><p>two</p></td></tr></table>",
+        );
+
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines.len(), 3);
+        // Row headers in the body are kept, pipes escaped
+        assert!(lines[2].starts_with("| a\\|b"), "{}", markdown);
+        assert!(lines[2].contains("one<br>two"), "{}", markdown);
+    }
+
+    #[test]
+    fn test_wide_table_falls_back_to_list() {
+        let header: String = (1..=9).map(|i| format!("<th>H{}</th>", i)).collect();
+        let row: String = (1..=9).map(|i| format!("<td>v{}</td>", i)).collect();
+        let markdown = render(&format!(
+            "<table><tr>{}</tr><tr>{}</tr></table>",
+            header, row
+        ));
+
+        assert!(
+            markdown.starts_with("- H1: v1\n  - H2: v2\n"),
+            "{}",
+            markdown
+        );
+        assert!(!markdown.contains('|'));
+    }
+
+    #[test]
+    fn test_nested_table_falls_back_to_list() {
+        let markdown = render(
+            "<table><tr><td>outer</td><td><table><tr><td>x</td><td>y</td></tr></table></td></tr></table>",
+        );
+
+        assert!(markdown.starts_with("- outer\n  - "), "{}", markdown);
+    }
+}
@@ -0,0 +1,103 @@
+//! Frontmatter tags for the agent-skills-generator.
+//!
+//! `metadata.tags` helps an agent route to the right skill. Tags come from
+//! the page's `<meta name="keywords">`; with `heading_tags`, pages without
+//! keywords are tagged with the terms their headings use most. Either way
+//! tags are lowercase, unique and capped at [`MAX_TAGS`].
+
+use std::collections::HashMap;
+
+/// Most tags a page gets.
+pub const MAX_TAGS: usize = 10;
+
+/// Shortest heading term worth a tag.
+const MIN_TERM_CHARS: usize = 3;
+
+/// Words that say nothing about a page. Terms shorter than
+/// [`MIN_TERM_CHARS`] are skipped anyway, so short words aren't listed.
+const STOPWORDS: &[&str] = &[
+    "about", "after", "all", "also", "and", "any", "are", "before", "but", "can", "does", "for",
+    "from", "get", "getting", "guide", "has", "have", "how", "into", "its", "more", "not", "our",
+    "out", "over", "overview", "started", "than", "that", "the", "their", "then", "there", "these",
+    "this", "use", "using", "what", "when", "where", "which", "why", "will", "with", "you", "your",
+];
+
+/// Tags from the `content` of a keywords meta tag, a comma-separated list.
+pub fn from_keywords(keywords: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    for keyword in keywords.split(',') {
+        let tag = keyword.split_whitespace().collect::<Vec<_>>().join(" ");
+        let tag = tag.to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+        if tags.len() == MAX_TAGS {
+            break;
+        }
+    }
+    tags
+}
+
+/// The terms used most across `headings`, skipping stopwords and numbers.
+/// Ties go to the term that appears first.
+pub fn from_headings<'a>(headings: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut order = Vec::new();
+    for heading in headings {
+        let terms = heading
+            .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+            .map(|term| term.trim_matches(['-', '_']).to_lowercase())
+            .filter(|term| {
+                term.chars().count() >= MIN_TERM_CHARS
+                    && !term.chars().all(|c| c.is_numeric())
+                    && !STOPWORDS.contains(&term.as_str())
+            });
+        for term in terms {
+            let count = counts.entry(term.clone()).or_default();
+            if *count == 0 {
+                order.push(term);
+            }
+            *count += 1;
+        }
+    }
+
+    // Stable, so ties keep their first appearance
+    order.sort_by_key(|term| std::cmp::Reverse(counts[term]));
+    order.truncate(MAX_TAGS);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_keywords() {
+        assert_eq!(
+            from_keywords(" Flutter, Widgets,,flutter ,  State   Management "),
+            ["flutter", "widgets", "state management"]
+        );
+        let many: Vec<String> = (0..20).map(|i| format!("k{}", i)).collect();
+        assert_eq!(from_keywords(&many.join(",")).len(), MAX_TAGS);
+        assert!(from_keywords(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_from_headings() {
+        let tags = from_headings([
+            "Getting started with Widgets",
+            "Stateful widgets",
+            "Layout: rows and columns",
+            "Widgets in 2024",
+        ]);
+        assert_eq!(tags[0], "widgets");
+        assert!(tags.contains(&"stateful".to_string()));
+        assert!(!tags.iter().any(|tag| tag == "with" || tag == "2024"));
+
+        let headings: Vec<String> = (0..20).map(|i| format!("topic{}", i)).collect();
+        assert_eq!(
+            from_headings(headings.iter().map(String::as_str)).len(),
+            MAX_TAGS
+        );
+    }
+}
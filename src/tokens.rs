@@ -0,0 +1,178 @@
+//! Token estimates for the agent-skills-generator.
+//!
+//! Skills end up in an agent's context, so their size is reported in
+//! tokens rather than characters. The estimate follows how BPE tokenizers
+//! pre-split text before merging: words, numbers, punctuation and
+//! whitespace are cut apart, then each piece is charged what such
+//! tokenizers typically spend on it. It is an estimate, not a tokenizer,
+//! but it tracks code and Markdown far better than characters / 4, without
+//! shipping a vocabulary.
+
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Letters of a word that typically fit in one token.
+const LETTERS_PER_TOKEN: usize = 6;
+
+/// Digits that typically fit in one token.
+const DIGITS_PER_TOKEN: usize = 3;
+
+/// Punctuation characters that typically fit in one token (`**`, `](`).
+const SYMBOLS_PER_TOKEN: usize = 2;
+
+/// Estimates the number of tokens in `text`.
+pub fn estimate(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let class = CharClass::of(c);
+        let mut len = 1;
+        let mut wide = usize::from(!c.is_ascii());
+        let mut newline = c == '\n';
+        while let Some(&next) = chars.peek() {
+            if CharClass::of(next) != class {
+                break;
+            }
+            len += 1;
+            wide += usize::from(!next.is_ascii());
+            newline |= next == '\n';
+            chars.next();
+        }
+
+        tokens += match class {
+            // Non-ASCII letters (accents, CJK) rarely merge
+            CharClass::Letter => (len - wide).div_ceil(LETTERS_PER_TOKEN) + wide,
+            CharClass::Digit => len.div_ceil(DIGITS_PER_TOKEN),
+            // A single space joins the next word; indentation and line
+            // breaks are tokens of their own
+            CharClass::Space => usize::from(newline || len > 1),
+            CharClass::Symbol => (len - wide).div_ceil(SYMBOLS_PER_TOKEN) + wide,
+        };
+    }
+    tokens
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Letter,
+    Digit,
+    Space,
+    Symbol,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_alphabetic() {
+            Self::Letter
+        } else if c.is_numeric() {
+            Self::Digit
+        } else if c.is_whitespace() {
+            Self::Space
+        } else {
+            Self::Symbol
+        }
+    }
+}
+
+/// Formats a token count for humans: `950`, `412k`, `1.2M`.
+pub fn format_count(tokens: usize) -> String {
+    match tokens {
+        0..1_000 => tokens.to_string(),
+        1_000..1_000_000 => format!("{}k", (tokens + 500) / 1_000),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// Caps the estimated tokens of the skills a crawl emits
+/// (`max_total_tokens`). Shared by every crawler of a run; once a skill
+/// doesn't fit, no more are emitted.
+#[derive(Debug, Default)]
+pub struct TokenBudget {
+    /// 0 means unlimited.
+    limit: usize,
+    state: Mutex<BudgetState>,
+}
+
+#[derive(Debug, Default)]
+struct BudgetState {
+    spent: usize,
+    exhausted: bool,
+}
+
+impl TokenBudget {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Spends `tokens` on a new skill if they fit in what is left of the
+    /// budget. Returns `false`, and for good, once a skill doesn't fit.
+    pub fn try_spend(&self, tokens: usize) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.exhausted {
+            return false;
+        }
+        if self.limit > 0 && state.spent + tokens > self.limit {
+            warn!(
+                "Token budget of {} reached (~{} spent); skipping the remaining pages",
+                format_count(self.limit),
+                format_count(state.spent)
+            );
+            state.exhausted = true;
+            return false;
+        }
+        state.spent += tokens;
+        true
+    }
+
+    /// Counts the tokens of a skill kept from an earlier crawl, which is
+    /// part of the skill set whether it fits or not.
+    pub fn add(&self, tokens: usize) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.spent += tokens;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(estimate(""), 0);
+        assert_eq!(estimate("Hello world"), 2);
+        assert_eq!(estimate("fn main() {}"), 4);
+        assert_eq!(estimate("Internationalization"), 4);
+        assert_eq!(estimate("1234567"), 3);
+        assert_eq!(estimate("## Install\n\n    cargo build"), 6);
+        assert_eq!(estimate("日本語"), 3);
+
+        // Markdown prose comes out well under characters / 4
+        let prose = "Run the installer, then open a new terminal so the `PATH` change \
+                     takes effect. See [the guide](https://example.com/setup) for details.";
+        assert!(estimate(prose) < prose.len() / 3, "{}", estimate(prose));
+        assert!(estimate(prose) > prose.len() / 8, "{}", estimate(prose));
+    }
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(950), "950");
+        assert_eq!(format_count(412_345), "412k");
+        assert_eq!(format_count(1_234_567), "1.2M");
+    }
+
+    #[test]
+    fn test_budget() {
+        let budget = TokenBudget::new(100);
+        assert!(budget.try_spend(60));
+        budget.add(10);
+        assert!(!budget.try_spend(40));
+        // Exhausted for good, even for a skill that would fit
+        assert!(!budget.try_spend(5));
+
+        let unlimited = TokenBudget::new(0);
+        assert!(unlimited.try_spend(usize::MAX / 2));
+    }
+}
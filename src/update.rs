@@ -0,0 +1,445 @@
+//! The `update` command of the agent-skills-generator.
+//!
+//! Every SKILL.md records the page it was generated from in
+//! `metadata.url`, so existing skills can be refreshed without crawling:
+//! each skill's page is fetched again, processed with the current config and
+//! written back to the same directory if the result differs. Pages that are
+//! gone (404/410) are reported as failed, or deleted with `--prune-missing`.
+//!
+//! With `incremental: true` the crawl cache of the output directory is used
+//! as well: pages are requested with their cached validators and aren't
+//! processed again if they answer `304 Not Modified` or are byte-identical.
+
+use crate::cache::{CacheEntry, CrawlCache, content_hash};
+use crate::frontmatter;
+use crate::http::{self, HttpClient};
+use crate::processor::{PageContext, ProcessedPage, Processor};
+use crate::utils::{self, SkillFile};
+use anyhow::{Context, Result};
+use globset::{Glob, GlobMatcher};
+use std::path::{Path, PathBuf};
+use tracing::{debug, error, info, warn};
+
+/// An existing skill and the page it was generated from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkillSource {
+    /// Skill directory name.
+    pub name: String,
+    /// Skill directory, or the `<name>.md` file of a flat skill.
+    pub path: PathBuf,
+    /// The skill's markdown file.
+    pub skill_md: PathBuf,
+    /// `metadata.url` from the frontmatter.
+    pub url: String,
+}
+
+/// Options of an update run.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateOptions {
+    /// Only skills whose name matches this glob are updated.
+    pub pattern: Option<String>,
+    /// Delete skills whose page returns 404 or 410.
+    pub prune_missing: bool,
+    /// Revalidate pages with the crawl cache and record them in it.
+    pub incremental: bool,
+}
+
+/// What happened to each skill of an update run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpdateStats {
+    pub updated: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+    pub pruned: usize,
+}
+
+impl UpdateStats {
+    /// Returns a summary of the update.
+    pub fn summary(&self) -> String {
+        format!(
+            "Update complete: {} updated, {} unchanged, {} failed, {} pruned",
+            self.updated, self.unchanged, self.failed, self.pruned
+        )
+    }
+}
+
+/// A skill's page fetched again.
+#[derive(Debug)]
+pub enum Fetched {
+    /// The page, processed with the current config, and what to cache
+    /// about it.
+    Page(Box<ProcessedPage>, CacheEntry),
+    /// The page answered `304 Not Modified` or has the cached content hash.
+    NotModified,
+    /// The page answered 404 or 410.
+    Missing(u16),
+}
+
+/// Result of refreshing one skill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Updated,
+    Unchanged,
+    /// The page answered 404 or 410.
+    Missing(u16),
+}
+
+/// Lists the skills in `output_dir` whose name matches `pattern`, sorted by
+/// name. Skills without a source URL are skipped with a warning.
+pub fn find_skills(output_dir: &Path, pattern: Option<&GlobMatcher>) -> Result<Vec<SkillSource>> {
+    let mut skills = Vec::new();
+    for skill in utils::skill_files(output_dir)? {
+        let SkillFile {
+            name,
+            path,
+            skill_md,
+            ..
+        } = skill;
+        if pattern.is_some_and(|pattern| !pattern.is_match(&name)) {
+            continue;
+        }
+
+        let content = fs_err::read_to_string(&skill_md)?;
+        let url = match frontmatter::source_url(&content) {
+            Some(url) => url,
+            None => {
+                warn!(
+                    "Skipping {}: no source URL (metadata.url in its frontmatter, or url in skill.json)",
+                    skill_md.display()
+                );
+                continue;
+            }
+        };
+
+        skills.push(SkillSource {
+            name,
+            path,
+            skill_md,
+            url,
+        });
+    }
+
+    Ok(skills)
+}
+
+/// Compiles a `--pattern` glob over skill names.
+pub fn skill_pattern(pattern: Option<&str>) -> Result<Option<GlobMatcher>> {
+    pattern
+        .map(|pattern| {
+            Glob::new(pattern)
+                .map(|glob| glob.compile_matcher())
+                .with_context(|| format!("Invalid skill pattern: {}", pattern))
+        })
+        .transpose()
+}
+
+/// Refreshes the skills in `output_dir` from their source pages.
+pub async fn update_skills(
+    client: &HttpClient,
+    processor: &Processor,
+    response_headers: &[String],
+    output_dir: &Path,
+    options: &UpdateOptions,
+) -> Result<UpdateStats> {
+    let pattern = skill_pattern(options.pattern.as_deref())?;
+    let skills = find_skills(output_dir, pattern.as_ref())?;
+    info!(
+        "Updating {} skills in {}",
+        skills.len(),
+        output_dir.display()
+    );
+
+    let cache = options.incremental.then(|| CrawlCache::load(output_dir));
+
+    let mut stats = UpdateStats::default();
+    for skill in &skills {
+        match update_skill(client, processor, response_headers, skill, cache.as_ref()).await {
+            Ok(Outcome::Updated) => {
+                info!("Updated: {} ({})", skill.name, skill.url);
+                stats.updated += 1;
+            }
+            Ok(Outcome::Unchanged) => {
+                debug!("Unchanged: {} ({})", skill.name, skill.url);
+                // Still live, so `clean --older-than` keeps it
+                if let Err(e) = crate::utils::touch(&skill.skill_md) {
+                    debug!("{:#}", e);
+                }
+                stats.unchanged += 1;
+            }
+            Ok(Outcome::Missing(status)) if options.prune_missing => {
+                let removed = if skill.path.is_dir() {
+                    fs_err::tokio::remove_dir_all(&skill.path).await
+                } else {
+                    fs_err::tokio::remove_file(&skill.path).await
+                };
+                removed
+                    .with_context(|| format!("Failed to remove skill: {}", skill.path.display()))?;
+                info!("Pruned: {} ({} returned {})", skill.name, skill.url, status);
+                stats.pruned += 1;
+            }
+            Ok(Outcome::Missing(status)) => {
+                warn!(
+                    "Source of {} is gone ({} returned {}); use --prune-missing to delete it",
+                    skill.name, skill.url, status
+                );
+                stats.failed += 1;
+            }
+            Err(e) => {
+                error!("Failed to update {}: {:#}", skill.name, e);
+                stats.failed += 1;
+            }
+        }
+    }
+
+    if let Some(cache) = cache
+        && let Err(e) = cache.save()
+    {
+        warn!("Failed to save crawl cache: {:#}", e);
+    }
+
+    Ok(stats)
+}
+
+/// Fetches `url` and processes it, or reports it missing on 404 or 410.
+/// With a `cached` entry the request is conditional, and a page that
+/// hasn't changed since isn't processed.
+pub async fn fetch_page(
+    client: &HttpClient,
+    processor: &Processor,
+    response_headers: &[String],
+    url: &str,
+    cached: Option<&CacheEntry>,
+) -> Result<Fetched> {
+    let conditions = cached.map(CacheEntry::conditions).unwrap_or_default();
+    let response = client.get_with_headers(url, conditions).await?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    if matches!(status.as_u16(), 404 | 410) {
+        return Ok(Fetched::Missing(status.as_u16()));
+    }
+    if !status.is_success() {
+        anyhow::bail!("Failed to fetch URL: {}: HTTP {}", url, status);
+    }
+
+    let headers = response.headers().clone();
+    let context = PageContext::new(url).with_headers(&headers, response_headers);
+    let body = http::read_body(response, processor, &context).await?;
+
+    let hash = content_hash(body.text().as_bytes());
+    if cached.is_some_and(|entry| entry.content_hash == hash) {
+        return Ok(Fetched::NotModified);
+    }
+
+    let mut processed = processor
+        .process_body(&context, &body)
+        .with_context(|| format!("Failed to process page: {}", url))?;
+    processor.download_images(&mut processed).await?;
+    let entry = CacheEntry::new(Some(&headers), hash, &processed.metadata.skill_name);
+    Ok(Fetched::Page(Box::new(processed), entry))
+}
+
+/// Fetches and processes a skill's page, rewriting the skill if it changed.
+async fn update_skill(
+    client: &HttpClient,
+    processor: &Processor,
+    response_headers: &[String],
+    skill: &SkillSource,
+    cache: Option<&CrawlCache>,
+) -> Result<Outcome> {
+    let cached = cache
+        .and_then(|cache| cache.get(&skill.url))
+        .filter(|entry| entry.skill_name == skill.name);
+    let fetched = fetch_page(
+        client,
+        processor,
+        response_headers,
+        &skill.url,
+        cached.as_ref(),
+    )
+    .await?;
+    let (mut processed, mut entry) = match fetched {
+        Fetched::Page(processed, entry) => (processed, entry),
+        Fetched::NotModified => return Ok(Outcome::Unchanged),
+        Fetched::Missing(status) => return Ok(Outcome::Missing(status)),
+    };
+
+    // Keep the directory the skill was written to, e.g. a disambiguated name
+    if processed.metadata.skill_name != skill.name {
+        processor.rename(&mut processed, &skill.name)?;
+        entry.skill_name = skill.name.clone();
+    }
+    if let Some(cache) = cache {
+        cache.record(&skill.url, entry);
+    }
+
+    let output_dir = skill.path.parent().unwrap_or(Path::new("."));
+    let written = processor.write_to_disk(&processed, output_dir).await?;
+    Ok(if written.changed {
+        Outcome::Updated
+    } else {
+        Outcome::Unchanged
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::http::{build_http_client, ok_response, test_server};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn docs_site(request: &str) -> String {
+        if request.starts_with("GET /gone ") {
+            return "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                .to_string();
+        }
+        ok_response(
+            "<html><head><title>Install</title><meta name=\"description\" content=\"How to install\"></head>\
+             <body><main><h1>Install</h1><p>Run the new installer.</p></main></body></html>",
+        )
+    }
+
+    fn write_skill(output_dir: &Path, name: &str, url: &str) {
+        std::fs::create_dir_all(output_dir.join(name)).unwrap();
+        std::fs::write(
+            output_dir.join(name).join("SKILL.md"),
+            format!(
+                "---\nname: {}\ndescription: Old\nmetadata:\n  url: {}\n---\n\n# Old\n\nStale.\n",
+                name, url
+            ),
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_refreshes_changed_skills_and_prunes_missing() {
+        let port = test_server(docs_site).await;
+        let output_dir =
+            std::env::temp_dir().join(format!("agent-skills-update-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        write_skill(
+            &output_dir,
+            "install",
+            &format!("http://127.0.0.1:{}/install", port),
+        );
+        write_skill(
+            &output_dir,
+            "gone",
+            &format!("http://127.0.0.1:{}/gone", port),
+        );
+        std::fs::create_dir_all(output_dir.join("notes")).unwrap();
+
+        let config = Config::default();
+        let client = build_http_client(&config).unwrap();
+        let processor = Processor::new(&config).unwrap();
+        let update = |options: UpdateOptions| {
+            let (client, processor, output_dir) = (&client, &processor, &output_dir);
+            async move {
+                update_skills(client, processor, &[], output_dir, &options)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let stats = update(UpdateOptions::default()).await;
+        assert_eq!(
+            stats,
+            UpdateStats {
+                updated: 1,
+                failed: 1,
+                ..Default::default()
+            }
+        );
+        let skill_md =
+            std::fs::read_to_string(output_dir.join("install").join("SKILL.md")).unwrap();
+        assert!(skill_md.contains("Run the new installer."), "{}", skill_md);
+        assert!(skill_md.starts_with("---\nname: install\n"), "{}", skill_md);
+
+        // Nothing changed since, and only matching skills are visited
+        let stats = update(UpdateOptions {
+            pattern: Some("inst*".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(
+            stats,
+            UpdateStats {
+                unchanged: 1,
+                ..Default::default()
+            }
+        );
+
+        let stats = update(UpdateOptions {
+            prune_missing: true,
+            ..Default::default()
+        })
+        .await;
+        assert_eq!((stats.unchanged, stats.pruned), (1, 1));
+        assert!(!output_dir.join("gone").exists());
+        assert!(output_dir.join("notes").exists());
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    static NOT_MODIFIED_SENT: AtomicUsize = AtomicUsize::new(0);
+
+    fn etag_site(request: &str) -> String {
+        if request.to_lowercase().contains("if-none-match: \"v1\"") {
+            NOT_MODIFIED_SENT.fetch_add(1, Ordering::SeqCst);
+            return "HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n".to_string();
+        }
+        let body = "<html><head><title>Install</title></head>\
+                    <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>";
+        format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\netag: \"v1\"\r\n\
+             content-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    #[tokio::test]
+    async fn test_incremental_update_skips_not_modified_pages() {
+        let port = test_server(etag_site).await;
+        let output_dir = std::env::temp_dir().join(format!(
+            "agent-skills-update-incremental-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&output_dir);
+        let url = format!("http://127.0.0.1:{}/install", port);
+        write_skill(&output_dir, "install", &url);
+
+        let config = Config::default();
+        let client = build_http_client(&config).unwrap();
+        let processor = Processor::new(&config).unwrap();
+        let options = UpdateOptions {
+            incremental: true,
+            ..Default::default()
+        };
+
+        let stats = update_skills(&client, &processor, &[], &output_dir, &options)
+            .await
+            .unwrap();
+        assert_eq!(stats.updated, 1);
+        let entry = CrawlCache::load(&output_dir).get(&url).unwrap();
+        assert_eq!(entry.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(entry.skill_name, "install");
+
+        let stats = update_skills(&client, &processor, &[], &output_dir, &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            UpdateStats {
+                unchanged: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(NOT_MODIFIED_SENT.load(Ordering::SeqCst), 1);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
@@ -3,11 +3,103 @@
 //! This module provides helper functions for string sanitization,
 //! path manipulation, and other common operations used throughout the crate.
 
+use crate::config::SkillFormat;
+use crate::images::ASSETS_DIR;
+use anyhow::{Context, Result};
 use regex::Regex;
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 
 /// Maximum length for skill names (strict compliance requirement).
-const MAX_SKILL_NAME_LENGTH: usize = 64;
+pub const MAX_SKILL_NAME_LENGTH: usize = 64;
+
+/// Levels of `group_by` directories skills may be nested in.
+const MAX_GROUP_DEPTH: usize = 2;
+
+/// A generated skill on disk, in either output layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkillFile {
+    /// Directory name, or the file stem of a flat skill.
+    pub name: String,
+    /// Skill directory, or the `<name>.md` file of a flat skill.
+    pub path: PathBuf,
+    /// The skill's markdown: `SKILL.md` (or `skill.json`, `skill.txt`) in
+    /// its directory, or `path` itself.
+    pub skill_md: PathBuf,
+    /// The `group_by` directory holding the skill, like
+    /// `docs-flutter-dev/ui`, if it is in one.
+    pub group: Option<String>,
+}
+
+/// Lists the skills in `output_dir`, sorted by name: directories holding a
+/// SKILL.md, skill.json or skill.txt, and the `<name>.md` and
+/// `<name>.json` files written with `flat`. Other directories are taken
+/// for `group_by` directories and searched too, two levels deep. Flat
+/// plain-text skills can't be told apart from other text files and aren't
+/// listed, nor are hidden files like the crawl cache.
+pub fn skill_files(output_dir: &Path) -> Result<Vec<SkillFile>> {
+    let mut skills = Vec::new();
+    if output_dir.exists() {
+        find_skill_files(output_dir, None, &mut skills)?;
+    }
+    skills.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.group.cmp(&b.group)));
+    Ok(skills)
+}
+
+/// Adds the skills in `dir`, the `group` directory if it has one, to
+/// `skills`.
+fn find_skill_files(dir: &Path, group: Option<&str>, skills: &mut Vec<SkillFile>) -> Result<()> {
+    let depth = group.map_or(0, |group| group.split('/').count());
+    for entry in fs_err::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let skill_md = if path.is_dir() {
+            let found = SkillFormat::ALL
+                .iter()
+                .map(|format| path.join(format.file_name()))
+                .find(|file| file.is_file());
+            match found {
+                Some(file) => file,
+                // Images of flat skills aren't a group
+                None if depth < MAX_GROUP_DEPTH && entry.file_name() != ASSETS_DIR => {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let group = match group {
+                        Some(group) => format!("{}/{}", group, name),
+                        None => name,
+                    };
+                    find_skill_files(&path, Some(&group), skills)?;
+                    continue;
+                }
+                None => continue,
+            }
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "md" || extension == "json")
+        {
+            path.clone()
+        } else {
+            continue;
+        };
+        if !skill_md.is_file() {
+            continue;
+        }
+
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        skills.push(SkillFile {
+            name,
+            path,
+            skill_md,
+            group: group.map(str::to_string),
+        });
+    }
+    Ok(())
+}
 
 /// Pre-compiled regex patterns for sanitization.
 /// Using LazyLock for thread-safe, one-time initialization.
@@ -69,6 +161,111 @@ pub fn sanitize_skill_name(path: &str) -> String {
     truncate_at_word_boundary(&trimmed, MAX_SKILL_NAME_LENGTH)
 }
 
+/// Separators sites put between a page title and the site name, as in
+/// `Install | Flutter`.
+const TITLE_SEPARATORS: [&str; 4] = [" | ", " – ", " — ", " · "];
+
+/// Turns a page title into a kebab-case skill name.
+///
+/// The trailing site name (`Install | Flutter`) is dropped, accented Latin
+/// letters lose their accents, apostrophes are removed and any other run of
+/// punctuation, emoji or whitespace becomes a single hyphen. Returns an
+/// empty string if nothing usable is left.
+///
+/// # Examples
+/// ```
+/// use agent_skills_generator::utils::title_to_skill_name;
+///
+/// assert_eq!(title_to_skill_name("Getting Started | Acme Docs"), "getting-started");
+/// assert_eq!(title_to_skill_name("🚀 What's New in 2.0?"), "whats-new-in-2-0");
+/// ```
+pub fn title_to_skill_name(title: &str) -> String {
+    let mut name = String::with_capacity(title.len());
+    for c in strip_site_name(title).chars().flat_map(char::to_lowercase) {
+        match c {
+            '\'' | '’' | '‘' => {}
+            c if c.is_ascii_alphanumeric() => name.push(c),
+            c => name.push_str(fold_accent(c).unwrap_or("-")),
+        }
+    }
+
+    let collapsed = MULTIPLE_HYPHENS.replace_all(&name, "-");
+    let trimmed = LEADING_TRAILING_HYPHENS.replace_all(&collapsed, "");
+    truncate_at_word_boundary(&trimmed, MAX_SKILL_NAME_LENGTH)
+        .trim_end_matches('-')
+        .to_string()
+}
+
+/// Drops the last ` | Site Name` segment of a title, unless nothing would
+/// be left.
+fn strip_site_name(title: &str) -> &str {
+    let title = title.trim();
+    TITLE_SEPARATORS
+        .iter()
+        .filter_map(|separator| title.rfind(separator))
+        .max()
+        .map(|i| title[..i].trim())
+        .filter(|page| !page.is_empty())
+        .unwrap_or(title)
+}
+
+/// ASCII spelling of a lowercase accented Latin letter.
+fn fold_accent(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à'..='å' => "a",
+        'æ' => "ae",
+        'ç' => "c",
+        'è'..='ë' => "e",
+        'ì'..='ï' => "i",
+        'ñ' => "n",
+        'ò'..='ö' | 'ø' => "o",
+        'œ' => "oe",
+        'ù'..='ü' => "u",
+        'ý' | 'ÿ' => "y",
+        'ß' => "ss",
+        _ => return None,
+    })
+}
+
+/// Spells `text` in ASCII for skill names (`transliterate_names`), with
+/// [`deunicode`]: accents are dropped, other scripts are romanized (Chinese
+/// in pinyin, a word per character) and the result is lowercased.
+///
+/// # Examples
+/// ```
+/// use agent_skills_generator::utils::transliterate;
+///
+/// assert_eq!(transliterate("/guía/configuración"), "/guia/configuracion");
+/// assert_eq!(transliterate("/Привет"), "/privet");
+/// ```
+pub fn transliterate(text: &str) -> String {
+    deunicode::deunicode(text).to_lowercase()
+}
+
+/// Decodes every `%XX` escape of a URL path, reading the bytes as UTF-8.
+pub fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
 /// Removes common file extensions from a string.
 fn remove_file_extension(s: &str) -> String {
     let extensions = [
@@ -96,7 +293,7 @@ fn urlencoding_decode(s: &str) -> String {
 }
 
 /// Truncates a string at a word (hyphen) boundary if possible.
-fn truncate_at_word_boundary(s: &str, max_len: usize) -> String {
+pub fn truncate_at_word_boundary(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         return s.to_string();
     }
@@ -154,6 +351,37 @@ pub fn extract_url_path(url_str: &str) -> String {
     }
 }
 
+/// Query parameters of a URL for which `keep` holds, as path segments to
+/// append to [`extract_url_path`]: `/name/value`, or `/name` for a
+/// parameter without a value. Empty if none are kept.
+///
+/// # Examples
+/// ```
+/// use agent_skills_generator::utils::query_path;
+///
+/// let version = |name: &str| name == "version";
+/// assert_eq!(query_path("https://example.com/api?version=2&x=1", version), "/version/2");
+/// assert_eq!(query_path("https://example.com/api", version), "");
+/// ```
+pub fn query_path(url_str: &str, keep: impl Fn(&str) -> bool) -> String {
+    let Ok(url) = url::Url::parse(url_str) else {
+        return String::new();
+    };
+    let mut path = String::new();
+    for (name, value) in url.query_pairs() {
+        if name.is_empty() || !keep(&name) {
+            continue;
+        }
+        path.push('/');
+        path.push_str(&name);
+        if !value.is_empty() {
+            path.push('/');
+            path.push_str(&value);
+        }
+    }
+    path
+}
+
 /// Truncates a description to fit within token limits.
 ///
 /// This is part of the **Reference Pattern** - we keep SKILL.md lightweight
@@ -165,13 +393,14 @@ pub fn extract_url_path(url_str: &str) -> String {
 /// * `max_chars` - Maximum character limit (default 1024)
 ///
 /// # Returns
-/// A truncated description that ends at a sentence boundary if possible.
+/// A truncated description that ends at a sentence boundary if possible,
+/// and never longer than `max_chars` bytes, ellipsis included.
 pub fn truncate_description(description: &str, max_chars: usize) -> String {
     if description.len() <= max_chars {
         return description.to_string();
     }
 
-    let truncated = &description[..max_chars];
+    let truncated = &description[..description.floor_char_boundary(max_chars)];
 
     // Try to find the last sentence boundary
     let sentence_endings = [". ", "! ", "? "];
@@ -188,7 +417,8 @@ pub fn truncate_description(description: &str, max_chars: usize) -> String {
     if best_end > max_chars / 2 {
         truncated[..best_end].trim().to_string()
     } else {
-        // Fall back to word boundary
+        // Fall back to word boundary, leaving room for the ellipsis
+        let truncated = &truncated[..truncated.floor_char_boundary(max_chars.saturating_sub(3))];
         if let Some(last_space) = truncated.rfind(' ') {
             format!("{}...", truncated[..last_space].trim())
         } else {
@@ -260,6 +490,97 @@ pub fn extract_domain_with_protocol(url_str: &str) -> Option<String> {
         .map(|url| format!("{}://{}", url.scheme(), url.host_str().unwrap_or("")))
 }
 
+/// Checks that files can be written to `dir`, or that it can be created:
+/// its closest existing ancestor must be a directory that accepts new
+/// files. Probes by creating and removing an empty file.
+pub fn check_writable(dir: &Path) -> Result<()> {
+    // A relative path's last ancestor is empty, meaning the current directory
+    let existing = dir
+        .ancestors()
+        .map(|path| match path.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => path,
+        })
+        .find(|path| path.exists())
+        .unwrap_or(Path::new("."));
+    if !existing.is_dir() {
+        anyhow::bail!(
+            "Can't create {}: {} is not a directory",
+            dir.display(),
+            existing.display()
+        );
+    }
+
+    let probe = existing.join(format!(".skills-write-check-{}", std::process::id()));
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .with_context(|| format!("{} is not writable", existing.display()))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Sets the modification time of `path` to now without touching its
+/// content, recording that a crawl still produces the file.
+pub fn touch(path: &Path) -> Result<()> {
+    std::fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(std::time::SystemTime::now()))
+        .with_context(|| format!("Failed to touch {}", path.display()))
+}
+
+/// Parses a duration written as numbers with units, like `7d`, `24h`,
+/// `90s` or `1h30m`: `s`, `m`, `h`, `d` and `w` for seconds to weeks.
+///
+/// # Examples
+/// ```
+/// use agent_skills_generator::utils::parse_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+/// assert!(parse_duration("7").is_err());
+/// ```
+pub fn parse_duration(text: &str) -> Result<std::time::Duration, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("empty duration".to_string());
+    }
+
+    let mut secs: u64 = 0;
+    let mut digits = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(format!("invalid duration '{}': unknown unit '{}'", text, c)),
+        };
+        let value: u64 = digits
+            .parse()
+            .map_err(|_| format!("invalid duration '{}': '{}' has no number", text, c))?;
+        secs = value
+            .checked_mul(unit)
+            .and_then(|value| secs.checked_add(value))
+            .ok_or_else(|| format!("duration '{}' is too long", text))?;
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        return Err(format!(
+            "invalid duration '{}': give {} a unit (s, m, h, d or w)",
+            text, digits
+        ));
+    }
+    Ok(std::time::Duration::from_secs(secs))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -334,6 +655,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_title_to_skill_name() {
+        assert_eq!(title_to_skill_name("Getting Started"), "getting-started");
+        // The site name goes, whichever separator the site uses
+        assert_eq!(title_to_skill_name("Install | Flutter"), "install");
+        assert_eq!(title_to_skill_name("Hooks — React"), "hooks");
+        assert_eq!(
+            title_to_skill_name("Layout · Widgets · Flutter"),
+            "layout-widgets"
+        );
+        assert_eq!(title_to_skill_name("| Flutter"), "flutter");
+        // Punctuation
+        assert_eq!(
+            title_to_skill_name("What's new in C++ 2.0? (Beta)"),
+            "whats-new-in-c-2-0-beta"
+        );
+        assert_eq!(
+            title_to_skill_name("async/await & Futures"),
+            "async-await-futures"
+        );
+        // Emoji and accents
+        assert_eq!(title_to_skill_name("🚀 Quick Start ✨"), "quick-start");
+        assert_eq!(title_to_skill_name("Café Über Straße"), "cafe-uber-strasse");
+        assert_eq!(title_to_skill_name("🚀✨"), "");
+        assert_eq!(title_to_skill_name("日本語"), "");
+    }
+
+    #[test]
+    fn test_title_to_skill_name_long_title() {
+        let title = "The Ultimate Complete Beginner's Guide to Building, Testing, \
+                     Deploying and Scaling Modern Web Applications with Rust, WebAssembly, \
+                     Docker, Kubernetes and Serverless Functions in 2026 (Updated!!) | DevBlog";
+        assert!(title.len() >= 200, "{}", title.len());
+
+        let name = title_to_skill_name(title);
+        assert_eq!(
+            name,
+            "the-ultimate-complete-beginners-guide-to-building-testing"
+        );
+        assert!(name.len() <= MAX_SKILL_NAME_LENGTH);
+    }
+
     #[test]
     fn test_extract_url_path() {
         assert_eq!(
@@ -347,6 +710,57 @@ mod tests {
         assert_eq!(extract_url_path("https://example.com"), "/");
     }
 
+    #[test]
+    fn test_parse_duration() {
+        use std::time::Duration;
+
+        assert_eq!(parse_duration("7d"), Ok(Duration::from_secs(7 * 86400)));
+        assert_eq!(parse_duration("24h"), Ok(Duration::from_secs(86400)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::from_secs(14 * 86400)));
+        assert_eq!(parse_duration(" 90s "), Ok(Duration::from_secs(90)));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+        assert!(parse_duration("99999999999999999999w").is_err());
+    }
+
+    #[test]
+    fn test_query_path() {
+        let keep = |name: &str| name == "version" || name == "preview";
+        assert_eq!(
+            query_path("https://example.com/api?lang=en&version=2&preview", keep),
+            "/version/2/preview"
+        );
+        assert_eq!(query_path("https://example.com/api?lang=en", keep), "");
+        assert_eq!(query_path("not a url?version=2", keep), "");
+    }
+
+    #[test]
+    fn test_transliterate() {
+        assert_eq!(
+            sanitize_skill_name(&transliterate(&percent_decode(
+                "/gu%C3%ADa/configuraci%C3%B3n"
+            ))),
+            "guia-configuracion"
+        );
+        assert_eq!(
+            transliterate("Łódź/Ελληνικά/Привет"),
+            "lodz/ellenika/privet"
+        );
+
+        // Chinese in pinyin, a word per character
+        assert_eq!(
+            sanitize_skill_name(&transliterate("/文档/安装")),
+            "wendang-anzhuang"
+        );
+        assert_eq!(
+            sanitize_skill_name(&transliterate("/ドキュメント/시작하기")),
+            "dokiyumento-sijaghagi"
+        );
+    }
+
     #[test]
     fn test_truncate_description() {
         let short = "A short description.";
@@ -357,6 +771,35 @@ mod tests {
         assert!(result.len() <= 103); // 100 + "..."
     }
 
+    #[test]
+    fn test_truncate_description_fits_with_ellipsis() {
+        let long = "A".repeat(2000);
+        let result = truncate_description(&long, 100);
+        assert!(result.len() <= 100, "{}", result.len());
+        assert!(result.ends_with("..."));
+
+        let words = "word ".repeat(50);
+        assert_eq!(truncate_description(&words, 18), "word word word...");
+    }
+
+    #[test]
+    fn test_truncate_description_at_sentence() {
+        let text = "Install the SDK first. Then run the setup wizard. It asks for a key.";
+        assert_eq!(
+            truncate_description(text, 55),
+            "Install the SDK first. Then run the setup wizard."
+        );
+    }
+
+    #[test]
+    fn test_truncate_description_multibyte() {
+        // Multi-byte characters are never cut in half
+        let accented = "é".repeat(100);
+        let result = truncate_description(&accented, 51);
+        assert!(result.len() <= 51, "{}", result.len());
+        assert!(result.ends_with("..."));
+    }
+
     #[test]
     fn test_extract_domain() {
         assert_eq!(
@@ -395,6 +838,32 @@ mod tests {
         assert_eq!(pattern, Some("https://example.com/v?/api".to_string()));
     }
 
+    #[test]
+    fn test_check_writable() {
+        let root = std::env::temp_dir().join(format!("check-writable-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        check_writable(&root).unwrap();
+        // Created on demand
+        check_writable(&root.join(".claude/skills")).unwrap();
+        assert!(!root.join(".claude").exists());
+        // Nothing is left behind
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 0);
+
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+        let message = format!(
+            "{:#}",
+            check_writable(&root.join("notes.txt/skills")).unwrap_err()
+        );
+        assert!(
+            message.contains("notes.txt is not a directory"),
+            "{}",
+            message
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
     #[test]
     fn test_extract_domain_with_protocol() {
         assert_eq!(
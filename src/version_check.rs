@@ -0,0 +1,188 @@
+//! Opt-in check for newer releases.
+//!
+//! Only runs when the user passes `--version-check` or sets
+//! `version_check: true`, and as a row of `doctor`, which reports why a
+//! lookup failed; it never downloads or installs anything. The
+//! release lookup sits behind [`ReleaseSource`] so it can be stubbed in tests.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// GitHub API endpoint for the latest published release.
+pub const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/AmanSikarwar/agent-skills-generator/releases/latest";
+
+/// Upper bound for the whole lookup, so an unreachable API never holds up a run.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The running version, from Cargo.toml.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Somewhere the latest release version can be looked up.
+pub trait ReleaseSource {
+    /// Returns the latest release version (e.g. `v0.3.0` or `0.3.0`).
+    fn latest_version(&self) -> impl Future<Output = Result<String>> + Send;
+}
+
+/// Looks up the latest release through the GitHub releases API.
+pub struct GithubReleases {
+    client: reqwest::Client,
+}
+
+impl GithubReleases {
+    /// Creates a release source with a short-timeout client.
+    pub fn new() -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(crate::http::DEFAULT_USER_AGENT)
+            .timeout(CHECK_TIMEOUT)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self { client })
+    }
+}
+
+#[derive(Deserialize)]
+struct LatestRelease {
+    tag_name: String,
+}
+
+impl ReleaseSource for GithubReleases {
+    async fn latest_version(&self) -> Result<String> {
+        let release: LatestRelease = self
+            .client
+            .get(LATEST_RELEASE_URL)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .send()
+            .await
+            .context("Failed to reach the GitHub releases API")?
+            .error_for_status()
+            .context("GitHub releases API returned an error")?
+            .json()
+            .await
+            .context("Failed to parse the latest release")?;
+
+        Ok(release.tag_name)
+    }
+}
+
+/// Result of comparing the running version with the latest release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionStatus {
+    /// The running version is the latest (or newer).
+    UpToDate,
+    /// A newer release exists.
+    UpdateAvailable { latest: String },
+}
+
+/// Compares `current` against the latest release from `source`.
+pub async fn check_for_update(source: &impl ReleaseSource, current: &str) -> Result<VersionStatus> {
+    let latest = tokio::time::timeout(CHECK_TIMEOUT, source.latest_version())
+        .await
+        .context("Timed out checking for a newer release")??;
+
+    let latest_version = parse_version(&latest)
+        .with_context(|| format!("Unrecognised release version: {}", latest))?;
+    let current_version = parse_version(current)
+        .with_context(|| format!("Unrecognised current version: {}", current))?;
+
+    if latest_version > current_version {
+        Ok(VersionStatus::UpdateAvailable {
+            latest: latest.trim_start_matches('v').to_string(),
+        })
+    } else {
+        Ok(VersionStatus::UpToDate)
+    }
+}
+
+/// Runs the check for `--version-check`: prints a one-line notice when a
+/// newer release exists and only debug-logs failures.
+pub async fn notify_if_outdated(source: &impl ReleaseSource, current: &str) {
+    match check_for_update(source, current).await {
+        Ok(VersionStatus::UpdateAvailable { latest }) => info!(
+            "A newer version of agent-skills-generator is available: {} (you have {})",
+            latest, current
+        ),
+        Ok(VersionStatus::UpToDate) => debug!("agent-skills-generator {} is up to date", current),
+        Err(e) => debug!("Version check failed: {:#}", e),
+    }
+}
+
+/// Parses `major.minor.patch`, ignoring a leading `v` and any pre-release
+/// or build suffix.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version
+        .trim()
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()?;
+
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Release source returning a fixed answer.
+    struct StubReleases(Option<&'static str>);
+
+    impl ReleaseSource for StubReleases {
+        async fn latest_version(&self) -> Result<String> {
+            match self.0 {
+                Some(version) => Ok(version.to_string()),
+                None => anyhow::bail!("connection refused"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_newer_release_available() {
+        let status = check_for_update(&StubReleases(Some("v0.3.0")), "0.2.1")
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            VersionStatus::UpdateAvailable {
+                latest: "0.3.0".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_same_or_older_release() {
+        let same = check_for_update(&StubReleases(Some("v0.2.1")), "0.2.1")
+            .await
+            .unwrap();
+        assert_eq!(same, VersionStatus::UpToDate);
+
+        let older = check_for_update(&StubReleases(Some("0.2.0")), "0.2.1")
+            .await
+            .unwrap();
+        assert_eq!(older, VersionStatus::UpToDate);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_release_source() {
+        let result = check_for_update(&StubReleases(None), "0.2.1").await;
+        assert!(result.is_err());
+
+        // The --version-check path swallows the failure
+        notify_if_outdated(&StubReleases(None), "0.2.1").await;
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v1.2.3"), Some((1, 2, 3)));
+        assert_eq!(parse_version("0.10.0-beta.1"), Some((0, 10, 0)));
+        assert_eq!(parse_version("2.0"), Some((2, 0, 0)));
+        assert_eq!(parse_version("latest"), None);
+    }
+}
@@ -0,0 +1,170 @@
+//! Watch mode for the agent-skills-generator.
+//!
+//! `crawl --watch <MINUTES>` re-runs the crawl on a fixed schedule to keep a
+//! local mirror of skills fresh. Watch mode always crawls incrementally, so
+//! each cycle only rewrites the pages that changed since the previous one;
+//! after every cycle the number of updated and unchanged skills is logged.
+//! Ctrl-C stops the watch, also in the middle of a cycle.
+
+use crate::crawler::StatsSnapshot;
+use anyhow::Result;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Re-runs a crawl every `interval` and reports what each cycle changed.
+#[derive(Debug)]
+pub struct Watch {
+    interval: Duration,
+    /// Cycles started so far.
+    cycles: usize,
+}
+
+impl Watch {
+    /// Creates a watch that waits `interval` between the end of one cycle
+    /// and the start of the next.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            cycles: 0,
+        }
+    }
+
+    /// Runs `cycle` until interrupted with Ctrl-C.
+    pub async fn run<F, Fut>(&mut self, mut cycle: F)
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<StatsSnapshot>>,
+    {
+        loop {
+            tokio::select! {
+                _ = self.run_cycle(cycle()) => {}
+                _ = tokio::signal::ctrl_c() => break,
+            }
+
+            info!(
+                "Next crawl in {} minute(s); press Ctrl-C to stop",
+                self.interval.as_secs() / 60
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
+
+        info!("Watch stopped after {} cycle(s)", self.cycles);
+    }
+
+    /// Runs a single cycle and logs how many skills it updated. A failed
+    /// cycle is logged and doesn't stop the watch.
+    pub async fn run_cycle(
+        &mut self,
+        cycle: impl Future<Output = Result<StatsSnapshot>>,
+    ) -> Option<StatsSnapshot> {
+        self.cycles += 1;
+        info!("Watch cycle {} started", self.cycles);
+
+        match cycle.await {
+            Ok(stats) => {
+                info!(
+                    "Watch cycle {}: {} skills updated, {} unchanged, {} failed",
+                    self.cycles, stats.processed, stats.unchanged, stats.failed
+                );
+                Some(stats)
+            }
+            Err(e) => {
+                error!("Watch cycle {} failed: {:#}", self.cycles, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::crawler::Crawler;
+    use std::path::Path;
+
+    /// Answers with a fixed page and ETag, and `304 Not Modified` when the
+    /// request already carries that ETag.
+    fn etag_response(request: &str) -> String {
+        if request
+            .to_lowercase()
+            .contains("if-none-match: \"docs-v1\"")
+        {
+            return "HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n".to_string();
+        }
+
+        let body = if request.starts_with("GET /install ") {
+            "<html><head><title>Install</title></head>\
+             <body><main><h1>Install</h1><p>Run the installer.</p></main></body></html>"
+        } else {
+            "<html><head><title>Docs</title></head>\
+             <body><main><h1>Docs</h1><a href=\"/install\">Install</a></main></body></html>"
+        };
+        format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\netag: \"docs-v1\"\r\n\
+             content-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    async fn crawl_once(config: &Config, output_dir: &Path, seed: &str) -> Result<StatsSnapshot> {
+        let crawler = Crawler::new(config.clone(), vec![output_dir.to_path_buf()])?;
+        Ok(crawler.crawl(seed).await?.snapshot())
+    }
+
+    #[tokio::test]
+    async fn test_second_cycle_with_unchanged_etag_rewrites_nothing() {
+        let port = crate::http::test_server(etag_response).await;
+        let seed = format!("http://127.0.0.1:{}/", port);
+
+        let output_dir =
+            std::env::temp_dir().join(format!("agent-skills-watch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_dir);
+        let config = Config {
+            delay_ms: 0,
+            respect_robots_txt: false,
+            incremental: true,
+            ..Default::default()
+        };
+
+        let mut watch = Watch::new(Duration::ZERO);
+        let first = watch
+            .run_cycle(crawl_once(&config, &output_dir, &seed))
+            .await
+            .unwrap();
+        assert_eq!((first.processed, first.unchanged), (2, 0));
+
+        let skill_md = std::fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path().join("SKILL.md"))
+            .find(|path| path.is_file())
+            .unwrap();
+        let written = std::fs::read_to_string(&skill_md).unwrap();
+
+        let second = watch
+            .run_cycle(crawl_once(&config, &output_dir, &seed))
+            .await
+            .unwrap();
+        assert_eq!((second.processed, second.unchanged), (0, 2));
+        assert_eq!(std::fs::read_to_string(&skill_md).unwrap(), written);
+        assert_eq!(watch.cycles, 2);
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_cycle_is_reported() {
+        let mut watch = Watch::new(Duration::ZERO);
+        let result = watch
+            .run_cycle(async { Err(anyhow::anyhow!("config went missing")) })
+            .await;
+
+        assert!(result.is_none());
+        assert_eq!(watch.cycles, 1);
+    }
+}